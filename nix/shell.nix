@@ -51,6 +51,7 @@ pkgs.mkShell {
     novnc
     tigervnc
     xdotool
+    xclip
     scrot
     imagemagick
     ffmpeg-full     # Screen recording
@@ -93,6 +94,16 @@ pkgs.mkShell {
     procps
     util-linux
 
+    # Data queries
+    sqlite
+    duckdb
+
+    # Document conversion
+    pandoc
+    poppler_utils  # pdftotext
+    xlsx2csv
+    weasyprint     # Markdown to PDF
+
     # File utilities
     file
     unzip
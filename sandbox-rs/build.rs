@@ -0,0 +1,51 @@
+use std::process::Command;
+
+fn main() {
+    build_info();
+
+    #[cfg(feature = "grpc")]
+    {
+        // A bundled protoc, so building with gRPC needs nothing installed
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("no bundled protoc for this platform");
+        std::env::set_var("PROTOC", protoc);
+        println!("cargo:rerun-if-changed=proto");
+        tonic_prost_build::configure()
+            .build_client(false)
+            .compile_protos(&["proto/sandbox/v1/sandbox.proto"], &["proto"])
+            .expect("failed to compile protos");
+    }
+}
+
+/// Commit, build time, and compiler for `/version`. Builds outside a git checkout,
+/// such as Nix, can pass `GIT_COMMIT` and `SOURCE_DATE_EPOCH` instead.
+fn build_info() {
+    let output = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let commit = std::env::var("GIT_COMMIT")
+        .ok()
+        .or_else(|| output("git", &["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".into());
+    let epoch = std::env::var("SOURCE_DATE_EPOCH").ok().unwrap_or_else(|| {
+        let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+        now.as_secs().to_string()
+    });
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+    let rustc = output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".into());
+
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=BUILD_EPOCH={}", epoch);
+    println!("cargo:rustc-env=BUILD_RUSTC={}", rustc);
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if let Some(git_dir) = output("git", &["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", git_dir);
+        println!("cargo:rerun-if-changed={}/refs/heads", git_dir);
+    }
+}
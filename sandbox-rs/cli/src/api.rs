@@ -0,0 +1,370 @@
+use anyhow::{Context as _, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::StreamExt;
+use nixosandbox_client::admin::AuditQuery;
+use nixosandbox_client::browser::{ContentRequest, ScreenshotRequest};
+use nixosandbox_client::shell::{ExecRequest, StreamEvent};
+use nixosandbox_client::skills::RunScriptRequest;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::args::Args;
+use crate::{CliError, Context, GLOBAL};
+
+/// `nixosandbox api <command>`; returns the process exit code
+pub async fn run(context: &Context, args: &Args) -> Result<i32> {
+    let Some(command) = args.positional.get(1) else {
+        usage!("api needs a command: exec, cp, logs, skills, or browse");
+    };
+    let operands = &args.positional[2..];
+    match command.as_str() {
+        "exec" => {
+            args.only(&[GLOBAL, &["cwd", "timeout", "env", "stream"]].concat())?;
+            exec(context, args, operands).await
+        }
+        "cp" => {
+            args.only(&[GLOBAL, &["recursive"]].concat())?;
+            copy(context, operands, args.switch("recursive")).await
+        }
+        "logs" => {
+            args.only(&[GLOBAL, &["since", "until", "path", "method", "status", "failed", "limit"]].concat())?;
+            logs(context, args).await
+        }
+        "skills" => {
+            args.only(GLOBAL)?;
+            skills(context, operands, &args.rest).await
+        }
+        "browse" => {
+            args.only(&[GLOBAL, &["text", "html", "screenshot", "full-page"]].concat())?;
+            browse(context, args, operands).await
+        }
+        other => usage!("unknown api command '{}'; see --help", other),
+    }
+}
+
+async fn exec(context: &Context, args: &Args, operands: &[String]) -> Result<i32> {
+    let words = if args.rest.is_empty() { operands } else { &args.rest[..] };
+    if words.is_empty() {
+        usage!("exec needs a command, e.g. nixosandbox api exec -- ls -la");
+    }
+    let mut env = HashMap::new();
+    for pair in args.all("env") {
+        let Some((key, value)) = pair.split_once('=') else {
+            usage!("--env takes KEY=VALUE, got '{}'", pair);
+        };
+        env.insert(key.to_string(), value.to_string());
+    }
+    let request = ExecRequest {
+        command: command_line(words),
+        cwd: args.get("cwd").map(String::from),
+        timeout: args.parse_value("timeout")?,
+        env: (!env.is_empty()).then_some(env),
+        ..Default::default()
+    };
+
+    if args.switch("stream") {
+        let mut events = Box::pin(context.client.shell().stream(&request).await?);
+        let mut stdout = std::io::stdout().lock();
+        while let Some(event) = events.next().await {
+            match event? {
+                StreamEvent::Line(line) if context.json => writeln!(stdout, "{}", json!({ "line": line }))?,
+                StreamEvent::Line(line) => writeln!(stdout, "{}", line)?,
+                StreamEvent::Exit(code) => {
+                    if context.json {
+                        writeln!(stdout, "{}", json!({ "exit_code": code }))?;
+                    }
+                    return Ok(code);
+                }
+                StreamEvent::Error(error) => return Err(CliError::new("EXEC_FAILED", error).into()),
+            }
+        }
+        return Err(CliError::new("STREAM_ENDED", "the stream ended before the command exited").into());
+    }
+
+    let result = context.client.shell().exec(&request).await?;
+    if context.json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        print!("{}", result.stdout);
+        eprint!("{}", result.stderr);
+    }
+    Ok(result.exit_code)
+}
+
+/// One shell command line from `words`: a single word is taken as a whole command line,
+/// several are quoted so each reaches the command as one argument
+fn command_line(words: &[String]) -> String {
+    if let [line] = words {
+        return line.clone();
+    }
+    let quote = |word: &String| {
+        let plain = !word.is_empty()
+            && word.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c));
+        match plain {
+            true => word.clone(),
+            false => format!("'{}'", word.replace('\'', r"'\''")),
+        }
+    };
+    words.iter().map(quote).collect::<Vec<_>>().join(" ")
+}
+
+/// `cp SOURCE DEST`; with `recursive`, copies a directory's files, creating directories as needed
+pub async fn copy(context: &Context, operands: &[String], recursive: bool) -> Result<i32> {
+    let [source, dest] = operands else {
+        usage!("cp takes SOURCE and DEST, one of them a sandbox path starting with ':'");
+    };
+    let files = context.client.files();
+    let mut copied = Vec::new();
+    match (source.strip_prefix(':'), dest.strip_prefix(':')) {
+        (None, Some(remote)) => {
+            let root = remote_path(remote, source);
+            let local = if Path::new(source).is_dir() {
+                if !recursive {
+                    usage!("{} is a directory; copy it with --recursive", source);
+                }
+                local_files(Path::new(source)).with_context(|| format!("reading {}", source))?
+            } else {
+                vec![(PathBuf::from(source), String::new())]
+            };
+            for (path, relative) in local {
+                let remote = join(&root, &relative);
+                let written = files.upload_file(&path, &remote).await.with_context(|| format!("copying {}", path.display()))?;
+                copied.push(written.size);
+            }
+        }
+        (Some(remote), None) => {
+            let name = remote.trim_end_matches('/').rsplit('/').next().unwrap_or(remote);
+            let root = match Path::new(dest).is_dir() {
+                true => Path::new(dest).join(name),
+                false => dest.into(),
+            };
+            let remote_files = match recursive {
+                true => remote_files(context, remote).await?,
+                false => vec![String::new()],
+            };
+            for relative in remote_files {
+                let local = if relative.is_empty() { root.clone() } else { root.join(&relative) };
+                if let Some(parent) = local.parent().filter(|_| recursive) {
+                    tokio::fs::create_dir_all(parent).await.with_context(|| format!("creating {}", parent.display()))?;
+                }
+                let remote = join(remote, &relative);
+                copied.push(files.download_to(&remote, &local).await.with_context(|| format!("copying :{}", remote))?);
+            }
+        }
+        _ => usage!("cp copies between the host and the sandbox: start exactly one path with ':'"),
+    };
+    let size: u64 = copied.iter().sum();
+    if context.json {
+        let body = json!({ "source": source, "dest": dest, "files": copied.len(), "size": size });
+        println!("{}", serde_json::to_string_pretty(&body)?);
+    } else if recursive {
+        eprintln!("{} -> {} ({} files, {} bytes)", source, dest, copied.len(), size);
+    } else {
+        eprintln!("{} -> {} ({} bytes)", source, dest, size);
+    }
+    Ok(0)
+}
+
+/// Regular files under `dir`, with their paths relative to it; symlinks are skipped
+fn local_files(dir: &Path) -> std::io::Result<Vec<(PathBuf, String)>> {
+    let mut found = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), String::new())];
+    while let Some((dir, prefix)) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let relative = join(&prefix, &entry.file_name().to_string_lossy());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push((entry.path(), relative));
+            } else if file_type.is_file() {
+                found.push((entry.path(), relative));
+            }
+        }
+    }
+    found.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(found)
+}
+
+/// Regular files under the sandbox directory `dir`, relative to it
+async fn remote_files(context: &Context, dir: &str) -> Result<Vec<String>> {
+    let listing = context.client.files().list(dir, true).await?;
+    let root = format!("{}/", listing.path.trim_end_matches('/'));
+    let mut found: Vec<_> = listing
+        .entries
+        .into_iter()
+        .filter(|entry| entry.file_type == "file")
+        .filter_map(|entry| entry.path.strip_prefix(&root).map(String::from))
+        .collect();
+    found.sort();
+    Ok(found)
+}
+
+/// `relative` under `dir`, or `dir` itself for an empty `relative`
+fn join(dir: &str, relative: &str) -> String {
+    match (dir.trim_end_matches('/'), relative) {
+        (dir, "") => dir.to_string(),
+        ("", relative) => relative.to_string(),
+        (dir, relative) => format!("{}/{}", dir, relative),
+    }
+}
+
+/// `remote`, or `remote` plus the local file's name when it names a directory
+fn remote_path(remote: &str, local: &str) -> String {
+    let name = Path::new(local).file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    match remote {
+        "" | "." => name.into_owned(),
+        dir if dir.ends_with('/') => format!("{}{}", dir, name),
+        file => file.to_string(),
+    }
+}
+
+async fn logs(context: &Context, args: &Args) -> Result<i32> {
+    let time = |name: &str| -> Result<_> {
+        let parse = |value: &str| {
+            value.parse().map_err(|_| CliError::usage(format!("--{} takes an RFC 3339 time, got '{}'", name, value)))
+        };
+        Ok(args.get(name).map(parse).transpose()?)
+    };
+    let query = AuditQuery {
+        since: time("since")?,
+        until: time("until")?,
+        path: args.get("path").map(String::from),
+        method: args.get("method").map(str::to_ascii_uppercase),
+        status: args.parse_value("status")?,
+        failed: args.switch("failed").then_some(true),
+        limit: args.parse_value("limit")?,
+        ..Default::default()
+    };
+    let entries = context.client.admin().audit(&query).await?;
+    if context.json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(0);
+    }
+    for entry in entries {
+        let error = entry.error.map(|error| format!("  {}", error)).unwrap_or_default();
+        println!(
+            "{}  {:<6} {:<40} {}  {}ms  {}{}",
+            entry.time.format("%Y-%m-%dT%H:%M:%SZ"),
+            entry.method,
+            entry.path,
+            entry.status,
+            entry.duration_ms,
+            entry.client,
+            error
+        );
+    }
+    Ok(0)
+}
+
+async fn skills(context: &Context, operands: &[String], rest: &[String]) -> Result<i32> {
+    let skills = context.client.skills();
+    let print_list = |list: Vec<nixosandbox_client::skills::SkillSummary>| -> Result<()> {
+        if context.json {
+            println!("{}", serde_json::to_string_pretty(&list)?);
+        } else {
+            for skill in list {
+                println!("{:<24} {}", skill.name, skill.description);
+            }
+        }
+        Ok(())
+    };
+    match operands.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] | ["list"] => print_list(skills.list().await?)?,
+        ["search", query] => print_list(skills.search(query).await?)?,
+        ["show", name] => {
+            let skill = skills.get(name).await?;
+            if context.json {
+                println!("{}", serde_json::to_string_pretty(&skill)?);
+            } else {
+                println!("{}\n{}\n", skill.name, skill.description);
+                if !skill.scripts.is_empty() {
+                    println!("scripts: {}\n", skill.scripts.join(", "));
+                }
+                println!("{}", skill.body.trim_end());
+            }
+        }
+        ["run", name, script, ..] => {
+            let request = RunScriptRequest { args: [&operands[3..], rest].concat(), ..Default::default() };
+            let result = skills.run_script(name, script, &request).await?;
+            if context.json {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                print!("{}", result.stdout);
+                eprint!("{}", result.stderr);
+            }
+            return Ok(result.exit_code);
+        }
+        _ => usage!("skills takes list, search QUERY, show NAME, or run NAME SCRIPT [ARGS]..."),
+    }
+    Ok(0)
+}
+
+async fn browse(context: &Context, args: &Args, operands: &[String]) -> Result<i32> {
+    let [url] = operands else {
+        usage!("browse takes one URL");
+    };
+    let browser = context.client.browser();
+    let html = args.switch("html");
+    let content = browser
+        .content(&ContentRequest { url: Some(url.clone()), include_html: Some(html), ..Default::default() })
+        .await?;
+
+    let mut screenshot = None;
+    if let Some(file) = args.get("screenshot") {
+        let shot = browser
+            .screenshot(&ScreenshotRequest {
+                url: Some(url.clone()),
+                full_page: args.switch("full-page"),
+                ..Default::default()
+            })
+            .await?;
+        let data = BASE64.decode(&shot.data).context("decoding the screenshot")?;
+        tokio::fs::write(file, &data).await.with_context(|| format!("writing {}", file))?;
+        screenshot = Some(file);
+    }
+
+    if context.json {
+        let mut body = serde_json::to_value(&content)?;
+        body["screenshot"] = json!(screenshot);
+        println!("{}", serde_json::to_string_pretty(&body)?);
+        return Ok(0);
+    }
+    println!("{}\n{}\n", content.title, content.url);
+    match (html, content.html) {
+        (true, Some(html)) => println!("{}", html),
+        _ => println!("{}", content.text.trim_end()),
+    }
+    if let Some(file) = screenshot {
+        eprintln!("screenshot saved to {}", file);
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_line() {
+        let words = |words: &[&str]| words.iter().map(|word| word.to_string()).collect::<Vec<_>>();
+        assert_eq!(command_line(&words(&["ls -la | wc -l"])), "ls -la | wc -l");
+        assert_eq!(command_line(&words(&["echo", "a b", "it's"])), r"echo 'a b' 'it'\''s'");
+        assert_eq!(command_line(&words(&["grep", "-r", "x=1", "src/"])), "grep -r x=1 src/");
+    }
+
+    #[test]
+    fn test_remote_path() {
+        assert_eq!(remote_path("", "data/report.csv"), "report.csv");
+        assert_eq!(remote_path("input/", "data/report.csv"), "input/report.csv");
+        assert_eq!(remote_path("input/r.csv", "data/report.csv"), "input/r.csv");
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join("out/", "a/b.txt"), "out/a/b.txt");
+        assert_eq!(join("out", ""), "out");
+        assert_eq!(join("", "b.txt"), "b.txt");
+    }
+}
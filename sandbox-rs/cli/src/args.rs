@@ -0,0 +1,108 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::CliError;
+
+/// Parsed command line: positional arguments, `--flag value` options, `--switch`es, and
+/// whatever follows `--`
+#[derive(Debug, Default)]
+pub struct Args {
+    pub positional: Vec<String>,
+    options: HashMap<String, Vec<String>>,
+    /// Arguments after `--`, passed through untouched
+    pub rest: Vec<String>,
+}
+
+impl Args {
+    /// Parse `args`; names in `switches` take no value, every other `--name` takes one
+    pub fn parse(args: impl IntoIterator<Item = String>, switches: &[&str]) -> Result<Self> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                parsed.rest.extend(args.by_ref());
+                break;
+            }
+            let Some(flag) = arg.strip_prefix("--") else {
+                parsed.positional.push(arg);
+                continue;
+            };
+            let (name, inline) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            let value = if switches.contains(&name) {
+                inline.unwrap_or_else(|| "true".into())
+            } else {
+                match inline.or_else(|| args.next()) {
+                    Some(value) => value,
+                    None => usage!("--{} needs a value", name),
+                }
+            };
+            parsed.options.entry(name.to_string()).or_default().push(value);
+        }
+        Ok(parsed)
+    }
+
+    /// Last value of `--name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|values| values.last()).map(String::as_str)
+    }
+
+    /// Every value of a repeatable `--name`
+    pub fn all(&self, name: &str) -> &[String] {
+        self.options.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn switch(&self, name: &str) -> bool {
+        matches!(self.get(name), Some("true" | "1" | "yes"))
+    }
+
+    pub fn parse_value<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.get(name)
+            .map(|value| {
+                let invalid = |e: T::Err| CliError::usage(format!("invalid value '{}' for --{}: {}", value, name, e));
+                value.parse().map_err(|e| invalid(e).into())
+            })
+            .transpose()
+    }
+
+    /// Reject options the command does not know, so typos are not silently ignored
+    pub fn only(&self, known: &[&str]) -> Result<()> {
+        match self.options.keys().find(|name| !known.contains(&name.as_str())) {
+            Some(name) => usage!("unknown option --{}", name),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Args {
+        Args::parse(args.iter().map(|arg| arg.to_string()), &["json", "stream"]).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = args(&["exec", "--json", "--cwd", "src", "--env=A=1", "--env", "B=2", "--", "ls", "--all"]);
+        assert_eq!(parsed.positional, vec!["exec"]);
+        assert!(parsed.switch("json"));
+        assert!(!parsed.switch("stream"));
+        assert_eq!(parsed.get("cwd"), Some("src"));
+        assert_eq!(parsed.all("env"), ["A=1", "B=2"]);
+        assert_eq!(parsed.rest, vec!["ls", "--all"]);
+        assert!(parsed.only(&["json", "cwd"]).is_err());
+        assert!(parsed.only(&["json", "cwd", "env"]).is_ok());
+    }
+
+    #[test]
+    fn test_missing_value() {
+        assert!(Args::parse(vec!["--timeout".to_string()], &[]).is_err());
+        assert!(args(&["--timeout", "x"]).parse_value::<u64>("timeout").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Result;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::Context;
+
+/// Oldest Nix with flakes
+const MIN_NIX: (u32, u32) = (2, 4);
+/// Free space below which builds are likely to fail, and below which to warn
+const DISK_FAIL_KB: u64 = 1024 * 1024;
+const DISK_WARN_KB: u64 = 10 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    fn as_str(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "fail",
+        }
+    }
+}
+
+/// One finding, with what to do about it when it is not ok
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: Status::Ok, detail: detail.into(), fix: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Warn, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { name, status: Status::Fail, detail: detail.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// `nixosandbox doctor`: check the host for what sandboxes need; exits with 1 if any check
+/// fails
+pub async fn run(context: &Context) -> Result<i32> {
+    let mut checks = vec![platform()];
+    checks.extend(nix());
+    checks.push(path());
+    checks.push(disk());
+    if cfg!(target_os = "linux") {
+        checks.extend([kvm(), vsock()]);
+    }
+    checks.push(docker());
+    checks.push(api(context).await);
+
+    let healthy = checks.iter().all(|check| check.status != Status::Fail);
+    if context.json {
+        let checks: Vec<_> = checks
+            .iter()
+            .map(|check| {
+                json!({ "name": check.name, "status": check.status.as_str(), "detail": check.detail, "fix": check.fix })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json!({ "ok": healthy, "checks": checks }))?);
+    } else {
+        for check in &checks {
+            println!("{:<5} {:<10} {}", check.status.as_str(), check.name, check.detail);
+            if let Some(fix) = &check.fix {
+                println!("{:<16} fix: {}", "", fix);
+            }
+        }
+    }
+    Ok(if healthy { 0 } else { 1 })
+}
+
+fn platform() -> Check {
+    let platform = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64" | "aarch64") => Check::ok("platform", platform),
+        ("macos", _) => {
+            Check::warn("platform", platform, "sandboxes run in a Linux VM here; use Docker Desktop or a Linux builder")
+        }
+        _ => Check::fail("platform", platform, "sandboxes need x86_64 or aarch64 Linux, or macOS with Docker"),
+    }
+}
+
+/// The Nix version, whether flakes are on, and settings Nix warns about
+fn nix() -> Vec<Check> {
+    let version = match output("nix", &["--version"]) {
+        Some((stdout, _)) => stdout,
+        None => {
+            let fix = if Path::new("/nix/var/nix/profiles/default/bin/nix").exists() {
+                "Nix is installed but not on PATH; source /nix/var/nix/profiles/default/etc/profile.d/nix-daemon.sh"
+            } else {
+                "install Nix from https://nixos.org/download"
+            };
+            return vec![Check::fail("nix", "nix not found on PATH", fix)];
+        }
+    };
+    let version = version.trim().to_string();
+    let mut checks = vec![match parse_version(&version) {
+        Some(found) if found < MIN_NIX => {
+            let fix = format!("upgrade to Nix {}.{} or newer for flakes", MIN_NIX.0, MIN_NIX.1);
+            Check::fail("nix", version, fix)
+        }
+        Some(_) => Check::ok("nix", version),
+        None => Check::warn("nix", version, "could not read the Nix version; check that `nix --version` works"),
+    }];
+
+    let args = ["--extra-experimental-features", "nix-command", "config", "show"];
+    let legacy = ["--extra-experimental-features", "nix-command", "show-config"];
+    let Some((config, stderr)) = output("nix", &args).or_else(|| output("nix", &legacy)) else {
+        checks.push(Check::warn("flakes", "could not read the Nix configuration", "check that `nix config show` works"));
+        return checks;
+    };
+    let config = parse_config(&config);
+    let features: Vec<_> = config.get("experimental-features").map(|v| v.split_whitespace().collect()).unwrap_or_default();
+    checks.push(if ["nix-command", "flakes"].iter().all(|feature| features.contains(feature)) {
+        Check::ok("flakes", "nix-command and flakes enabled")
+    } else {
+        let fix = "add `experimental-features = nix-command flakes` to ~/.config/nix/nix.conf";
+        Check::fail("flakes", "nix-command and flakes are not both enabled", fix)
+    });
+
+    if stderr.contains("unknown setting 'filter-syscalls'") {
+        let fix = "remove `filter-syscalls` from nix.conf; this Nix was built without seccomp";
+        checks.push(Check::warn("seccomp", "nix.conf sets filter-syscalls, which this Nix does not know", fix));
+    } else if cfg!(target_os = "linux") && config.get("filter-syscalls").map(String::as_str) == Some("true") && !seccomp() {
+        let fix = "set `filter-syscalls = false` in nix.conf, or allow seccomp in the container running Nix";
+        checks.push(Check::fail("seccomp", "filter-syscalls is on but seccomp is unavailable", fix));
+    }
+    checks
+}
+
+/// Whether the kernel offers seccomp, per `/proc/self/status`
+fn seccomp() -> bool {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap_or_default();
+    status.lines().any(|line| line.starts_with("Seccomp:"))
+}
+
+/// Whether `nixosandbox` itself is reachable on PATH
+fn path() -> Check {
+    let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) else {
+        return Check::warn("path", "could not find where nixosandbox is installed", "reinstall nixosandbox");
+    };
+    let on_path = std::env::var_os("PATH").is_some_and(|paths| std::env::split_paths(&paths).any(|entry| entry == dir));
+    if on_path {
+        Check::ok("path", format!("installed in {}", dir.display()))
+    } else {
+        Check::warn("path", format!("{} is not on PATH", dir.display()), format!("add {} to PATH", dir.display()))
+    }
+}
+
+/// Free space where Nix keeps its store, or the home directory without one
+fn disk() -> Check {
+    let dir = [PathBuf::from("/nix/store"), std::env::var_os("HOME").map(PathBuf::from).unwrap_or_default()]
+        .into_iter()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from("/"));
+    let free = output("df", &["-Pk", &dir.to_string_lossy()]).and_then(|(stdout, _)| parse_df(&stdout));
+    let Some(free) = free else {
+        return Check::warn("disk", format!("could not read free space on {}", dir.display()), "check `df` works");
+    };
+    let detail = format!("{:.1} GiB free on {}", free as f64 / (1024.0 * 1024.0), dir.display());
+    let fix = "free space, e.g. with `nix-collect-garbage -d`";
+    match free {
+        free if free < DISK_FAIL_KB => Check::fail("disk", detail, fix),
+        free if free < DISK_WARN_KB => Check::warn("disk", detail, fix),
+        _ => Check::ok("disk", detail),
+    }
+}
+
+fn kvm() -> Check {
+    let kvm = Path::new("/dev/kvm");
+    if !kvm.exists() {
+        let fix = "enable virtualization in the firmware and load kvm_intel or kvm_amd";
+        return Check::warn("kvm", "/dev/kvm not found; microVM sandboxes are unavailable", fix);
+    }
+    match std::fs::OpenOptions::new().read(true).write(true).open(kvm) {
+        Ok(_) => Check::ok("kvm", "/dev/kvm usable"),
+        Err(e) => Check::warn("kvm", format!("/dev/kvm: {}", e), "add yourself to the kvm group and log in again"),
+    }
+}
+
+fn vsock() -> Check {
+    if Path::new("/dev/vhost-vsock").exists() {
+        Check::ok("vsock", "/dev/vhost-vsock present")
+    } else {
+        Check::warn("vsock", "/dev/vhost-vsock not found", "load the vhost_vsock module: sudo modprobe vhost_vsock")
+    }
+}
+
+/// Whether the Docker daemon at `DOCKER_HOST`, or its default socket, accepts connections
+fn docker() -> Check {
+    let host = std::env::var("DOCKER_HOST").unwrap_or_default();
+    let socket = match host.strip_prefix("unix://") {
+        Some(socket) => socket.to_string(),
+        None if host.is_empty() => "/var/run/docker.sock".to_string(),
+        None => return Check::ok("docker", format!("DOCKER_HOST={} (not checked)", host)),
+    };
+    if !Path::new(&socket).exists() {
+        return Check::warn("docker", format!("{} not found", socket), "install and start Docker to run container sandboxes");
+    }
+    #[cfg(unix)]
+    if let Err(e) = std::os::unix::net::UnixStream::connect(&socket) {
+        let fix = match e.kind() {
+            std::io::ErrorKind::PermissionDenied => "add yourself to the docker group and log in again",
+            _ => "start the Docker daemon",
+        };
+        return Check::warn("docker", format!("{}: {}", socket, e), fix);
+    }
+    Check::ok("docker", format!("{} accepting connections", socket))
+}
+
+/// The sandbox at `--url`, if one is running
+async fn api(context: &Context) -> Check {
+    let url = context.client.base_url();
+    match context.client.ready().await {
+        Ok(ready) if ready.ready => Check::ok("api", format!("{} ready", url)),
+        Ok(ready) => {
+            let failing: Vec<_> = ready.checks.iter().filter(|check| check.required && !check.ok).map(|check| &check.name[..]).collect();
+            let detail = format!("{} not ready: {}", url, failing.join(", "));
+            Check::warn("api", detail, "see the sandbox's /ready endpoint for details")
+        }
+        Err(e) => Check::warn("api", format!("no sandbox at {}: {}", url, e), "start one, or point --url at it"),
+    }
+}
+
+/// Stdout and stderr of a program that ran and succeeded
+fn output(program: &str, args: &[&str]) -> Option<(String, String)> {
+    let output = Command::new(program).args(args).output().ok()?;
+    let text = |bytes: Vec<u8>| String::from_utf8_lossy(&bytes).into_owned();
+    output.status.success().then(|| (text(output.stdout), text(output.stderr)))
+}
+
+/// `(major, minor)` from `nix --version`, e.g. `nix (Nix) 2.18.1`
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let number = version.split_whitespace().last()?;
+    let mut parts = number.split('.').map(|part| part.parse().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// `key = value` lines of `nix config show`
+fn parse_config(config: &str) -> HashMap<String, String> {
+    config
+        .lines()
+        .filter_map(|line| line.split_once(" = "))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// Available kilobytes from `df -Pk`
+fn parse_df(df: &str) -> Option<u64> {
+    df.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_nix_versions() {
+        assert_eq!(parse_version("nix (Nix) 2.18.1"), Some((2, 18)));
+        assert_eq!(parse_version("nix (Nix) 2.3"), Some((2, 3)));
+        assert_eq!(parse_version("nix (Determinate Nix 3.1.0) 2.26.3"), Some((2, 26)));
+        assert_eq!(parse_version("nix"), None);
+    }
+
+    #[test]
+    fn reads_nix_config_and_df() {
+        let config = parse_config("experimental-features = flakes nix-command\nfilter-syscalls = true\n");
+        assert_eq!(config["experimental-features"], "flakes nix-command");
+        assert_eq!(config["filter-syscalls"], "true");
+
+        let df = "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 100 40 60 40% /\n";
+        assert_eq!(parse_df(df), Some(60));
+    }
+}
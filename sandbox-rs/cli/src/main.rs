@@ -0,0 +1,190 @@
+use anyhow::Result;
+use nixosandbox_client::{Client, Error};
+use std::fmt;
+use std::process::ExitCode;
+
+/// Return a [`CliError`] with code `USAGE` from the current function
+macro_rules! usage {
+    ($($arg:tt)*) => {
+        return Err($crate::CliError::usage(format!($($arg)*)).into())
+    };
+}
+
+mod api;
+mod args;
+mod doctor;
+
+use args::Args;
+
+const USAGE: &str = "\
+Usage: nixosandbox [OPTIONS] <COMMAND>
+
+Drive a running sandbox through its API.
+
+Commands:
+  doctor
+      Check Nix, drivers, disk space, and the sandbox at --url, printing fixes for problems
+  api exec [--cwd DIR] [--timeout SECS] [--env KEY=VALUE]... [--stream] -- COMMAND...
+      Run a shell command, exiting with its exit code
+  api cp [--recursive] SOURCE DEST
+      Copy a file, or a directory with --recursive, to or from the sandbox; sandbox paths
+      start with ':', e.g. :out/report.pdf. Also available as plain `nixosandbox cp`
+  api logs [--since TIME] [--until TIME] [--path PREFIX] [--method METHOD] [--status CODE]
+           [--failed] [--limit N]
+      Show the audit log of mutating requests, newest first
+  api skills [list | search QUERY | show NAME | run NAME SCRIPT [ARGS]...]
+      List, find, inspect, and run skills
+  api browse URL [--text | --html] [--screenshot FILE] [--full-page]
+      Load a page in the sandbox's browser and print its title and readable text
+
+Options:
+  --url URL          Sandbox API address [env: NIXOSANDBOX_URL] [default: http://localhost:8080]
+  --token TOKEN      Bearer token [env: NIXOSANDBOX_TOKEN]
+  --session ID       Session to run in [env: NIXOSANDBOX_SESSION]
+  --json             Print results, and errors, as JSON
+  --help             Print this help
+
+Exit status: the command's own for exec and skills run, 2 for usage errors, 1 for other errors
+and for failed doctor checks.
+";
+
+/// Options that take no value, for every command
+const SWITCHES: &[&str] = &["json", "help", "stream", "failed", "text", "html", "full-page", "recursive"];
+
+/// Options every command accepts
+pub const GLOBAL: &[&str] = &["url", "token", "session", "json", "help"];
+
+/// A failure found by the CLI itself rather than reported by the API
+#[derive(Debug)]
+pub struct CliError {
+    /// Stable code for `--json` output, e.g. `USAGE`
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+
+    /// A mistake in the command line, reported with exit code 2
+    pub fn usage(message: impl Into<String>) -> Self {
+        Self::new("USAGE", message)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// What every command is handed: the client and how to print
+pub struct Context {
+    pub client: Client,
+    pub json: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let args = match Args::parse(raw.clone(), SWITCHES) {
+        Ok(args) => args,
+        Err(e) => return fail(&e, raw.iter().any(|arg| arg == "--json")),
+    };
+    let json = args.switch("json");
+    if args.switch("help") || args.positional.is_empty() {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    match run(&args, json).await {
+        Ok(code) => ExitCode::from(code.clamp(0, 255) as u8),
+        Err(e) => fail(&e, json),
+    }
+}
+
+async fn run(args: &Args, json: bool) -> Result<i32> {
+    let client = connect(args)?;
+    let context = Context { client, json };
+    match args.positional[0].as_str() {
+        "api" => api::run(&context, args).await,
+        "cp" => {
+            args.only(&[GLOBAL, &["recursive"]].concat())?;
+            api::copy(&context, &args.positional[1..], args.switch("recursive")).await
+        }
+        "doctor" => {
+            args.only(GLOBAL)?;
+            if args.positional.len() > 1 {
+                usage!("doctor takes no arguments");
+            }
+            doctor::run(&context).await
+        }
+        other => usage!("unknown command '{}'; see --help", other),
+    }
+}
+
+/// A client for the sandbox named by `--url`, `--token`, and `--session` or their variables
+fn connect(args: &Args) -> Result<Client> {
+    let option = |name: &str, var: &str| args.get(name).map(String::from).or_else(|| std::env::var(var).ok());
+    let url = option("url", "NIXOSANDBOX_URL").unwrap_or_else(|| "http://localhost:8080".into());
+    let mut builder = Client::builder(url);
+    if let Some(token) = option("token", "NIXOSANDBOX_TOKEN") {
+        builder = builder.bearer_token(token);
+    }
+    if let Some(session) = option("session", "NIXOSANDBOX_SESSION") {
+        builder = builder.session(session);
+    }
+    builder.build().map_err(|e| CliError::usage(format!("invalid --url: {}", e)).into())
+}
+
+/// Report `error` on stderr, or as a JSON object on stdout with `--json`; usage errors exit
+/// with 2 and every other failure with 1
+fn fail(error: &anyhow::Error, json: bool) -> ExitCode {
+    let api = error.chain().find_map(|cause| cause.downcast_ref::<Error>()).and_then(Error::api);
+    let code = code(error);
+    if json {
+        let mut body = serde_json::json!({ "code": code, "message": format!("{:#}", error) });
+        if let Some(api) = api {
+            body["message"] = api.message.clone().into();
+            body["status"] = api.status.into();
+            body["request_id"] = serde_json::json!(api.request_id);
+            body["details"] = serde_json::json!(api.details);
+        }
+        println!("{}", serde_json::json!({ "error": body }));
+    } else {
+        eprintln!("error: {:#}", error);
+        if let Some(request_id) = api.and_then(|api| api.request_id.as_deref()) {
+            eprintln!("  request id: {}", request_id);
+        }
+    }
+    match code {
+        "USAGE" => ExitCode::from(2),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+/// Stable code of `error`: the API's own code, or one naming what went wrong locally
+fn code(error: &anyhow::Error) -> &str {
+    for cause in error.chain() {
+        if let Some(error) = cause.downcast_ref::<CliError>() {
+            return error.code;
+        }
+        if let Some(error) = cause.downcast_ref::<Error>() {
+            return match error {
+                Error::Api(api) => &api.code,
+                Error::Http(e) if e.is_connect() => "CONNECTION_FAILED",
+                Error::Http(e) if e.is_timeout() => "TIMEOUT",
+                Error::Http(_) => "HTTP_ERROR",
+                Error::Url(_) => "INVALID_URL",
+                Error::Io(_) => "IO_ERROR",
+                Error::Decode(_) => "UNEXPECTED_RESPONSE",
+            };
+        }
+        if cause.is::<std::io::Error>() {
+            return "IO_ERROR";
+        }
+    }
+    "CLI_ERROR"
+}
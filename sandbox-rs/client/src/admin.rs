@@ -0,0 +1,111 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminStatus {
+    pub draining: bool,
+    pub drain_started_at: Option<DateTime<Utc>>,
+    pub running_executions: usize,
+    pub sessions: usize,
+    pub uptime: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Settings that changed and are now in effect
+    pub applied: Vec<String>,
+    /// Settings that changed but take effect after a restart
+    pub restart_required: Vec<String>,
+    pub browser_restarted: bool,
+}
+
+/// Filters for [`Admin::audit`]; entries come newest first
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// e.g. `ip:10.0.0.5` or `key:1a2b3c4d5e6f7a8b`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Path prefix, e.g. `/shell`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed: Option<bool>,
+    /// 100 by default, at most 1000
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub time: DateTime<Utc>,
+    pub request_id: Option<String>,
+    pub client: String,
+    pub ip: Option<String>,
+    pub method: String,
+    pub path: String,
+    /// Body or query, with secrets redacted
+    pub params: Value,
+    pub status: u16,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Deserialize)]
+struct AuditList {
+    entries: Vec<AuditEntry>,
+}
+
+/// `/admin/*` and `/audit`: operating the sandbox
+pub struct Admin<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn admin(&self) -> Admin<'_> {
+        Admin { client: self }
+    }
+}
+
+impl Admin<'_> {
+    /// Effective configuration, with credentials redacted
+    pub async fn config(&self) -> Result<Value> {
+        self.client.get("/admin/config").await
+    }
+
+    /// Re-read configuration and apply the settings that can change while running
+    pub async fn reload(&self) -> Result<ReloadReport> {
+        self.client.post("/admin/reload", &serde_json::json!({})).await
+    }
+
+    pub async fn status(&self) -> Result<AdminStatus> {
+        self.client.get("/admin/status").await
+    }
+
+    /// Stop accepting executions, letting running ones finish
+    pub async fn drain(&self) -> Result<AdminStatus> {
+        self.client.post("/admin/drain", &serde_json::json!({})).await
+    }
+
+    pub async fn undrain(&self) -> Result<AdminStatus> {
+        self.client.delete("/admin/drain").await
+    }
+
+    /// Search the audit log of mutating requests
+    pub async fn audit(&self, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+        Ok(self.client.get_query::<AuditList>("/audit", query).await?.entries)
+    }
+}
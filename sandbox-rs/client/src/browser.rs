@@ -0,0 +1,677 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+// Requests name their page by `page_id`, an open tab, or by `url`, loaded into a
+// throwaway page; with neither they act on the throwaway page as is
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GotoRequest {
+    pub url: String,
+    /// `load`, `domcontentloaded`, or `networkidle`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Navigate this existing tab instead of a throwaway page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    /// Keep the page open as a new tab and return its id
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub new_tab: bool,
+    /// Route the new tab through its own proxy; requires `new_tab`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<Viewport>,
+    /// Extra HTTP headers sent with every request of the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headers: Option<HashMap<String, String>>,
+    /// How the tab answers alert/confirm/prompt dialogs; requires `page_id` or `new_tab`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialog: Option<DialogPolicy>,
+}
+
+impl GotoRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProxyConfig {
+    /// `http://host:port` or `socks5://host:port`, credentials optionally embedded
+    pub server: String,
+    /// Comma-separated hosts that bypass the proxy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bypass: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    pub device_scale_factor: f64,
+    pub mobile: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DialogAction {
+    #[default]
+    Accept,
+    Dismiss,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DialogPolicy {
+    pub action: DialogAction,
+    /// Text entered into `prompt()` dialogs when accepting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GotoResponse {
+    pub url: String,
+    pub title: String,
+    /// The new tab, with `new_tab`
+    pub page_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScreenshotRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// `png`, `jpeg`, or `webp`; `png` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// 0-100, for jpeg and webp
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub full_page: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clip: Option<ClipRect>,
+}
+
+/// Region of the page in CSS pixels
+#[derive(Debug, Clone, Serialize)]
+pub struct ClipRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub scale: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotResponse {
+    /// Base64 image
+    pub data: String,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvaluateRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub script: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EvaluateResponse {
+    result: Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ClickRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub selector: String,
+    /// Return only once the navigation the click starts has loaded
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub wait_for_navigation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClickResponse {
+    pub success: bool,
+    /// Where the page landed, with `wait_for_navigation`
+    pub url: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TypeRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub selector: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScrollRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Scroll this element into view; otherwise scroll the window by `x`/`y` pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScrollResponse {
+    pub scroll_x: f64,
+    pub scroll_y: f64,
+    pub scroll_height: f64,
+}
+
+/// For hover and focus
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ElementRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub selector: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PressRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Focus this element first; otherwise keys go to the focused element
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// e.g. `Enter`, `Control+A`, `Shift+Tab`
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UploadRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The `<input type=file>` to set
+    pub selector: String,
+    /// Workspace files to attach
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadResponse {
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub selector: String,
+    /// Attributes to read from each match
+    pub attributes: Vec<String>,
+    /// Matches to describe; 20 by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResponse {
+    pub exists: bool,
+    /// Every match, not only those described
+    pub count: usize,
+    pub matches: Vec<ElementMatch>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementMatch {
+    pub tag: String,
+    pub text: String,
+    pub attributes: HashMap<String, Option<String>>,
+    pub bounding_box: BoundingBox,
+    pub visible: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Choose `<select>` options or set a checkbox or radio; set exactly one of `value`,
+/// `label`, `index`, `values`, or `checked`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SelectRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub selector: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub values: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectResponse {
+    #[serde(default)]
+    pub selected: Vec<String>,
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FillRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub fields: Vec<FillField>,
+    /// Element to click once every field is filled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submit: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub wait_for_navigation: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_until: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// One form operation; set exactly one of `value`, `check`, or `select`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FillField {
+    pub selector: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub select: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillResponse {
+    pub filled: usize,
+    pub submitted: bool,
+    pub url: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContentRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Whether to return HTML too; true by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_html: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentResponse {
+    pub url: String,
+    pub canonical_url: Option<String>,
+    pub title: String,
+    /// Readable text of the main content
+    pub text: String,
+    pub html: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PdfRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `letter` by default, or `legal`, `tabloid`, `a3`, `a4`, `a5`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub landscape: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub print_background: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub margin: Option<PdfMargin>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_ranges: Option<String>,
+    /// Write the PDF to this workspace path instead of returning base64
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Page margins in inches
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PdfMargin {
+    pub top: Option<f64>,
+    pub bottom: Option<f64>,
+    pub left: Option<f64>,
+    pub right: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfResponse {
+    /// Base64 PDF, unless written to `path`
+    pub data: Option<String>,
+    pub path: Option<String>,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CaptureRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `mhtml` by default, or `html` with assets inlined
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureResponse {
+    pub path: String,
+    pub format: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarStartResponse {
+    pub har_id: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarStopResponse {
+    /// The HAR, unless written to a path
+    pub har: Option<Value>,
+    pub path: Option<String>,
+    pub entries: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordStartRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    /// Page to load once recording has started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// `webm` by default, or `mp4`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// JPEG quality of frames, 1-100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub every_nth_frame: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordStartResponse {
+    pub recording_id: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordStopResponse {
+    pub path: String,
+    pub frames: usize,
+    pub duration_secs: f64,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DownloadRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_id: Option<String>,
+    /// With `selector`, the page to load first; alone, the URL that triggers the download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Element to click to start the download
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadInfo {
+    pub download_id: String,
+    pub url: String,
+    pub filename: String,
+    /// Location in the workspace, once completed
+    pub path: Option<String>,
+    /// `in_progress`, `completed`, `canceled`, or `failed`
+    pub state: String,
+    pub received_bytes: u64,
+    pub total_bytes: u64,
+    pub started_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct DownloadList {
+    downloads: Vec<DownloadInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageInfo {
+    pub page_id: String,
+    pub url: String,
+    pub title: String,
+    pub created_at: String,
+    pub idle_secs: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PageList {
+    pages: Vec<PageInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsoleEntry {
+    /// `console`, `dialog`, or `blocked`
+    pub kind: String,
+    pub level: String,
+    pub text: String,
+    pub action: Option<String>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ConsoleList {
+    entries: Vec<ConsoleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserStatus {
+    pub running: bool,
+    pub version: Option<String>,
+    pub user_agent: Option<String>,
+    pub pid: Option<u32>,
+    pub uptime_secs: Option<f64>,
+    pub memory_bytes: Option<u64>,
+    pub open_targets: usize,
+    pub pages: Vec<PageInfo>,
+}
+
+/// `/browser/*`: the sandbox's Chromium
+pub struct Browser<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn browser(&self) -> Browser<'_> {
+        Browser { client: self }
+    }
+}
+
+impl Browser<'_> {
+    pub async fn goto(&self, request: &GotoRequest) -> Result<GotoResponse> {
+        self.client.post("/browser/goto", request).await
+    }
+
+    pub async fn screenshot(&self, request: &ScreenshotRequest) -> Result<ScreenshotResponse> {
+        self.client.post("/browser/screenshot", request).await
+    }
+
+    /// Evaluate `script` and return its result
+    pub async fn evaluate(&self, request: &EvaluateRequest) -> Result<Value> {
+        Ok(self.client.post::<EvaluateResponse>("/browser/evaluate", request).await?.result)
+    }
+
+    pub async fn click(&self, request: &ClickRequest) -> Result<ClickResponse> {
+        self.client.post("/browser/click", request).await
+    }
+
+    pub async fn type_text(&self, request: &TypeRequest) -> Result<Value> {
+        self.client.post("/browser/type", request).await
+    }
+
+    pub async fn scroll(&self, request: &ScrollRequest) -> Result<ScrollResponse> {
+        self.client.post("/browser/scroll", request).await
+    }
+
+    pub async fn hover(&self, request: &ElementRequest) -> Result<Value> {
+        self.client.post("/browser/hover", request).await
+    }
+
+    pub async fn focus(&self, request: &ElementRequest) -> Result<Value> {
+        self.client.post("/browser/focus", request).await
+    }
+
+    pub async fn press(&self, request: &PressRequest) -> Result<Value> {
+        self.client.post("/browser/press", request).await
+    }
+
+    pub async fn fill(&self, request: &FillRequest) -> Result<FillResponse> {
+        self.client.post("/browser/fill", request).await
+    }
+
+    pub async fn select(&self, request: &SelectRequest) -> Result<SelectResponse> {
+        self.client.post("/browser/select", request).await
+    }
+
+    pub async fn query(&self, request: &QueryRequest) -> Result<QueryResponse> {
+        self.client.post("/browser/query", request).await
+    }
+
+    /// Attach workspace files to a file input
+    pub async fn upload(&self, request: &UploadRequest) -> Result<UploadResponse> {
+        self.client.post("/browser/upload", request).await
+    }
+
+    pub async fn pdf(&self, request: &PdfRequest) -> Result<PdfResponse> {
+        self.client.post("/browser/pdf", request).await
+    }
+
+    /// The page's readable text, and optionally its HTML
+    pub async fn content(&self, request: &ContentRequest) -> Result<ContentResponse> {
+        self.client.post("/browser/content", request).await
+    }
+
+    /// Save the page as a single MHTML or HTML file in the workspace
+    pub async fn capture(&self, request: &CaptureRequest) -> Result<CaptureResponse> {
+        self.client.post("/browser/capture", request).await
+    }
+
+    /// Start recording a page's network traffic, loading `url` once recording
+    pub async fn har_start(&self, page_id: Option<&str>, url: Option<&str>) -> Result<HarStartResponse> {
+        self.client.post("/browser/har/start", &serde_json::json!({ "page_id": page_id, "url": url })).await
+    }
+
+    /// Stop a HAR recording, writing it to `path` or returning it inline
+    pub async fn har_stop(&self, har_id: &str, path: Option<&str>) -> Result<HarStopResponse> {
+        self.client.post("/browser/har/stop", &serde_json::json!({ "har_id": har_id, "path": path })).await
+    }
+
+    pub async fn record_start(&self, request: &RecordStartRequest) -> Result<RecordStartResponse> {
+        self.client.post("/browser/record/start", request).await
+    }
+
+    pub async fn record_stop(&self, recording_id: &str, path: Option<&str>) -> Result<RecordStopResponse> {
+        let body = serde_json::json!({ "recording_id": recording_id, "path": path });
+        self.client.post("/browser/record/stop", &body).await
+    }
+
+    /// Download a file into the workspace, waiting for it to finish
+    pub async fn download(&self, request: &DownloadRequest) -> Result<DownloadInfo> {
+        self.client.post("/browser/download", request).await
+    }
+
+    pub async fn downloads(&self) -> Result<Vec<DownloadInfo>> {
+        Ok(self.client.get::<DownloadList>("/browser/downloads").await?.downloads)
+    }
+
+    /// Open tabs
+    pub async fn pages(&self) -> Result<Vec<PageInfo>> {
+        Ok(self.client.get::<PageList>("/browser/pages").await?.pages)
+    }
+
+    /// Console messages, dialogs, and blocked requests of a tab
+    pub async fn console(&self, page_id: &str) -> Result<Vec<ConsoleEntry>> {
+        let path = format!("/browser/pages/{}/console", segment(page_id));
+        Ok(self.client.get::<ConsoleList>(&path).await?.entries)
+    }
+
+    pub async fn activate_page(&self, page_id: &str) -> Result<PageInfo> {
+        let path = format!("/browser/pages/{}/activate", segment(page_id));
+        self.client.post(&path, &serde_json::json!({})).await
+    }
+
+    pub async fn close_page(&self, page_id: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/browser/pages/{}", segment(page_id))).await.map(drop)
+    }
+
+    pub async fn status(&self) -> Result<BrowserStatus> {
+        self.client.get("/browser/status").await
+    }
+}
@@ -0,0 +1,290 @@
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, RETRY_AFTER};
+use reqwest::{Method, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::error::{ApiError, Error, Result};
+use crate::retry::RetryPolicy;
+
+/// API version this client speaks, sent as `X-API-Version`
+pub const API_VERSION: &str = "1";
+
+/// Handle to one sandbox; cheap to clone, and clones share connections
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+    session: Option<String>,
+    retry: RetryPolicy,
+}
+
+/// Settings for a [`Client`]
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    token: Option<String>,
+    session: Option<String>,
+    retry: RetryPolicy,
+    timeout: Option<Duration>,
+    connect_timeout: Duration,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    /// Send `Authorization: Bearer <token>`: a JWT when the server has `JWT_SECRET` or
+    /// `JWT_PUBLIC_KEY`, or a `/tee/auth` token when it has `TEE_AUTH`
+    pub fn bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Run every request in a session, sent as `X-Session-Id`
+    pub fn session(mut self, id: impl Into<String>) -> Self {
+        self.session = Some(id.into());
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Give up on a request after `timeout`, streaming responses included; unset by default,
+    /// since each execution endpoint takes its own `timeout`
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Send requests with `http`, e.g. one with custom TLS roots; `timeout` and
+    /// `connect_timeout` are then up to it
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let url = url::Url::parse(&self.base_url)?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(Error::Decode(format!("Base URL must be http or https: {}", self.base_url)));
+        }
+        // Paths are added per request, versioned or not
+        let base_url = url.as_str().trim_end_matches('/');
+        let base_url = base_url.strip_suffix("/v1").unwrap_or(base_url).to_string();
+
+        let http = match self.http {
+            Some(http) => http,
+            None => {
+                let mut builder = reqwest::Client::builder()
+                    .connect_timeout(self.connect_timeout)
+                    .user_agent(concat!("nixosandbox-client/", env!("CARGO_PKG_VERSION")));
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+        Ok(Client { http, base_url, token: self.token, session: self.session, retry: self.retry })
+    }
+}
+
+impl Client {
+    /// A client for the sandbox at `base_url`, e.g. `http://localhost:8080`
+    pub fn new(base_url: &str) -> Result<Self> {
+        Self::builder(base_url).build()
+    }
+
+    pub fn builder(base_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            base_url: base_url.into(),
+            token: None,
+            session: None,
+            retry: RetryPolicy::default(),
+            timeout: None,
+            connect_timeout: Duration::from_secs(10),
+            http: None,
+        }
+    }
+
+    /// This client, running its requests in session `id` instead
+    pub fn with_session(&self, id: impl Into<String>) -> Self {
+        Self { session: Some(id.into()), ..self.clone() }
+    }
+
+    /// This client, authenticating with `token` instead
+    pub fn with_bearer_token(&self, token: impl Into<String>) -> Self {
+        Self { token: Some(token.into()), ..self.clone() }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Full URL of an API path such as `/shell/exec`, under `/v1` unless the server keeps the
+    /// path unversioned
+    pub fn url(&self, path: &str) -> String {
+        let unversioned = matches!(path, "/health" | "/ready" | "/version" | "/openapi.json")
+            || path.starts_with("/.well-known/");
+        match unversioned {
+            true => format!("{}{}", self.base_url, path),
+            false => format!("{}/v1{}", self.base_url, path),
+        }
+    }
+
+    /// A request to `path` with this client's credentials and session, for endpoints and
+    /// options the typed methods do not cover; send it with [`Client::execute`]
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-version", HeaderValue::from_static(API_VERSION));
+        if let Some(token) = &self.token {
+            if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                headers.insert(AUTHORIZATION, value);
+            }
+        }
+        if let Some(session) = &self.session {
+            if let Ok(value) = HeaderValue::from_str(session) {
+                headers.insert("x-session-id", value);
+            }
+        }
+        self.http.request(method, self.url(path)).headers(headers)
+    }
+
+    /// Send `request`, retrying per the client's policy, and turn error statuses into
+    /// [`Error::Api`]
+    pub async fn execute(&self, request: RequestBuilder) -> Result<Response> {
+        let (http, request) = request.build_split();
+        let request = request?;
+        let method = request.method().clone();
+        let mut retry = 0;
+        loop {
+            // Streaming bodies cannot be sent twice
+            let Some(attempt) = request.try_clone() else {
+                return check(http.execute(request).await?).await;
+            };
+            let wait = match http.execute(attempt).await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.parse().ok());
+                    match self.retry.after_status(&method, response.status(), retry_after, retry) {
+                        Some(wait) => wait,
+                        None => return check(response).await,
+                    }
+                }
+                Err(e) => match self.retry.after_error(&method, &e, retry) {
+                    Some(wait) => wait,
+                    None => return Err(e.into()),
+                },
+            };
+            tokio::time::sleep(wait).await;
+            retry += 1;
+        }
+    }
+
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        json(self.execute(self.request(Method::GET, path)).await?).await
+    }
+
+    pub(crate) async fn get_query<T: DeserializeOwned>(&self, path: &str, query: &impl Serialize) -> Result<T> {
+        json(self.execute(self.request(Method::GET, path).query(query)).await?).await
+    }
+
+    pub(crate) async fn post<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        json(self.execute(self.request(Method::POST, path).json(body)).await?).await
+    }
+
+    pub(crate) async fn put<T: DeserializeOwned>(&self, path: &str, body: &impl Serialize) -> Result<T> {
+        json(self.execute(self.request(Method::PUT, path).json(body)).await?).await
+    }
+
+    pub(crate) async fn delete<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        json(self.execute(self.request(Method::DELETE, path)).await?).await
+    }
+}
+
+/// `response`, or the API error it carries
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let request_id = response
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let body = response.bytes().await?;
+    let mut error: ApiError = serde_json::from_slice(&body).unwrap_or_else(|_| ApiError {
+        status: 0,
+        code: String::new(),
+        message: String::from_utf8_lossy(&body).trim().to_string(),
+        request_id: None,
+        details: None,
+        retry_after: None,
+    });
+    error.status = status.as_u16();
+    if error.code.is_empty() {
+        error.code = status.canonical_reason().unwrap_or("ERROR").to_ascii_uppercase().replace(' ', "_");
+    }
+    if error.request_id.is_none() {
+        error.request_id = request_id;
+    }
+    Err(Error::Api(Box::new(error)))
+}
+
+async fn json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let body = response.bytes().await?;
+    serde_json::from_slice(&body).map_err(|e| Error::Decode(e.to_string()))
+}
+
+/// Escape one path segment, such as a skill or service name
+pub(crate) fn segment(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => escaped.push(byte as char),
+            _ => escaped.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls() {
+        let client = Client::new("http://localhost:8080/v1/").unwrap();
+        assert_eq!(client.base_url(), "http://localhost:8080");
+        assert_eq!(client.url("/shell/exec"), "http://localhost:8080/v1/shell/exec");
+        assert_eq!(client.url("/health"), "http://localhost:8080/health");
+        assert_eq!(client.url("/.well-known/jwks.json"), "http://localhost:8080/.well-known/jwks.json");
+        assert!(Client::new("ftp://localhost").is_err());
+    }
+
+    #[test]
+    fn test_headers() {
+        let client = Client::builder("http://localhost:8080").bearer_token("t0ken").build().unwrap();
+        let request = client.with_session("agent-1").request(Method::GET, "/file/list").build().unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer t0ken");
+        assert_eq!(request.headers()["x-session-id"], "agent-1");
+        assert_eq!(request.headers()["x-api-version"], "1");
+    }
+
+    #[test]
+    fn test_segment() {
+        assert_eq!(segment("my-skill_1.0"), "my-skill_1.0");
+        assert_eq!(segment("a b/c"), "a%20b%2Fc");
+    }
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::tee::Receipt;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExecuteRequest {
+    pub code: String,
+    /// `python`, `javascript`, `typescript`, `go`, `rust`, or `bash`
+    pub language: String,
+    /// Seconds before the program is killed; 30 by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest_quote: bool,
+}
+
+impl ExecuteRequest {
+    pub fn new(language: impl Into<String>, code: impl Into<String>) -> Self {
+        Self { language: language.into(), code: code.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecuteResponse {
+    /// What the program printed to stdout
+    pub output: String,
+    /// What the program printed to stderr
+    pub error: String,
+    pub exit_code: i32,
+    pub duration_ms: f64,
+    pub receipt: Option<Receipt>,
+}
+
+/// `/code/*`: snippets in an interpreter or compiler
+pub struct Code<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn code(&self) -> Code<'_> {
+        Code { client: self }
+    }
+}
+
+impl Code<'_> {
+    pub async fn execute(&self, request: &ExecuteRequest) -> Result<ExecuteResponse> {
+        self.client.post("/code/execute", request).await
+    }
+
+    /// Run `code` in `language` with the defaults
+    pub async fn run(&self, language: &str, code: &str) -> Result<ExecuteResponse> {
+        self.execute(&ExecuteRequest::new(language, code)).await
+    }
+}
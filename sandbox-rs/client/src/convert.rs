@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConvertRequest {
+    /// Workspace file to convert
+    pub input: String,
+    /// `text`, `csv`, `markdown`, `html`, or `pdf`
+    pub to: String,
+    /// Format of `input`, when its extension does not say
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Where to write the result; `input` with the new extension by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// Worksheet to export from an XLSX file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl ConvertRequest {
+    pub fn new(input: impl Into<String>, to: impl Into<String>) -> Self {
+        Self { input: input.into(), to: to.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertResponse {
+    pub output: String,
+    pub from: String,
+    pub to: String,
+    pub size: u64,
+    pub duration_ms: f64,
+}
+
+impl Client {
+    /// Convert a workspace document between PDF, DOCX, XLSX, HTML, and Markdown
+    pub async fn convert(&self, request: &ConvertRequest) -> Result<ConvertResponse> {
+        self.post("/convert", request).await
+    }
+}
@@ -0,0 +1,79 @@
+use bytes::Bytes;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueryRequest {
+    pub sql: String,
+    /// SQLite or DuckDB file in the workspace; without one, an in-memory DuckDB that
+    /// can read CSV, Parquet, and JSON files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// `sqlite` or `duckdb`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub engine: Option<String>,
+    /// Open the database read-only; true by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+impl QueryRequest {
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self { sql: sql.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryResponse {
+    pub engine: String,
+    pub columns: Vec<String>,
+    /// Values in `columns` order
+    pub rows: Vec<Vec<Value>>,
+    pub row_count: usize,
+    /// Whether rows were left out to stay within the limits
+    pub truncated: bool,
+    pub duration_ms: f64,
+}
+
+/// Rows as an Arrow IPC stream
+#[derive(Debug, Clone)]
+pub struct ArrowResponse {
+    pub data: Bytes,
+    pub row_count: usize,
+    pub truncated: bool,
+}
+
+/// `/data/*`: SQL over SQLite, DuckDB, and data files
+pub struct Data<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn data(&self) -> Data<'_> {
+        Data { client: self }
+    }
+}
+
+impl Data<'_> {
+    pub async fn query(&self, request: &QueryRequest) -> Result<QueryResponse> {
+        self.client.post("/data/query", request).await
+    }
+
+    pub async fn query_arrow(&self, request: &QueryRequest) -> Result<ArrowResponse> {
+        let mut body = serde_json::to_value(request).map_err(|e| crate::Error::Decode(e.to_string()))?;
+        body["format"] = "arrow".into();
+        let response = self.client.execute(self.client.request(Method::POST, "/data/query").json(&body)).await?;
+        let header = |name: &str| response.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from);
+        let row_count = header("x-row-count").and_then(|count| count.parse().ok()).unwrap_or(0);
+        let truncated = header("x-truncated").is_some_and(|truncated| truncated == "true");
+        Ok(ArrowResponse { data: response.bytes().await?, row_count, truncated })
+    }
+}
@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::Client;
+use crate::error::Result;
+
+/// Part of the screen, in pixels
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScreenshotRequest {
+    /// `png` by default, or `jpeg`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreenshotResponse {
+    /// Base64 image
+    pub data: String,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RecordStartRequest {
+    /// `webm` by default, or `mp4`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Frames per second; 10 by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub framerate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+    /// Stop after this many seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_duration: Option<u64>,
+    /// Stop once the file reaches this many bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordStartResponse {
+    pub recording_id: String,
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordStopResponse {
+    pub path: String,
+    pub duration_secs: f64,
+    pub size: u64,
+    /// Whether a duration or size limit ended the recording
+    pub truncated: bool,
+}
+
+#[derive(Deserialize)]
+struct Clipboard {
+    text: String,
+}
+
+/// `/display/*`: the virtual desktop
+pub struct Display<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn display(&self) -> Display<'_> {
+        Display { client: self }
+    }
+}
+
+impl Display<'_> {
+    pub async fn screenshot(&self, request: &ScreenshotRequest) -> Result<ScreenshotResponse> {
+        self.client.post("/display/screenshot", request).await
+    }
+
+    /// Click `button` (`left`, `middle`, or `right`) `clicks` times at a point
+    pub async fn click(&self, x: u32, y: u32, button: &str, clicks: u32) -> Result<()> {
+        let body = serde_json::json!({ "x": x, "y": y, "button": button, "clicks": clicks });
+        self.client.post::<Value>("/display/click", &body).await.map(drop)
+    }
+
+    /// Type `text` into the focused window, `delay_ms` between keystrokes
+    pub async fn type_text(&self, text: &str, delay_ms: u64) -> Result<()> {
+        let body = serde_json::json!({ "text": text, "delay_ms": delay_ms });
+        self.client.post::<Value>("/display/type", &body).await.map(drop)
+    }
+
+    /// Press key combinations in order, e.g. `ctrl+c`
+    pub async fn keys(&self, keys: &[&str]) -> Result<()> {
+        self.client.post::<Value>("/display/keys", &serde_json::json!({ "keys": keys })).await.map(drop)
+    }
+
+    /// Text of `selection`: `clipboard` or `primary`
+    pub async fn clipboard(&self, selection: &str) -> Result<String> {
+        let clipboard: Clipboard = self.client.get_query("/display/clipboard", &[("selection", selection)]).await?;
+        Ok(clipboard.text)
+    }
+
+    pub async fn set_clipboard(&self, selection: &str, text: &str) -> Result<()> {
+        let body = serde_json::json!({ "selection": selection, "text": text });
+        self.client.post::<Value>("/display/clipboard", &body).await.map(drop)
+    }
+
+    pub async fn record_start(&self, request: &RecordStartRequest) -> Result<RecordStartResponse> {
+        self.client.post("/display/record/start", request).await
+    }
+
+    pub async fn record_stop(&self, recording_id: &str) -> Result<RecordStopResponse> {
+        self.client.post("/display/record/stop", &serde_json::json!({ "recording_id": recording_id })).await
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub attr_path: Option<String>,
+    pub store_paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallResponse {
+    /// The requested packages and their store paths
+    pub packages: Vec<InstalledPackage>,
+    /// Requested packages that were not installed before
+    pub added: Vec<String>,
+    /// Bytes of store paths the added packages need
+    pub closure_size: u64,
+    pub duration_ms: f64,
+}
+
+#[derive(Deserialize)]
+struct PackageList {
+    packages: Vec<InstalledPackage>,
+}
+
+impl Client {
+    /// Install Nix packages by attribute name, e.g. `ripgrep`
+    pub async fn install_packages(&self, packages: &[&str], timeout: Option<u64>) -> Result<InstallResponse> {
+        let mut body = serde_json::json!({ "packages": packages });
+        if let Some(timeout) = timeout {
+            body["timeout"] = timeout.into();
+        }
+        self.post("/env/install", &body).await
+    }
+
+    pub async fn packages(&self) -> Result<Vec<InstalledPackage>> {
+        Ok(self.get::<PackageList>("/env/packages").await?.packages)
+    }
+}
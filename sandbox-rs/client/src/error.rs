@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// An error the API answered with, as `{"error", "code", "details"}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    /// HTTP status of the response
+    #[serde(skip)]
+    pub status: u16,
+    /// Machine-readable code, e.g. `EXEC_TIMEOUT` or `SKILL_NOT_FOUND`
+    #[serde(default)]
+    pub code: String,
+    #[serde(rename = "error", default)]
+    pub message: String,
+    /// Request ID to quote when reading the server's logs or audit log
+    pub request_id: Option<String>,
+    /// Code-specific details, e.g. `{"timeout": 30}` for `EXEC_TIMEOUT`
+    pub details: Option<Value>,
+    /// Seconds the server asked to wait before trying again
+    pub retry_after: Option<u64>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.status, self.code, self.message)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("{0}")]
+    Api(Box<ApiError>),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Invalid URL: {0}")]
+    Url(#[from] url::ParseError),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("Unexpected response: {0}")]
+    Decode(String),
+}
+
+impl Error {
+    /// The API's error, when the server answered with one
+    pub fn api(&self) -> Option<&ApiError> {
+        match self {
+            Error::Api(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The API's error code, e.g. `NOT_FOUND`
+    pub fn code(&self) -> Option<&str> {
+        self.api().map(|error| error.code.as_str())
+    }
+
+    /// The HTTP status the server answered with
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Api(error) => Some(error.status),
+            Error::Http(error) => error.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::skills::SkillSummary;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FactoryStep {
+    pub session_id: String,
+    pub step: String,
+    /// What to answer next
+    pub prompt: String,
+    pub done: bool,
+    /// The skill created, once `done`
+    pub skill: Option<SkillSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub triggers_factory: bool,
+    pub matched_phrases: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StartRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    initial_input: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ContinueRequest<'a> {
+    session_id: &'a str,
+    input: &'a str,
+}
+
+#[derive(Serialize)]
+struct CheckRequest<'a> {
+    input: &'a str,
+}
+
+/// `/factory/*`: building a skill step by step through a conversation
+pub struct Factory<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn factory(&self) -> Factory<'_> {
+        Factory { client: self }
+    }
+}
+
+impl Factory<'_> {
+    pub async fn start(&self, initial_input: Option<&str>) -> Result<FactoryStep> {
+        self.client.post("/factory/start", &StartRequest { initial_input }).await
+    }
+
+    /// Answer the prompt of session `session_id`
+    pub async fn reply(&self, session_id: &str, input: &str) -> Result<FactoryStep> {
+        self.client.post("/factory/continue", &ContinueRequest { session_id, input }).await
+    }
+
+    /// Whether `input` asks for a new skill
+    pub async fn check(&self, input: &str) -> Result<Trigger> {
+        self.client.post("/factory/check", &CheckRequest { input }).await
+    }
+}
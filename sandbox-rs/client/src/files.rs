@@ -0,0 +1,137 @@
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use reqwest::multipart::{Form, Part};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadResponse {
+    pub content: String,
+    pub size: u64,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WriteRequest {
+    pub path: String,
+    pub content: String,
+    /// Octal permissions, `644` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+impl WriteRequest {
+    pub fn new(path: impl Into<String>, content: impl Into<String>) -> Self {
+        Self { path: path.into(), content: content.into(), mode: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteResponse {
+    /// Absolute path of the file in the sandbox
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListResponse {
+    pub path: String,
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub path: String,
+    /// `file`, `directory`, or `symlink`
+    #[serde(rename = "type")]
+    pub file_type: String,
+    pub size: u64,
+    pub modified: String,
+}
+
+/// `/file/*`: the workspace's files
+pub struct Files<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn files(&self) -> Files<'_> {
+        Files { client: self }
+    }
+}
+
+impl Files<'_> {
+    /// Read a text file
+    pub async fn read(&self, path: &str) -> Result<ReadResponse> {
+        self.client.get_query("/file/read", &[("path", path)]).await
+    }
+
+    pub async fn write(&self, request: &WriteRequest) -> Result<WriteResponse> {
+        self.client.post("/file/write", request).await
+    }
+
+    pub async fn list(&self, path: &str, recursive: bool) -> Result<ListResponse> {
+        let recursive = recursive.to_string();
+        self.client.get_query("/file/list", &[("path", path), ("recursive", &recursive)]).await
+    }
+
+    /// Upload `data` to `path`, creating its directories
+    pub async fn upload(&self, path: &str, data: impl Into<Bytes>) -> Result<WriteResponse> {
+        let data: Bytes = data.into();
+        let form = Form::new().text("path", path.to_string()).part("file", Part::stream(data).file_name(file_name(path)));
+        self.send_upload(form).await
+    }
+
+    /// Upload the local file at `local` to `path` without reading it into memory first
+    pub async fn upload_file(&self, local: impl AsRef<Path>, path: &str) -> Result<WriteResponse> {
+        let file = tokio::fs::File::open(local).await?;
+        let size = file.metadata().await?.len();
+        let body = reqwest::Body::wrap_stream(tokio_util::io::ReaderStream::new(file));
+        let part = Part::stream_with_length(body, size).file_name(file_name(path));
+        self.send_upload(Form::new().text("path", path.to_string()).part("file", part)).await
+    }
+
+    async fn send_upload(&self, form: Form) -> Result<WriteResponse> {
+        let response = self.client.execute(self.client.request(Method::POST, "/file/upload").multipart(form)).await?;
+        response.json().await.map_err(Into::into)
+    }
+
+    /// Download a file whole
+    pub async fn download(&self, path: &str) -> Result<Bytes> {
+        Ok(self.download_response(path).await?.bytes().await?)
+    }
+
+    /// Download a file as a stream of chunks
+    pub async fn download_stream(&self, path: &str) -> Result<impl Stream<Item = Result<Bytes>>> {
+        let response = self.download_response(path).await?;
+        Ok(response.bytes_stream().map(|chunk| chunk.map_err(Into::into)))
+    }
+
+    /// Download a file to `local`, chunk by chunk, returning its size
+    pub async fn download_to(&self, path: &str, local: impl AsRef<Path>) -> Result<u64> {
+        let mut chunks = Box::pin(self.download_stream(path).await?);
+        let mut file = tokio::fs::File::create(local).await?;
+        let mut size = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            file.write_all(&chunk).await?;
+            size += chunk.len() as u64;
+        }
+        file.flush().await?;
+        Ok(size)
+    }
+
+    async fn download_response(&self, path: &str) -> Result<reqwest::Response> {
+        self.client.execute(self.client.request(Method::GET, "/file/download").query(&[("path", path)])).await
+    }
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit('/').next().filter(|name| !name.is_empty()).unwrap_or("upload").to_string()
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub status: String,
+    /// Seconds since the server started
+    pub uptime: f64,
+    pub services: Services,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Services {
+    pub display: bool,
+    pub browser: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ready {
+    /// Whether every required check passed
+    pub ready: bool,
+    pub checks: Vec<ReadyCheck>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadyCheck {
+    pub name: String,
+    pub ok: bool,
+    pub required: bool,
+    /// Version found, or why the check failed
+    pub detail: Option<String>,
+    pub duration_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version {
+    pub version: String,
+    pub git_commit: String,
+    pub build_time: Option<DateTime<Utc>>,
+    pub rustc: String,
+    pub features: Vec<String>,
+    pub api_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxInfo {
+    pub hostname: String,
+    pub workspace: String,
+    pub display: String,
+    pub cdp_url: String,
+    pub vnc_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub sampled_at: DateTime<Utc>,
+    pub cpu: CpuUsage,
+    pub memory: MemoryUsage,
+    pub disks: Vec<DiskUsage>,
+    pub open_fds: u64,
+    pub processes: u64,
+    pub browser_memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuUsage {
+    pub percent: Option<f64>,
+    pub cores: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub used_bytes: u64,
+    pub limit_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub path: String,
+    pub used_bytes: u64,
+    pub filesystem_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: String,
+    pub git_commit: String,
+    pub features: Vec<String>,
+    pub config_sha256: String,
+    pub tools: Vec<Tool>,
+    /// Absent where Nix is not installed
+    pub nix: Option<Closure>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub store_path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Closure {
+    pub roots: Vec<String>,
+    pub paths: usize,
+    pub sha256: String,
+}
+
+impl Client {
+    /// Liveness and service status
+    pub async fn health(&self) -> Result<Health> {
+        self.get("/health").await
+    }
+
+    /// Readiness checks; a sandbox that is not ready answers with its checks all the same
+    pub async fn ready(&self) -> Result<Ready> {
+        let response = self.request(reqwest::Method::GET, "/ready").send().await?;
+        response.json().await.map_err(Into::into)
+    }
+
+    pub async fn version(&self) -> Result<Version> {
+        self.get("/version").await
+    }
+
+    pub async fn sandbox_info(&self) -> Result<SandboxInfo> {
+        self.get("/sandbox/info").await
+    }
+
+    pub async fn sandbox_usage(&self) -> Result<Usage> {
+        self.get("/sandbox/usage").await
+    }
+
+    /// Tool versions, Nix closure, features, and config digest, to reproduce a run later
+    pub async fn sandbox_manifest(&self) -> Result<Manifest> {
+        self.get("/sandbox/manifest").await
+    }
+}
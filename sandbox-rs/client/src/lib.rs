@@ -0,0 +1,60 @@
+//! Async client for the NixOS sandbox API
+//!
+//! Each group of endpoints has a handle on [`Client`], e.g. [`Client::shell`] or
+//! [`Client::files`], with a typed method per endpoint. Requests go to `/v1` with
+//! `X-API-Version: 1`, carry the client's bearer token and session, and are retried
+//! per its [`RetryPolicy`]. Error responses become [`Error::Api`] with the server's
+//! code, details, and request ID.
+//!
+//! ```no_run
+//! # async fn run() -> nixosandbox_client::Result<()> {
+//! use futures::StreamExt;
+//! use nixosandbox_client::{shell::ExecRequest, shell::StreamEvent, Client};
+//!
+//! let client = Client::builder("http://localhost:8080").bearer_token("eyJhbGciOi...").build()?;
+//!
+//! let result = client.shell().run("uname -a").await?;
+//! println!("{}", result.stdout);
+//!
+//! client.files().upload_file("data.csv", "input/data.csv").await?;
+//!
+//! let mut events = Box::pin(client.shell().stream(&ExecRequest::new("make test")).await?);
+//! while let Some(event) = events.next().await {
+//!     match event? {
+//!         StreamEvent::Line(line) => println!("{}", line),
+//!         StreamEvent::Exit(code) => println!("exited with {}", code),
+//!         StreamEvent::Error(error) => eprintln!("{}", error),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod client;
+mod error;
+mod retry;
+
+pub mod admin;
+pub mod browser;
+pub mod code;
+pub mod convert;
+pub mod data;
+pub mod display;
+pub mod env;
+pub mod factory;
+pub mod files;
+pub mod health;
+pub mod net;
+pub mod notebook;
+pub mod secrets;
+pub mod services;
+pub mod sessions;
+pub mod shell;
+pub mod skills;
+pub mod sse;
+pub mod tee;
+pub mod webhooks;
+
+pub use client::{Client, ClientBuilder, API_VERSION};
+pub use error::{ApiError, Error, Result};
+pub use retry::RetryPolicy;
@@ -0,0 +1,94 @@
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::client::Client;
+use crate::error::Result;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FetchRequest {
+    pub url: String,
+    /// `GET` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Values may use `{{secret:NAME}}`
+    pub headers: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Redirects to follow; 0 returns the redirect itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_redirects: Option<usize>,
+    /// Largest response body in bytes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Save the body to this workspace path instead of returning it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub save_to: Option<String>,
+}
+
+impl FetchRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchResponse {
+    pub status: u16,
+    /// The URL answered, after redirects
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    /// The body, unless saved; text as is and anything else base64-encoded
+    pub body: Option<String>,
+    /// `utf8` or `base64`
+    pub encoding: Option<String>,
+    /// Where the body was saved
+    pub path: Option<String>,
+    pub size: u64,
+    pub redirects: Vec<String>,
+    pub duration_ms: f64,
+}
+
+/// A response whose body is still arriving
+pub struct FetchStream<S> {
+    /// Status of the fetched response, not of the sandbox's
+    pub status: u16,
+    pub url: Option<String>,
+    pub content_type: Option<String>,
+    pub body: S,
+}
+
+/// `/net/*`: HTTP requests made from the sandbox, under its egress policy
+pub struct Net<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn net(&self) -> Net<'_> {
+        Net { client: self }
+    }
+}
+
+impl Net<'_> {
+    pub async fn fetch(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        self.client.post("/net/fetch", request).await
+    }
+
+    /// Fetch, receiving the body as it arrives
+    pub async fn fetch_stream(&self, request: &FetchRequest) -> Result<FetchStream<impl Stream<Item = Result<Bytes>>>> {
+        let mut body = serde_json::to_value(request).map_err(|e| crate::Error::Decode(e.to_string()))?;
+        body["stream"] = true.into();
+        let response = self.client.execute(self.client.request(Method::POST, "/net/fetch").json(&body)).await?;
+        let header = |name: &str| response.headers().get(name).and_then(|value| value.to_str().ok()).map(String::from);
+        Ok(FetchStream {
+            status: header("x-fetch-status").and_then(|status| status.parse().ok()).unwrap_or(0),
+            url: header("x-fetch-url"),
+            content_type: header("content-type"),
+            body: response.bytes_stream().map(|chunk| chunk.map_err(Into::into)),
+        })
+    }
+}
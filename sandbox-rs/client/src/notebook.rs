@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellType {
+    #[default]
+    Code,
+    Markdown,
+}
+
+/// An output in nbformat's shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+pub enum Output {
+    /// Text the cell printed; `name` is `stdout` or `stderr`
+    Stream { name: String, text: String },
+    /// A rich output keyed by MIME type, e.g. `image/png` as base64
+    DisplayData { data: BTreeMap<String, Value>, metadata: Map<String, Value> },
+    Error { ename: String, evalue: String, traceback: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cell {
+    pub id: String,
+    pub cell_type: CellType,
+    pub source: String,
+    /// Overrides the notebook's language
+    pub language: Option<String>,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+    pub execution_count: Option<u32>,
+    pub duration_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notebook {
+    pub id: String,
+    pub title: String,
+    pub language: String,
+    pub cells: Vec<Cell>,
+    pub execution_count: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotebookSummary {
+    pub id: String,
+    pub title: String,
+    pub language: String,
+    pub cells: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct NotebookList {
+    notebooks: Vec<NotebookSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NewCell {
+    pub cell_type: CellType,
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+impl NewCell {
+    pub fn code(source: impl Into<String>) -> Self {
+        Self { cell_type: CellType::Code, source: source.into(), language: None }
+    }
+
+    pub fn markdown(source: impl Into<String>) -> Self {
+        Self { cell_type: CellType::Markdown, source: source.into(), language: None }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateNotebookRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Language of code cells; `python` by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    pub cells: Vec<NewCell>,
+}
+
+/// Fields left as `None` are kept
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateCellRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cell_type: Option<CellType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Move the cell to this position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunNotebookResponse {
+    pub notebook: Notebook,
+    /// Ids of the cells run, in order
+    pub executed: Vec<String>,
+    /// The cell that stopped the run
+    pub failed: Option<String>,
+}
+
+/// `/notebook/*`: notebooks of code and markdown cells
+pub struct Notebooks<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn notebooks(&self) -> Notebooks<'_> {
+        Notebooks { client: self }
+    }
+}
+
+impl Notebooks<'_> {
+    pub async fn create(&self, request: &CreateNotebookRequest) -> Result<Notebook> {
+        self.client.post("/notebook", request).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<NotebookSummary>> {
+        Ok(self.client.get::<NotebookList>("/notebook").await?.notebooks)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Notebook> {
+        self.client.get(&format!("/notebook/{}", segment(id))).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/notebook/{}", segment(id))).await.map(drop)
+    }
+
+    /// Add a cell at `index`, or at the end
+    pub async fn add_cell(&self, id: &str, cell: &NewCell, index: Option<usize>) -> Result<Cell> {
+        let mut body = serde_json::to_value(cell).map_err(|e| crate::Error::Decode(e.to_string()))?;
+        if let Some(index) = index {
+            body["index"] = index.into();
+        }
+        self.client.post(&format!("/notebook/{}/cells", segment(id)), &body).await
+    }
+
+    pub async fn update_cell(&self, id: &str, cell_id: &str, request: &UpdateCellRequest) -> Result<Cell> {
+        self.client.put(&cell_path(id, cell_id), request).await
+    }
+
+    pub async fn delete_cell(&self, id: &str, cell_id: &str) -> Result<()> {
+        self.client.delete::<Value>(&cell_path(id, cell_id)).await.map(drop)
+    }
+
+    /// Run one cell, replacing its outputs; `timeout` is 30 seconds by default
+    pub async fn run_cell(&self, id: &str, cell_id: &str, timeout: Option<u64>) -> Result<Cell> {
+        let mut body = serde_json::json!({});
+        if let Some(timeout) = timeout {
+            body["timeout"] = timeout.into();
+        }
+        self.client.post(&format!("{}/run", cell_path(id, cell_id)), &body).await
+    }
+
+    /// Run every code cell in order
+    pub async fn run(&self, id: &str, timeout: Option<u64>, stop_on_error: bool) -> Result<RunNotebookResponse> {
+        let mut body = serde_json::json!({ "stop_on_error": stop_on_error });
+        if let Some(timeout) = timeout {
+            body["timeout"] = timeout.into();
+        }
+        self.client.post(&format!("/notebook/{}/run", segment(id)), &body).await
+    }
+
+    /// The notebook as .ipynb JSON
+    pub async fn export(&self, id: &str) -> Result<Value> {
+        self.client.get(&format!("/notebook/{}/export", segment(id))).await
+    }
+}
+
+fn cell_path(id: &str, cell_id: &str) -> String {
+    format!("/notebook/{}/cells/{}", segment(id), segment(cell_id))
+}
@@ -0,0 +1,97 @@
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+/// When and how long to wait before sending a request again
+///
+/// Requests are retried when they could not be delivered, and when the server turned them away
+/// before running them: `429` from the rate limiter and `503` while draining or overloaded. Bad
+/// gateways, gateway timeouts, and timeouts are only retried for `GET`, `PUT`, and `DELETE`,
+/// since a `POST` may have run. Streaming uploads are never retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts after the first; zero disables retries
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each one after
+    pub initial_backoff: Duration,
+    /// Longest wait; a `Retry-After` asking for longer is returned as the error instead
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Send every request once
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Wait before retry number `retry`, counting from zero
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry);
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// Wait before retrying a response with `status`, if it is worth retrying
+    pub(crate) fn after_status(&self, method: &Method, status: StatusCode, retry_after: Option<u64>, retry: u32) -> Option<Duration> {
+        let retryable = match status {
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+            StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT => idempotent(method),
+            _ => false,
+        };
+        if !retryable || retry >= self.max_retries {
+            return None;
+        }
+        match retry_after.map(Duration::from_secs) {
+            Some(wait) if wait > self.max_backoff => None,
+            Some(wait) => Some(wait),
+            None => Some(self.backoff(retry)),
+        }
+    }
+
+    /// Wait before retrying a request that failed with `error`, if it is worth retrying
+    pub(crate) fn after_error(&self, method: &Method, error: &reqwest::Error, retry: u32) -> Option<Duration> {
+        let retryable = error.is_connect() || (error.is_timeout() && idempotent(method));
+        (retryable && retry < self.max_retries).then(|| self.backoff(retry))
+    }
+}
+
+fn idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(250));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(40), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_which_statuses_are_retried() {
+        let policy = RetryPolicy::default();
+        let after = |method: Method, status: u16, retry_after: Option<u64>| {
+            policy.after_status(&method, StatusCode::from_u16(status).unwrap(), retry_after, 0)
+        };
+        assert_eq!(after(Method::POST, 429, Some(2)), Some(Duration::from_secs(2)));
+        assert_eq!(after(Method::POST, 503, None), Some(Duration::from_millis(250)));
+        assert_eq!(after(Method::POST, 503, Some(60)), None);
+        assert_eq!(after(Method::POST, 502, None), None);
+        assert_eq!(after(Method::GET, 502, None), Some(Duration::from_millis(250)));
+        assert_eq!(after(Method::GET, 500, None), None);
+        assert_eq!(policy.after_status(&Method::GET, StatusCode::TOO_MANY_REQUESTS, None, 3), None);
+        assert_eq!(RetryPolicy::none().after_status(&Method::GET, StatusCode::TOO_MANY_REQUESTS, None, 0), None);
+    }
+}
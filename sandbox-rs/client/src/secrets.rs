@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+/// A stored secret; its value is never returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct SecretList {
+    secrets: Vec<SecretInfo>,
+}
+
+/// `/secrets/*`: values requests refer to as `{{secret:NAME}}`
+pub struct Secrets<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn secrets(&self) -> Secrets<'_> {
+        Secrets { client: self }
+    }
+}
+
+impl Secrets<'_> {
+    pub async fn create(&self, name: &str, secret: &str) -> Result<SecretInfo> {
+        self.client.post("/secrets", &serde_json::json!({ "name": name, "secret": secret })).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<SecretInfo>> {
+        Ok(self.client.get::<SecretList>("/secrets").await?.secrets)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<SecretInfo> {
+        self.client.get(&format!("/secrets/{}", segment(name))).await
+    }
+
+    pub async fn update(&self, name: &str, secret: &str) -> Result<SecretInfo> {
+        let path = format!("/secrets/{}", segment(name));
+        self.client.put(&path, &serde_json::json!({ "secret": secret })).await
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/secrets/{}", segment(name))).await.map(drop)
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeclareServiceRequest {
+    /// Lowercase letters, digits, and dashes; declaring a name again replaces it
+    pub name: String,
+    /// Port the process listens on at 127.0.0.1
+    pub port: u16,
+    /// Path to GET for health checks; a TCP connect when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health_path: Option<String>,
+    /// Forward `/proxy/{name}/` to the service
+    pub proxy: bool,
+}
+
+impl DeclareServiceRequest {
+    pub fn new(name: impl Into<String>, port: u16) -> Self {
+        Self { name: name.into(), port, health_path: None, proxy: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub port: u16,
+    pub health_path: Option<String>,
+    pub proxy: bool,
+    pub created_at: DateTime<Utc>,
+    /// Path of the service behind the proxy, e.g. `/v1/proxy/web/`
+    pub proxy_url: Option<String>,
+    pub health: Health,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub healthy: bool,
+    /// Status the health path answered with
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub latency_ms: f64,
+}
+
+#[derive(Deserialize)]
+struct ServiceList {
+    services: Vec<ServiceInfo>,
+}
+
+/// `/services/*`: long-running processes in the sandbox and their health
+pub struct Services<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn services(&self) -> Services<'_> {
+        Services { client: self }
+    }
+}
+
+impl Services<'_> {
+    pub async fn declare(&self, request: &DeclareServiceRequest) -> Result<ServiceInfo> {
+        self.client.post("/services", request).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<ServiceInfo>> {
+        Ok(self.client.get::<ServiceList>("/services").await?.services)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<ServiceInfo> {
+        self.client.get(&format!("/services/{}", segment(name))).await
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/services/{}", segment(name))).await.map(drop)
+    }
+
+    /// A request to `path` of service `name` through the sandbox's reverse proxy
+    pub fn proxy(&self, method: reqwest::Method, name: &str, path: &str) -> reqwest::RequestBuilder {
+        let path = format!("/proxy/{}/{}", segment(name), path.trim_start_matches('/'));
+        self.client.request(method, &path)
+    }
+}
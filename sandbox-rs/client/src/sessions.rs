@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateSessionRequest {
+    /// Generated when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_rpm: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_concurrent: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub workspace: String,
+    pub rate_limit_rpm: u32,
+    pub rate_limit_concurrent: usize,
+}
+
+#[derive(Deserialize)]
+struct SessionList {
+    sessions: Vec<SessionInfo>,
+}
+
+/// `/sessions/*`: tenants with their own workspace, skills, browser, and quotas; use
+/// one with [`Client::with_session`]
+pub struct Sessions<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn sessions(&self) -> Sessions<'_> {
+        Sessions { client: self }
+    }
+}
+
+impl Sessions<'_> {
+    pub async fn create(&self, request: &CreateSessionRequest) -> Result<SessionInfo> {
+        self.client.post("/sessions", request).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<SessionInfo>> {
+        Ok(self.client.get::<SessionList>("/sessions").await?.sessions)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<SessionInfo> {
+        self.client.get(&format!("/sessions/{}", segment(id))).await
+    }
+
+    /// Delete a session and its workspace
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/sessions/{}", segment(id))).await.map(drop)
+    }
+}
@@ -0,0 +1,119 @@
+use futures::stream::{Stream, StreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::client::Client;
+use crate::error::Result;
+use crate::sse;
+use crate::tee::Receipt;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ExecRequest {
+    pub command: String,
+    /// Working directory, relative to the workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// Seconds before the command is killed; 30 by default
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Extra environment; values may use `{{secret:NAME}}`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+    /// Return a TEE-signed receipt binding the request to its result
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest: bool,
+    /// Also include a quote in the receipt
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest_quote: bool,
+}
+
+impl ExecRequest {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self { command: command.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecResponse {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    pub duration_ms: f64,
+    pub receipt: Option<Receipt>,
+}
+
+/// What a streamed command reports
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// A line of stdout
+    Line(String),
+    /// The command exited; the last event
+    Exit(i32),
+    /// The command could not be run or waited for; the last event
+    Error(String),
+}
+
+impl StreamEvent {
+    fn parse(data: String) -> Self {
+        if let Some(code) = data.strip_prefix("[exit_code:").and_then(|rest| rest.strip_suffix(']')) {
+            if let Ok(code) = code.parse() {
+                return StreamEvent::Exit(code);
+            }
+        }
+        if let Some(error) = data.strip_prefix("[error:").and_then(|rest| rest.strip_suffix(']')) {
+            return StreamEvent::Error(error.to_string());
+        }
+        StreamEvent::Line(data)
+    }
+}
+
+/// `/shell/*`: shell commands
+pub struct Shell<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn shell(&self) -> Shell<'_> {
+        Shell { client: self }
+    }
+}
+
+impl Shell<'_> {
+    /// Run a command and wait for it
+    pub async fn exec(&self, request: &ExecRequest) -> Result<ExecResponse> {
+        self.client.post("/shell/exec", request).await
+    }
+
+    /// Run `command` with the defaults
+    pub async fn run(&self, command: &str) -> Result<ExecResponse> {
+        self.exec(&ExecRequest::new(command)).await
+    }
+
+    /// Run a command, receiving its stdout line by line as it is printed
+    pub async fn stream(&self, request: &ExecRequest) -> Result<impl Stream<Item = Result<StreamEvent>>> {
+        let request = self.client.request(Method::POST, "/shell/stream").json(request);
+        let response = self.client.execute(request).await?;
+        Ok(sse::events(response).map(|event| event.map(|event| StreamEvent::parse(event.data))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_event_parse() {
+        assert_eq!(StreamEvent::parse("[exit_code:3]".into()), StreamEvent::Exit(3));
+        assert_eq!(StreamEvent::parse("[exit_code:-1]".into()), StreamEvent::Exit(-1));
+        assert_eq!(StreamEvent::parse("[error:spawn failed]".into()), StreamEvent::Error("spawn failed".into()));
+        assert_eq!(StreamEvent::parse("[exit_code:x]".into()), StreamEvent::Line("[exit_code:x]".into()));
+        assert_eq!(StreamEvent::parse("hello".into()), StreamEvent::Line("hello".into()));
+    }
+
+    #[test]
+    fn test_exec_request_leaves_defaults_to_the_server() {
+        let body = serde_json::to_value(ExecRequest::new("ls")).unwrap();
+        assert_eq!(body, serde_json::json!({ "command": "ls" }));
+    }
+}
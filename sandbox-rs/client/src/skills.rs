@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+use crate::tee::Receipt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillSummary {
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SkillList {
+    skills: Vec<SkillSummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skill {
+    pub name: String,
+    pub description: String,
+    pub license: Option<String>,
+    pub compatibility: Option<String>,
+    pub metadata: Option<serde_json::Value>,
+    /// SKILL.md after its frontmatter
+    pub body: String,
+    /// File names under `scripts/`
+    pub scripts: Vec<String>,
+    pub references: Vec<String>,
+    pub assets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateSkillRequest {
+    pub name: String,
+    pub description: String,
+    pub body: String,
+    /// File name to contents
+    pub scripts: HashMap<String, String>,
+    pub references: HashMap<String, String>,
+    pub assets: HashMap<String, String>,
+}
+
+impl CreateSkillRequest {
+    pub fn new(name: impl Into<String>, description: impl Into<String>, body: impl Into<String>) -> Self {
+        Self { name: name.into(), description: description.into(), body: body.into(), ..Default::default() }
+    }
+}
+
+/// Fields left as `None` are kept
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UpdateSkillRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scripts: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub references: Option<HashMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunScriptRequest {
+    pub args: Vec<String>,
+    /// Extra environment; values may use `{{secret:NAME}}`
+    pub env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub attest_quote: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunScriptResponse {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    pub receipt: Option<Receipt>,
+}
+
+/// `/skills/*`: Agent Skills kept by the sandbox
+pub struct Skills<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn skills(&self) -> Skills<'_> {
+        Skills { client: self }
+    }
+}
+
+impl Skills<'_> {
+    pub async fn list(&self) -> Result<Vec<SkillSummary>> {
+        Ok(self.client.get::<SkillList>("/skills").await?.skills)
+    }
+
+    /// Skills whose name or description matches `query`
+    pub async fn search(&self, query: &str) -> Result<Vec<SkillSummary>> {
+        Ok(self.client.get_query::<SkillList>("/skills/search", &[("q", query)]).await?.skills)
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Skill> {
+        self.client.get(&format!("/skills/{}", segment(name))).await
+    }
+
+    pub async fn create(&self, request: &CreateSkillRequest) -> Result<Skill> {
+        self.client.post("/skills", request).await
+    }
+
+    pub async fn update(&self, name: &str, request: &UpdateSkillRequest) -> Result<Skill> {
+        self.client.put(&format!("/skills/{}", segment(name)), request).await
+    }
+
+    pub async fn delete(&self, name: &str) -> Result<DeleteResponse> {
+        self.client.delete(&format!("/skills/{}", segment(name))).await
+    }
+
+    /// Run one of a skill's scripts from its `scripts/` directory
+    pub async fn run_script(&self, name: &str, script: &str, request: &RunScriptRequest) -> Result<RunScriptResponse> {
+        let path = format!("/skills/{}/scripts/{}", segment(name), segment(script));
+        self.client.post(&path, request).await
+    }
+}
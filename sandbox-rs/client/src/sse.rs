@@ -0,0 +1,105 @@
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::error::{Error, Result};
+
+/// One server-sent event
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Event {
+    /// The `event:` field, absent for plain messages
+    pub event: Option<String>,
+    /// The `data:` lines, joined by newlines
+    pub data: String,
+    pub id: Option<String>,
+}
+
+/// Splits a `text/event-stream` body into events as bytes arrive
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    event: Option<String>,
+    data: Option<String>,
+    id: Option<String>,
+}
+
+impl Decoder {
+    /// Feed a chunk, returning the events it completed
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Event> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if let Some(data) = self.data.take() {
+                    events.push(Event { event: self.event.take(), data, id: self.id.take() });
+                }
+                self.event = None;
+                continue;
+            }
+            // Lines starting with a colon are comments, which keep-alives are sent as
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "data" => match &mut self.data {
+                    Some(data) => {
+                        data.push('\n');
+                        data.push_str(value);
+                    }
+                    None => self.data = Some(value.to_string()),
+                },
+                "event" => self.event = Some(value.to_string()),
+                "id" => self.id = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+/// The events of an event-stream response
+pub fn events(response: reqwest::Response) -> impl Stream<Item = Result<Event>> {
+    let chunks = response.bytes_stream();
+    let state = (Box::pin(chunks), Decoder::default());
+    stream::unfold(state, |(mut chunks, mut decoder)| async move {
+        let chunk: Option<std::result::Result<Bytes, reqwest::Error>> = chunks.next().await;
+        let items: Vec<Result<Event>> = match chunk {
+            Some(Ok(chunk)) => decoder.push(&chunk).into_iter().map(Ok).collect(),
+            Some(Err(e)) => vec![Err(Error::Http(e))],
+            None => return None,
+        };
+        Some((stream::iter(items), (chunks, decoder)))
+    })
+    .flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_across_chunks() {
+        let mut decoder = Decoder::default();
+        assert!(decoder.push(b"data: hel").is_empty());
+        assert_eq!(decoder.push(b"lo\r\n\r\n"), [Event { data: "hello".into(), ..Default::default() }]);
+
+        let events = decoder.push(b": keep-alive\n\nevent: progress\nid: 7\ndata: a\ndata:b\n\ndata: [exit_code:0]\n\n");
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event.as_deref(), Some("progress"));
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+        assert_eq!(events[0].data, "a\nb");
+        assert_eq!(events[1].data, "[exit_code:0]");
+        assert_eq!(events[1].event, None);
+    }
+
+    #[test]
+    fn test_empty_data_is_an_event() {
+        let mut decoder = Decoder::default();
+        assert_eq!(decoder.push(b"data:\n\n"), [Event::default()]);
+        assert!(decoder.push(b"event: ignored\n\n").is_empty());
+    }
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+/// TEE-signed proof that a request produced a response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Receipt {
+    /// `shell.exec`, `code.execute`, or `skills.script`
+    pub kind: String,
+    pub request_sha256: String,
+    pub response_sha256: String,
+    pub timestamp: String,
+    /// SHA-256 of the canonical JSON of `{kind, request_sha256, response_sha256, timestamp}`
+    pub digest: String,
+    /// dstack signature response over `digest`
+    pub signature: Value,
+    pub quote: Option<Value>,
+}
+
+/// How binary-safe content travels in JSON
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum Encoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyInfo {
+    pub name: String,
+    pub path: String,
+    pub purpose: String,
+    pub algorithm: String,
+    /// Hex Ed25519 public key
+    pub public_key: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct KeyList {
+    keys: Vec<KeyInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLog {
+    pub events: Vec<Value>,
+    /// RTMR0-3 recomputed from `events`
+    pub replayed_rtmrs: Vec<String>,
+    /// RTMR0-3 as reported by a fresh quote
+    pub quote_rtmrs: Vec<String>,
+    pub matches: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttestedPayload {
+    pub payload: Value,
+    pub payload_sha256: String,
+    pub quote: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealResponse {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnsealResponse {
+    pub content: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretEnv {
+    /// Hex X25519 public key to encrypt secrets to
+    pub public_key: String,
+    /// Names of injected variables; values are never returned
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub nonce: String,
+    /// Seconds the nonce can be answered for
+    pub expires_in: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Proof {
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub token: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TokenRequest {
+    pub audience: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// Seconds the token is valid for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<u64>,
+    pub claims: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedOutput {
+    pub skill: String,
+    pub skill_sha256: String,
+    pub output_sha256: String,
+    pub algorithm: String,
+    pub public_key: String,
+    /// Signature over the canonical JSON of `{output_sha256, skill, skill_sha256}`
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct PublicKey {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct Ciphertext {
+    ciphertext: String,
+}
+
+#[derive(Deserialize)]
+struct Plaintext {
+    plaintext: String,
+}
+
+/// `/tee/*` and the other endpoints only a sandbox in a TEE serves
+pub struct Tee<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn tee(&self) -> Tee<'_> {
+        Tee { client: self }
+    }
+}
+
+impl Tee<'_> {
+    /// CVM metadata and the sandbox's startup measurements
+    pub async fn info(&self) -> Result<Value> {
+        self.client.get("/tee/info").await
+    }
+
+    /// A TDX quote over up to 64 bytes of `report_data`
+    pub async fn quote(&self, report_data: &[u8]) -> Result<Value> {
+        self.client.post("/tee/quote", &serde_json::json!({ "report_data": hex(report_data) })).await
+    }
+
+    pub async fn derive_key(&self, path: Option<&str>, purpose: Option<&str>) -> Result<Value> {
+        self.client.post("/tee/derive-key", &serde_json::json!({ "path": path, "purpose": purpose })).await
+    }
+
+    /// Sign `data` with the app key under `algorithm`, e.g. `secp256k1`
+    pub async fn sign(&self, algorithm: &str, data: &[u8]) -> Result<Value> {
+        self.client.post("/tee/sign", &serde_json::json!({ "algorithm": algorithm, "data": hex(data) })).await
+    }
+
+    /// Sign `data` with a key registered with [`Tee::register_key`]
+    pub async fn sign_with_key(&self, key: &str, data: &[u8]) -> Result<Value> {
+        self.client.post("/tee/sign", &serde_json::json!({ "key": key, "data": hex(data) })).await
+    }
+
+    pub async fn verify(&self, algorithm: &str, data: &str, signature: &str, public_key: &str) -> Result<Value> {
+        let body = serde_json::json!({
+            "algorithm": algorithm,
+            "data": data,
+            "signature": signature,
+            "public_key": public_key,
+        });
+        self.client.post("/tee/verify", &body).await
+    }
+
+    /// Register a named Ed25519 key derived at a fixed `path`
+    pub async fn register_key(&self, name: &str, path: &str, purpose: Option<&str>) -> Result<KeyInfo> {
+        let body = serde_json::json!({ "name": name, "path": path, "purpose": purpose });
+        self.client.post("/tee/keys", &body).await
+    }
+
+    pub async fn keys(&self) -> Result<Vec<KeyInfo>> {
+        Ok(self.client.get::<KeyList>("/tee/keys").await?.keys)
+    }
+
+    pub async fn remove_key(&self, name: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/tee/keys/{}", segment(name))).await.map(drop)
+    }
+
+    /// Extend RTMR3 with a named event
+    pub async fn emit_event(&self, event: &str, payload: &str) -> Result<Value> {
+        self.client.post("/tee/emit-event", &serde_json::json!({ "event": event, "payload": payload })).await
+    }
+
+    /// A quote whose report_data starts with the SHA-256 of `payload`'s canonical JSON
+    pub async fn attest_payload(&self, payload: &Value) -> Result<AttestedPayload> {
+        self.client.post("/tee/attest-payload", &serde_json::json!({ "payload": payload })).await
+    }
+
+    pub async fn event_log(&self) -> Result<EventLog> {
+        self.client.get("/tee/event-log").await
+    }
+
+    /// Encrypt `content` with a TEE-derived key and write it to `path`
+    pub async fn seal(&self, path: &str, content: &str, encoding: Encoding) -> Result<SealResponse> {
+        let body = serde_json::json!({ "path": path, "content": content, "encoding": encoding });
+        self.client.post("/file/seal", &body).await
+    }
+
+    /// Decrypt a sealed file; the plaintext is returned, never written
+    pub async fn unseal(&self, path: &str, encoding: Encoding) -> Result<UnsealResponse> {
+        self.client.post("/file/unseal", &serde_json::json!({ "path": path, "encoding": encoding })).await
+    }
+
+    pub async fn secret_env(&self) -> Result<SecretEnv> {
+        self.client.get("/tee/env").await
+    }
+
+    /// Inject secrets into exec, code, and skill runs; each value is base64 ECIES
+    /// ciphertext encrypted to [`SecretEnv::public_key`]
+    pub async fn set_secret_env(&self, secrets: &HashMap<String, String>) -> Result<SecretEnv> {
+        self.client.post("/tee/env", &serde_json::json!({ "secrets": secrets })).await
+    }
+
+    pub async fn remove_secret_env(&self, name: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/tee/env/{}", segment(name))).await.map(drop)
+    }
+
+    pub async fn auth_challenge(&self) -> Result<Challenge> {
+        self.client.post("/tee/auth/challenge", &serde_json::json!({})).await
+    }
+
+    /// Sign another sandbox's challenge with this app's auth key
+    pub async fn auth_prove(&self, nonce: &str) -> Result<Proof> {
+        self.client.post("/tee/auth/prove", &serde_json::json!({ "nonce": nonce })).await
+    }
+
+    /// Exchange a signed challenge for a bearer token; see [`Client::with_bearer_token`]
+    pub async fn authenticate(&self, nonce: &str, proof: &Proof) -> Result<Token> {
+        let body = serde_json::json!({
+            "nonce": nonce,
+            "public_key": proof.public_key,
+            "signature": proof.signature,
+        });
+        self.client.post("/tee/auth", &body).await
+    }
+
+    /// A short-lived JWT signed by the TEE-derived token key
+    pub async fn issue_token(&self, request: &TokenRequest) -> Result<Token> {
+        self.client.post("/tee/token", request).await
+    }
+
+    pub async fn jwks(&self) -> Result<Jwks> {
+        self.client.get("/.well-known/jwks.json").await
+    }
+
+    pub async fn openid_configuration(&self) -> Result<Value> {
+        self.client.get("/.well-known/openid-configuration").await
+    }
+
+    /// Hex X25519 key that only this enclave can decrypt for
+    pub async fn encryption_key(&self) -> Result<String> {
+        Ok(self.client.get::<PublicKey>("/tee/encryption-key").await?.public_key)
+    }
+
+    /// Encrypt `plaintext` to a client's hex X25519 `public_key`, returning base64 ciphertext
+    pub async fn encrypt(&self, public_key: &str, plaintext: &str, encoding: Encoding) -> Result<String> {
+        let body = serde_json::json!({ "public_key": public_key, "plaintext": plaintext, "encoding": encoding });
+        Ok(self.client.post::<Ciphertext>("/tee/encrypt", &body).await?.ciphertext)
+    }
+
+    /// Decrypt base64 ciphertext sent to [`Tee::encryption_key`]
+    pub async fn decrypt(&self, ciphertext: &str, encoding: Encoding) -> Result<String> {
+        let body = serde_json::json!({ "ciphertext": ciphertext, "encoding": encoding });
+        Ok(self.client.post::<Plaintext>("/tee/decrypt", &body).await?.plaintext)
+    }
+
+    /// Sign an artifact, a workspace file or `content`, with skill `name`'s own derived key
+    pub async fn sign_skill_output(&self, name: &str, path: Option<&str>, content: Option<&str>, encoding: Encoding) -> Result<SignedOutput> {
+        let body = serde_json::json!({ "path": path, "content": content, "encoding": encoding });
+        self.client.post(&format!("/skills/{}/sign-output", segment(name)), &body).await
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(serde_json::to_value(Encoding::Utf8).unwrap(), "utf-8");
+        assert_eq!(serde_json::to_value(Encoding::Base64).unwrap(), "base64");
+        assert_eq!(hex(&[0, 0xab, 0x10]), "00ab10");
+    }
+}
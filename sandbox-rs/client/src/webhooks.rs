@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::{segment, Client};
+use crate::error::Result;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RegisterWebhookRequest {
+    /// URL that receives a POST per event
+    pub url: String,
+    /// Key for the HMAC-SHA256 `X-Webhook-Signature` of each delivery
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Events to send; all of them when empty
+    pub events: Vec<String>,
+    /// Path whose changes are sent as `file.changed`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watch: Option<String>,
+}
+
+impl RegisterWebhookRequest {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub watch: Option<String>,
+    pub signed: bool,
+    pub created_at: DateTime<Utc>,
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct WebhookList {
+    webhooks: Vec<WebhookInfo>,
+}
+
+/// `/webhooks/*`: POSTs to other services when jobs finish or files change
+pub struct Webhooks<'a> {
+    client: &'a Client,
+}
+
+impl Client {
+    pub fn webhooks(&self) -> Webhooks<'_> {
+        Webhooks { client: self }
+    }
+}
+
+impl Webhooks<'_> {
+    pub async fn register(&self, request: &RegisterWebhookRequest) -> Result<WebhookInfo> {
+        self.client.post("/webhooks", request).await
+    }
+
+    pub async fn list(&self) -> Result<Vec<WebhookInfo>> {
+        Ok(self.client.get::<WebhookList>("/webhooks").await?.webhooks)
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        self.client.delete::<Value>(&format!("/webhooks/{}", segment(id))).await.map(drop)
+    }
+
+    /// Send a `ping` event
+    pub async fn test(&self, id: &str) -> Result<()> {
+        let path = format!("/webhooks/{}/test", segment(id));
+        self.client.post::<Value>(&path, &serde_json::json!({})).await.map(drop)
+    }
+}
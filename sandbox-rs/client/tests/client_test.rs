@@ -0,0 +1,98 @@
+use futures::StreamExt;
+use nixosandbox_client::shell::{ExecRequest, StreamEvent};
+use nixosandbox_client::skills::CreateSkillRequest;
+use nixosandbox_client::{Client, Error};
+use std::time::Duration;
+use tokio::time::sleep;
+
+async fn client() -> Client {
+    let base_url = std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+    let client = Client::new(&base_url).unwrap();
+    for _ in 0..50 {
+        if client.health().await.is_ok() {
+            return client;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Server did not start in time");
+}
+
+#[tokio::test]
+async fn test_health_and_version() {
+    let client = client().await;
+    assert_eq!(client.health().await.unwrap().status, "healthy");
+    assert_eq!(client.version().await.unwrap().api_version, "1");
+}
+
+#[tokio::test]
+async fn test_exec_and_stream() {
+    let client = client().await;
+    let result = client.shell().run("echo hello").await.unwrap();
+    assert_eq!(result.stdout.trim(), "hello");
+    assert_eq!(result.exit_code, 0);
+
+    let events = client.shell().stream(&ExecRequest::new("echo one; echo two; exit 3")).await.unwrap();
+    let events: Vec<_> = events.map(|event| event.unwrap()).collect().await;
+    assert_eq!(
+        events,
+        vec![StreamEvent::Line("one".into()), StreamEvent::Line("two".into()), StreamEvent::Exit(3)]
+    );
+}
+
+#[tokio::test]
+async fn test_code_execute() {
+    let client = client().await;
+    let result = client.code().run("python", "print(6 * 7)").await.unwrap();
+    assert_eq!(result.output.trim(), "42");
+}
+
+#[tokio::test]
+async fn test_upload_and_download() {
+    let client = client().await;
+    let dir = tempfile::tempdir().unwrap();
+    let local = dir.path().join("data.bin");
+    let data: Vec<u8> = (0..=255u8).cycle().take(256 * 1024).collect();
+    tokio::fs::write(&local, &data).await.unwrap();
+
+    let uploaded = client.files().upload_file(&local, "client-test/data.bin").await.unwrap();
+    assert_eq!(uploaded.size, data.len() as u64);
+    assert_eq!(client.files().download("client-test/data.bin").await.unwrap(), data);
+
+    let copy = dir.path().join("copy.bin");
+    let size = client.files().download_to("client-test/data.bin", &copy).await.unwrap();
+    assert_eq!(size, data.len() as u64);
+    assert_eq!(tokio::fs::read(&copy).await.unwrap(), data);
+}
+
+#[tokio::test]
+async fn test_skills_round_trip() {
+    let client = client().await;
+    let _ = client.skills().delete("client-test-skill").await;
+
+    let mut request = CreateSkillRequest::new("client-test-skill", "Created by the client test", "# Test");
+    request.scripts.insert("hello.sh".into(), "echo hi \"$1\"".into());
+    let skill = client.skills().create(&request).await.unwrap();
+    assert_eq!(skill.scripts, vec!["hello.sh"]);
+
+    let run = client
+        .skills()
+        .run_script("client-test-skill", "hello.sh", &nixosandbox_client::skills::RunScriptRequest {
+            args: vec!["there".into()],
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(run.stdout.trim(), "hi there");
+
+    client.skills().delete("client-test-skill").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_api_errors() {
+    let client = client().await;
+    let error = client.skills().get("no-such-skill").await.unwrap_err();
+    let Error::Api(error) = error else { panic!("expected an API error, got {:?}", error) };
+    assert_eq!(error.status, 404);
+    assert!(!error.code.is_empty());
+    assert!(error.request_id.is_some());
+}
@@ -0,0 +1,374 @@
+use anyhow::{anyhow, bail, Context};
+use axum::{
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use ring::{hmac, signature};
+use rustls::pki_types::{pem::PemObject, SubjectPublicKeyInfoDer};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::state::AppState;
+use crate::versioning;
+
+/// Clock skew tolerated when checking `exp` and `nbf`
+const LEEWAY_SECS: i64 = 30;
+
+/// DER of the rsaEncryption algorithm identifier's OID, 1.2.840.113549.1.1.1
+const RSA_ENCRYPTION_OID: &[u8] = &[0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+/// The claims that are checked; everything else in the token is ignored
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    #[serde(default)]
+    exp: Option<i64>,
+    #[serde(default)]
+    nbf: Option<i64>,
+    #[serde(default)]
+    iss: Option<String>,
+    #[serde(default)]
+    aud: Option<Audience>,
+    /// OAuth-style space-separated string, or a list
+    #[serde(default)]
+    scope: Option<Scopes>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Scopes {
+    Spaced(String),
+    List(Vec<String>),
+}
+
+impl Claims {
+    fn scopes(&self) -> Vec<&str> {
+        match &self.scope {
+            Some(Scopes::Spaced(scopes)) => scopes.split_whitespace().collect(),
+            Some(Scopes::List(scopes)) => scopes.iter().map(String::as_str).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether a scope grants `required`: exactly, through `<group>:*`, or through `*`
+    pub fn allows(&self, required: &str) -> bool {
+        let group = required.split_once(':').map_or(required, |(group, _)| group);
+        self.scopes().iter().any(|scope| {
+            *scope == "*" || *scope == required || scope.strip_suffix(":*") == Some(group)
+        })
+    }
+}
+
+/// Bearer JWTs signed with an HS256 secret or an RS256 key, carrying scopes per route group
+pub struct JwtAuth {
+    hs256: Option<hmac::Key>,
+    /// PKCS#1 RSAPublicKey, as ring wants it
+    rs256: Option<Vec<u8>>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtAuth {
+    /// JWT authentication as configured, or `None` when neither key is set
+    pub fn from_config(config: &Config) -> anyhow::Result<Option<Self>> {
+        if config.jwt_secret.is_none() && config.jwt_public_key.is_none() {
+            return Ok(None);
+        }
+        let rs256 = config
+            .jwt_public_key
+            .as_deref()
+            .map(|path| load_rsa_public_key(Path::new(path)).with_context(|| format!("jwt_public_key {}", path)))
+            .transpose()?;
+        Ok(Some(Self {
+            hs256: config.jwt_secret.as_ref().map(|secret| hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())),
+            rs256,
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+        }))
+    }
+
+    /// Check a token's signature, lifetime, issuer, and audience, and decode its claims
+    pub fn verify(&self, token: &str) -> anyhow::Result<Claims> {
+        let mut parts = token.split('.');
+        let (Some(header), Some(claims), Some(sig), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("malformed token");
+        };
+
+        #[derive(Deserialize)]
+        struct Header {
+            alg: String,
+        }
+        let decoded: Header = serde_json::from_slice(&BASE64URL.decode(header).context("malformed header")?)
+            .context("malformed header")?;
+        let sig = BASE64URL.decode(sig).context("malformed signature")?;
+        let signing_input = &token[..header.len() + 1 + claims.len()];
+
+        // Each algorithm is only accepted with its own configured key, so a token cannot
+        // pass off an RS256 public key as an HS256 secret
+        match (decoded.alg.as_str(), &self.hs256, &self.rs256) {
+            ("HS256", Some(key), _) => {
+                hmac::verify(key, signing_input.as_bytes(), &sig).map_err(|_| anyhow!("invalid signature"))?
+            }
+            ("RS256", _, Some(key)) => signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, key)
+                .verify(signing_input.as_bytes(), &sig)
+                .map_err(|_| anyhow!("invalid signature"))?,
+            (alg, _, _) => bail!("unsupported algorithm {}", alg),
+        }
+
+        let claims: Claims = serde_json::from_slice(&BASE64URL.decode(claims).context("malformed claims")?)
+            .context("malformed claims")?;
+        let now = chrono::Utc::now().timestamp();
+        if claims.exp.is_some_and(|exp| exp + LEEWAY_SECS <= now) {
+            bail!("token expired");
+        }
+        if claims.nbf.is_some_and(|nbf| nbf - LEEWAY_SECS > now) {
+            bail!("token not yet valid");
+        }
+        if let Some(issuer) = &self.issuer {
+            if claims.iss.as_ref() != Some(issuer) {
+                bail!("wrong issuer");
+            }
+        }
+        if let Some(audience) = &self.audience {
+            let matches = match &claims.aud {
+                Some(Audience::One(aud)) => aud == audience,
+                Some(Audience::Many(auds)) => auds.contains(audience),
+                None => false,
+            };
+            if !matches {
+                bail!("wrong audience");
+            }
+        }
+        Ok(claims)
+    }
+}
+
+/// The RSAPublicKey inside a PEM `PUBLIC KEY` (SubjectPublicKeyInfo) file
+fn load_rsa_public_key(path: &Path) -> anyhow::Result<Vec<u8>> {
+    let spki = SubjectPublicKeyInfoDer::from_pem_file(path).map_err(|e| anyhow!("{}", e))?;
+    let (spki, _) = der_sequence(spki.as_ref())?;
+    let (algorithm, rest) = der_sequence(spki)?;
+    if !algorithm.starts_with(RSA_ENCRYPTION_OID) {
+        bail!("not an RSA public key");
+    }
+    let (bits, _) = der_element(rest, 0x03)?;
+    match bits.split_first() {
+        Some((0, key)) => Ok(key.to_vec()),
+        _ => bail!("malformed public key"),
+    }
+}
+
+fn der_sequence(input: &[u8]) -> anyhow::Result<(&[u8], &[u8])> {
+    der_element(input, 0x30)
+}
+
+/// The contents of the DER element with `tag` at the start of `input`, and what follows it
+fn der_element(input: &[u8], tag: u8) -> anyhow::Result<(&[u8], &[u8])> {
+    let malformed = || anyhow!("malformed public key");
+    let (&first, rest) = input.split_first().ok_or_else(malformed)?;
+    if first != tag {
+        return Err(malformed());
+    }
+    let (&len, mut rest) = rest.split_first().ok_or_else(malformed)?;
+    let len = if len < 0x80 {
+        len as usize
+    } else {
+        let bytes = (len & 0x7f) as usize;
+        if bytes == 0 || bytes > 4 || rest.len() < bytes {
+            return Err(malformed());
+        }
+        let (len, after) = rest.split_at(bytes);
+        rest = after;
+        len.iter().fold(0, |acc, b| (acc << 8) | *b as usize)
+    };
+    if rest.len() < len {
+        return Err(malformed());
+    }
+    Ok(rest.split_at(len))
+}
+
+/// Scope a `/ws` connection's token needs to watch files
+pub const WATCH_SCOPE: &str = "files:read";
+
+/// The scope a request needs: `shell:exec`, `code:exec` (also for running notebooks),
+/// `skills:exec`, `env:exec`, `data:exec`, and `convert:exec` for running things, otherwise
+/// `<group>:read` for GET and HEAD and `<group>:write` for the rest.
+/// `None` for probes, API docs, discovery documents, and the `/tee/auth` handshake, which
+/// stay open; an empty scope for `/ws`, whose calls are checked one by one and whose
+/// watches need [`WATCH_SCOPE`].
+pub fn required_scope(method: &Method, path: &str) -> Option<String> {
+    let path = versioning::unprefixed(path);
+    if matches!(
+        path,
+        "/health" | "/ready" | "/version" | "/openapi.json" | "/tee/auth" | "/tee/auth/challenge"
+    ) || path.starts_with("/swagger-ui")
+        || path.starts_with("/.well-known/")
+    {
+        return None;
+    }
+    if path == "/ws" {
+        return Some(String::new());
+    }
+    if let Some(call) = path.strip_prefix("/sandbox.v1.") {
+        let scope = match call.split_once('/').unwrap_or((call, "")) {
+            ("Shell", _) => "shell:exec",
+            ("Code", _) => "code:exec",
+            ("Files", "Write") => "files:write",
+            ("Files", _) => "files:read",
+            ("Skills", "RunScript") => "skills:exec",
+            ("Skills", _) => "skills:read",
+            (service, _) => return Some(format!("{}:write", service.to_lowercase())),
+        };
+        return Some(scope.to_string());
+    }
+
+    let mut segments = path.trim_start_matches('/').split('/');
+    let group = match segments.next().unwrap_or_default() {
+        "shell" => return Some("shell:exec".into()),
+        "code" => return Some("code:exec".into()),
+        "skills" if segments.nth(1) == Some("scripts") => return Some("skills:exec".into()),
+        // Running cells executes code, the same as /code/*
+        "notebook" if *method == Method::POST && path.ends_with("/run") => return Some("code:exec".into()),
+        // Runs `nix profile install`
+        "env" if path == "/env/install" => return Some("env:exec".into()),
+        // Spawns the query engine's process
+        "data" if path == "/data/query" => return Some("data:exec".into()),
+        // Runs converters such as pandoc and pdftotext
+        "convert" if *method == Method::POST => return Some("convert:exec".into()),
+        "file" => "files",
+        "audit" => "admin",
+        group => group,
+    };
+    let access = if matches!(*method, Method::GET | Method::HEAD) { "read" } else { "write" };
+    Some(format!("{}:{}", group, access))
+}
+
+/// Require a valid bearer JWT whose scopes cover the route. Its claims are passed on to the
+/// handler as an `Arc<Claims>` extension.
+pub async fn require_scope(State(state): State<Arc<AppState>>, mut request: Request, next: Next) -> Response {
+    let Some(jwt) = &state.jwt else {
+        return next.run(request).await;
+    };
+    let Some(required) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let Some(token) = token else {
+        return AppError::Unauthorized("Missing bearer token".into()).into_response();
+    };
+    let claims = match jwt.verify(token) {
+        Ok(claims) => claims,
+        Err(e) => return AppError::Unauthorized(format!("Invalid token: {}", e)).into_response(),
+    };
+    if !required.is_empty() && !claims.allows(&required) {
+        return AppError::Forbidden(format!("Token lacks the {} scope", required))
+            .with_code("INSUFFICIENT_SCOPE")
+            .with_details(json!({ "required": required }))
+            .into_response();
+    }
+    request.extensions_mut().insert(Arc::new(claims));
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hs256(secret: &str, claims: serde_json::Value) -> String {
+        let input = format!(
+            "{}.{}",
+            BASE64URL.encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+            BASE64URL.encode(claims.to_string())
+        );
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()), input.as_bytes());
+        format!("{}.{}", input, BASE64URL.encode(tag.as_ref()))
+    }
+
+    fn auth() -> JwtAuth {
+        JwtAuth {
+            hs256: Some(hmac::Key::new(hmac::HMAC_SHA256, b"0123456789abcdef0123456789abcdef")),
+            rs256: None,
+            issuer: Some("ops".into()),
+            audience: None,
+        }
+    }
+
+    #[test]
+    fn test_verify_hs256() {
+        let secret = "0123456789abcdef0123456789abcdef";
+        let now = chrono::Utc::now().timestamp();
+        let claims = auth()
+            .verify(&hs256(secret, json!({"iss": "ops", "exp": now + 60, "scope": "files:read browser:*"})))
+            .unwrap();
+        assert!(claims.allows("files:read"));
+        assert!(claims.allows("browser:write"));
+        assert!(!claims.allows("files:write"));
+        assert!(!claims.allows("shell:exec"));
+
+        let expired = hs256(secret, json!({"iss": "ops", "exp": now - 120}));
+        assert!(auth().verify(&expired).is_err());
+        let wrong_issuer = hs256(secret, json!({"iss": "someone"}));
+        assert!(auth().verify(&wrong_issuer).is_err());
+        let wrong_secret = hs256("another secret of thirty-two bytes", json!({"iss": "ops"}));
+        assert!(auth().verify(&wrong_secret).is_err());
+        // No RS256 key is configured, so an RS256 header is refused before any signature check
+        let rs256 = hs256(secret, json!({"iss": "ops"})).replacen(
+            &BASE64URL.encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+            &BASE64URL.encode(br#"{"alg":"RS256","typ":"JWT"}"#),
+            1,
+        );
+        assert!(auth().verify(&rs256).is_err());
+    }
+
+    #[test]
+    fn test_required_scopes() {
+        assert_eq!(required_scope(&Method::POST, "/v1/shell/exec").unwrap(), "shell:exec");
+        assert_eq!(required_scope(&Method::GET, "/file/read").unwrap(), "files:read");
+        assert_eq!(required_scope(&Method::POST, "/v1/file/write").unwrap(), "files:write");
+        assert_eq!(required_scope(&Method::PUT, "/skills/pdf").unwrap(), "skills:write");
+        assert_eq!(required_scope(&Method::POST, "/skills/pdf/scripts/run.sh").unwrap(), "skills:exec");
+        assert_eq!(required_scope(&Method::GET, "/browser/pages").unwrap(), "browser:read");
+        assert_eq!(required_scope(&Method::POST, "/notebook/n1/run").unwrap(), "code:exec");
+        assert_eq!(required_scope(&Method::POST, "/v1/notebook/n1/cells/c1/run").unwrap(), "code:exec");
+        assert_eq!(required_scope(&Method::PUT, "/notebook/n1/cells/c1").unwrap(), "notebook:write");
+        assert_eq!(required_scope(&Method::POST, "/v1/env/install").unwrap(), "env:exec");
+        assert_eq!(required_scope(&Method::GET, "/env/packages").unwrap(), "env:read");
+        assert_eq!(required_scope(&Method::POST, "/data/query").unwrap(), "data:exec");
+        assert_eq!(required_scope(&Method::POST, "/v1/convert").unwrap(), "convert:exec");
+        assert_eq!(required_scope(&Method::GET, "/audit").unwrap(), "admin:read");
+        assert_eq!(required_scope(&Method::POST, "/sandbox.v1.Files/Write").unwrap(), "files:write");
+        assert_eq!(required_scope(&Method::POST, "/sandbox.v1.Skills/Get").unwrap(), "skills:read");
+        assert_eq!(required_scope(&Method::GET, "/v1/ws").unwrap(), "");
+        assert!(required_scope(&Method::GET, "/health").is_none());
+        assert!(required_scope(&Method::GET, "/swagger-ui/index.html").is_none());
+    }
+
+    #[test]
+    fn test_der_element() {
+        let (contents, rest) = der_element(&[0x30, 0x02, 0xaa, 0xbb, 0xcc], 0x30).unwrap();
+        assert_eq!((contents, rest), (&[0xaa, 0xbb][..], &[0xcc][..]));
+        let long = [&[0x03, 0x81, 0x80][..], &[0u8; 0x80]].concat();
+        assert_eq!(der_element(&long, 0x03).unwrap().0.len(), 0x80);
+        assert!(der_element(&[0x30, 0x05, 0x00], 0x30).is_err());
+        assert!(der_element(&[0x02, 0x00], 0x30).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use chromiumoxide::cdp::browser_protocol::page::{EventJavascriptDialogOpening, HandleJavaScriptDialogParams};
+use chromiumoxide::cdp::js_protocol::runtime::{EventConsoleApiCalled, RemoteObject};
+use chromiumoxide::Page;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+use crate::browser::types::{BrowserError, ConsoleEntry, DialogAction, DialogPolicy};
+
+/// Oldest entries are dropped beyond this many
+const MAX_ENTRIES: usize = 500;
+
+/// Page events we care about, merged into a single stream
+enum PageEvent {
+    Dialog(Arc<EventJavascriptDialogOpening>),
+    Console(Arc<EventConsoleApiCalled>),
+}
+
+/// Answers a page's JavaScript dialogs per its policy and keeps a log of
+/// console output and dialogs. Without this an `alert()` blocks the page.
+#[derive(Clone)]
+pub struct PageConsole {
+    policy: Arc<RwLock<DialogPolicy>>,
+    entries: Arc<Mutex<VecDeque<ConsoleEntry>>>,
+}
+
+impl PageConsole {
+    /// Start handling dialogs and collecting console output on the page
+    pub async fn attach(page: &Page, policy: DialogPolicy) -> Result<Self, BrowserError> {
+        let map_err = |e: chromiumoxide::error::CdpError| BrowserError::NavigationFailed(e.to_string());
+
+        let dialogs = page.event_listener::<EventJavascriptDialogOpening>().await.map_err(map_err)?;
+        let console = page.event_listener::<EventConsoleApiCalled>().await.map_err(map_err)?;
+        let streams: Vec<BoxStream<'static, PageEvent>> = vec![
+            dialogs.map(PageEvent::Dialog).boxed(),
+            console.map(PageEvent::Console).boxed(),
+        ];
+        let mut events = stream::select_all(streams);
+
+        let this = Self {
+            policy: Arc::new(RwLock::new(policy)),
+            entries: Arc::new(Mutex::new(VecDeque::new())),
+        };
+
+        // Ends on its own when the page closes and its event streams finish
+        let task_console = this.clone();
+        let task_page = page.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    PageEvent::Dialog(ev) => task_console.handle_dialog(&task_page, &ev).await,
+                    PageEvent::Console(ev) => task_console.push(ConsoleEntry {
+                        kind: "console".into(),
+                        level: ev.r#type.as_ref().to_string(),
+                        text: ev.args.iter().map(remote_object_text).collect::<Vec<_>>().join(" "),
+                        action: None,
+                        timestamp: chrono::Utc::now().to_rfc3339(),
+                    }),
+                }
+            }
+        });
+
+        Ok(this)
+    }
+
+    async fn handle_dialog(&self, page: &Page, ev: &EventJavascriptDialogOpening) {
+        let policy = self.policy.read().unwrap_or_else(PoisonError::into_inner).clone();
+        let accept = policy.action == DialogAction::Accept;
+
+        let mut params = HandleJavaScriptDialogParams::new(accept);
+        if accept {
+            params.prompt_text = policy.prompt_text.or_else(|| ev.default_prompt.clone());
+        }
+        let handled = page.execute(params).await;
+
+        self.push(ConsoleEntry {
+            kind: "dialog".into(),
+            level: ev.r#type.as_ref().to_string(),
+            text: ev.message.clone(),
+            action: Some(match (handled, accept) {
+                (Err(_), _) => "unhandled".into(),
+                (Ok(_), true) => "accepted".into(),
+                (Ok(_), false) => "dismissed".into(),
+            }),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    fn push(&self, entry: ConsoleEntry) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() == MAX_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Log a request the URL policy refused
+    pub fn record_blocked(&self, url: &str, resource_type: &str, reason: &str) {
+        self.push(ConsoleEntry {
+            kind: "blocked".into(),
+            level: resource_type.to_string(),
+            text: url.to_string(),
+            action: Some(reason.to_string()),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    pub fn set_policy(&self, policy: DialogPolicy) {
+        *self.policy.write().unwrap_or_else(PoisonError::into_inner) = policy;
+    }
+
+    /// Logged entries, oldest first
+    pub fn entries(&self) -> Vec<ConsoleEntry> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner).iter().cloned().collect()
+    }
+}
+
+/// Render a console argument the way DevTools would show it inline
+fn remote_object_text(obj: &RemoteObject) -> String {
+    match &obj.value {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+        None => obj
+            .description
+            .clone()
+            .unwrap_or_else(|| obj.r#type.as_ref().to_string()),
+    }
+}
@@ -0,0 +1,259 @@
+use chromiumoxide::cdp::browser_protocol::browser::{
+    BrowserContextId, DownloadProgressState, EventDownloadProgress, EventDownloadWillBegin,
+    SetDownloadBehaviorBehavior, SetDownloadBehaviorParams,
+};
+use chromiumoxide::Browser;
+use dashmap::DashMap;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+use crate::browser::types::{BrowserError, DownloadInfo};
+
+/// Download events we care about, merged into a single stream
+enum DownloadEvent {
+    Begin(Arc<EventDownloadWillBegin>),
+    Progress(Arc<EventDownloadProgress>),
+}
+
+struct TrackedDownload {
+    // Order in which downloads began, so callers can wait for "the next one"
+    seq: u64,
+    info: DownloadInfo,
+}
+
+/// Routes Chromium downloads into a workspace directory and tracks their progress.
+///
+/// Chromium saves each file under its GUID; once complete it is renamed to the
+/// suggested filename, de-duplicated against files already in the directory.
+#[derive(Clone)]
+pub struct DownloadTracker {
+    dir: PathBuf,
+    downloads: Arc<DashMap<String, TrackedDownload>>,
+    seq: Arc<AtomicU64>,
+    changed: Arc<Notify>,
+}
+
+impl DownloadTracker {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            downloads: Arc::new(DashMap::new()),
+            seq: Arc::new(AtomicU64::new(0)),
+            changed: Arc::new(Notify::new()),
+        }
+    }
+
+    /// Download behavior for the default context, or a dedicated one
+    pub fn behavior(&self, context: Option<BrowserContextId>) -> SetDownloadBehaviorParams {
+        let mut params = SetDownloadBehaviorParams::new(SetDownloadBehaviorBehavior::AllowAndName);
+        params.download_path = Some(self.dir.to_string_lossy().into_owned());
+        params.events_enabled = Some(true);
+        params.browser_context_id = context;
+        params
+    }
+
+    /// Enable downloads on a freshly launched browser and start tracking them
+    pub async fn attach(&self, browser: &Browser) -> Result<(), BrowserError> {
+        let map_err = |e: chromiumoxide::error::CdpError| BrowserError::DownloadFailed(e.to_string());
+
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| BrowserError::DownloadFailed(e.to_string()))?;
+        browser.execute(self.behavior(None)).await.map_err(map_err)?;
+
+        let begins = browser.event_listener::<EventDownloadWillBegin>().await.map_err(map_err)?;
+        let progress = browser.event_listener::<EventDownloadProgress>().await.map_err(map_err)?;
+
+        let streams: Vec<BoxStream<'static, DownloadEvent>> = vec![
+            begins.map(DownloadEvent::Begin).boxed(),
+            progress.map(DownloadEvent::Progress).boxed(),
+        ];
+        let mut events = stream::select_all(streams);
+
+        let tracker = self.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                tracker.apply(event).await;
+                tracker.changed.notify_waiters();
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn apply(&self, event: DownloadEvent) {
+        match event {
+            DownloadEvent::Begin(ev) => {
+                let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+                self.downloads.insert(
+                    ev.guid.clone(),
+                    TrackedDownload {
+                        seq,
+                        info: DownloadInfo {
+                            download_id: ev.guid.clone(),
+                            url: ev.url.clone(),
+                            filename: sanitize_filename(&ev.suggested_filename, &ev.guid),
+                            path: None,
+                            state: "in_progress".into(),
+                            received_bytes: 0,
+                            total_bytes: 0,
+                            started_at: chrono::Utc::now().to_rfc3339(),
+                        },
+                    },
+                );
+            }
+            DownloadEvent::Progress(ev) => {
+                let filename = {
+                    let Some(mut tracked) = self.downloads.get_mut(&ev.guid) else {
+                        return;
+                    };
+                    tracked.info.received_bytes = ev.received_bytes as u64;
+                    tracked.info.total_bytes = ev.total_bytes as u64;
+                    match ev.state {
+                        DownloadProgressState::InProgress => return,
+                        DownloadProgressState::Canceled => {
+                            tracked.info.state = "canceled".into();
+                            return;
+                        }
+                        DownloadProgressState::Completed => tracked.info.filename.clone(),
+                    }
+                };
+
+                // Rename outside the map guard; the entry flips to completed with its final path
+                let (state, path) = match self.finalize(&ev.guid, &filename).await {
+                    Ok(path) => ("completed", Some(path.to_string_lossy().into_owned())),
+                    Err(e) => {
+                        tracing::warn!("Failed to finalize download {}: {}", ev.guid, e);
+                        ("failed", None)
+                    }
+                };
+                if let Some(mut tracked) = self.downloads.get_mut(&ev.guid) {
+                    tracked.info.state = state.into();
+                    tracked.info.path = path;
+                }
+            }
+        }
+    }
+
+    /// Move a finished download from its GUID name to a unique human-readable one
+    async fn finalize(&self, guid: &str, filename: &str) -> std::io::Result<PathBuf> {
+        let target = unique_path(&self.dir, filename);
+        tokio::fs::rename(self.dir.join(guid), &target).await?;
+        Ok(target)
+    }
+
+    /// Sequence number of the most recent download, used to wait for the next one
+    pub fn mark(&self) -> u64 {
+        self.seq.load(Ordering::SeqCst)
+    }
+
+    /// Wait for the first download begun after `after` to finish
+    pub async fn wait_for_next(&self, after: u64, timeout: Duration) -> Result<DownloadInfo, BrowserError> {
+        let wait = async {
+            loop {
+                // Register for wakeups before checking, so no event slips in between
+                let changed = self.changed.notified();
+                tokio::pin!(changed);
+                changed.as_mut().enable();
+
+                let next = self.downloads
+                    .iter()
+                    .filter(|item| item.seq > after)
+                    .min_by_key(|item| item.seq)
+                    .map(|item| item.info.clone());
+                if let Some(info) = next {
+                    if info.state != "in_progress" {
+                        return info;
+                    }
+                }
+
+                changed.await;
+            }
+        };
+
+        let info = tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| BrowserError::Timeout(timeout.as_secs()))?;
+
+        match info.state.as_str() {
+            "completed" => Ok(info),
+            state => Err(BrowserError::DownloadFailed(format!("{} ({})", info.url, state))),
+        }
+    }
+
+    /// All downloads seen this session, oldest first
+    pub fn list(&self) -> Vec<DownloadInfo> {
+        let mut downloads: Vec<(u64, DownloadInfo)> = self
+            .downloads
+            .iter()
+            .map(|item| (item.seq, item.info.clone()))
+            .collect();
+        downloads.sort_by_key(|(seq, _)| *seq);
+        downloads.into_iter().map(|(_, info)| info).collect()
+    }
+}
+
+/// Reduce a server-suggested filename to a single safe path component
+fn sanitize_filename(suggested: &str, fallback: &str) -> String {
+    let name = suggested
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .trim();
+    if name.is_empty() || name == "." || name == ".." {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `dir/name`, or `dir/stem (n).ext` if that file already exists
+fn unique_path(dir: &Path, filename: &str) -> PathBuf {
+    let candidate = dir.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let (stem, ext) = match filename.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (filename, String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|path| !path.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("report.pdf", "guid"), "report.pdf");
+        assert_eq!(sanitize_filename("../../etc/passwd", "guid"), "passwd");
+        assert_eq!(sanitize_filename("dir\\file.txt", "guid"), "file.txt");
+        assert_eq!(sanitize_filename("", "guid"), "guid");
+        assert_eq!(sanitize_filename("..", "guid"), "guid");
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let dir = std::env::temp_dir().join(format!("downloads-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "a.txt"), dir.join("a.txt"));
+        std::fs::write(dir.join("a.txt"), b"1").unwrap();
+        assert_eq!(unique_path(&dir, "a.txt"), dir.join("a (1).txt"));
+        std::fs::write(dir.join("a (1).txt"), b"2").unwrap();
+        assert_eq!(unique_path(&dir, "a.txt"), dir.join("a (2).txt"));
+        std::fs::write(dir.join("README"), b"3").unwrap();
+        assert_eq!(unique_path(&dir, "README"), dir.join("README (1)"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,326 @@
+use chromiumoxide::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived,
+    Headers, Response,
+};
+use chromiumoxide::Page;
+use futures::stream::{self, BoxStream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::browser::types::BrowserError;
+
+/// Network events we care about, merged into a single stream
+enum NetEvent {
+    Request(Arc<EventRequestWillBeSent>),
+    Response(Arc<EventResponseReceived>),
+    Finished(Arc<EventLoadingFinished>),
+    Failed(Arc<EventLoadingFailed>),
+}
+
+/// One request/response exchange being assembled from CDP events
+struct PendingEntry {
+    wall_time: f64,
+    start: f64,
+    end: Option<f64>,
+    method: String,
+    url: String,
+    request_headers: Headers,
+    response: Option<Response>,
+    encoded_size: Option<f64>,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+struct HarLog {
+    entries: Vec<PendingEntry>,
+    // CDP request id -> index of its latest entry (redirects reuse the id)
+    index: HashMap<String, usize>,
+}
+
+impl HarLog {
+    fn apply(&mut self, event: NetEvent) {
+        match event {
+            NetEvent::Request(ev) => {
+                let id = ev.request_id.inner().clone();
+                let timestamp = *ev.timestamp.inner();
+                // A redirect arrives as a new request with the same id; close out the hop
+                if let Some(redirect) = &ev.redirect_response {
+                    if let Some(entry) = self.index.get(&id).map(|i| &mut self.entries[*i]) {
+                        entry.response = Some(redirect.clone());
+                        entry.end = Some(timestamp);
+                    }
+                }
+                self.index.insert(id, self.entries.len());
+                self.entries.push(PendingEntry {
+                    wall_time: *ev.wall_time.inner(),
+                    start: timestamp,
+                    end: None,
+                    method: ev.request.method.clone(),
+                    url: ev.request.url.clone(),
+                    request_headers: ev.request.headers.clone(),
+                    response: None,
+                    encoded_size: None,
+                    error: None,
+                });
+            }
+            NetEvent::Response(ev) => {
+                if let Some(entry) = self.entry_mut(ev.request_id.inner()) {
+                    entry.response = Some(ev.response.clone());
+                }
+            }
+            NetEvent::Finished(ev) => {
+                if let Some(entry) = self.entry_mut(ev.request_id.inner()) {
+                    entry.end = Some(*ev.timestamp.inner());
+                    entry.encoded_size = Some(ev.encoded_data_length);
+                }
+            }
+            NetEvent::Failed(ev) => {
+                if let Some(entry) = self.entry_mut(ev.request_id.inner()) {
+                    entry.end = Some(*ev.timestamp.inner());
+                    entry.error = Some(ev.error_text.clone());
+                }
+            }
+        }
+    }
+
+    fn entry_mut(&mut self, id: &str) -> Option<&mut PendingEntry> {
+        let index = *self.index.get(id)?;
+        self.entries.get_mut(index)
+    }
+}
+
+/// Records a page's network activity and renders it as a HAR 1.2 document
+pub struct HarRecorder {
+    log: Arc<Mutex<HarLog>>,
+    task: JoinHandle<()>,
+}
+
+impl HarRecorder {
+    /// Subscribe to the page's network events. Only traffic after this call is captured.
+    pub async fn start(page: &Page) -> Result<Self, BrowserError> {
+        let map_err = |e: chromiumoxide::error::CdpError| BrowserError::HarFailed(e.to_string());
+
+        let requests = page.event_listener::<EventRequestWillBeSent>().await.map_err(map_err)?;
+        let responses = page.event_listener::<EventResponseReceived>().await.map_err(map_err)?;
+        let finished = page.event_listener::<EventLoadingFinished>().await.map_err(map_err)?;
+        let failed = page.event_listener::<EventLoadingFailed>().await.map_err(map_err)?;
+
+        let streams: Vec<BoxStream<'static, NetEvent>> = vec![
+            requests.map(NetEvent::Request).boxed(),
+            responses.map(NetEvent::Response).boxed(),
+            finished.map(NetEvent::Finished).boxed(),
+            failed.map(NetEvent::Failed).boxed(),
+        ];
+        let mut events = stream::select_all(streams);
+
+        let log = Arc::new(Mutex::new(HarLog::default()));
+        let task_log = log.clone();
+        let task = tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                if let Ok(mut log) = task_log.lock() {
+                    log.apply(event);
+                }
+            }
+        });
+
+        Ok(Self { log, task })
+    }
+
+    /// Stop recording and build the HAR document
+    pub fn finish(self, page_url: &str, page_title: &str) -> (Value, usize) {
+        self.task.abort();
+        let log = match self.log.lock() {
+            Ok(log) => log,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        let started = log
+            .entries
+            .first()
+            .map(|e| format_wall_time(e.wall_time))
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+
+        let entries: Vec<Value> = log.entries.iter().map(entry_to_har).collect();
+        let count = entries.len();
+
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": "sandbox-api",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "pages": [{
+                    "startedDateTime": started,
+                    "id": "page_1",
+                    "title": if page_title.is_empty() { page_url } else { page_title },
+                    "pageTimings": {},
+                }],
+                "entries": entries,
+            }
+        });
+
+        (har, count)
+    }
+}
+
+fn format_wall_time(secs: f64) -> String {
+    chrono::DateTime::from_timestamp_millis((secs * 1000.0) as i64)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+fn headers_to_har(headers: &Headers) -> Vec<Value> {
+    match headers.inner() {
+        Value::Object(map) => map
+            .iter()
+            .map(|(name, value)| {
+                let value = value.as_str().map(String::from).unwrap_or_else(|| value.to_string());
+                json!({ "name": name, "value": value })
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn entry_to_har(entry: &PendingEntry) -> Value {
+    let total_ms = entry
+        .end
+        .map(|end| ((end - entry.start) * 1000.0).max(0.0))
+        .unwrap_or(0.0);
+
+    let query: Vec<Value> = url_query_pairs(&entry.url)
+        .into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect();
+
+    let (response, timings) = match &entry.response {
+        Some(resp) => {
+            let timings = resp.timing.as_ref().map(|t| {
+                let span = |start: f64, end: f64| if start >= 0.0 && end >= start { end - start } else { -1.0 };
+                let send = span(t.send_start, t.send_end).max(0.0);
+                let wait = span(t.send_end, t.receive_headers_end).max(0.0);
+                json!({
+                    "blocked": -1,
+                    "dns": span(t.dns_start, t.dns_end),
+                    "connect": span(t.connect_start, t.connect_end),
+                    "ssl": span(t.ssl_start, t.ssl_end),
+                    "send": send,
+                    "wait": wait,
+                    "receive": (total_ms - send - wait).max(0.0),
+                })
+            });
+            let redirect = redirect_location(&resp.headers);
+            let response = json!({
+                "status": resp.status,
+                "statusText": resp.status_text,
+                "httpVersion": resp.protocol.clone().unwrap_or_else(|| "http/1.1".into()),
+                "headers": headers_to_har(&resp.headers),
+                "cookies": [],
+                "content": {
+                    "size": entry.encoded_size.unwrap_or(resp.encoded_data_length),
+                    "mimeType": resp.mime_type,
+                },
+                "redirectURL": redirect,
+                "headersSize": -1,
+                "bodySize": entry.encoded_size.unwrap_or(-1.0),
+            });
+            (response, timings)
+        }
+        None => (
+            json!({
+                "status": 0,
+                "statusText": entry.error.clone().unwrap_or_default(),
+                "httpVersion": "",
+                "headers": [],
+                "cookies": [],
+                "content": { "size": 0, "mimeType": "" },
+                "redirectURL": "",
+                "headersSize": -1,
+                "bodySize": -1,
+            }),
+            None,
+        ),
+    };
+
+    let mut har_entry = json!({
+        "startedDateTime": format_wall_time(entry.wall_time),
+        "time": total_ms,
+        "request": {
+            "method": entry.method,
+            "url": entry.url,
+            "httpVersion": "http/1.1",
+            "headers": headers_to_har(&entry.request_headers),
+            "queryString": query,
+            "cookies": [],
+            "headersSize": -1,
+            "bodySize": -1,
+        },
+        "response": response,
+        "cache": {},
+        "timings": timings.unwrap_or_else(|| json!({ "send": 0, "wait": total_ms, "receive": 0 })),
+        "pageref": "page_1",
+    });
+    if let Some(ref error) = entry.error {
+        har_entry["_error"] = json!(error);
+    }
+    har_entry
+}
+
+fn redirect_location(headers: &Headers) -> String {
+    match headers.inner() {
+        Value::Object(map) => map
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+            .and_then(|(_, value)| value.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Split a URL's query string into raw name/value pairs
+fn url_query_pairs(url: &str) -> Vec<(String, String)> {
+    let query = match url.split_once('?') {
+        Some((_, rest)) => rest.split('#').next().unwrap_or_default(),
+        None => return Vec::new(),
+    };
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => (pair.to_string(), String::new()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_query_pairs() {
+        assert!(url_query_pairs("https://example.com/").is_empty());
+        assert_eq!(
+            url_query_pairs("https://example.com/?a=1&b=&c#frag"),
+            vec![
+                ("a".to_string(), "1".to_string()),
+                ("b".to_string(), String::new()),
+                ("c".to_string(), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_headers_to_har() {
+        let headers = Headers::new(json!({ "Content-Type": "text/html" }));
+        assert_eq!(
+            headers_to_har(&headers),
+            vec![json!({ "name": "Content-Type", "value": "text/html" })]
+        );
+        assert_eq!(redirect_location(&Headers::new(json!({ "location": "/next" }))), "/next");
+    }
+}
@@ -0,0 +1,154 @@
+use chromiumoxide::cdp::browser_protocol::input::{DispatchKeyEventParams, DispatchKeyEventType};
+use chromiumoxide::keys::{get_key_definition, KeyDefinition};
+use chromiumoxide::Page;
+
+use crate::browser::types::BrowserError;
+
+// CDP modifier bit flags
+const ALT: i64 = 1;
+const CONTROL: i64 = 2;
+const META: i64 = 4;
+const SHIFT: i64 = 8;
+
+/// A parsed key combination such as "Control+Shift+T"
+#[derive(Debug)]
+pub struct KeyCombo {
+    modifiers: Vec<(&'static KeyDefinition, i64)>,
+    key: &'static KeyDefinition,
+}
+
+impl KeyCombo {
+    /// Parse `Modifier+...+Key`. Modifiers accept common aliases (Ctrl, Cmd, Option);
+    /// key names follow the DOM `KeyboardEvent.key` values ("Enter", "ArrowDown", "a").
+    pub fn parse(combo: &str) -> Result<Self, BrowserError> {
+        let (modifiers, key) = if combo == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = combo.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            combo.rsplit_once('+').unwrap_or(("", combo))
+        };
+
+        let modifiers = modifiers
+            .split('+')
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                let (key, flag) = match name.to_ascii_lowercase().as_str() {
+                    "alt" | "option" => ("Alt", ALT),
+                    "control" | "ctrl" => ("Control", CONTROL),
+                    "meta" | "cmd" | "command" | "super" => ("Meta", META),
+                    "shift" => ("Shift", SHIFT),
+                    _ => {
+                        return Err(BrowserError::InvalidRequest(format!("Unknown modifier: {}", name)))
+                    }
+                };
+                Ok((lookup(key)?, flag))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { modifiers, key: lookup(key)? })
+    }
+
+    fn mask(&self) -> i64 {
+        self.modifiers.iter().fold(0, |mask, (_, flag)| mask | flag)
+    }
+
+    /// Press and release the combination on the page's focused element
+    pub async fn press(&self, page: &Page) -> Result<(), BrowserError> {
+        let dispatch = |params: DispatchKeyEventParams| async move {
+            page.execute(params)
+                .await
+                .map(|_| ())
+                .map_err(|e| BrowserError::ScriptError(e.to_string()))
+        };
+
+        // Hold modifiers down in order, accumulating their flags
+        let mut held = 0;
+        for (definition, flag) in &self.modifiers {
+            held |= flag;
+            dispatch(key_event(definition, DispatchKeyEventType::RawKeyDown, held, None)).await?;
+        }
+
+        // Only plain or shifted keys produce text; Ctrl/Alt/Meta chords are shortcuts
+        let mask = self.mask();
+        let text = if mask & !SHIFT == 0 { key_text(self.key, mask & SHIFT != 0) } else { None };
+        let down = if text.is_some() { DispatchKeyEventType::KeyDown } else { DispatchKeyEventType::RawKeyDown };
+        dispatch(key_event(self.key, down, mask, text)).await?;
+        dispatch(key_event(self.key, DispatchKeyEventType::KeyUp, mask, None)).await?;
+
+        for (definition, flag) in self.modifiers.iter().rev() {
+            held &= !flag;
+            dispatch(key_event(definition, DispatchKeyEventType::KeyUp, held, None)).await?;
+        }
+
+        Ok(())
+    }
+}
+
+fn lookup(key: &str) -> Result<&'static KeyDefinition, BrowserError> {
+    get_key_definition(key).ok_or_else(|| BrowserError::InvalidRequest(format!("Unknown key: {}", key)))
+}
+
+fn key_text(definition: &KeyDefinition, shift: bool) -> Option<String> {
+    if let Some(text) = definition.text {
+        return Some(text.to_string());
+    }
+    if definition.key.chars().count() == 1 {
+        return Some(if shift { definition.key.to_uppercase() } else { definition.key.to_string() });
+    }
+    None
+}
+
+fn key_event(
+    definition: &KeyDefinition,
+    r#type: DispatchKeyEventType,
+    modifiers: i64,
+    text: Option<String>,
+) -> DispatchKeyEventParams {
+    let mut params = DispatchKeyEventParams::new(r#type);
+    params.modifiers = Some(modifiers);
+    params.key = Some(definition.key.to_string());
+    params.code = Some(definition.code.to_string());
+    params.windows_virtual_key_code = Some(definition.key_code);
+    params.native_virtual_key_code = Some(definition.key_code);
+    params.text = text;
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_key() {
+        let combo = KeyCombo::parse("Enter").unwrap();
+        assert_eq!(combo.key.key, "Enter");
+        assert_eq!(combo.mask(), 0);
+    }
+
+    #[test]
+    fn test_parse_combo_with_aliases() {
+        let combo = KeyCombo::parse("Ctrl+Shift+t").unwrap();
+        assert_eq!(combo.key.key, "t");
+        assert_eq!(combo.mask(), CONTROL | SHIFT);
+
+        let combo = KeyCombo::parse("cmd++").unwrap();
+        assert_eq!(combo.key.key, "+");
+        assert_eq!(combo.mask(), META);
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_names() {
+        assert!(KeyCombo::parse("Hyper+a").is_err());
+        assert!(KeyCombo::parse("Control+NotAKey").is_err());
+    }
+
+    #[test]
+    fn test_key_text() {
+        let a = lookup("a").unwrap();
+        assert_eq!(key_text(a, false).as_deref(), Some("a"));
+        assert_eq!(key_text(a, true).as_deref(), Some("A"));
+        assert_eq!(key_text(lookup("Enter").unwrap(), false).as_deref(), Some("\r"));
+        assert_eq!(key_text(lookup("ArrowDown").unwrap(), false), None);
+    }
+}
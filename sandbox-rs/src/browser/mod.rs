@@ -1,5 +1,15 @@
 pub mod types;
 pub mod service;
+pub mod har;
+pub mod pages;
+pub mod console;
+pub mod downloads;
+pub mod input;
+pub mod process;
+pub mod recording;
+pub mod policy;
+pub mod navigation;
 
 pub use types::*;
 pub use service::*;
+pub use policy::UrlPolicy;
@@ -0,0 +1,106 @@
+use chromiumoxide::cdp::browser_protocol::page::{
+    EventFrameNavigated, EventLifecycleEvent, EventNavigatedWithinDocument, FrameId,
+};
+use chromiumoxide::Page;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::browser::types::BrowserError;
+
+/// Navigation events we care about, merged into a single stream
+enum NavigationEvent {
+    Navigated(Arc<EventFrameNavigated>),
+    WithinDocument(Arc<EventNavigatedWithinDocument>),
+    Lifecycle(Arc<EventLifecycleEvent>),
+}
+
+/// Map a `wait_until` value to the lifecycle event that satisfies it
+pub fn lifecycle_event(wait_until: Option<&str>) -> Result<&'static str, BrowserError> {
+    match wait_until.unwrap_or("load") {
+        "load" => Ok("load"),
+        "domcontentloaded" => Ok("DOMContentLoaded"),
+        "networkidle" => Ok("networkIdle"),
+        other => Err(BrowserError::InvalidRequest(format!(
+            "Unsupported wait_until: {} (expected load, domcontentloaded, or networkidle)",
+            other
+        ))),
+    }
+}
+
+/// Watches a page's main frame for the navigation an action triggers.
+///
+/// Subscribe before the click or submit, so a fast navigation cannot finish
+/// before anyone is listening.
+pub struct NavigationWatcher {
+    main_frame: FrameId,
+    events: BoxStream<'static, NavigationEvent>,
+}
+
+impl NavigationWatcher {
+    pub async fn start(page: &Page) -> Result<Self, BrowserError> {
+        let map_err = |e: chromiumoxide::error::CdpError| BrowserError::NavigationFailed(e.to_string());
+
+        let main_frame = page
+            .mainframe()
+            .await
+            .map_err(map_err)?
+            .ok_or_else(|| BrowserError::NavigationFailed("page has no main frame".into()))?;
+
+        let navigated = page.event_listener::<EventFrameNavigated>().await.map_err(map_err)?;
+        let within = page.event_listener::<EventNavigatedWithinDocument>().await.map_err(map_err)?;
+        let lifecycle = page.event_listener::<EventLifecycleEvent>().await.map_err(map_err)?;
+        let streams: Vec<BoxStream<'static, NavigationEvent>> = vec![
+            navigated.map(NavigationEvent::Navigated).boxed(),
+            within.map(NavigationEvent::WithinDocument).boxed(),
+            lifecycle.map(NavigationEvent::Lifecycle).boxed(),
+        ];
+
+        Ok(Self { main_frame, events: stream::select_all(streams).boxed() })
+    }
+
+    /// Wait for the main frame to navigate and reach `lifecycle_event`.
+    /// Same-document navigations (history.pushState, anchors) finish immediately.
+    pub async fn wait(mut self, lifecycle_event: &str, timeout: Duration) -> Result<(), BrowserError> {
+        let wait = async {
+            let mut loader = None;
+            while let Some(event) = self.events.next().await {
+                match event {
+                    NavigationEvent::Navigated(ev) if ev.frame.parent_id.is_none() => {
+                        loader = Some(ev.frame.loader_id.clone());
+                    }
+                    NavigationEvent::WithinDocument(ev)
+                        if ev.frame_id == self.main_frame && loader.is_none() =>
+                    {
+                        return Ok(());
+                    }
+                    NavigationEvent::Lifecycle(ev)
+                        if ev.frame_id == self.main_frame
+                            && ev.name == lifecycle_event
+                            && loader.as_ref() == Some(&ev.loader_id) =>
+                    {
+                        return Ok(());
+                    }
+                    _ => {}
+                }
+            }
+            Err(BrowserError::NavigationFailed("page closed before navigating".into()))
+        };
+
+        tokio::time::timeout(timeout, wait)
+            .await
+            .map_err(|_| BrowserError::Timeout(timeout.as_secs()))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lifecycle_event() {
+        assert_eq!(lifecycle_event(None).unwrap(), "load");
+        assert_eq!(lifecycle_event(Some("networkidle")).unwrap(), "networkIdle");
+        assert!(lifecycle_event(Some("idle")).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use chromiumoxide::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide::Page;
+
+use crate::browser::console::PageConsole;
+use dashmap::DashMap;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// A tab kept open between requests
+struct ManagedPage {
+    page: Page,
+    /// Dedicated browser context (e.g. for a per-tab proxy), disposed on close
+    context: Option<BrowserContextId>,
+    console: PageConsole,
+    created_at: chrono::DateTime<chrono::Utc>,
+    last_used: Instant,
+}
+
+/// Snapshot of a managed page's bookkeeping
+pub struct PageEntry {
+    pub id: String,
+    pub page: Page,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub idle_secs: f64,
+}
+
+/// Open tabs addressable by id across requests
+#[derive(Clone, Default)]
+pub struct PageRegistry {
+    pages: Arc<DashMap<String, ManagedPage>>,
+}
+
+impl PageRegistry {
+    /// Register a page and return its id
+    pub fn insert(&self, page: Page, context: Option<BrowserContextId>, console: PageConsole) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.pages.insert(
+            id.clone(),
+            ManagedPage {
+                page,
+                context,
+                console,
+                created_at: chrono::Utc::now(),
+                last_used: Instant::now(),
+            },
+        );
+        id
+    }
+
+    /// Look up a page, marking it as used
+    pub fn get(&self, id: &str) -> Option<Page> {
+        self.pages.get_mut(id).map(|mut managed| {
+            managed.last_used = Instant::now();
+            managed.page.clone()
+        })
+    }
+
+    /// The dialog handler and console log of a page
+    pub fn console(&self, id: &str) -> Option<PageConsole> {
+        self.pages.get(id).map(|managed| managed.console.clone())
+    }
+
+    /// Unregister a page without closing it
+    pub fn remove(&self, id: &str) -> Option<(Page, Option<BrowserContextId>)> {
+        self.pages.remove(id).map(|(_, managed)| (managed.page, managed.context))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn entries(&self) -> Vec<PageEntry> {
+        let mut entries: Vec<PageEntry> = self
+            .pages
+            .iter()
+            .map(|item| PageEntry {
+                id: item.key().clone(),
+                page: item.page.clone(),
+                created_at: item.created_at,
+                idle_secs: item.last_used.elapsed().as_secs_f64(),
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.created_at);
+        entries
+    }
+}
+
+/// A page borrowed for one operation. Throwaway pages are closed when the
+/// lease is dropped, including on early error returns.
+pub struct PageLease {
+    page: Option<Page>,
+    ephemeral: bool,
+}
+
+impl PageLease {
+    pub fn managed(page: Page) -> Self {
+        Self { page: Some(page), ephemeral: false }
+    }
+
+    pub fn ephemeral(page: Page) -> Self {
+        Self { page: Some(page), ephemeral: true }
+    }
+
+    /// Keep the page open past this lease (e.g. when promoting it to a tab)
+    pub fn into_inner(mut self) -> Page {
+        self.ephemeral = false;
+        self.page.take().expect("page lease already released")
+    }
+}
+
+impl Deref for PageLease {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        self.page.as_ref().expect("page lease already released")
+    }
+}
+
+impl Drop for PageLease {
+    fn drop(&mut self) {
+        if !self.ephemeral {
+            return;
+        }
+        if let Some(page) = self.page.take() {
+            tokio::spawn(async move {
+                page.close().await.ok();
+            });
+        }
+    }
+}
@@ -0,0 +1,334 @@
+use chromiumoxide::cdp::browser_protocol::fetch::{
+    ContinueRequestParams, EventRequestPaused, FailRequestParams,
+};
+use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+use chromiumoxide::Page;
+use futures::StreamExt;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::{Arc, PoisonError, RwLock};
+use url::{Host, Url};
+
+use crate::browser::console::PageConsole;
+use crate::browser::types::BrowserError;
+
+/// Schemes that never touch the network and are always allowed
+const LOCAL_SCHEMES: &[&str] = &["about", "data", "blob", "javascript"];
+
+/// A host pattern in an allow or deny list
+#[derive(Debug, Clone, PartialEq)]
+enum HostRule {
+    /// `*`: every host
+    Any,
+    /// `example.com` or `*.example.com`: the domain and all its subdomains
+    Domain(String),
+    /// `10.0.0.0/8` or a bare address: IP hosts in the network, and domains resolving into it
+    Network(IpAddr, u8),
+}
+
+impl HostRule {
+    fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim().to_ascii_lowercase();
+        if rule == "*" {
+            return Ok(Self::Any);
+        }
+
+        if let Some((addr, prefix)) = rule.split_once('/') {
+            let addr: IpAddr = addr.parse().map_err(|_| format!("invalid network: {}", rule))?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+            let prefix: u8 = prefix
+                .parse()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length: {}", rule))?;
+            return Self::network(addr, prefix).ok_or_else(|| format!("invalid prefix length: {}", rule));
+        }
+
+        let bare = rule.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(addr) = bare.parse::<IpAddr>() {
+            let prefix = if addr.is_ipv4() { 32 } else { 128 };
+            return Self::network(addr, prefix).ok_or_else(|| format!("invalid network: {}", rule));
+        }
+
+        let domain = rule.trim_start_matches("*.").trim_start_matches('.');
+        if domain.is_empty() {
+            return Err(format!("invalid host rule: {}", rule));
+        }
+        Ok(Self::Domain(domain.to_string()))
+    }
+
+    /// A network with IPv4-mapped IPv6 addresses stored as IPv4, their prefix shortened to
+    /// match; `None` for a mapped network wider than the mapped range
+    fn network(addr: IpAddr, prefix: u8) -> Option<Self> {
+        match (addr, addr.to_canonical()) {
+            (IpAddr::V6(_), IpAddr::V4(v4)) => Some(Self::Network(IpAddr::V4(v4), prefix.checked_sub(96)?)),
+            _ => Some(Self::Network(addr, prefix)),
+        }
+    }
+
+    fn matches(&self, host: &str, addrs: &[IpAddr]) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Domain(domain) => {
+                host == domain || host.strip_suffix(domain.as_str()).is_some_and(|rest| rest.ends_with('.'))
+            }
+            Self::Network(network, prefix) => addrs.iter().any(|addr| in_network(*addr, *network, *prefix)),
+        }
+    }
+}
+
+impl fmt::Display for HostRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "*"),
+            Self::Domain(domain) => write!(f, "{}", domain),
+            Self::Network(addr, prefix) => write!(f, "{}/{}", addr, prefix),
+        }
+    }
+}
+
+fn in_network(addr: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (addr.to_canonical(), network) {
+        (IpAddr::V4(addr), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(addr) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(addr), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(addr) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// The policy in force, replaceable while page interceptors keep consulting it
+pub type SharedUrlPolicy = Arc<RwLock<Arc<UrlPolicy>>>;
+
+/// Allow and deny lists applied to every URL the browser loads.
+///
+/// Deny rules win. With an allow list, anything it does not match is blocked,
+/// including URLs without a host such as `file:`.
+#[derive(Debug, Clone, Default)]
+pub struct UrlPolicy {
+    allow: Vec<HostRule>,
+    deny: Vec<HostRule>,
+}
+
+impl UrlPolicy {
+    pub fn new(allow: &[String], deny: &[String]) -> Result<Self, String> {
+        let parse = |rules: &[String]| {
+            rules
+                .iter()
+                .filter(|rule| !rule.trim().is_empty())
+                .map(|rule| HostRule::parse(rule))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self { allow: parse(allow)?, deny: parse(deny)? })
+    }
+
+    /// Whether any rules are configured; an empty policy allows everything
+    pub fn is_active(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// Whether an allow list is configured, so that anything it does not match is blocked
+    pub fn has_allow_rules(&self) -> bool {
+        !self.allow.is_empty()
+    }
+
+    /// Check a URL, returning why it is blocked
+    pub async fn check(&self, url: &str) -> Result<(), String> {
+        self.vet(url).await.map(|_| ())
+    }
+
+    /// Check a URL, returning the addresses its domain was checked at, if network rules
+    /// needed it resolved, so the connection can be pinned to them
+    pub async fn vet(&self, url: &str) -> Result<Vec<IpAddr>, String> {
+        if !self.is_active() {
+            return Ok(Vec::new());
+        }
+
+        let url = Url::parse(url).map_err(|e| format!("unparseable URL ({})", e))?;
+        if LOCAL_SCHEMES.contains(&url.scheme()) {
+            return Ok(Vec::new());
+        }
+
+        let (host, addrs) = match url.host() {
+            Some(Host::Domain(domain)) => {
+                let domain = domain.trim_end_matches('.').to_string();
+                let addrs = if self.has_network_rules() {
+                    // Network rules cannot be applied to a host that does not resolve
+                    resolve(&domain, url.port_or_known_default().unwrap_or(80)).await?
+                } else {
+                    Vec::new()
+                };
+                (domain, addrs)
+            }
+            Some(Host::Ipv4(addr)) => (addr.to_string(), vec![IpAddr::V4(addr)]),
+            Some(Host::Ipv6(addr)) => (addr.to_string(), vec![IpAddr::V6(addr)]),
+            None if self.allow.is_empty() => return Ok(Vec::new()),
+            None => return Err(format!("{} URLs are not in the allow list", url.scheme())),
+        };
+
+        self.check_host(&host, &addrs)?;
+        Ok(match url.host() {
+            Some(Host::Domain(_)) => addrs,
+            _ => Vec::new(),
+        })
+    }
+
+    fn check_host(&self, host: &str, addrs: &[IpAddr]) -> Result<(), String> {
+        if let Some(rule) = self.deny.iter().find(|rule| rule.matches(host, addrs)) {
+            return Err(format!("{} matches deny rule {}", host, rule));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|rule| rule.matches(host, addrs)) {
+            return Err(format!("{} is not in the allow list", host));
+        }
+        Ok(())
+    }
+
+    fn has_network_rules(&self) -> bool {
+        self.allow
+            .iter()
+            .chain(&self.deny)
+            .any(|rule| matches!(rule, HostRule::Network(..)))
+    }
+
+    /// Intercept every request the page makes and fail those the current policy
+    /// in `shared` blocks, so a reloaded policy covers tabs that are already open.
+    /// Requires request interception to be enabled on the browser, which
+    /// otherwise continues paused requests on its own.
+    pub async fn enforce(shared: &SharedUrlPolicy, page: &Page, console: PageConsole) -> Result<(), BrowserError> {
+        let mut events = page
+            .event_listener::<EventRequestPaused>()
+            .await
+            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+
+        let shared = shared.clone();
+        let page = page.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                // Checks may resolve DNS, so one slow lookup must not hold up other requests
+                let policy = shared.read().unwrap_or_else(PoisonError::into_inner).clone();
+                let page = page.clone();
+                let console = console.clone();
+                tokio::spawn(async move {
+                    match policy.check(&event.request.url).await {
+                        Ok(()) => {
+                            page.execute(ContinueRequestParams::new(event.request_id.clone())).await.ok();
+                        }
+                        Err(reason) => {
+                            tracing::warn!(
+                                url = %event.request.url,
+                                resource_type = event.resource_type.as_ref(),
+                                "Blocked browser request: {}",
+                                reason
+                            );
+                            console.record_blocked(&event.request.url, event.resource_type.as_ref(), &reason);
+                            page.execute(FailRequestParams::new(event.request_id.clone(), ErrorReason::BlockedByClient))
+                                .await
+                                .ok();
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+async fn resolve(host: &str, port: u16) -> Result<Vec<IpAddr>, String> {
+    let addrs: Vec<_> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("{} could not be resolved ({})", host, e))?
+        .map(|addr| addr.ip().to_canonical())
+        .collect();
+    if addrs.is_empty() {
+        return Err(format!("{} did not resolve to any address", host));
+    }
+    Ok(addrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], deny: &[&str]) -> UrlPolicy {
+        let owned = |rules: &[&str]| rules.iter().map(|r| r.to_string()).collect::<Vec<_>>();
+        UrlPolicy::new(&owned(allow), &owned(deny)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        assert_eq!(HostRule::parse("*").unwrap(), HostRule::Any);
+        assert_eq!(HostRule::parse("*.Example.com").unwrap(), HostRule::Domain("example.com".into()));
+        assert_eq!(
+            HostRule::parse("169.254.169.254").unwrap(),
+            HostRule::Network("169.254.169.254".parse().unwrap(), 32)
+        );
+        assert_eq!(HostRule::parse("[::1]").unwrap(), HostRule::Network("::1".parse().unwrap(), 128));
+        // IPv4-mapped networks become IPv4 ones
+        assert_eq!(
+            HostRule::parse("::ffff:10.0.0.0/104").unwrap(),
+            HostRule::Network("10.0.0.0".parse().unwrap(), 8)
+        );
+        assert_eq!(
+            HostRule::parse("[::ffff:169.254.169.254]").unwrap(),
+            HostRule::Network("169.254.169.254".parse().unwrap(), 32)
+        );
+        assert!(HostRule::parse("::ffff:10.0.0.0/64").is_err());
+        assert!(HostRule::parse("10.0.0.0/33").is_err());
+        assert!(HostRule::parse("nope/8").is_err());
+    }
+
+    #[test]
+    fn test_domain_rules_cover_subdomains_only() {
+        let rule = HostRule::parse("example.com").unwrap();
+        assert!(rule.matches("example.com", &[]));
+        assert!(rule.matches("api.example.com", &[]));
+        assert!(!rule.matches("badexample.com", &[]));
+    }
+
+    #[test]
+    fn test_networks() {
+        let v4: IpAddr = "10.1.2.3".parse().unwrap();
+        assert!(in_network(v4, "10.0.0.0".parse().unwrap(), 8));
+        assert!(!in_network(v4, "10.0.0.0".parse().unwrap(), 16));
+        assert!(in_network(v4, "0.0.0.0".parse().unwrap(), 0));
+        // IPv4-mapped IPv6 addresses match IPv4 rules
+        assert!(in_network("::ffff:10.1.2.3".parse().unwrap(), "10.0.0.0".parse().unwrap(), 8));
+        assert!(in_network("fd00::1".parse().unwrap(), "fc00::".parse().unwrap(), 7));
+    }
+
+    #[tokio::test]
+    async fn test_deny_wins_over_allow() {
+        let policy = policy(&["example.com", "localhost"], &["admin.example.com", "169.254.0.0/16"]);
+        assert!(policy.check("http://localhost:8080/a").await.is_ok());
+        assert!(policy.check("https://admin.example.com/").await.is_err());
+        assert!(policy.check("https://other.org/").await.is_err());
+        assert!(policy.check("http://169.254.169.254/latest/meta-data").await.is_err());
+        assert!(policy.check("file:///etc/passwd").await.is_err());
+        assert!(policy.check("data:text/html,hi").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_unresolvable_hosts_blocked_by_network_rules() {
+        let networks = policy(&["*"], &["169.254.0.0/16"]);
+        assert!(networks.check("http://nonexistent.invalid/").await.is_err());
+        assert_eq!(networks.vet("http://10.1.2.3/").await, Ok(Vec::new()));
+        // Without network rules nothing needs resolving
+        let domains = policy(&["*"], &["admin.example.com"]);
+        assert_eq!(domains.vet("http://nonexistent.invalid/").await, Ok(Vec::new()));
+        // Resolved hosts come back with the addresses they were checked at
+        let addrs = networks.vet("http://localhost/").await.unwrap();
+        assert!(!addrs.is_empty() && addrs.iter().all(IpAddr::is_loopback));
+    }
+
+    #[tokio::test]
+    async fn test_empty_policy_allows_everything() {
+        let policy = UrlPolicy::default();
+        assert!(!policy.is_active());
+        assert!(policy.check("http://127.0.0.1:8080/").await.is_ok());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// The Chromium process we launched
+#[derive(Debug, Clone, Copy)]
+pub struct BrowserProcess {
+    pub pid: Option<u32>,
+    pub started: Instant,
+}
+
+impl BrowserProcess {
+    /// Resident memory of the browser and all its renderer/GPU/utility children
+    pub fn memory_bytes(&self) -> Option<u64> {
+        self.pid.and_then(tree_memory_bytes)
+    }
+}
+
+/// Sum of VmRSS over `root` and its descendants. Linux only (reads /proc).
+pub fn tree_memory_bytes(root: u32) -> Option<u64> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        if let Some(ppid) = parent_pid(pid) {
+            children.entry(ppid).or_default().push(pid);
+        }
+    }
+
+    let mut total = rss_bytes(root)?;
+    let mut stack = children.get(&root).cloned().unwrap_or_default();
+    while let Some(pid) = stack.pop() {
+        total += rss_bytes(pid).unwrap_or(0);
+        if let Some(grandchildren) = children.get(&pid) {
+            stack.extend(grandchildren);
+        }
+    }
+    Some(total)
+}
+
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces or parens; fields resume after the last ')'
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+fn rss_bytes(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_tree_memory_of_self() {
+        let pid = std::process::id();
+        assert!(parent_pid(pid).is_some());
+        assert!(tree_memory_bytes(pid).unwrap() > 0);
+    }
+
+    #[test]
+    fn test_missing_process() {
+        assert!(tree_memory_bytes(u32::MAX).is_none());
+    }
+}
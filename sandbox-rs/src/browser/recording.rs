@@ -0,0 +1,188 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chromiumoxide::cdp::browser_protocol::page::{
+    EventScreencastFrame, ScreencastFrameAckParams, StartScreencastFormat, StartScreencastParams,
+    StopScreencastParams,
+};
+use chromiumoxide::Page;
+use futures::StreamExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::browser::types::BrowserError;
+
+/// Frame shown for the final image, which has no successor to measure against
+const LAST_FRAME_SECS: f64 = 0.5;
+
+/// A JPEG written to disk and the time Chromium painted it
+struct Frame {
+    path: PathBuf,
+    timestamp: f64,
+}
+
+/// Captures a page's screencast frames into a directory for later encoding
+pub struct ScreenRecorder {
+    page: Page,
+    dir: PathBuf,
+    frames: Arc<Mutex<Vec<Frame>>>,
+    task: JoinHandle<()>,
+}
+
+/// What a finished recording produced
+pub struct Recording {
+    pub frames: usize,
+    pub duration_secs: f64,
+}
+
+impl ScreenRecorder {
+    /// Start a JPEG screencast on the page, writing frames under `dir`
+    pub async fn start(page: &Page, dir: PathBuf, quality: u8, every_nth_frame: u32) -> Result<Self, BrowserError> {
+        let map_err = |e: chromiumoxide::error::CdpError| BrowserError::RecordingFailed(e.to_string());
+
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| BrowserError::RecordingFailed(e.to_string()))?;
+
+        let mut events = page.event_listener::<EventScreencastFrame>().await.map_err(map_err)?;
+        page.execute(
+            StartScreencastParams::builder()
+                .format(StartScreencastFormat::Jpeg)
+                .quality(quality as i64)
+                .every_nth_frame(every_nth_frame.max(1) as i64)
+                .build(),
+        )
+        .await
+        .map_err(map_err)?;
+
+        let frames = Arc::new(Mutex::new(Vec::new()));
+        let task_frames = frames.clone();
+        let task_page = page.clone();
+        let task_dir = dir.clone();
+        let task = tokio::spawn(async move {
+            let mut index = 0;
+            while let Some(event) = events.next().await {
+                // Chromium stops sending frames until each one is acknowledged
+                task_page.execute(ScreencastFrameAckParams::new(event.session_id)).await.ok();
+
+                let Ok(data) = BASE64.decode(AsRef::<str>::as_ref(&event.data)) else {
+                    continue;
+                };
+                let timestamp = event.metadata.timestamp
+                    .as_ref()
+                    .map(|t| *t.inner())
+                    .unwrap_or_else(|| chrono::Utc::now().timestamp_millis() as f64 / 1000.0);
+
+                let path = task_dir.join(format!("frame-{:06}.jpg", index));
+                index += 1;
+                if tokio::fs::write(&path, data).await.is_ok() {
+                    if let Ok(mut frames) = task_frames.lock() {
+                        frames.push(Frame { path, timestamp });
+                    }
+                }
+            }
+        });
+
+        Ok(Self { page: page.clone(), dir, frames, task })
+    }
+
+    /// The page being recorded
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    /// Stop capturing and encode the frames to `output` (WebM or MP4 by extension) with ffmpeg
+    pub async fn finish(self, output: &Path) -> Result<Recording, BrowserError> {
+        self.page.execute(StopScreencastParams::default()).await.ok();
+        self.task.abort();
+
+        let frames = match self.frames.lock() {
+            Ok(mut frames) => std::mem::take(&mut *frames),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        };
+
+        let result = encode(&self.dir, &frames, output).await;
+        tokio::fs::remove_dir_all(&self.dir).await.ok();
+        result
+    }
+}
+
+/// Build an ffmpeg concat list that holds each frame until the next one was painted
+fn concat_list(frames: &[Frame]) -> String {
+    let mut list = String::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let duration = frames
+            .get(i + 1)
+            .map(|next| (next.timestamp - frame.timestamp).max(0.001))
+            .unwrap_or(LAST_FRAME_SECS);
+        list.push_str(&format!("file '{}'\nduration {:.3}\n", frame.path.display(), duration));
+    }
+    // The concat demuxer ignores the last duration unless the file is repeated
+    if let Some(last) = frames.last() {
+        list.push_str(&format!("file '{}'\n", last.path.display()));
+    }
+    list
+}
+
+async fn encode(dir: &Path, frames: &[Frame], output: &Path) -> Result<Recording, BrowserError> {
+    if frames.is_empty() {
+        return Err(BrowserError::RecordingFailed("no frames were captured".into()));
+    }
+
+    let list_path = dir.join("frames.txt");
+    tokio::fs::write(&list_path, concat_list(frames))
+        .await
+        .map_err(|e| BrowserError::RecordingFailed(e.to_string()))?;
+
+    if let Some(parent) = output.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| BrowserError::OutputFailed(e.to_string()))?;
+    }
+
+    let codec: &[&str] = match output.extension().and_then(|e| e.to_str()) {
+        Some("mp4") => &["-c:v", "libx264", "-pix_fmt", "yuv420p", "-movflags", "+faststart"],
+        _ => &["-c:v", "libvpx-vp9", "-b:v", "0", "-crf", "32", "-pix_fmt", "yuv420p"],
+    };
+
+    let result = tokio::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-f", "concat", "-safe", "0", "-i"])
+        .arg(&list_path)
+        // Encoders need even dimensions
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-fps_mode", "vfr"])
+        .args(codec)
+        .arg(output)
+        .output()
+        .await
+        .map_err(|e| BrowserError::RecordingFailed(format!("failed to run ffmpeg: {}", e)))?;
+
+    if !result.status.success() {
+        return Err(BrowserError::RecordingFailed(
+            String::from_utf8_lossy(&result.stderr).trim().to_string(),
+        ));
+    }
+
+    let duration_secs = frames.last().unwrap().timestamp - frames[0].timestamp + LAST_FRAME_SECS;
+    Ok(Recording {
+        frames: frames.len(),
+        duration_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_list_durations() {
+        let frames = vec![
+            Frame { path: PathBuf::from("/tmp/a.jpg"), timestamp: 10.0 },
+            Frame { path: PathBuf::from("/tmp/b.jpg"), timestamp: 10.25 },
+        ];
+        assert_eq!(
+            concat_list(&frames),
+            "file '/tmp/a.jpg'\nduration 0.250\n\
+             file '/tmp/b.jpg'\nduration 0.500\n\
+             file '/tmp/b.jpg'\n"
+        );
+    }
+}
@@ -1,10 +1,33 @@
-use chromiumoxide::{Browser, BrowserConfig};
-use tokio::sync::OnceCell;
-use std::sync::Arc;
+use chromiumoxide::{Browser, BrowserConfig, Page};
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+use std::path::PathBuf;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use futures::StreamExt;
 
+use crate::browser::console::PageConsole;
+use crate::browser::downloads::DownloadTracker;
+use crate::browser::har::HarRecorder;
+use crate::browser::input::KeyCombo;
+use crate::browser::process::BrowserProcess;
+use crate::browser::recording::ScreenRecorder;
+use crate::browser::pages::{PageEntry, PageLease, PageRegistry};
+use crate::browser::navigation::{lifecycle_event, NavigationWatcher};
+use crate::browser::policy::{SharedUrlPolicy, UrlPolicy};
 use crate::browser::types::*;
+use crate::handlers::file::resolve_path;
+use chromiumoxide::cdp::browser_protocol::page::{
+    CaptureScreenshotFormat, CaptureSnapshotFormat, CaptureSnapshotParams, NavigateParams, PrintToPdfParams, Viewport,
+};
+use chromiumoxide::auth::Credentials;
+use chromiumoxide::cdp::browser_protocol::browser::{BrowserContextId, CloseParams};
+use chromiumoxide::cdp::browser_protocol::dom::SetFileInputFilesParams;
+use chromiumoxide::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
+use chromiumoxide::cdp::browser_protocol::network::{Headers, SetExtraHttpHeadersParams};
+use chromiumoxide::cdp::browser_protocol::target::{CreateBrowserContextParams, CreateTargetParams};
+use chromiumoxide::page::ScreenshotParams;
 
 #[derive(Debug, Clone)]
 pub struct BrowserServiceConfig {
@@ -14,8 +37,49 @@ pub struct BrowserServiceConfig {
     pub viewport_height: u32,
     #[allow(dead_code)] // Reserved for future timeout support
     pub timeout: u64,
+    /// Base directory for relative output paths (PDFs, captures, downloads)
+    pub workspace: String,
+    /// Whether output and upload paths must stay inside `workspace`, as in a session
+    pub confined: bool,
+    /// Proxy applied to every page unless a tab has its own
+    pub proxy: Option<ProxyConfig>,
+    /// Initial limits, dialog policy, and URL policy; `reconfigure` replaces them
+    pub limits: BrowserLimits,
+    /// Dialog policy for pages that don't set their own
+    pub dialog: DialogPolicy,
+    /// Hosts pages may load; enforced on navigation and every sub-resource
+    pub url_policy: Arc<UrlPolicy>,
+    /// Chromium profile directory, so several browsers can run side by side
+    pub user_data_dir: Option<PathBuf>,
 }
 
+/// Resource caps enforced on tab creation and by the background reaper
+#[derive(Debug, Clone)]
+pub struct BrowserLimits {
+    /// Maximum managed tabs open at once
+    pub max_pages: usize,
+    /// Close tabs unused for this long
+    pub idle_timeout: Option<Duration>,
+    /// Close tabs older than this, however active
+    pub max_lifetime: Option<Duration>,
+    /// Restart Chromium when its process tree exceeds this much resident memory
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl Default for BrowserLimits {
+    fn default() -> Self {
+        Self {
+            max_pages: 20,
+            idle_timeout: Some(Duration::from_secs(900)),
+            max_lifetime: None,
+            max_memory_bytes: None,
+        }
+    }
+}
+
+/// How often the reaper checks tabs and memory
+const REAP_INTERVAL: Duration = Duration::from_secs(30);
+
 impl Default for BrowserServiceConfig {
     fn default() -> Self {
         Self {
@@ -24,84 +88,354 @@ impl Default for BrowserServiceConfig {
             viewport_width: 1280,
             viewport_height: 720,
             timeout: 30,
+            workspace: "/home/sandbox/workspace".into(),
+            confined: false,
+            proxy: None,
+            limits: BrowserLimits::default(),
+            dialog: DialogPolicy::default(),
+            url_policy: Arc::new(UrlPolicy::default()),
+            user_data_dir: None,
         }
     }
 }
 
+/// A page kept open while its network traffic is recorded
+struct HarSession {
+    page: Page,
+    recorder: HarRecorder,
+    /// Whether the page was opened for this recording (and closes with it)
+    owned: bool,
+}
+
+/// A page whose screencast is being captured
+struct RecordingSession {
+    recorder: ScreenRecorder,
+    format: String,
+    /// Whether the page was opened for this recording (and closes with it)
+    owned: bool,
+}
+
+/// A blank page ready for use, with its dialog handler attached
+struct OpenedPage {
+    page: Page,
+    context: Option<BrowserContextId>,
+    console: PageConsole,
+}
+
+/// A launched Chromium and its process info
+#[derive(Clone)]
+struct RunningBrowser {
+    browser: Arc<Browser>,
+    process: BrowserProcess,
+    /// Whether requests are paused for the URL policy; fixed at launch
+    intercepting: bool,
+}
+
 #[derive(Clone)]
 pub struct BrowserService {
-    browser: Arc<OnceCell<Browser>>,
+    // Replaced on restart, so held behind a lock rather than a OnceCell
+    browser: Arc<RwLock<Option<RunningBrowser>>>,
+    launch_lock: Arc<Mutex<()>>,
+    /// When a launch last failed and why, so readiness probes don't retry it every time
+    launch_failure: Arc<RwLock<Option<(Instant, String)>>>,
     config: BrowserServiceConfig,
+    // Replaced by `reconfigure` without relaunching Chromium
+    limits: Arc<RwLock<BrowserLimits>>,
+    dialog: Arc<RwLock<DialogPolicy>>,
+    url_policy: SharedUrlPolicy,
+    har_sessions: Arc<DashMap<String, HarSession>>,
+    recordings: Arc<DashMap<String, RecordingSession>>,
+    pages: PageRegistry,
+    downloads: DownloadTracker,
 }
 
 impl BrowserService {
     pub fn new(config: BrowserServiceConfig) -> Self {
+        let downloads = DownloadTracker::new(PathBuf::from(&config.workspace).join("downloads"));
         Self {
-            browser: Arc::new(OnceCell::new()),
+            browser: Arc::new(RwLock::new(None)),
+            launch_lock: Arc::new(Mutex::new(())),
+            launch_failure: Arc::new(RwLock::new(None)),
+            limits: Arc::new(RwLock::new(config.limits.clone())),
+            dialog: Arc::new(RwLock::new(config.dialog.clone())),
+            url_policy: Arc::new(RwLock::new(config.url_policy.clone())),
             config,
+            har_sessions: Arc::new(DashMap::new()),
+            recordings: Arc::new(DashMap::new()),
+            pages: PageRegistry::default(),
+            downloads,
         }
     }
 
-    /// Lazy-init browser on first call
-    async fn get_browser(&self) -> Result<&Browser, BrowserError> {
-        self.browser.get_or_try_init(|| async {
-            let mut builder = BrowserConfig::builder();
+    /// The running browser, launched on first use and again after a restart
+    async fn get_browser(&self) -> Result<Arc<Browser>, BrowserError> {
+        if let Some(running) = self.running() {
+            return Ok(running.browser);
+        }
+
+        // Serialize launches so concurrent first requests share one Chromium
+        let _launching = self.launch_lock.lock().await;
+        if let Some(running) = self.running() {
+            return Ok(running.browser);
+        }
 
-            if self.config.headless {
-                builder = builder.arg("--headless=new");
+        let running = match self.launch().await {
+            Ok(running) => running,
+            Err(e) => {
+                let reason = match &e {
+                    BrowserError::LaunchFailed(reason) => reason.clone(),
+                    other => other.to_string(),
+                };
+                *self.launch_failure.write().unwrap_or_else(PoisonError::into_inner) =
+                    Some((Instant::now(), reason));
+                return Err(e);
             }
+        };
+        *self.launch_failure.write().unwrap_or_else(PoisonError::into_inner) = None;
+        let browser = running.browser.clone();
+        *self.browser.write().unwrap_or_else(PoisonError::into_inner) = Some(running);
+        Ok(browser)
+    }
+
+    fn running(&self) -> Option<RunningBrowser> {
+        self.browser.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    fn limits(&self) -> BrowserLimits {
+        self.limits.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    fn url_policy(&self) -> Arc<UrlPolicy> {
+        self.url_policy.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
 
-            // Container-safe args
-            builder = builder
-                .arg("--disable-gpu")
-                .arg("--disable-dev-shm-usage")
-                .arg("--disable-setuid-sandbox");
+    /// Replace the tab limits, default dialog policy, and URL policy. Open tabs
+    /// keep their dialog policy but are held to the new URL policy at once.
+    /// Returns whether Chromium had to be restarted, which happens when a policy
+    /// is set on a browser launched without request interception.
+    pub async fn reconfigure(&self, limits: BrowserLimits, dialog: DialogPolicy, url_policy: UrlPolicy) -> bool {
+        let needs_intercept = url_policy.is_active();
+        *self.limits.write().unwrap_or_else(PoisonError::into_inner) = limits;
+        *self.dialog.write().unwrap_or_else(PoisonError::into_inner) = dialog;
+        *self.url_policy.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(url_policy);
 
-            // Detect container and disable sandbox
-            if std::path::Path::new("/.dockerenv").exists()
-               || std::env::var("CONTAINER").is_ok() {
-                builder = builder.arg("--no-sandbox");
+        let restart = needs_intercept && self.running().is_some_and(|running| !running.intercepting);
+        if restart {
+            tracing::warn!("URL policy set on a browser without request interception, restarting Chromium");
+            self.restart().await;
+        }
+        restart
+    }
+
+    async fn launch(&self) -> Result<RunningBrowser, BrowserError> {
+        let mut builder = BrowserConfig::builder();
+
+        if self.config.headless {
+            builder = builder.arg("--headless=new");
+        }
+
+        // Container-safe args
+        builder = builder
+            .arg("--disable-gpu")
+            .arg("--disable-dev-shm-usage")
+            .arg("--disable-setuid-sandbox");
+
+        // Detect container and disable sandbox
+        if std::path::Path::new("/.dockerenv").exists()
+           || std::env::var("CONTAINER").is_ok() {
+            builder = builder.arg("--no-sandbox");
+        }
+
+        builder = builder
+            .viewport(chromiumoxide::handler::viewport::Viewport {
+                width: self.config.viewport_width,
+                height: self.config.viewport_height,
+                ..Default::default()
+            });
+
+        if let Some(ref proxy) = self.config.proxy {
+            builder = builder.arg(format!("--proxy-server={}", proxy.server));
+            if let Some(ref bypass) = proxy.bypass {
+                builder = builder.arg(format!("--proxy-bypass-list={}", bypass));
             }
+        }
+
+        if let Some(ref path) = self.config.executable_path {
+            builder = builder.chrome_executable(path);
+        }
+
+        if let Some(ref dir) = self.config.user_data_dir {
+            builder = builder.user_data_dir(dir);
+        }
+
+        // Pause every request so the URL policy can decide on it
+        let intercepting = self.url_policy().is_active();
+        if intercepting {
+            builder = builder.enable_request_intercept();
+        }
+
+        let config = builder.build()
+            .map_err(|e| BrowserError::LaunchFailed(e.to_string()))?;
 
-            builder = builder
-                .viewport(chromiumoxide::handler::viewport::Viewport {
-                    width: self.config.viewport_width,
-                    height: self.config.viewport_height,
-                    ..Default::default()
-                });
+        let (mut browser, mut handler) = Browser::launch(config)
+            .await
+            .map_err(|e| BrowserError::LaunchFailed(e.to_string()))?;
+
+        let pid = browser.get_mut_child().map(|child| child.inner.id());
+        let process = BrowserProcess { pid, started: std::time::Instant::now() };
 
-            if let Some(ref path) = self.config.executable_path {
-                builder = builder.chrome_executable(path);
+        // Spawn handler task (required by chromiumoxide)
+        tokio::spawn(async move {
+            while let Some(event) = handler.next().await {
+                // Handle browser events (required by chromiumoxide)
+                let _ = event;
             }
+        });
 
-            let config = builder.build()
-                .map_err(|e| BrowserError::LaunchFailed(e.to_string()))?;
+        self.downloads.attach(&browser).await?;
 
-            let (browser, mut handler) = Browser::launch(config)
-                .await
-                .map_err(|e| BrowserError::LaunchFailed(e.to_string()))?;
+        Ok(RunningBrowser { browser: Arc::new(browser), process, intercepting })
+    }
 
-            // Spawn handler task (required by chromiumoxide)
-            tokio::spawn(async move {
-                while let Some(event) = handler.next().await {
-                    // Handle browser events (required by chromiumoxide)
-                    let _ = event;
+    /// Open a blank page, in its own browser context when a per-tab proxy is given
+    async fn open_page(&self, proxy: Option<&ProxyConfig>) -> Result<OpenedPage, BrowserError> {
+        let browser = self.get_browser().await?;
+        let mut params = CreateTargetParams::new("about:blank");
+
+        let context = match proxy {
+            Some(proxy) => {
+                let mut context_params = CreateBrowserContextParams::builder()
+                    .dispose_on_detach(true)
+                    .proxy_server(proxy.server.clone());
+                if let Some(ref bypass) = proxy.bypass {
+                    context_params = context_params.proxy_bypass_list(bypass.clone());
                 }
-            });
+                let context = browser.create_browser_context(context_params.build())
+                    .await
+                    .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+                browser.execute(self.downloads.behavior(Some(context.clone())))
+                    .await
+                    .map_err(|e| BrowserError::DownloadFailed(e.to_string()))?;
+                params.browser_context_id = Some(context.clone());
+                Some(context)
+            }
+            None => None,
+        };
+
+        // Leased until set up, so a failure below closes the tab instead of leaking it
+        let page = PageLease::ephemeral(browser.new_page(params)
+            .await
+            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?);
 
-            Ok(browser)
-        }).await
+        // Answer proxy auth challenges with the tab's credentials, else the global ones
+        let credentials = proxy
+            .or(self.config.proxy.as_ref())
+            .and_then(|proxy| proxy.username.clone().zip(proxy.password.clone()));
+        if let Some((username, password)) = credentials {
+            page.authenticate(Credentials { username, password })
+                .await
+                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        }
+
+        let dialog = self.dialog.read().unwrap_or_else(PoisonError::into_inner).clone();
+        let console = PageConsole::attach(&page, dialog).await?;
+
+        // Interception stays on after a policy is cleared, so paused requests still need answering
+        if self.running().is_some_and(|running| running.intercepting) {
+            UrlPolicy::enforce(&self.url_policy, &page, console.clone()).await?;
+        }
+
+        Ok(OpenedPage { page: page.into_inner(), context, console })
+    }
+
+    /// Borrow a page for one operation: the managed tab `page_id` if given,
+    /// otherwise a fresh page closed when the lease drops. Navigates to `url` if set.
+    async fn lease_page(&self, page_id: Option<&str>, url: Option<&str>) -> Result<PageLease, BrowserError> {
+        if let Some(url) = url {
+            self.check_url(url).await?;
+        }
+
+        let lease = match page_id {
+            Some(id) => {
+                let page = self.pages
+                    .get(id)
+                    .ok_or_else(|| BrowserError::PageNotFound(id.to_string()))?;
+                PageLease::managed(page)
+            }
+            None => {
+                PageLease::ephemeral(self.open_page(None).await?.page)
+            }
+        };
+
+        if let Some(url) = url {
+            lease.goto(url)
+                .await
+                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        }
+
+        Ok(lease)
     }
 
     pub async fn goto(&self, req: GotoRequest) -> Result<GotoResponse, BrowserError> {
-        let browser = self.get_browser().await?;
-        let page = browser.new_page("about:blank")
-            .await
-            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        if req.new_tab && req.page_id.is_some() {
+            return Err(BrowserError::InvalidRequest(
+                "new_tab cannot be combined with page_id".into(),
+            ));
+        }
+        if req.proxy.is_some() && !req.new_tab {
+            return Err(BrowserError::InvalidRequest(
+                "proxy requires new_tab".into(),
+            ));
+        }
 
-        page.goto(&req.url)
-            .await
-            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        if req.dialog.is_some() && !req.new_tab && req.page_id.is_none() {
+            return Err(BrowserError::InvalidRequest(
+                "dialog requires page_id or new_tab".into(),
+            ));
+        }
+
+        self.check_url(&req.url).await?;
+
+        let max_pages = self.limits().max_pages;
+        if req.new_tab && self.pages.len() >= max_pages {
+            return Err(BrowserError::LimitReached(format!(
+                "{} tabs already open; close one first",
+                max_pages
+            )));
+        }
+
+        let (page, page_id) = if req.new_tab {
+            let proxy = req.proxy.clone().map(ProxyConfig::normalized);
+            let opened = self.open_page(proxy.as_ref()).await?;
+            let page_id = self.pages.insert(opened.page.clone(), opened.context, opened.console);
+            (PageLease::managed(opened.page), Some(page_id))
+        } else {
+            (self.lease_page(req.page_id.as_deref(), None).await?, req.page_id.clone())
+        };
+
+        if let Some(ref policy) = req.dialog {
+            if let Some(console) = page_id.as_deref().and_then(|id| self.pages.console(id)) {
+                console.set_policy(policy.clone());
+            }
+        }
+
+        let navigated = async {
+            apply_emulation(&page, &req.emulation).await?;
+            page.goto(&req.url)
+                .await
+                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+            Ok::<_, BrowserError>(())
+        }
+        .await;
+
+        if let Err(e) = navigated {
+            if req.new_tab {
+                if let Some(ref id) = page_id {
+                    self.close_page(id).await.ok();
+                }
+            }
+            return Err(e);
+        }
 
         let title = page.get_title()
             .await
@@ -114,106 +448,594 @@ impl BrowserService {
             .map(|u| u.to_string())
             .unwrap_or_else(|| req.url.clone());
 
-        page.close().await.ok();
+        Ok(GotoResponse { url, title, page_id })
+    }
 
-        Ok(GotoResponse { url, title })
+    /// List managed tabs with their current URL, title, and idle time
+    pub async fn list_pages(&self) -> Vec<PageInfo> {
+        let mut pages = Vec::new();
+        for entry in self.pages.entries() {
+            pages.push(page_info(&entry).await);
+        }
+        pages
     }
 
-    pub async fn screenshot(&self, req: ScreenshotRequest) -> Result<ScreenshotResponse, BrowserError> {
-        let browser = self.get_browser().await?;
-        let page = browser.new_page("about:blank")
+    /// Bring a managed tab to the foreground
+    pub async fn activate_page(&self, page_id: &str) -> Result<PageInfo, BrowserError> {
+        let page = self.pages
+            .get(page_id)
+            .ok_or_else(|| BrowserError::PageNotFound(page_id.to_string()))?;
+
+        page.bring_to_front()
             .await
-            .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        // Navigate if URL provided
-        if let Some(ref url) = req.url {
-            page.goto(url)
-                .await
-                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        let entry = self.pages
+            .entries()
+            .into_iter()
+            .find(|entry| entry.id == page_id)
+            .ok_or_else(|| BrowserError::PageNotFound(page_id.to_string()))?;
+        Ok(page_info(&entry).await)
+    }
+
+    /// Console output and dialogs seen on a managed tab
+    pub fn console(&self, page_id: &str) -> Result<Vec<ConsoleEntry>, BrowserError> {
+        self.pages
+            .console(page_id)
+            .map(|console| console.entries())
+            .ok_or_else(|| BrowserError::PageNotFound(page_id.to_string()))
+    }
+
+    /// Close a managed tab
+    pub async fn close_page(&self, page_id: &str) -> Result<(), BrowserError> {
+        let (page, context) = self.pages
+            .remove(page_id)
+            .ok_or_else(|| BrowserError::PageNotFound(page_id.to_string()))?;
+        page.close().await.ok();
+        if let (Some(context), Some(running)) = (context, self.running()) {
+            running.browser.dispose_browser_context(context).await.ok();
         }
+        Ok(())
+    }
+
+    pub async fn screenshot(&self, req: ScreenshotRequest) -> Result<ScreenshotResponse, BrowserError> {
+        let (format, format_name) = screenshot_format(&req.format)?;
+        if let Some(quality) = req.quality {
+            if format == CaptureScreenshotFormat::Png {
+                return Err(BrowserError::InvalidRequest(
+                    "quality is only supported for jpeg and webp".into(),
+                ));
+            }
+            if quality > 100 {
+                return Err(BrowserError::InvalidRequest(
+                    "quality must be between 0 and 100".into(),
+                ));
+            }
+        }
+        if req.full_page && (req.clip.is_some() || req.selector.is_some()) {
+            return Err(BrowserError::InvalidRequest(
+                "full_page cannot be combined with clip or selector".into(),
+            ));
+        }
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
 
-        // Take screenshot
-        let screenshot_data = if let Some(ref selector) = req.selector {
-            // Element screenshot
+        // Resolve the capture region; `None` means the visible viewport
+        let clip = if let Some(ref selector) = req.selector {
+            // Element screenshot: clip to the element's box in page coordinates
             let element = page.find_element(selector)
                 .await
                 .map_err(|_| BrowserError::ElementNotFound(selector.clone()))?;
-            element.screenshot(chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotFormat::Png)
+            element.scroll_into_view()
                 .await
-                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?
+                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+            let bounds = element.bounding_box()
+                .await
+                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+            let metrics = page.layout_metrics()
+                .await
+                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+            Some(Viewport {
+                x: metrics.css_layout_viewport.page_x as f64 + bounds.x,
+                y: metrics.css_layout_viewport.page_y as f64 + bounds.y,
+                width: bounds.width,
+                height: bounds.height,
+                scale: 1.0,
+            })
         } else {
-            // Full page screenshot
-            page.screenshot(chromiumoxide::cdp::browser_protocol::page::CaptureScreenshotParams::default())
+            req.clip.as_ref().map(|clip| Viewport {
+                x: clip.x,
+                y: clip.y,
+                width: clip.width,
+                height: clip.height,
+                scale: clip.scale,
+            })
+        };
+
+        let (width, height) = if let Some(ref clip) = clip {
+            (clip.width * clip.scale, clip.height * clip.scale)
+        } else if req.full_page {
+            let metrics = page.layout_metrics()
                 .await
-                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?
+                .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+            (metrics.css_content_size.width, metrics.css_content_size.height)
+        } else {
+            (self.config.viewport_width as f64, self.config.viewport_height as f64)
         };
 
-        let data = BASE64.encode(&screenshot_data);
+        let mut params = ScreenshotParams::builder()
+            .format(format)
+            .full_page(req.full_page);
+        if let Some(quality) = req.quality {
+            params = params.quality(quality as i64);
+        }
+        if let Some(clip) = clip {
+            params = params.clip(clip).capture_beyond_viewport(true);
+        }
 
-        page.close().await.ok();
+        let screenshot_data = page.screenshot(params.build())
+            .await
+            .map_err(|e| BrowserError::ScreenshotFailed(e.to_string()))?;
+
+        let data = BASE64.encode(&screenshot_data);
 
         Ok(ScreenshotResponse {
             data,
-            format: req.format,
-            width: self.config.viewport_width,
-            height: self.config.viewport_height,
+            format: format_name.into(),
+            width: width.round() as u32,
+            height: height.round() as u32,
         })
     }
 
     pub async fn evaluate(&self, req: EvaluateRequest) -> Result<EvaluateResponse, BrowserError> {
-        let browser = self.get_browser().await?;
-        let page = browser.new_page("about:blank")
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let eval_result = page.evaluate(req.script)
             .await
             .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        if let Some(ref url) = req.url {
-            page.goto(url)
-                .await
-                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
-        }
+        let result = eval_result.into_value()
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        let eval_result = page.evaluate(req.script)
+        Ok(EvaluateResponse { result })
+    }
+
+    pub async fn click(&self, req: ClickRequest) -> Result<ClickResponse, BrowserError> {
+        let lifecycle = lifecycle_event(req.wait_until.as_deref())?;
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let element = page.find_element(&req.selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
+
+        let watcher = if req.wait_for_navigation {
+            Some(NavigationWatcher::start(&page).await?)
+        } else {
+            None
+        };
+
+        element.click()
             .await
             .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        let result = eval_result.into_value()
+        let navigation = match watcher {
+            Some(watcher) => {
+                watcher.wait(lifecycle, Duration::from_secs(req.timeout)).await?;
+                Some(navigation_result(&page).await?)
+            }
+            None => None,
+        };
+
+        Ok(ClickResponse { success: true, navigation })
+    }
+
+    pub async fn type_text(&self, req: TypeRequest) -> Result<(), BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let element = page.find_element(&req.selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
+
+        element.type_str(&req.text)
+            .await
             .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        page.close().await.ok();
+        Ok(())
+    }
 
-        Ok(EvaluateResponse { result })
+    pub async fn scroll(&self, req: ScrollRequest) -> Result<ScrollResponse, BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        match req.selector {
+            Some(ref selector) => {
+                let element = page.find_element(selector)
+                    .await
+                    .map_err(|_| BrowserError::ElementNotFound(selector.clone()))?;
+                element.scroll_into_view()
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            }
+            None => {
+                page.evaluate_expression(format!("window.scrollBy({}, {})", req.x, req.y))
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            }
+        }
+
+        page.evaluate_expression(SCROLL_POSITION_JS)
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?
+            .into_value::<ScrollResponse>()
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))
     }
 
-    pub async fn click(&self, req: ClickRequest) -> Result<(), BrowserError> {
-        let browser = self.get_browser().await?;
-        let page = browser.new_page("about:blank")
+    pub async fn hover(&self, req: ElementRequest) -> Result<(), BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let element = page.find_element(&req.selector)
             .await
-            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
 
-        if let Some(ref url) = req.url {
-            page.goto(url)
+        element.hover()
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn focus(&self, req: ElementRequest) -> Result<(), BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let element = page.find_element(&req.selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
+
+        element.focus()
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn press(&self, req: PressRequest) -> Result<(), BrowserError> {
+        // Reject unknown keys before opening a page
+        let combo = KeyCombo::parse(&req.key)?;
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        if let Some(ref selector) = req.selector {
+            page.find_element(selector)
                 .await
-                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+                .map_err(|_| BrowserError::ElementNotFound(selector.clone()))?
+                .focus()
+                .await
+                .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
         }
 
+        combo.press(&page).await
+    }
+
+    pub async fn fill(&self, req: FillRequest) -> Result<FillResponse, BrowserError> {
+        // Validate every operation before touching the page
+        for field in &req.fields {
+            let set = [field.value.is_some(), field.check.is_some(), field.select.is_some()]
+                .iter()
+                .filter(|s| **s)
+                .count();
+            if set != 1 {
+                return Err(BrowserError::InvalidRequest(format!(
+                    "Field '{}' must set exactly one of value, check, or select",
+                    field.selector
+                )));
+            }
+        }
+
+        if req.wait_for_navigation && req.submit.is_none() {
+            return Err(BrowserError::InvalidRequest(
+                "wait_for_navigation requires submit".into(),
+            ));
+        }
+        let lifecycle = lifecycle_event(req.wait_until.as_deref())?;
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        for field in &req.fields {
+            let element = page.find_element(&field.selector)
+                .await
+                .map_err(|_| BrowserError::ElementNotFound(field.selector.clone()))?;
+
+            if let Some(ref value) = field.value {
+                element.call_js_fn(CLEAR_VALUE_JS, false)
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+                element.type_str(value)
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            } else if let Some(checked) = field.check {
+                element.call_js_fn(set_checked_js(checked), false)
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            } else if let Some(ref option) = field.select {
+                let result = element.call_js_fn(select_option_js(option), false)
+                    .await
+                    .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+                if result.result.value != Some(serde_json::Value::Bool(true)) {
+                    return Err(BrowserError::InvalidRequest(format!(
+                        "No option '{}' in select '{}'",
+                        option, field.selector
+                    )));
+                }
+            }
+        }
+
+        let submitted = if let Some(ref submit) = req.submit {
+            let element = page.find_element(submit)
+                .await
+                .map_err(|_| BrowserError::ElementNotFound(submit.clone()))?;
+            let watcher = if req.wait_for_navigation {
+                Some(NavigationWatcher::start(&page).await?)
+            } else {
+                None
+            };
+            element.click()
+                .await
+                .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            if let Some(watcher) = watcher {
+                watcher.wait(lifecycle, Duration::from_secs(req.timeout)).await?;
+            }
+            true
+        } else {
+            false
+        };
+
+        let NavigationResult { url, title } = navigation_result(&page).await?;
+
+        Ok(FillResponse {
+            filled: req.fields.len(),
+            submitted,
+            url,
+            title,
+        })
+    }
+
+    pub async fn upload(&self, req: UploadRequest) -> Result<UploadResponse, BrowserError> {
+        if req.files.is_empty() {
+            return Err(BrowserError::InvalidRequest("files must not be empty".into()));
+        }
+
+        // Chromium reads the files itself, so they must exist on this host
+        let mut files = Vec::with_capacity(req.files.len());
+        for file in &req.files {
+            let path = self.resolve_output(file)?;
+            let is_file = tokio::fs::metadata(&path).await.map(|m| m.is_file()).unwrap_or(false);
+            if !is_file {
+                return Err(BrowserError::InvalidRequest(format!("File not found: {}", file)));
+            }
+            files.push(path.to_string_lossy().into_owned());
+        }
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
         let element = page.find_element(&req.selector)
             .await
             .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
 
-        element.click()
+        let accepts = element.call_js_fn(FILE_INPUT_CAPACITY_JS, false)
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?
+            .result
+            .value;
+        match accepts.as_ref().and_then(|v| v.as_str()) {
+            Some("multiple") => {}
+            Some("single") if files.len() == 1 => {}
+            Some("single") => {
+                return Err(BrowserError::InvalidRequest(format!(
+                    "'{}' accepts a single file",
+                    req.selector
+                )))
+            }
+            _ => {
+                return Err(BrowserError::InvalidRequest(format!(
+                    "'{}' is not a file input",
+                    req.selector
+                )))
+            }
+        }
+
+        page.execute(
+            SetFileInputFilesParams::builder()
+                .files(files.clone())
+                .backend_node_id(element.backend_node_id)
+                .build()
+                .map_err(BrowserError::InvalidRequest)?,
+        )
+        .await
+        .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+
+        Ok(UploadResponse { files })
+    }
+
+    pub async fn query(&self, req: QueryRequest) -> Result<QueryResponse, BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let selector = serde_json::to_string(&req.selector)
+            .map_err(|e| BrowserError::InvalidRequest(e.to_string()))?;
+        let attributes = serde_json::to_string(&req.attributes)
+            .map_err(|e| BrowserError::InvalidRequest(e.to_string()))?;
+        let script = format!("({})({}, {}, {})", QUERY_JS, selector, attributes, req.limit);
+
+        page.evaluate_expression(script)
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?
+            .into_value::<QueryResponse>()
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))
+    }
+
+    pub async fn select(&self, req: SelectRequest) -> Result<SelectResponse, BrowserError> {
+        let set = [
+            req.value.is_some(),
+            req.label.is_some(),
+            req.index.is_some(),
+            req.values.is_some(),
+            req.checked.is_some(),
+        ]
+        .iter()
+        .filter(|s| **s)
+        .count();
+        if set != 1 {
+            return Err(BrowserError::InvalidRequest(
+                "Set exactly one of value, label, index, values, or checked".into(),
+            ));
+        }
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let element = page.find_element(&req.selector)
+            .await
+            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
+
+        let spec = serde_json::to_string(&req)
+            .map_err(|e| BrowserError::InvalidRequest(e.to_string()))?;
+        let result = element.call_js_fn(SELECT_JS.replace("SPEC", &spec), false)
             .await
             .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
 
-        page.close().await.ok();
+        let outcome = result.result.value.unwrap_or_default();
+        if let Some(error) = outcome.get("error").and_then(|e| e.as_str()) {
+            return Err(BrowserError::InvalidRequest(format!("{} ({})", error, req.selector)));
+        }
+        serde_json::from_value(outcome).map_err(|e| BrowserError::ScriptError(e.to_string()))
+    }
 
-        Ok(())
+    pub async fn content(&self, req: ContentRequest) -> Result<ContentResponse, BrowserError> {
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let selector = serde_json::to_string(&req.selector)
+            .map_err(|e| BrowserError::InvalidRequest(e.to_string()))?;
+        let script = format!("({})({}, {})", EXTRACT_CONTENT_JS, selector, req.include_html);
+
+        let eval_result = page.evaluate_expression(script)
+            .await
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+
+        let extracted: Option<ContentResponse> = eval_result.into_value()
+            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+
+        extracted.ok_or_else(|| {
+            BrowserError::ElementNotFound(req.selector.unwrap_or_default())
+        })
     }
 
-    pub async fn type_text(&self, req: TypeRequest) -> Result<(), BrowserError> {
-        let browser = self.get_browser().await?;
-        let page = browser.new_page("about:blank")
+    pub async fn pdf(&self, req: PdfRequest) -> Result<PdfResponse, BrowserError> {
+        let (paper_width, paper_height) = paper_size(&req.format).ok_or_else(|| {
+            BrowserError::InvalidRequest(format!("Unsupported paper format: {}", req.format))
+        })?;
+        let (paper_width, paper_height) = if req.landscape {
+            (paper_height, paper_width)
+        } else {
+            (paper_width, paper_height)
+        };
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        // Paper size is passed explicitly, so landscape is already applied
+        let mut params = PrintToPdfParams::builder()
+            .print_background(req.print_background)
+            .paper_width(paper_width)
+            .paper_height(paper_height);
+        if let Some(top) = req.margin.top {
+            params = params.margin_top(top);
+        }
+        if let Some(bottom) = req.margin.bottom {
+            params = params.margin_bottom(bottom);
+        }
+        if let Some(left) = req.margin.left {
+            params = params.margin_left(left);
+        }
+        if let Some(right) = req.margin.right {
+            params = params.margin_right(right);
+        }
+        if let Some(scale) = req.scale {
+            params = params.scale(scale);
+        }
+        if let Some(ref ranges) = req.page_ranges {
+            params = params.page_ranges(ranges.clone());
+        }
+
+        let pdf_data = page.pdf(params.build())
             .await
-            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+            .map_err(|e| BrowserError::PdfFailed(e.to_string()))?;
+
+        let size = pdf_data.len() as u64;
+
+        match req.path {
+            Some(ref path) => {
+                let full_path = self.write_output(path, &pdf_data).await?;
+                Ok(PdfResponse {
+                    data: None,
+                    path: Some(full_path.to_string_lossy().into_owned()),
+                    size,
+                })
+            }
+            None => Ok(PdfResponse {
+                data: Some(BASE64.encode(&pdf_data)),
+                path: None,
+                size,
+            }),
+        }
+    }
+
+    /// Archive the rendered page as MHTML or a self-contained HTML file
+    pub async fn capture(&self, req: CaptureRequest) -> Result<CaptureResponse, BrowserError> {
+        let ext = match req.format.as_str() {
+            "mhtml" => "mhtml",
+            "html" => "html",
+            other => {
+                return Err(BrowserError::InvalidRequest(format!(
+                    "Unsupported capture format: {} (expected mhtml or html)",
+                    other
+                )))
+            }
+        };
+
+        let page = self.lease_page(req.page_id.as_deref(), req.url.as_deref()).await?;
+
+        let data = if ext == "mhtml" {
+            page.execute(CaptureSnapshotParams::builder().format(CaptureSnapshotFormat::Mhtml).build())
+                .await
+                .map_err(|e| BrowserError::CaptureFailed(e.to_string()))?
+                .result
+                .data
+        } else {
+            page.evaluate_expression(format!("({})()", SINGLE_FILE_JS))
+                .await
+                .map_err(|e| BrowserError::CaptureFailed(e.to_string()))?
+                .into_value::<String>()
+                .map_err(|e| BrowserError::CaptureFailed(e.to_string()))?
+        };
+
+        let path = req.path.unwrap_or_else(|| {
+            format!("captures/capture-{}.{}", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"), ext)
+        });
+        let full_path = self.write_output(&path, data.as_bytes()).await?;
+
+        Ok(CaptureResponse {
+            path: full_path.to_string_lossy().into_owned(),
+            format: req.format,
+            size: data.len() as u64,
+        })
+    }
+
+    pub async fn har_start(&self, req: HarStartRequest) -> Result<HarStartResponse, BrowserError> {
+        if let Some(ref url) = req.url {
+            self.check_url(url).await?;
+        }
+
+        // Recording must begin before navigation, so the URL is loaded below
+        let page = self.lease_page(req.page_id.as_deref(), None).await?;
+        let owned = req.page_id.is_none();
+        let page = page.into_inner();
+
+        let recorder = HarRecorder::start(&page).await?;
 
         if let Some(ref url) = req.url {
             page.goto(url)
@@ -221,23 +1043,638 @@ impl BrowserService {
                 .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
         }
 
-        let element = page.find_element(&req.selector)
+        let url = page.url()
             .await
-            .map_err(|_| BrowserError::ElementNotFound(req.selector.clone()))?;
+            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?
+            .unwrap_or_default();
 
-        element.type_str(&req.text)
+        let har_id = uuid::Uuid::new_v4().to_string();
+        self.har_sessions.insert(har_id.clone(), HarSession { page, recorder, owned });
+
+        Ok(HarStartResponse { har_id, url })
+    }
+
+    pub async fn har_stop(&self, req: HarStopRequest) -> Result<HarStopResponse, BrowserError> {
+        // Before taking the session, so a rejected path leaves the capture running
+        if let Some(path) = &req.path {
+            self.resolve_output(path)?;
+        }
+        let (_, session) = self.har_sessions
+            .remove(&req.har_id)
+            .ok_or_else(|| BrowserError::SessionNotFound(req.har_id.clone()))?;
+
+        let url = session.page.url().await.ok().flatten().unwrap_or_default();
+        let title = session.page.get_title().await.ok().flatten().unwrap_or_default();
+        let (har, entries) = session.recorder.finish(&url, &title);
+        if session.owned {
+            session.page.close().await.ok();
+        }
+
+        match req.path {
+            Some(ref path) => {
+                let data = serde_json::to_vec_pretty(&har)
+                    .map_err(|e| BrowserError::HarFailed(e.to_string()))?;
+                let full_path = self.write_output(path, &data).await?;
+                Ok(HarStopResponse {
+                    har: None,
+                    path: Some(full_path.to_string_lossy().into_owned()),
+                    entries,
+                })
+            }
+            None => Ok(HarStopResponse {
+                har: Some(har),
+                path: None,
+                entries,
+            }),
+        }
+    }
+
+    /// Trigger a download by clicking an element or navigating to a URL, and wait for it to land
+    pub async fn download(&self, req: DownloadRequest) -> Result<DownloadInfo, BrowserError> {
+        let mark = self.downloads.mark();
+
+        match (req.selector.as_deref(), req.url.as_deref()) {
+            (Some(selector), url) => {
+                let page = self.lease_page(req.page_id.as_deref(), url).await?;
+                let element = page.find_element(selector)
+                    .await
+                    .map_err(|_| BrowserError::ElementNotFound(selector.to_string()))?;
+                element.click()
+                    .await
+                    .map_err(|e| BrowserError::DownloadFailed(e.to_string()))?;
+                self.downloads.wait_for_next(mark, Duration::from_secs(req.timeout)).await
+            }
+            (None, Some(url)) => {
+                // A download aborts the navigation, so don't wait for a page load
+                let page = self.lease_page(req.page_id.as_deref(), None).await?;
+                page.execute(NavigateParams::new(url))
+                    .await
+                    .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+                self.downloads.wait_for_next(mark, Duration::from_secs(req.timeout)).await
+            }
+            (None, None) => Err(BrowserError::InvalidRequest(
+                "either url or selector is required".into(),
+            )),
+        }
+    }
+
+    /// Downloads seen since the browser started, with progress
+    pub fn list_downloads(&self) -> Vec<DownloadInfo> {
+        self.downloads.list()
+    }
+
+    pub async fn record_start(&self, req: RecordStartRequest) -> Result<RecordStartResponse, BrowserError> {
+        if req.format != "webm" && req.format != "mp4" {
+            return Err(BrowserError::InvalidRequest(format!(
+                "Unsupported video format: {} (expected webm or mp4)",
+                req.format
+            )));
+        }
+        if !(1..=100).contains(&req.quality) {
+            return Err(BrowserError::InvalidRequest("quality must be between 1 and 100".into()));
+        }
+        if let Some(ref url) = req.url {
+            self.check_url(url).await?;
+        }
+
+        // Like HAR capture, start before navigating so the load is on film
+        let page = self.lease_page(req.page_id.as_deref(), None).await?;
+        let owned = req.page_id.is_none();
+        let page = page.into_inner();
+
+        let recording_id = uuid::Uuid::new_v4().to_string();
+        let frames_dir = PathBuf::from(&self.config.workspace)
+            .join(".recordings")
+            .join(&recording_id);
+        let recorder = match ScreenRecorder::start(&page, frames_dir, req.quality, req.every_nth_frame).await {
+            Ok(recorder) => recorder,
+            Err(e) => {
+                if owned {
+                    page.close().await.ok();
+                }
+                return Err(e);
+            }
+        };
+
+        if let Some(ref url) = req.url {
+            page.goto(url)
+                .await
+                .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?;
+        }
+
+        let url = page.url()
             .await
-            .map_err(|e| BrowserError::ScriptError(e.to_string()))?;
+            .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?
+            .unwrap_or_default();
 
-        page.close().await.ok();
+        self.recordings.insert(
+            recording_id.clone(),
+            RecordingSession { recorder, format: req.format, owned },
+        );
+
+        Ok(RecordStartResponse { recording_id, url })
+    }
+
+    pub async fn record_stop(&self, req: RecordStopRequest) -> Result<RecordStopResponse, BrowserError> {
+        // Before taking the recording, so a rejected path leaves it running
+        let requested = req.path.as_deref().map(|path| self.resolve_output(path)).transpose()?;
+        let (_, session) = self.recordings
+            .remove(&req.recording_id)
+            .ok_or_else(|| BrowserError::SessionNotFound(req.recording_id.clone()))?;
+
+        let full_path = match requested {
+            Some(path) => path,
+            None => self.resolve_output(&format!(
+                "recordings/recording-{}.{}",
+                chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"),
+                session.format
+            ))?,
+        };
+
+        let page = session.recorder.page().clone();
+        let result = session.recorder.finish(&full_path).await;
+        if session.owned {
+            page.close().await.ok();
+        }
+        let recording = result?;
+
+        let size = tokio::fs::metadata(&full_path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+
+        Ok(RecordStopResponse {
+            path: full_path.to_string_lossy().into_owned(),
+            frames: recording.frames,
+            duration_secs: recording.duration_secs,
+            size,
+        })
+    }
+
+    /// Reject a URL the policy blocks before navigating to it. Redirects and
+    /// sub-resources are checked as they happen by the page's interceptor.
+    async fn check_url(&self, url: &str) -> Result<(), BrowserError> {
+        self.url_policy().check(url).await.map_err(|reason| {
+            tracing::warn!(url, "Blocked browser navigation: {}", reason);
+            BrowserError::UrlBlocked(reason)
+        })
+    }
+
+    /// Resolve an output or upload path against the workspace, keeping it inside when confined
+    fn resolve_output(&self, path: &str) -> Result<PathBuf, BrowserError> {
+        resolve_path(&self.config.workspace, path, self.config.confined).map_err(|_| BrowserError::OutsideWorkspace {
+            path: path.to_string(),
+            workspace: self.config.workspace.clone(),
+        })
+    }
+
+    /// Write an artifact to a path, resolving relative paths against the workspace
+    async fn write_output(&self, path: &str, data: &[u8]) -> Result<PathBuf, BrowserError> {
+        let full_path = self.resolve_output(path)?;
+
+        if let Some(parent) = full_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| BrowserError::OutputFailed(e.to_string()))?;
+        }
+
+        tokio::fs::write(&full_path, data)
+            .await
+            .map_err(|e| BrowserError::OutputFailed(e.to_string()))?;
+
+        Ok(full_path)
+    }
+
+    /// Start the background task that enforces `BrowserLimits`; abort it to stop
+    pub fn spawn_reaper(&self) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REAP_INTERVAL);
+            loop {
+                interval.tick().await;
+                service.reap().await;
+            }
+        })
+    }
+
+    /// Close idle or expired tabs, then restart Chromium if it is over its memory cap
+    async fn reap(&self) {
+        let Some(running) = self.running() else {
+            return;
+        };
+        let limits = self.limits();
+
+        for entry in self.pages.entries() {
+            let idle = limits.idle_timeout
+                .is_some_and(|timeout| entry.idle_secs > timeout.as_secs_f64());
+            let expired = limits.max_lifetime.is_some_and(|lifetime| {
+                (chrono::Utc::now() - entry.created_at).to_std().unwrap_or_default() > lifetime
+            });
+            if idle || expired {
+                tracing::info!("Reaping {} tab {}", if idle { "idle" } else { "expired" }, entry.id);
+                self.close_page(&entry.id).await.ok();
+            }
+        }
+
+        if let Some(max) = limits.max_memory_bytes {
+            let process = running.process;
+            let used = tokio::task::spawn_blocking(move || process.memory_bytes())
+                .await
+                .ok()
+                .flatten();
+            if let Some(used) = used.filter(|used| *used > max) {
+                tracing::warn!("Chromium using {} bytes (cap {}), restarting", used, max);
+                self.restart().await;
+            }
+        }
+    }
+
+    /// Close Chromium and forget its tabs and recordings; the next request relaunches it
+    pub async fn restart(&self) {
+        let _launching = self.launch_lock.lock().await;
+        let running = self.browser.write().unwrap_or_else(PoisonError::into_inner).take();
 
+        for entry in self.pages.entries() {
+            self.pages.remove(&entry.id);
+        }
+        self.har_sessions.clear();
+        self.recordings.clear();
+
+        if let Some(running) = running {
+            running.browser.execute(CloseParams::default()).await.ok();
+        }
+    }
+
+    /// Whether Chromium is up and answering, launching it if needed. A failed
+    /// launch is reported again for `retry_after` rather than retried.
+    pub async fn check_launch(&self, retry_after: Duration) -> Result<(), BrowserError> {
+        if !self.is_running() {
+            let failure = self.launch_failure.read().unwrap_or_else(PoisonError::into_inner).clone();
+            if let Some((at, error)) = failure.filter(|(at, _)| at.elapsed() < retry_after) {
+                return Err(BrowserError::LaunchFailed(format!(
+                    "{} ({}s ago)",
+                    error,
+                    at.elapsed().as_secs()
+                )));
+            }
+        }
+        let browser = self.get_browser().await?;
+        browser.version().await.map_err(|e| BrowserError::LaunchFailed(e.to_string()))?;
         Ok(())
     }
 
-    pub fn status(&self) -> BrowserStatus {
+    pub fn is_running(&self) -> bool {
+        self.running().is_some()
+    }
+
+    /// The Chromium process, if one is running; never launches it
+    pub fn process(&self) -> Option<BrowserProcess> {
+        self.running().map(|running| running.process)
+    }
+
+    pub async fn status(&self) -> BrowserStatus {
+        // Don't launch Chromium just to report on it
+        let Some(RunningBrowser { browser, process, .. }) = self.running() else {
+            return BrowserStatus::default();
+        };
+
+        let version = browser.version().await.ok();
+        let open_targets = browser.pages().await.map(|pages| pages.len()).unwrap_or(0);
+        let memory_bytes = tokio::task::spawn_blocking(move || process.memory_bytes())
+            .await
+            .ok()
+            .flatten();
+
         BrowserStatus {
-            running: self.browser.get().is_some(),
-            version: None,  // Could query browser for version if needed
+            running: true,
+            version: version.as_ref().map(|v| v.product.clone()),
+            user_agent: version.map(|v| v.user_agent),
+            pid: process.pid,
+            uptime_secs: Some(process.started.elapsed().as_secs_f64()),
+            memory_bytes,
+            open_targets,
+            pages: self.list_pages().await,
+        }
+    }
+}
+
+async fn page_info(entry: &PageEntry) -> PageInfo {
+    PageInfo {
+        page_id: entry.id.clone(),
+        url: entry.page.url().await.ok().flatten().unwrap_or_default(),
+        title: entry.page.get_title().await.ok().flatten().unwrap_or_default(),
+        created_at: entry.created_at.to_rfc3339(),
+        idle_secs: entry.idle_secs,
+    }
+}
+
+/// The URL and title a page settled on after navigating
+async fn navigation_result(page: &Page) -> Result<NavigationResult, BrowserError> {
+    let url = page.url()
+        .await
+        .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?
+        .unwrap_or_default();
+
+    let title = page.get_title()
+        .await
+        .map_err(|e| BrowserError::NavigationFailed(e.to_string()))?
+        .unwrap_or_default();
+
+    Ok(NavigationResult { url, title })
+}
+
+/// Apply user agent, viewport, and header overrides to a page
+async fn apply_emulation(page: &Page, emulation: &PageEmulation) -> Result<(), BrowserError> {
+    let map_err = |e: chromiumoxide::error::CdpError| BrowserError::NavigationFailed(e.to_string());
+
+    if let Some(ref user_agent) = emulation.user_agent {
+        page.set_user_agent(user_agent.as_str()).await.map_err(map_err)?;
+    }
+
+    if let Some(ref viewport) = emulation.viewport {
+        if viewport.width == 0 || viewport.height == 0 || viewport.device_scale_factor <= 0.0 {
+            return Err(BrowserError::InvalidRequest(
+                "viewport width, height, and device_scale_factor must be positive".into(),
+            ));
+        }
+        page.execute(SetDeviceMetricsOverrideParams::new(
+            viewport.width,
+            viewport.height,
+            viewport.device_scale_factor,
+            viewport.mobile,
+        ))
+        .await
+        .map_err(map_err)?;
+    }
+
+    if let Some(ref headers) = emulation.headers {
+        let headers = serde_json::to_value(headers)
+            .map_err(|e| BrowserError::InvalidRequest(e.to_string()))?;
+        page.execute(SetExtraHttpHeadersParams::new(Headers::new(headers)))
+            .await
+            .map_err(map_err)?;
+    }
+
+    Ok(())
+}
+
+const SCROLL_POSITION_JS: &str = "({ \
+    scroll_x: window.scrollX, \
+    scroll_y: window.scrollY, \
+    scroll_height: document.documentElement.scrollHeight \
+})";
+
+/// Clears an input's current value so typing replaces rather than appends
+const CLEAR_VALUE_JS: &str = "function() { \
+    this.focus(); \
+    this.value = ''; \
+    this.dispatchEvent(new Event('input', { bubbles: true })); \
+}";
+
+/// Sets a checkbox/radio state, clicking so the page sees real change events
+fn set_checked_js(checked: bool) -> String {
+    format!(
+        "function() {{ \
+            if (this.checked !== {checked}) this.click(); \
+            return this.checked; \
+        }}"
+    )
+}
+
+/// Chooses a `<select>` option by value, falling back to its visible label.
+/// Returns false when no option matches.
+fn select_option_js(option: &str) -> String {
+    let option = serde_json::to_string(option).unwrap_or_default();
+    format!(
+        "function() {{ \
+            const wanted = {option}; \
+            const opt = Array.from(this.options || []) \
+                .find((o) => o.value === wanted || o.label === wanted || o.text.trim() === wanted); \
+            if (!opt) return false; \
+            this.value = opt.value; \
+            this.dispatchEvent(new Event('input', {{ bubbles: true }})); \
+            this.dispatchEvent(new Event('change', {{ bubbles: true }})); \
+            return true; \
+        }}"
+    )
+}
+
+/// "single" or "multiple" for a file input, null for anything else
+const FILE_INPUT_CAPACITY_JS: &str = r#"function() {
+    if (this.tagName !== 'INPUT' || this.type !== 'file') return null;
+    return this.multiple ? 'multiple' : 'single';
+}"#;
+
+/// Describes up to `limit` elements matching a selector. An invalid selector
+/// throws, which surfaces as a script error.
+const QUERY_JS: &str = r#"(selector, attributes, limit) => {
+    const all = document.querySelectorAll(selector);
+    const matches = Array.from(all).slice(0, limit).map((el) => {
+        const rect = el.getBoundingClientRect();
+        const style = window.getComputedStyle(el);
+        return {
+            tag: el.tagName.toLowerCase(),
+            text: (el.innerText ?? el.textContent ?? '').trim(),
+            attributes: Object.fromEntries(attributes.map((name) => [name, el.getAttribute(name)])),
+            bounding_box: {
+                x: rect.x + window.scrollX,
+                y: rect.y + window.scrollY,
+                width: rect.width,
+                height: rect.height,
+            },
+            visible: rect.width > 0 && rect.height > 0
+                && style.visibility !== 'hidden' && style.display !== 'none',
+        };
+    });
+    return { exists: all.length > 0, count: all.length, matches };
+}"#;
+
+/// Applies a `SelectRequest` (substituted for SPEC) to a select, checkbox, or
+/// radio. Returns `{ error }` when the element or option doesn't fit the request.
+const SELECT_JS: &str = r#"function() {
+    const spec = SPEC;
+    if (spec.checked !== null) {
+        if (this.type !== 'checkbox' && this.type !== 'radio') return { error: 'not a checkbox or radio' };
+        if (this.type === 'radio' && !spec.checked) return { error: 'a radio is unchecked by choosing another in its group' };
+        // click() toggles the state and fires input/change like a real user
+        if (this.checked !== spec.checked) this.click();
+        return { checked: this.checked };
+    }
+    if (!this.options) return { error: 'not a select element' };
+    const options = Array.from(this.options);
+    let wanted;
+    if (spec.values !== null) {
+        if (!this.multiple && spec.values.length > 1) return { error: 'select does not allow multiple values' };
+        wanted = options.filter((o) => spec.values.includes(o.value));
+        if (wanted.length !== spec.values.length) return { error: 'no option for some values' };
+    } else {
+        const option = options.find((o) => spec.value !== null ? o.value === spec.value
+            : spec.label !== null ? (o.label === spec.label || o.text.trim() === spec.label)
+            : o.index === spec.index);
+        if (!option) return { error: 'no matching option' };
+        wanted = [option];
+    }
+    options.forEach((o) => { o.selected = wanted.includes(o); });
+    this.dispatchEvent(new Event('input', { bubbles: true }));
+    this.dispatchEvent(new Event('change', { bubbles: true }));
+    return { selected: options.filter((o) => o.selected).map((o) => o.value) };
+}"#;
+
+/// Serializes a copy of the DOM with scripts removed, stylesheets and images
+/// inlined where the page is allowed to read them, and a `<base>` so anything
+/// left external still resolves. The live page is not modified.
+const SINGLE_FILE_JS: &str = r#"async () => {
+    const toDataUrl = async (url) => {
+        try {
+            const resp = await fetch(url);
+            if (!resp.ok) return null;
+            const blob = await resp.blob();
+            return await new Promise((resolve) => {
+                const reader = new FileReader();
+                reader.onload = () => resolve(reader.result);
+                reader.onerror = () => resolve(null);
+                reader.readAsDataURL(blob);
+            });
+        } catch (e) {
+            return null;
+        }
+    };
+    const root = document.documentElement.cloneNode(true);
+    root.querySelectorAll('script, noscript').forEach((el) => el.remove());
+
+    for (const link of Array.from(root.querySelectorAll('link[rel~="stylesheet"]'))) {
+        const sheet = Array.from(document.styleSheets).find((s) => s.href === link.href);
+        let css = null;
+        try {
+            if (sheet) css = Array.from(sheet.cssRules).map((r) => r.cssText).join('\n');
+        } catch (e) {
+            // Cross-origin sheet; try fetching it instead
+        }
+        if (css === null) {
+            const resp = await fetch(link.href).catch(() => null);
+            css = resp && resp.ok ? await resp.text() : null;
+        }
+        if (css !== null) {
+            const style = document.createElement('style');
+            style.textContent = css;
+            link.replaceWith(style);
+        }
+    }
+
+    const live = Array.from(document.images);
+    await Promise.all(Array.from(root.querySelectorAll('img')).map(async (img, i) => {
+        const src = live[i] ? (live[i].currentSrc || live[i].src) : img.src;
+        if (!src || src.startsWith('data:')) return;
+        const data = await toDataUrl(src);
+        if (data) {
+            img.src = data;
+            img.removeAttribute('srcset');
+        }
+    }));
+
+    const head = root.querySelector('head');
+    if (head && !head.querySelector('base')) {
+        const base = document.createElement('base');
+        base.href = location.href;
+        head.prepend(base);
+    }
+    return '<!DOCTYPE html>\n' + root.outerHTML;
+}"#;
+
+/// In-page extraction script. Takes an optional selector and whether to include
+/// HTML; returns null when the selector matches nothing. Without a selector the
+/// main content is located readability-style: semantic containers first, then
+/// the block with the most paragraph text.
+const EXTRACT_CONTENT_JS: &str = r#"(selector, includeHtml) => {
+    const canonical = document.querySelector('link[rel="canonical"]');
+    const base = {
+        url: location.href,
+        canonical_url: canonical ? canonical.href : null,
+        title: document.title || '',
+    };
+    if (selector) {
+        const el = document.querySelector(selector);
+        if (!el) return null;
+        return { ...base, text: el.innerText.trim(), html: includeHtml ? el.outerHTML : null };
+    }
+    const pickMain = () => {
+        for (const sel of ['article', 'main', '[role="main"]']) {
+            const el = document.querySelector(sel);
+            if (el && el.innerText.trim().length > 200) return el;
+        }
+        let best = document.body, bestLen = 0;
+        for (const el of document.querySelectorAll('div, section')) {
+            let len = 0;
+            for (const p of el.querySelectorAll(':scope > p')) len += p.innerText.length;
+            if (len > bestLen) { best = el; bestLen = len; }
+        }
+        return best;
+    };
+    const main = pickMain() || document.body;
+    const clone = main.cloneNode(true);
+    clone.querySelectorAll('script, style, noscript, nav, header, footer, aside, form, iframe')
+        .forEach((el) => el.remove());
+    const text = (clone.innerText || clone.textContent || '')
+        .split('\n').map((l) => l.trim()).filter((l) => l.length > 0).join('\n');
+    return {
+        ...base,
+        text,
+        html: includeHtml ? document.documentElement.outerHTML : null,
+    };
+}"#;
+
+/// Paper dimensions in inches (width, height) for a named format
+fn paper_size(format: &str) -> Option<(f64, f64)> {
+    match format.to_lowercase().as_str() {
+        "letter" => Some((8.5, 11.0)),
+        "legal" => Some((8.5, 14.0)),
+        "tabloid" => Some((11.0, 17.0)),
+        "a3" => Some((11.69, 16.54)),
+        "a4" => Some((8.27, 11.69)),
+        "a5" => Some((5.83, 8.27)),
+        _ => None,
+    }
+}
+
+/// Map a requested image format to its CDP value and canonical name
+fn screenshot_format(format: &str) -> Result<(CaptureScreenshotFormat, &'static str), BrowserError> {
+    match format.to_lowercase().as_str() {
+        "png" => Ok((CaptureScreenshotFormat::Png, "png")),
+        "jpeg" | "jpg" => Ok((CaptureScreenshotFormat::Jpeg, "jpeg")),
+        "webp" => Ok((CaptureScreenshotFormat::Webp, "webp")),
+        _ => Err(BrowserError::InvalidRequest(format!("Unsupported image format: {}", format))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Every script handed to the page must at least parse
+    #[test]
+    fn test_embedded_scripts_parse() {
+        if Command::new("node").arg("--version").output().is_err() {
+            eprintln!("node not found; not checking embedded scripts");
+            return;
+        }
+        let scripts = [
+            ("SCROLL_POSITION_JS", SCROLL_POSITION_JS.to_string()),
+            ("CLEAR_VALUE_JS", CLEAR_VALUE_JS.to_string()),
+            ("set_checked_js", set_checked_js(true)),
+            ("select_option_js", select_option_js("it's \"quoted\"")),
+            ("FILE_INPUT_CAPACITY_JS", FILE_INPUT_CAPACITY_JS.to_string()),
+            ("QUERY_JS", QUERY_JS.to_string()),
+            ("SELECT_JS", SELECT_JS.replace("SPEC", r#"{"value":"a","label":null,"index":null,"values":null,"checked":null}"#)),
+            ("SINGLE_FILE_JS", SINGLE_FILE_JS.to_string()),
+            ("EXTRACT_CONTENT_JS", EXTRACT_CONTENT_JS.to_string()),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        for (name, script) in scripts {
+            let path = dir.path().join(format!("{}.js", name));
+            std::fs::write(&path, format!("({});\n", script)).unwrap();
+            let output = Command::new("node").arg("--check").arg(&path).output().unwrap();
+            assert!(output.status.success(), "{} does not parse: {}", name, String::from_utf8_lossy(&output.stderr));
         }
     }
 }
@@ -0,0 +1,182 @@
+use serde_json::json;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+
+/// A document format `/convert` reads or writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Pdf,
+    Docx,
+    Xlsx,
+    Html,
+    Markdown,
+    Text,
+    Csv,
+}
+
+/// Every conversion there is a tool for, as `(from, to)`
+pub const SUPPORTED: &[(Format, Format)] = &[
+    (Format::Pdf, Format::Text),
+    (Format::Docx, Format::Text),
+    (Format::Docx, Format::Markdown),
+    (Format::Xlsx, Format::Csv),
+    (Format::Html, Format::Markdown),
+    (Format::Html, Format::Text),
+    (Format::Markdown, Format::Pdf),
+    (Format::Markdown, Format::Html),
+];
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "pdf" => Some(Format::Pdf),
+            "docx" => Some(Format::Docx),
+            "xlsx" => Some(Format::Xlsx),
+            "html" | "htm" => Some(Format::Html),
+            "markdown" | "md" => Some(Format::Markdown),
+            "text" | "txt" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    /// The format a file's extension names
+    pub fn of(path: &Path) -> Option<Self> {
+        Self::parse(path.extension()?.to_str()?)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Pdf => "pdf",
+            Format::Docx => "docx",
+            Format::Xlsx => "xlsx",
+            Format::Html => "html",
+            Format::Markdown => "markdown",
+            Format::Text => "text",
+            Format::Csv => "csv",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Text => "txt",
+            format => format.name(),
+        }
+    }
+}
+
+/// Options some conversions take
+#[derive(Default)]
+pub struct Options {
+    /// Worksheet of an XLSX file to export; the first by default
+    pub sheet: Option<String>,
+}
+
+/// Convert `input` from `from` to `to`, writing `output`, and return the output's size
+pub async fn convert(from: Format, to: Format, input: &Path, output: &Path, options: &Options) -> Result<u64> {
+    let (program, args) = command(from, to, input, output, options).ok_or_else(|| unsupported(from, to))?;
+    if let Some(parent) = output.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+
+    let mut command = Command::new(program);
+    command.args(&args).stdin(Stdio::null()).kill_on_drop(true);
+    // Relative links in the document, such as images, resolve against its directory
+    if let Some(dir) = input.parent() {
+        command.current_dir(dir);
+    }
+    let result = command.output().await.map_err(|e| {
+        AppError::ServiceUnavailable(format!("Could not run {}: {}", program, e), 30)
+            .with_code("CONVERTER_UNAVAILABLE")
+    })?;
+    if !result.status.success() {
+        tokio::fs::remove_file(output).await.ok();
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        return Err(AppError::BadRequest(format!("{} failed: {}", program, stderr.trim()))
+            .with_code("CONVERSION_FAILED")
+            .with_details(json!({ "tool": program, "error": stderr.trim() })));
+    }
+    Ok(tokio::fs::metadata(output).await?.len())
+}
+
+/// The tool and its arguments for one conversion, if there is one
+fn command(from: Format, to: Format, input: &Path, output: &Path, options: &Options) -> Option<(&'static str, Vec<OsString>)> {
+    let pandoc = |from: &str, to: &str, extra: &[&str]| {
+        let mut args: Vec<OsString> = ["--from", from, "--to", to, "--wrap", "none"].iter().map(Into::into).collect();
+        args.extend(extra.iter().map(Into::into));
+        args.extend([input.into(), "--output".into(), output.into()]);
+        ("pandoc", args)
+    };
+    // Standalone HTML wants a title; the file name serves
+    let title = format!("pagetitle={}", input.file_stem().unwrap_or_default().to_string_lossy());
+
+    Some(match (from, to) {
+        (Format::Pdf, Format::Text) => ("pdftotext", vec!["-layout".into(), input.into(), output.into()]),
+        (Format::Docx, Format::Text) => pandoc("docx", "plain", &[]),
+        (Format::Docx, Format::Markdown) => pandoc("docx", "gfm", &[]),
+        (Format::Xlsx, Format::Csv) => {
+            let mut args: Vec<OsString> = Vec::new();
+            if let Some(sheet) = &options.sheet {
+                args.extend(["--sheetname".into(), sheet.into()]);
+            }
+            args.extend([input.into(), output.into()]);
+            ("xlsx2csv", args)
+        }
+        (Format::Html, Format::Markdown) => pandoc("html", "gfm", &[]),
+        (Format::Html, Format::Text) => pandoc("html", "plain", &[]),
+        (Format::Markdown, Format::Pdf) => {
+            pandoc("gfm", "html5", &["--standalone", "--metadata", &title, "--pdf-engine", "weasyprint"])
+        }
+        (Format::Markdown, Format::Html) => pandoc("gfm", "html5", &["--standalone", "--metadata", &title]),
+        _ => return None,
+    })
+}
+
+fn unsupported(from: Format, to: Format) -> AppError {
+    let supported: Vec<String> = SUPPORTED.iter().map(|(f, t)| format!("{}->{}", f.name(), t.name())).collect();
+    AppError::BadRequest(format!("Cannot convert {} to {}", from.name(), to.name()))
+        .with_code("UNSUPPORTED_CONVERSION")
+        .with_details(json!({ "supported": supported }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_of_path() {
+        assert_eq!(Format::of(Path::new("report.PDF")), Some(Format::Pdf));
+        assert_eq!(Format::of(Path::new("notes.md")), Some(Format::Markdown));
+        assert_eq!(Format::of(Path::new("page.htm")), Some(Format::Html));
+        assert_eq!(Format::of(Path::new("archive.zip")), None);
+        assert_eq!(Format::Text.extension(), "txt");
+    }
+
+    #[test]
+    fn test_every_supported_conversion_has_a_command() {
+        let (input, output) = (Path::new("/w/in"), Path::new("/w/out"));
+        for &(from, to) in SUPPORTED {
+            assert!(command(from, to, input, output, &Options::default()).is_some(), "{:?} -> {:?}", from, to);
+        }
+        assert!(command(Format::Pdf, Format::Docx, input, output, &Options::default()).is_none());
+    }
+
+    #[test]
+    fn test_command_arguments() {
+        let sheet = Options { sheet: Some("Q1".into()) };
+        let (program, args) = command(Format::Xlsx, Format::Csv, Path::new("a.xlsx"), Path::new("a.csv"), &sheet).unwrap();
+        assert_eq!(program, "xlsx2csv");
+        assert_eq!(args, ["--sheetname", "Q1", "a.xlsx", "a.csv"]);
+
+        let (program, args) =
+            command(Format::Markdown, Format::Pdf, Path::new("d/r.md"), Path::new("r.pdf"), &Options::default()).unwrap();
+        assert_eq!(program, "pandoc");
+        assert!(args.contains(&"pagetitle=r".into()));
+        assert_eq!(args.last().unwrap(), "r.pdf");
+    }
+}
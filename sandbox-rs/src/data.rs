@@ -0,0 +1,276 @@
+use arrow_json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::{json, Value};
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+
+/// Most bytes of an engine's error message kept for the response
+const MAX_ERROR_SIZE: u64 = 64 * 1024;
+
+/// The CLI a query runs in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    Sqlite,
+    Duckdb,
+}
+
+impl Engine {
+    /// `name` if given, otherwise by the database's extension: DuckDB for `.duckdb` files and
+    /// for no database at all, so CSV and Parquet files can be queried in memory
+    pub fn choose(name: Option<&str>, database: Option<&Path>) -> Result<Self> {
+        match name {
+            Some("sqlite") => Ok(Engine::Sqlite),
+            Some("duckdb") => Ok(Engine::Duckdb),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unsupported engine: {} (expected sqlite or duckdb)",
+                other
+            ))),
+            None => match database.and_then(|db| db.extension()).and_then(|e| e.to_str()) {
+                Some("duckdb" | "ddb") => Ok(Engine::Duckdb),
+                Some(_) => Ok(Engine::Sqlite),
+                None if database.is_some() => Ok(Engine::Sqlite),
+                None => Ok(Engine::Duckdb),
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Engine::Sqlite => "sqlite",
+            Engine::Duckdb => "duckdb",
+        }
+    }
+
+    fn program(&self) -> &'static str {
+        match self {
+            Engine::Sqlite => "sqlite3",
+            Engine::Duckdb => "duckdb",
+        }
+    }
+}
+
+/// SQL to run against a database file, or an in-memory one
+pub struct Query<'a> {
+    pub engine: Engine,
+    pub database: Option<&'a Path>,
+    pub sql: &'a str,
+    pub read_only: bool,
+    /// Directory relative paths in the SQL resolve against
+    pub workdir: &'a str,
+    pub max_rows: u64,
+    pub max_size: u64,
+}
+
+/// The rows of the last statement that returned any
+#[derive(Debug, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// Whether rows were left out to stay within `max_rows` or `max_size`
+    pub truncated: bool,
+}
+
+impl Query<'_> {
+    pub async fn run(&self) -> Result<QueryResult> {
+        // Without a database file both engines use an in-memory one
+        let mut args = vec!["-bail", "-json"];
+        if self.read_only && self.database.is_some() {
+            args.push("-readonly");
+        }
+        let program = self.engine.program();
+        let mut child = Command::new(program)
+            .args(args)
+            .args(self.database)
+            .current_dir(self.workdir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                AppError::ServiceUnavailable(format!("Could not run {}: {}", program, e), 30)
+                    .with_code("QUERY_ENGINE_UNAVAILABLE")
+            })?;
+
+        // Fed and drained alongside stdout, so neither pipe can fill up and stall the engine
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let sql = format!("{}\n", self.sql);
+        tokio::spawn(async move { stdin.write_all(sql.as_bytes()).await.ok() });
+        let mut stderr = child.stderr.take().expect("stderr is piped").take(MAX_ERROR_SIZE);
+        let stderr = tokio::spawn(async move {
+            let mut message = String::new();
+            stderr.read_to_string(&mut message).await.ok();
+            message
+        });
+
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let (mut result, mut size, mut line) = (QueryResult::default(), 0u64, Vec::new());
+        loop {
+            line.clear();
+            let room = match self.max_size {
+                0 => u64::MAX,
+                max => max.saturating_sub(size) + 1,
+            };
+            let read = (&mut stdout).take(room).read_until(b'\n', &mut line).await?;
+            if read == 0 {
+                break;
+            }
+            let Some(row) = parse_row(&line, program)? else {
+                continue;
+            };
+            // Another statement's rows replace those before
+            if row.0.len() != result.columns.len() || row.0.iter().zip(&result.columns).any(|((c, _), n)| c != n) {
+                result.columns = row.0.iter().map(|(column, _)| column.clone()).collect();
+                result.rows.clear();
+                size = 0;
+            }
+            size += read as u64;
+            let full = self.max_rows > 0 && result.rows.len() as u64 == self.max_rows;
+            if full || (self.max_size > 0 && size > self.max_size) {
+                result.truncated = true;
+                break;
+            }
+            result.rows.push(row.0.into_iter().map(|(_, value)| value).collect());
+        }
+
+        if result.truncated {
+            child.kill().await.ok();
+            return Ok(result);
+        }
+        let status = child.wait().await?;
+        if !status.success() {
+            let message = stderr.await.unwrap_or_default();
+            return Err(AppError::BadRequest(format!("Query failed: {}", message.trim()))
+                .with_code("QUERY_FAILED")
+                .with_details(json!({ "engine": self.engine.name(), "error": message.trim() })));
+        }
+        Ok(result)
+    }
+}
+
+/// One row of `-json` output, which both engines print a line at a time:
+/// `[{"a":1},` then `{"a":2}]`
+fn parse_row(line: &[u8], program: &str) -> Result<Option<Row>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    let line = line.strip_prefix('[').unwrap_or(line);
+    let line = line.strip_suffix(']').unwrap_or(line);
+    let line = line.strip_suffix(',').unwrap_or(line).trim();
+    if line.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(line)
+        .map(Some)
+        .map_err(|e| AppError::Internal(format!("Unexpected output from {}: {}", program, e)))
+}
+
+/// A row's columns in the order the engine printed them
+struct Row(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for Row {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        struct RowVisitor;
+
+        impl<'de> Visitor<'de> for RowVisitor {
+            type Value = Row;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a row object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Row, A::Error> {
+                let mut columns = Vec::new();
+                while let Some(column) = map.next_entry()? {
+                    columns.push(column);
+                }
+                Ok(Row(columns))
+            }
+        }
+
+        deserializer.deserialize_map(RowVisitor)
+    }
+}
+
+impl QueryResult {
+    /// The rows as an Arrow IPC stream, column types inferred from the values
+    pub fn to_arrow(&self) -> Result<Vec<u8>> {
+        let arrow_error = |e: ArrowError| AppError::Internal(format!("Encoding Arrow failed: {}", e));
+        let objects: Vec<Value> = self
+            .rows
+            .iter()
+            .map(|row| Value::Object(self.columns.iter().cloned().zip(row.iter().cloned()).collect()))
+            .collect();
+        let inferred = infer_json_schema_from_iterator(objects.iter().map(Ok)).map_err(arrow_error)?;
+        // Inference sorts the columns, and finds no type for a column of nulls
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .map(|name| match inferred.field_with_name(name) {
+                Ok(field) => field.clone(),
+                Err(_) => Field::new(name, DataType::Null, true),
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        let mut decoder = ReaderBuilder::new(schema.clone())
+            .with_batch_size(objects.len().max(1))
+            .with_coerce_primitive(true)
+            .build_decoder()
+            .map_err(arrow_error)?;
+        decoder.serialize(&objects).map_err(arrow_error)?;
+        let mut writer = arrow_ipc::writer::StreamWriter::try_new(Vec::new(), &schema).map_err(arrow_error)?;
+        if let Some(batch) = decoder.flush().map_err(arrow_error)? {
+            writer.write(&batch).map_err(arrow_error)?;
+        }
+        writer.finish().map_err(arrow_error)?;
+        writer.into_inner().map_err(arrow_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_choose_engine() {
+        let db = |path: &'static str| Some(Path::new(path));
+        assert_eq!(Engine::choose(None, db("data/app.db")).unwrap(), Engine::Sqlite);
+        assert_eq!(Engine::choose(None, db("warehouse.duckdb")).unwrap(), Engine::Duckdb);
+        assert_eq!(Engine::choose(None, None).unwrap(), Engine::Duckdb);
+        assert_eq!(Engine::choose(Some("duckdb"), db("app.db")).unwrap(), Engine::Duckdb);
+        assert!(Engine::choose(Some("postgres"), None).is_err());
+    }
+
+    #[test]
+    fn test_parse_row_keeps_column_order() {
+        let row = parse_row(br#"[{"z":1,"a":"x\ny"},"#, "sqlite3").unwrap().unwrap();
+        assert_eq!(row.0, vec![("z".to_string(), json!(1)), ("a".to_string(), json!("x\ny"))]);
+        assert!(parse_row(b"{\"a\":null}]\n", "sqlite3").unwrap().is_some());
+        assert!(parse_row(b"]\n", "duckdb").unwrap().is_none());
+        assert!(parse_row(b"Error: nope", "duckdb").is_err());
+    }
+
+    #[test]
+    fn test_to_arrow() {
+        let result = QueryResult {
+            columns: vec!["name".into(), "score".into(), "note".into()],
+            rows: vec![vec![json!("a"), json!(1), Value::Null], vec![json!("b"), json!(2.5), Value::Null]],
+            truncated: false,
+        };
+        let stream = result.to_arrow().unwrap();
+        let reader = arrow_ipc::reader::StreamReader::try_new(std::io::Cursor::new(stream), None).unwrap();
+        let schema = reader.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(names, ["name", "score", "note"]);
+        assert_eq!(schema.field(1).data_type(), &DataType::Float64);
+        let rows: usize = reader.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 2);
+    }
+}
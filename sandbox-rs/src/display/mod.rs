@@ -0,0 +1,197 @@
+pub mod recording;
+
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::error::{AppError, Result};
+
+/// How long one xdotool, xclip, or ImageMagick call may take
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A rectangle of the screen, in pixels
+#[derive(Debug, Clone, Copy, serde::Deserialize, utoipa::ToSchema)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// The X display the desktop runs on, e.g. `:99`, driven with xdotool, xclip, and ImageMagick
+pub struct Display<'a>(pub &'a str);
+
+impl Display<'_> {
+    /// Width and height of the screen
+    pub async fn geometry(&self) -> Result<(u32, u32)> {
+        let output = self.run("xdotool", &["getdisplaygeometry"]).await?;
+        let text = String::from_utf8_lossy(&output);
+        let mut dims = text.split_whitespace().map(str::parse::<u32>);
+        match (dims.next(), dims.next()) {
+            (Some(Ok(width)), Some(Ok(height))) => Ok((width, height)),
+            _ => Err(AppError::Internal(format!("Unexpected display geometry '{}'", text.trim()))),
+        }
+    }
+
+    /// The screen, or `region` of it, as a `png` or `jpeg` image with its width and height
+    pub async fn screenshot(
+        &self,
+        format: &str,
+        quality: Option<u8>,
+        region: Option<Region>,
+    ) -> Result<(Vec<u8>, u32, u32)> {
+        if !matches!(format, "png" | "jpeg") {
+            return Err(AppError::BadRequest(format!("Unsupported format '{}', expected png or jpeg", format)));
+        }
+        let (screen_width, screen_height) = self.geometry().await?;
+        let mut size = (screen_width, screen_height);
+        let mut args = vec!["-window".to_string(), "root".to_string()];
+        if let Some(r) = region {
+            check_within(r.x, r.y, (screen_width, screen_height))?;
+            size = (r.width.min(screen_width - r.x), r.height.min(screen_height - r.y));
+            args.extend(["-crop".into(), format!("{}x{}+{}+{}", size.0, size.1, r.x, r.y), "+repage".into()]);
+        }
+        if let Some(quality) = quality {
+            args.extend(["-quality".into(), quality.min(100).to_string()]);
+        }
+        args.push(format!("{}:-", format));
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let image = self.run("import", &args).await?;
+        Ok((image, size.0, size.1))
+    }
+
+    /// Move the pointer to `x`,`y` and click `button` `count` times
+    pub async fn click(&self, x: u32, y: u32, button: &str, count: u32) -> Result<()> {
+        let button = match button {
+            "left" => "1",
+            "middle" => "2",
+            "right" => "3",
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown button '{}', expected left, middle, or right",
+                    other
+                )))
+            }
+        };
+        check_within(x, y, self.geometry().await?)?;
+        let (x, y, count) = (x.to_string(), y.to_string(), count.max(1).to_string());
+        self.run("xdotool", &["mousemove", "--sync", &x, &y, "click", "--repeat", &count, button])
+            .await
+            .map(|_| ())
+    }
+
+    /// Type `text` into the focused window, `delay_ms` apart per character
+    pub async fn type_text(&self, text: &str, delay_ms: u64) -> Result<()> {
+        let delay = delay_ms.to_string();
+        self.run("xdotool", &["type", "--delay", &delay, "--", text]).await.map(|_| ())
+    }
+
+    /// Press key combinations in order, e.g. `ctrl+l` then `Return`
+    pub async fn keys(&self, keys: &[String]) -> Result<()> {
+        if keys.is_empty() {
+            return Err(AppError::BadRequest("No keys given".into()));
+        }
+        let mut args = vec!["key", "--"];
+        args.extend(keys.iter().map(String::as_str));
+        self.run("xdotool", &args).await.map(|_| ())
+    }
+
+    /// Text in `selection`, `clipboard` or `primary`; empty when nothing holds it
+    pub async fn clipboard(&self, selection: &str) -> Result<String> {
+        check_selection(selection)?;
+        match self.run("xclip", &["-selection", selection, "-out"]).await {
+            Ok(text) => Ok(String::from_utf8_lossy(&text).into_owned()),
+            // xclip reports an empty selection as an error
+            Err(AppError::Internal(msg)) if msg.contains("not available") => Ok(String::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Put `text` in `selection`, served by an xclip left running until another
+    /// application takes the selection
+    pub async fn set_clipboard(&self, selection: &str, text: &str) -> Result<()> {
+        check_selection(selection)?;
+        // xclip forks to serve the selection, and the fork would hold on to output pipes
+        let mut child = Command::new("xclip")
+            .args(["-selection", selection, "-in"])
+            .env("DISPLAY", self.0)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| {
+                AppError::ServiceUnavailable(format!("Could not run xclip: {}", e), 30).with_code("DISPLAY_UNAVAILABLE")
+            })?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).await?;
+        }
+        let status = tokio::time::timeout(COMMAND_TIMEOUT, child.wait())
+            .await
+            .map_err(|_| AppError::Timeout(format!("xclip did not finish within {}s", COMMAND_TIMEOUT.as_secs())))??;
+        if !status.success() {
+            return Err(AppError::ServiceUnavailable(format!("xclip exited with {} on display {}", status, self.0), 30)
+                .with_code("DISPLAY_UNAVAILABLE"));
+        }
+        Ok(())
+    }
+
+    /// Run `program` against this display, returning its stdout
+    async fn run(&self, program: &str, args: &[&str]) -> Result<Vec<u8>> {
+        let output = Command::new(program).args(args).env("DISPLAY", self.0).kill_on_drop(true).output();
+        let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+            .await
+            .map_err(|_| AppError::Timeout(format!("{} did not finish within {}s", program, COMMAND_TIMEOUT.as_secs())))?
+            .map_err(|e| {
+                AppError::ServiceUnavailable(format!("Could not run {}: {}", program, e), 30)
+                    .with_code("DISPLAY_UNAVAILABLE")
+            })?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        if stderr.to_lowercase().contains("open display") {
+            return Err(AppError::ServiceUnavailable(format!("Display {} is not running: {}", self.0, stderr), 30)
+                .with_code("DISPLAY_UNAVAILABLE"));
+        }
+        Err(AppError::Internal(format!("{} failed: {}", program, stderr)))
+    }
+}
+
+fn check_selection(selection: &str) -> Result<()> {
+    if !matches!(selection, "clipboard" | "primary") {
+        return Err(AppError::BadRequest(format!(
+            "Unknown selection '{}', expected clipboard or primary",
+            selection
+        )));
+    }
+    Ok(())
+}
+
+fn check_within(x: u32, y: u32, (width, height): (u32, u32)) -> Result<()> {
+    if x >= width || y >= height {
+        return Err(AppError::BadRequest(format!("Point {},{} is outside the {}x{} screen", x, y, width, height)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_within() {
+        assert!(check_within(0, 0, (1920, 1080)).is_ok());
+        assert!(check_within(1919, 1079, (1920, 1080)).is_ok());
+        assert!(check_within(1920, 0, (1920, 1080)).is_err());
+        assert!(check_within(0, 1080, (1920, 1080)).is_err());
+    }
+
+    #[test]
+    fn test_check_selection() {
+        assert!(check_selection("clipboard").is_ok());
+        assert!(check_selection("primary").is_ok());
+        assert!(check_selection("secondary").is_err());
+    }
+}
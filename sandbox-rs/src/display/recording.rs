@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+
+use super::{check_within, Display, Region};
+use crate::error::{AppError, Result};
+
+/// How long ffmpeg gets to show it can open the display before a start counts as successful
+const STARTUP_GRACE: Duration = Duration::from_millis(500);
+
+/// How long ffmpeg gets to finish the file after being asked to stop
+const STOP_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Limits on one recording; ffmpeg ends it when either is reached
+pub struct Limits {
+    pub max_duration: u64,
+    pub max_size: u64,
+}
+
+/// ffmpeg grabbing the X display into a video file
+pub struct ScreenRecording {
+    child: Child,
+    path: PathBuf,
+    started: Instant,
+    max_duration: u64,
+}
+
+/// What a finished recording produced
+pub struct Recorded {
+    pub path: PathBuf,
+    pub duration_secs: f64,
+    pub size: u64,
+    /// Whether a limit ended the recording before it was stopped
+    pub truncated: bool,
+}
+
+impl ScreenRecording {
+    /// Start recording `display`, or `region` of it, to `output` (WebM or MP4 by extension)
+    pub async fn start(
+        display: &Display<'_>,
+        output: &Path,
+        framerate: u32,
+        region: Option<Region>,
+        limits: Limits,
+    ) -> Result<Self> {
+        let (screen_width, screen_height) = display.geometry().await?;
+        let (mut input, mut size) = (display.0.to_string(), (screen_width, screen_height));
+        if let Some(r) = region {
+            check_within(r.x, r.y, size)?;
+            size = (r.width.min(screen_width - r.x), r.height.min(screen_height - r.y));
+            input = format!("{}+{},{}", display.0, r.x, r.y);
+        }
+        if let Some(parent) = output.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let codec: &[&str] = match output.extension().and_then(|e| e.to_str()) {
+            Some("mp4") => &["-c:v", "libx264", "-preset", "veryfast", "-movflags", "+faststart"],
+            _ => &["-c:v", "libvpx-vp9", "-deadline", "realtime", "-cpu-used", "8", "-b:v", "0", "-crf", "36"],
+        };
+        let mut command = Command::new("ffmpeg");
+        command
+            .args(["-y", "-loglevel", "error", "-f", "x11grab"])
+            .args(["-framerate", &framerate.clamp(1, 60).to_string()])
+            .args(["-video_size", &format!("{}x{}", size.0, size.1), "-i", &input]);
+        if limits.max_duration > 0 {
+            command.args(["-t", &limits.max_duration.to_string()]);
+        }
+        if limits.max_size > 0 {
+            command.args(["-fs", &limits.max_size.to_string()]);
+        }
+        let mut child = command
+            .args(codec)
+            // Encoders need even dimensions
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+            .arg(output)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                AppError::ServiceUnavailable(format!("Could not run ffmpeg: {}", e), 30).with_code("DISPLAY_UNAVAILABLE")
+            })?;
+
+        let started = Instant::now();
+        // A display ffmpeg cannot open makes it exit straight away
+        tokio::time::sleep(STARTUP_GRACE).await;
+        if child.try_wait()?.is_some() {
+            return Err(AppError::Internal(format!("ffmpeg failed to start: {}", stderr_of(&mut child).await))
+                .with_code("RECORDING_FAILED"));
+        }
+        Ok(Self { child, path: output.to_path_buf(), started, max_duration: limits.max_duration })
+    }
+
+    /// Ask ffmpeg to finish the file, killing it if it does not
+    pub async fn stop(mut self) -> Result<Recorded> {
+        let duration_secs = self.started.elapsed().as_secs_f64();
+        // ffmpeg has already exited if it reached a limit
+        let truncated = self.child.try_wait()?.is_some();
+        if !truncated {
+            if let Some(mut stdin) = self.child.stdin.take() {
+                stdin.write_all(b"q").await.ok();
+            }
+        }
+        let status = match tokio::time::timeout(STOP_TIMEOUT, self.child.wait()).await {
+            Ok(status) => status?,
+            Err(_) => {
+                tracing::warn!("ffmpeg did not stop within {}s, killing it", STOP_TIMEOUT.as_secs());
+                self.child.kill().await.ok();
+                self.child.wait().await?
+            }
+        };
+        let size = tokio::fs::metadata(&self.path).await.map(|m| m.len()).unwrap_or(0);
+        if size == 0 {
+            return Err(AppError::Internal(format!(
+                "ffmpeg exited with {}: {}",
+                status,
+                stderr_of(&mut self.child).await
+            ))
+            .with_code("RECORDING_FAILED"));
+        }
+        // The size limit may have ended it earlier still, which only the file itself tells
+        let duration_secs = match self.max_duration {
+            0 => duration_secs,
+            max => duration_secs.min(max as f64),
+        };
+        Ok(Recorded { path: self.path, duration_secs, size, truncated })
+    }
+}
+
+async fn stderr_of(child: &mut Child) -> String {
+    let mut stderr = String::new();
+    if let Some(mut pipe) = child.stderr.take() {
+        pipe.read_to_string(&mut stderr).await.ok();
+    }
+    stderr.trim().to_string()
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::error::AppError;
+use crate::ratelimit::{hold_until_sent, is_execution};
+use crate::state::AppState;
+
+/// Seconds a client is asked to wait before retrying an execution refused while draining
+const DRAIN_RETRY_AFTER: u64 = 30;
+
+/// Drain mode, for rolling upgrades: new executions are refused while running
+/// ones finish, and reads keep working. Shared by every session.
+pub struct Drain {
+    since: Mutex<Option<DateTime<Utc>>>,
+    running: Arc<AtomicUsize>,
+}
+
+/// Counts one execution as running until dropped
+struct Running(Arc<AtomicUsize>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Drain {
+    pub fn new(draining: bool) -> Self {
+        Self {
+            since: Mutex::new(draining.then(Utc::now)),
+            running: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Start draining, keeping the original start time if already draining
+    pub fn start(&self) {
+        let mut since = self.since.lock().unwrap_or_else(PoisonError::into_inner);
+        if since.is_none() {
+            *since = Some(Utc::now());
+            tracing::info!("Draining: refusing new executions");
+        }
+    }
+
+    /// Accept executions again
+    pub fn stop(&self) {
+        if self.since.lock().unwrap_or_else(PoisonError::into_inner).take().is_some() {
+            tracing::info!("Drain ended: accepting executions");
+        }
+    }
+
+    /// When draining began, or `None` when not draining
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        *self.since.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Executions still running, streams included
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    fn begin(&self) -> Running {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        Running(self.running.clone())
+    }
+}
+
+/// Refuse executions with 503 while draining, and count the ones that run until
+/// their response, stream or not, has been sent
+pub async fn enforce(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if !is_execution(request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+    if let Some(since) = state.drain.since() {
+        return AppError::ServiceUnavailable(
+            format!("Server is draining since {} and accepts no new executions", since.to_rfc3339()),
+            DRAIN_RETRY_AFTER,
+        )
+        .with_code("DRAINING")
+        .into_response();
+    }
+    let running = state.drain.begin();
+    hold_until_sent(next.run(request).await, running)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_keeps_start_time_and_counts_running() {
+        let drain = Drain::new(false);
+        assert!(drain.since().is_none());
+
+        drain.start();
+        let since = drain.since().unwrap();
+        drain.start();
+        assert_eq!(drain.since(), Some(since));
+
+        let running = drain.begin();
+        assert_eq!(drain.running(), 1);
+        drop(running);
+        assert_eq!(drain.running(), 0);
+
+        drain.stop();
+        assert!(drain.since().is_none());
+        assert!(Drain::new(true).since().is_some());
+    }
+}
@@ -1,9 +1,9 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde_json::json;
+use serde_json::{json, Value};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -13,6 +13,25 @@ pub enum AppError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Unauthorized: {0}")]
+    #[cfg_attr(not(feature = "tee"), allow(dead_code))]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// The body limit in bytes that was exceeded
+    #[error("Request body exceeds the limit of {0} bytes")]
+    PayloadTooLarge(usize),
+
+    /// Message and seconds until the client may retry
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String, u64),
+
+    /// Message and seconds until the client may retry
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String, u64),
+
     #[error("Timeout: {0}")]
     Timeout(String),
 
@@ -21,20 +40,115 @@ pub enum AppError {
 
     #[error(transparent)]
     Io(#[from] std::io::Error),
+
+    /// Another error with a more specific `code`, and `details` for clients to act on
+    #[error("{error}")]
+    Coded {
+        error: Box<AppError>,
+        code: &'static str,
+        details: Option<Value>,
+    },
+}
+
+impl AppError {
+    /// This error with a stable code clients can match on, such as `SKILL_EXISTS`
+    pub fn with_code(self, code: &'static str) -> Self {
+        match self {
+            AppError::Coded { error, details, .. } => AppError::Coded { error, code, details },
+            error => AppError::Coded { error: Box::new(error), code, details: None },
+        }
+    }
+
+    /// This error with structured details, such as the limit that was hit
+    pub fn with_details(self, details: Value) -> Self {
+        match self {
+            AppError::Coded { error, code, .. } => AppError::Coded { error, code, details: Some(details) },
+            error => {
+                let code = code_for_status(error.status());
+                AppError::Coded { error: Box::new(error), code, details: Some(details) }
+            }
+        }
+    }
+
+    /// A command, snippet, or script that ran past its `timeout` seconds
+    pub fn exec_timeout(message: &str, timeout: u64) -> Self {
+        AppError::Timeout(message.into())
+            .with_code("EXEC_TIMEOUT")
+            .with_details(json!({ "timeout": timeout }))
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::TooManyRequests(..) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::ServiceUnavailable(..) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Timeout(_) => StatusCode::REQUEST_TIMEOUT,
+            AppError::Internal(_) | AppError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Coded { error, .. } => error.status(),
+        }
+    }
+
+    /// The message shown as `error`, without the variant's prefix
+    fn message(&self) -> String {
+        match self {
+            AppError::NotFound(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::TooManyRequests(msg, _)
+            | AppError::ServiceUnavailable(msg, _)
+            | AppError::Timeout(msg)
+            | AppError::Internal(msg) => msg.clone(),
+            AppError::PayloadTooLarge(_) | AppError::Io(_) => self.to_string(),
+            AppError::Coded { error, .. } => error.message(),
+        }
+    }
+}
+
+/// The `code` of an error that has no more specific one, by its status
+pub fn code_for_status(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "BAD_REQUEST",
+        StatusCode::UNAUTHORIZED => "UNAUTHORIZED",
+        StatusCode::FORBIDDEN => "FORBIDDEN",
+        StatusCode::NOT_FOUND => "NOT_FOUND",
+        StatusCode::METHOD_NOT_ALLOWED => "METHOD_NOT_ALLOWED",
+        StatusCode::REQUEST_TIMEOUT => "TIMEOUT",
+        StatusCode::PAYLOAD_TOO_LARGE => "PAYLOAD_TOO_LARGE",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE => "UNSUPPORTED_MEDIA_TYPE",
+        StatusCode::UNPROCESSABLE_ENTITY => "INVALID_BODY",
+        StatusCode::TOO_MANY_REQUESTS => "RATE_LIMITED",
+        StatusCode::SERVICE_UNAVAILABLE => "UNAVAILABLE",
+        status if status.is_server_error() => "INTERNAL",
+        _ => "ERROR",
+    }
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, message) = match &self {
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
-            AppError::Timeout(msg) => (StatusCode::REQUEST_TIMEOUT, msg.clone()),
-            AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
-            AppError::Io(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        let status = self.status();
+        let (error, code, details) = match self {
+            AppError::Coded { error, code, details } => (*error, code, details),
+            error => (error, code_for_status(status), None),
         };
 
-        let body = Json(json!({ "error": message }));
-        (status, body).into_response()
+        let mut body = json!({ "error": error.message(), "code": code });
+        if let Some(details) = details {
+            body["details"] = details;
+        }
+        match &error {
+            AppError::TooManyRequests(_, retry_after) | AppError::ServiceUnavailable(_, retry_after) => {
+                body["retry_after"] = json!(retry_after);
+                return (status, [(header::RETRY_AFTER, retry_after.to_string())], Json(body)).into_response();
+            }
+            AppError::PayloadTooLarge(limit) => body["limit"] = json!(limit),
+            _ => {}
+        }
+        (status, Json(body)).into_response()
     }
 }
 
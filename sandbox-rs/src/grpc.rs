@@ -0,0 +1,349 @@
+use axum::extract::{Path, Query, State};
+use axum::{Json, Router};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tonic::server::NamedService;
+use tonic::service::Routes;
+use tonic::{Request, Response, Status};
+
+use crate::error::AppError;
+use crate::handlers::{self, StreamOutput};
+use crate::state::AppState;
+
+pub mod proto {
+    tonic::include_proto!("sandbox.v1");
+}
+
+use proto::code_server::{Code, CodeServer};
+use proto::files_server::{Files, FilesServer};
+use proto::shell_server::{Shell, ShellServer};
+use proto::skills_server::{Skills, SkillsServer};
+use proto::*;
+
+/// Size of `Files.Download` chunks
+const CHUNK_SIZE: usize = 64 * 1024;
+
+type ResponseStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+/// Add the gRPC services to the router. They go through the same middleware
+/// as the REST endpoints, since every RPC is a `POST /<package.Service>/<Method>`.
+pub fn mount(app: Router<Arc<AppState>>, state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    let limit = state.config.max_body_bytes;
+    let routes = Routes::new(ShellServer::new(GrpcApi(state.clone())).max_decoding_message_size(limit))
+        .add_service(CodeServer::new(GrpcApi(state.clone())).max_decoding_message_size(limit))
+        .add_service(FilesServer::new(GrpcApi(state.clone())).max_decoding_message_size(limit))
+        .add_service(SkillsServer::new(GrpcApi(state.clone())).max_decoding_message_size(limit));
+
+    let services = [
+        <ShellServer<GrpcApi> as NamedService>::NAME,
+        <CodeServer<GrpcApi> as NamedService>::NAME,
+        <FilesServer<GrpcApi> as NamedService>::NAME,
+        <SkillsServer<GrpcApi> as NamedService>::NAME,
+    ];
+    services.iter().fold(app, |app, service| {
+        app.route_service(&format!("/{}/{{method}}", service), routes.clone())
+    })
+}
+
+/// Implements every service by calling the REST handlers
+#[derive(Clone)]
+pub struct GrpcApi(Arc<AppState>);
+
+impl From<AppError> for Status {
+    fn from(e: AppError) -> Self {
+        match e {
+            AppError::NotFound(msg) => Status::not_found(msg),
+            AppError::BadRequest(msg) => Status::invalid_argument(msg),
+            AppError::Unauthorized(msg) => Status::unauthenticated(msg),
+            AppError::Forbidden(msg) => Status::permission_denied(msg),
+            AppError::PayloadTooLarge(_) | AppError::TooManyRequests(..) => Status::resource_exhausted(e.to_string()),
+            AppError::ServiceUnavailable(msg, _) => Status::unavailable(msg),
+            AppError::Timeout(msg) => Status::deadline_exceeded(msg),
+            AppError::Internal(msg) => Status::internal(msg),
+            AppError::Io(e) => Status::internal(e.to_string()),
+            AppError::Coded { error, .. } => Status::from(*error),
+        }
+    }
+}
+
+fn exec_request(req: ExecRequest) -> handlers::ShellExecRequest {
+    handlers::ShellExecRequest {
+        command: req.command,
+        cwd: req.cwd,
+        timeout: req.timeout.unwrap_or(30),
+        env: Some(req.env),
+        attestation: Default::default(),
+    }
+}
+
+#[tonic::async_trait]
+impl Shell for GrpcApi {
+    async fn exec(&self, request: Request<ExecRequest>) -> Result<Response<ExecResponse>, Status> {
+        let req = exec_request(request.into_inner());
+        let Json(res) = handlers::exec_command(State(self.0.clone()), Json(req)).await?;
+        Ok(Response::new(ExecResponse {
+            stdout: res.stdout,
+            stderr: res.stderr,
+            exit_code: res.exit_code,
+            duration_ms: res.duration_ms,
+        }))
+    }
+
+    type StreamStream = ResponseStream<OutputLine>;
+
+    async fn stream(&self, request: Request<ExecRequest>) -> Result<Response<Self::StreamStream>, Status> {
+        let req = exec_request(request.into_inner());
+        let lines = handlers::stream_output(self.0.clone(), req).map(|output| {
+            let line = match output {
+                StreamOutput::Line(line) => output_line::Line::Stdout(line),
+                StreamOutput::Exit(code) => output_line::Line::ExitCode(code),
+                StreamOutput::Error(e) => output_line::Line::Error(e),
+            };
+            Ok(OutputLine { line: Some(line) })
+        });
+        Ok(Response::new(Box::pin(lines)))
+    }
+}
+
+#[tonic::async_trait]
+impl Code for GrpcApi {
+    async fn execute(&self, request: Request<CodeRequest>) -> Result<Response<CodeResponse>, Status> {
+        let req = request.into_inner();
+        let req = handlers::CodeExecRequest {
+            code: req.code,
+            language: req.language,
+            timeout: req.timeout.unwrap_or(30),
+            attestation: Default::default(),
+        };
+        let Json(res) = handlers::execute_code(State(self.0.clone()), Json(req)).await?;
+        Ok(Response::new(CodeResponse {
+            output: res.output,
+            error: res.error,
+            exit_code: res.exit_code,
+            duration_ms: res.duration_ms,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl Files for GrpcApi {
+    async fn read(&self, request: Request<ReadFileRequest>) -> Result<Response<ReadFileResponse>, Status> {
+        let query = handlers::FileReadQuery {
+            path: request.into_inner().path,
+            encoding: "utf-8".into(),
+        };
+        let Json(res) = handlers::read_file(State(self.0.clone()), Query(query)).await?;
+        Ok(Response::new(ReadFileResponse {
+            content: res.content,
+            size: res.size,
+            mime_type: res.mime_type,
+        }))
+    }
+
+    async fn write(&self, request: Request<WriteFileRequest>) -> Result<Response<WriteFileResponse>, Status> {
+        let req = request.into_inner();
+        let req = handlers::FileWriteRequest {
+            path: req.path,
+            content: req.content,
+            mode: req.mode.unwrap_or_else(|| "644".into()),
+        };
+        let Json(res) = handlers::write_file(State(self.0.clone()), Json(req)).await?;
+        Ok(Response::new(WriteFileResponse { path: res.path, size: res.size }))
+    }
+
+    async fn list(&self, request: Request<ListFilesRequest>) -> Result<Response<ListFilesResponse>, Status> {
+        let req = request.into_inner();
+        let query = handlers::FileListQuery { path: req.path, recursive: req.recursive };
+        let Json(res) = handlers::list_files(State(self.0.clone()), Query(query)).await?;
+        Ok(Response::new(ListFilesResponse {
+            path: res.path,
+            entries: res
+                .entries
+                .into_iter()
+                .map(|entry| FileEntry {
+                    name: entry.name,
+                    path: entry.path,
+                    r#type: entry.file_type,
+                    size: entry.size,
+                    modified: entry.modified,
+                })
+                .collect(),
+        }))
+    }
+
+    type DownloadStream = ResponseStream<FileChunk>;
+
+    async fn download(&self, request: Request<DownloadRequest>) -> Result<Response<Self::DownloadStream>, Status> {
+        let path = self.0.resolve(&request.into_inner().path)?;
+        let mut file = tokio::fs::File::open(&path).await.map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => Status::not_found("File not found"),
+            _ => Status::internal(e.to_string()),
+        })?;
+
+        let chunks = async_stream::stream! {
+            let mut buf = vec![0; CHUNK_SIZE];
+            loop {
+                match file.read(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(n) => yield Ok(FileChunk { data: buf[..n].to_vec() }),
+                    Err(e) => {
+                        yield Err(Status::internal(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        };
+        Ok(Response::new(Box::pin(chunks)))
+    }
+}
+
+#[tonic::async_trait]
+impl Skills for GrpcApi {
+    async fn list(&self, request: Request<ListSkillsRequest>) -> Result<Response<ListSkillsResponse>, Status> {
+        let state = State(self.0.clone());
+        let Json(res) = match request.into_inner().query {
+            Some(q) => handlers::search_skills(state, Query(handlers::SearchQuery { q })).await?,
+            None => handlers::list_skills(state).await?,
+        };
+        Ok(Response::new(ListSkillsResponse {
+            skills: res
+                .skills
+                .into_iter()
+                .map(|skill| SkillSummary { name: skill.name, description: skill.description })
+                .collect(),
+        }))
+    }
+
+    async fn get(&self, request: Request<GetSkillRequest>) -> Result<Response<Skill>, Status> {
+        let Json(skill) = handlers::get_skill(State(self.0.clone()), Path(request.into_inner().name)).await?;
+        Ok(Response::new(Skill {
+            name: skill.meta.name,
+            description: skill.meta.description,
+            license: skill.meta.license,
+            compatibility: skill.meta.compatibility,
+            body: skill.body,
+            scripts: skill.scripts,
+            references: skill.references,
+            assets: skill.assets,
+        }))
+    }
+
+    async fn run_script(&self, request: Request<RunScriptRequest>) -> Result<Response<RunScriptResponse>, Status> {
+        let req = request.into_inner();
+        let body = handlers::ExecuteScriptRequest {
+            args: req.args,
+            env: req.env,
+            attestation: Default::default(),
+        };
+        let Json(res) =
+            handlers::execute_script(State(self.0.clone()), Path((req.name, req.script)), Json(body)).await?;
+        Ok(Response::new(RunScriptResponse {
+            stdout: res.stdout,
+            stderr: res.stderr,
+            exit_code: res.exit_code,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{Body, HttpBody};
+    use axum::http::HeaderMap;
+    use prost::Message;
+    use tower::ServiceExt;
+
+    fn test_state() -> Arc<AppState> {
+        let args = crate::config::Args::parse(["--audit-log=".to_string()]).unwrap();
+        AppState::new(crate::config::Config::load(&args).unwrap(), args)
+    }
+
+    /// Call `path` with a length-prefixed message, returning the reply messages and
+    /// trailers, or the headers of a trailers-only error reply
+    async fn call(path: &str, message: impl Message) -> (Vec<Vec<u8>>, HeaderMap) {
+        let state = test_state();
+        // Through the timeout middleware, which wraps the body, to check trailers survive it
+        let app = mount(Router::new(), &state)
+            .layer(axum::middleware::from_fn_with_state(state.clone(), crate::overload::timeout))
+            .with_state(state);
+
+        let encoded = message.encode_to_vec();
+        let mut framed = vec![0];
+        framed.extend_from_slice(&(encoded.len() as u32).to_be_bytes());
+        framed.extend_from_slice(&encoded);
+        let request = axum::http::Request::post(path)
+            .header("content-type", "application/grpc")
+            .header("te", "trailers")
+            .body(Body::from(framed))
+            .unwrap();
+
+        let (parts, mut body) = app.oneshot(request).await.unwrap().into_parts();
+        let mut data = Vec::new();
+        let mut trailers = parts.headers;
+        while let Some(frame) = futures::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await {
+            let frame = frame.unwrap();
+            if let Some(chunk) = frame.data_ref() {
+                data.extend_from_slice(chunk);
+            } else if let Ok(headers) = frame.into_trailers() {
+                trailers.extend(headers);
+            }
+        }
+
+        let mut messages = Vec::new();
+        let mut rest = &data[..];
+        while rest.len() >= 5 {
+            let len = u32::from_be_bytes(rest[1..5].try_into().unwrap()) as usize;
+            messages.push(rest[5..5 + len].to_vec());
+            rest = &rest[5 + len..];
+        }
+        (messages, trailers)
+    }
+
+    #[tokio::test]
+    async fn test_exec() {
+        let dir = tempfile::tempdir().unwrap();
+        let request = ExecRequest {
+            command: "echo hi".into(),
+            cwd: Some(dir.path().display().to_string()),
+            ..Default::default()
+        };
+        let (messages, trailers) = call("/sandbox.v1.Shell/Exec", request).await;
+        assert_eq!(trailers["grpc-status"], "0");
+        let response = ExecResponse::decode(&messages[0][..]).unwrap();
+        assert_eq!(response.stdout, "hi\n");
+        assert_eq!(response.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_with_exit_code() {
+        let dir = tempfile::tempdir().unwrap();
+        let request = ExecRequest {
+            command: "echo a; echo b; exit 3".into(),
+            cwd: Some(dir.path().display().to_string()),
+            ..Default::default()
+        };
+        let (messages, trailers) = call("/sandbox.v1.Shell/Stream", request).await;
+        assert_eq!(trailers["grpc-status"], "0");
+        let lines: Vec<_> = messages
+            .iter()
+            .map(|m| OutputLine::decode(&m[..]).unwrap().line.unwrap())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                output_line::Line::Stdout("a".into()),
+                output_line::Line::Stdout("b".into()),
+                output_line::Line::ExitCode(3),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_map_to_status() {
+        let request = ReadFileRequest { path: "/nonexistent/file".into() };
+        let (_, trailers) = call("/sandbox.v1.Files/Read", request).await;
+        assert_eq!(trailers["grpc-status"], (tonic::Code::NotFound as i32).to_string());
+    }
+}
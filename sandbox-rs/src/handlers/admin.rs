@@ -0,0 +1,163 @@
+use crate::audit::{AuditEntry, AuditFilter};
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::reload::{self, ReloadReport};
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+// GET /admin/config - Effective configuration, with credentials redacted
+#[utoipa::path(
+    get,
+    path = "/admin/config",
+    tag = "admin",
+    summary = "Effective configuration after merging defaults, config file, env, and flags",
+    responses((status = 200, body = Object, description = "Settings keyed by config file name")),
+)]
+pub async fn get_config(State(state): State<Arc<AppState>>) -> Json<Config> {
+    Json(state.live.current().redacted())
+}
+
+// POST /admin/reload - Re-read configuration and apply tunable settings
+#[utoipa::path(
+    post,
+    path = "/admin/reload",
+    tag = "admin",
+    summary = "Re-read the config file and apply policies, limits, and triggers without restarting",
+    responses((status = 200, body = ReloadReport)),
+)]
+pub async fn reload_config(State(state): State<Arc<AppState>>) -> Result<Json<ReloadReport>> {
+    reload::reload(&state)
+        .await
+        .map(Json)
+        .map_err(|e| AppError::BadRequest(format!("Invalid configuration: {:#}", e)))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminStatus {
+    /// Whether new executions are refused
+    pub draining: bool,
+    /// When draining began
+    pub drain_started_at: Option<DateTime<Utc>>,
+    /// Executions still running, streams included: shell, code, browser, skill scripts, fetches, queries, conversions, and package installs
+    pub running_executions: usize,
+    pub sessions: usize,
+    pub uptime: f64,
+}
+
+fn admin_status_of(state: &AppState) -> AdminStatus {
+    let since = state.drain.since();
+    AdminStatus {
+        draining: since.is_some(),
+        drain_started_at: since,
+        running_executions: state.drain.running(),
+        sessions: state.sessions.list().len(),
+        uptime: state.uptime_secs(),
+    }
+}
+
+// GET /admin/status - Drain state and running executions
+#[utoipa::path(
+    get,
+    path = "/admin/status",
+    tag = "admin",
+    summary = "Whether the server is draining, and how many executions are still running",
+    responses((status = 200, body = AdminStatus)),
+)]
+pub async fn admin_status(State(state): State<Arc<AppState>>) -> Json<AdminStatus> {
+    Json(admin_status_of(&state))
+}
+
+// POST /admin/drain - Stop accepting executions
+#[utoipa::path(
+    post,
+    path = "/admin/drain",
+    tag = "admin",
+    summary = "Refuse new executions with 503 while running ones finish; reads keep working",
+    responses((status = 200, body = AdminStatus)),
+)]
+pub async fn start_drain(State(state): State<Arc<AppState>>) -> Json<AdminStatus> {
+    state.drain.start();
+    Json(admin_status_of(&state))
+}
+
+// DELETE /admin/drain - Accept executions again
+#[utoipa::path(
+    delete,
+    path = "/admin/drain",
+    tag = "admin",
+    summary = "Leave drain mode and accept executions again",
+    responses((status = 200, body = AdminStatus)),
+)]
+pub async fn stop_drain(State(state): State<Arc<AppState>>) -> Json<AdminStatus> {
+    state.drain.stop();
+    Json(admin_status_of(&state))
+}
+
+/// Most entries returned by one `/audit` query
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+// GET /audit
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// Only entries at or after this time (RFC 3339)
+    pub since: Option<DateTime<Utc>>,
+    /// Only entries before this time (RFC 3339)
+    pub until: Option<DateTime<Utc>>,
+    /// The `X-Request-Id` of one request
+    pub request_id: Option<String>,
+    /// Exact client, e.g. `ip:10.0.0.5` or `key:1a2b3c4d5e6f7a8b`
+    pub client: Option<String>,
+    pub method: Option<String>,
+    /// Path prefix, e.g. `/shell`
+    pub path: Option<String>,
+    pub status: Option<u16>,
+    /// Only failed (status 400 and up) or only successful requests
+    pub failed: Option<bool>,
+    /// Maximum entries to return, newest first (default 100, at most 1000)
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AuditResponse {
+    pub entries: Vec<AuditEntry>,
+}
+
+// GET /audit - Search the audit log of mutating requests
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "admin",
+    summary = "Search the audit log of mutating requests, newest first",
+    params(AuditQuery),
+    responses((status = 200, body = AuditResponse)),
+)]
+pub async fn audit_log(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditResponse>> {
+    let log = state
+        .audit
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("Audit log is disabled".into()))?;
+    let filter = AuditFilter {
+        since: query.since,
+        until: query.until,
+        request_id: query.request_id,
+        client: query.client,
+        method: query.method,
+        path_prefix: query.path,
+        status: query.status,
+        failed: query.failed,
+    };
+    let limit = query.limit.unwrap_or(100).min(MAX_AUDIT_ENTRIES);
+    let entries = log.query(&filter, limit).await?;
+    Ok(Json(AuditResponse { entries }))
+}
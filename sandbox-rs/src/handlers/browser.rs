@@ -1,12 +1,23 @@
 use std::sync::Arc;
-use axum::{extract::State, Json};
+use axum::{extract::{Path, State}, Json};
 use crate::state::AppState;
 use crate::error::{AppError, Result};
 use crate::browser::{
     GotoRequest, GotoResponse,
     ScreenshotRequest, ScreenshotResponse,
     EvaluateRequest, EvaluateResponse,
-    ClickRequest, TypeRequest,
+    ClickRequest, ClickResponse, TypeRequest,
+    ScrollRequest, ScrollResponse, ElementRequest, PressRequest,
+    PdfRequest, PdfResponse,
+    ContentRequest, ContentResponse,
+    CaptureRequest, CaptureResponse,
+    FillRequest, FillResponse,
+    SelectRequest, SelectResponse,
+    QueryRequest, QueryResponse, UploadRequest, UploadResponse,
+    HarStartRequest, HarStartResponse, HarStopRequest, HarStopResponse,
+    RecordStartRequest, RecordStartResponse, RecordStopRequest, RecordStopResponse,
+    PageInfo, PageListResponse, ConsoleResponse,
+    DownloadRequest, DownloadInfo, DownloadListResponse,
     BrowserStatus, BrowserError,
 };
 
@@ -14,17 +25,49 @@ use crate::browser::{
 impl From<BrowserError> for AppError {
     fn from(e: BrowserError) -> Self {
         match e {
-            BrowserError::ElementNotFound(msg) => AppError::NotFound(msg),
-            BrowserError::Timeout(secs) => AppError::Timeout(format!("Timeout after {}s", secs)),
-            BrowserError::LaunchFailed(msg) => AppError::Internal(format!("Browser launch failed: {}", msg)),
-            BrowserError::NavigationFailed(msg) => AppError::Internal(format!("Navigation failed: {}", msg)),
-            BrowserError::ScriptError(msg) => AppError::BadRequest(format!("Script error: {}", msg)),
+            BrowserError::ElementNotFound(msg) => AppError::NotFound(msg).with_code("ELEMENT_NOT_FOUND"),
+            BrowserError::Timeout(secs) => AppError::Timeout(format!("Timeout after {}s", secs))
+                .with_code("BROWSER_TIMEOUT")
+                .with_details(serde_json::json!({ "timeout": secs })),
+            BrowserError::LaunchFailed(msg) => {
+                AppError::Internal(format!("Browser launch failed: {}", msg)).with_code("BROWSER_LAUNCH_FAILED")
+            }
+            BrowserError::NavigationFailed(msg) => {
+                AppError::Internal(format!("Navigation failed: {}", msg)).with_code("NAVIGATION_FAILED")
+            }
+            BrowserError::ScriptError(msg) => AppError::BadRequest(format!("Script error: {}", msg)).with_code("SCRIPT_ERROR"),
             BrowserError::ScreenshotFailed(msg) => AppError::Internal(format!("Screenshot failed: {}", msg)),
+            BrowserError::PdfFailed(msg) => AppError::Internal(format!("PDF generation failed: {}", msg)),
+            BrowserError::InvalidRequest(msg) => AppError::BadRequest(msg),
+            BrowserError::OutputFailed(msg) => AppError::Internal(format!("Failed to write output: {}", msg)),
+            BrowserError::HarFailed(msg) => AppError::Internal(format!("HAR capture failed: {}", msg)),
+            BrowserError::SessionNotFound(id) => {
+                AppError::NotFound(format!("Session not found: {}", id)).with_code("BROWSER_SESSION_NOT_FOUND")
+            }
+            BrowserError::PageNotFound(id) => AppError::NotFound(format!("Page not found: {}", id)).with_code("PAGE_NOT_FOUND"),
+            BrowserError::DownloadFailed(msg) => AppError::Internal(format!("Download failed: {}", msg)),
+            BrowserError::CaptureFailed(msg) => AppError::Internal(format!("Page capture failed: {}", msg)),
+            BrowserError::LimitReached(msg) => AppError::BadRequest(format!("Limit reached: {}", msg)).with_code("BROWSER_LIMIT"),
+            BrowserError::RecordingFailed(msg) => AppError::Internal(format!("Recording failed: {}", msg)),
+            BrowserError::UrlBlocked(msg) => AppError::Forbidden(format!("URL blocked by policy: {}", msg)).with_code("URL_BLOCKED"),
+            BrowserError::OutsideWorkspace { path, workspace } => {
+                AppError::Forbidden(format!("{} is outside the session workspace", path))
+                    .with_code("PATH_OUTSIDE_WORKSPACE")
+                    .with_details(serde_json::json!({ "path": path, "workspace": workspace }))
+            }
         }
     }
 }
 
 // POST /browser/goto - Navigate to a URL
+#[utoipa::path(
+    post,
+    path = "/browser/goto",
+    tag = "browser",
+    summary = "Navigate to a URL",
+    request_body = GotoRequest,
+    responses((status = 200, body = GotoResponse)),
+)]
 pub async fn browser_goto(
     State(state): State<Arc<AppState>>,
     Json(req): Json<GotoRequest>,
@@ -34,6 +77,14 @@ pub async fn browser_goto(
 }
 
 // POST /browser/screenshot - Take a screenshot
+#[utoipa::path(
+    post,
+    path = "/browser/screenshot",
+    tag = "browser",
+    summary = "Take a screenshot",
+    request_body = ScreenshotRequest,
+    responses((status = 200, body = ScreenshotResponse)),
+)]
 pub async fn browser_screenshot(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ScreenshotRequest>,
@@ -43,6 +94,14 @@ pub async fn browser_screenshot(
 }
 
 // POST /browser/evaluate - Evaluate JavaScript
+#[utoipa::path(
+    post,
+    path = "/browser/evaluate",
+    tag = "browser",
+    summary = "Evaluate JavaScript",
+    request_body = EvaluateRequest,
+    responses((status = 200, body = EvaluateResponse)),
+)]
 pub async fn browser_evaluate(
     State(state): State<Arc<AppState>>,
     Json(req): Json<EvaluateRequest>,
@@ -52,15 +111,31 @@ pub async fn browser_evaluate(
 }
 
 // POST /browser/click - Click an element
+#[utoipa::path(
+    post,
+    path = "/browser/click",
+    tag = "browser",
+    summary = "Click an element",
+    request_body = ClickRequest,
+    responses((status = 200, body = ClickResponse)),
+)]
 pub async fn browser_click(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ClickRequest>,
-) -> Result<Json<serde_json::Value>> {
-    state.browser.click(req).await?;
-    Ok(Json(serde_json::json!({"success": true})))
+) -> Result<Json<ClickResponse>> {
+    let response = state.browser.click(req).await?;
+    Ok(Json(response))
 }
 
 // POST /browser/type - Type text into an element
+#[utoipa::path(
+    post,
+    path = "/browser/type",
+    tag = "browser",
+    summary = "Type text into an element",
+    request_body = TypeRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
 pub async fn browser_type(
     State(state): State<Arc<AppState>>,
     Json(req): Json<TypeRequest>,
@@ -69,9 +144,371 @@ pub async fn browser_type(
     Ok(Json(serde_json::json!({"success": true})))
 }
 
+// POST /browser/scroll - Scroll an element into view or the window by pixels
+#[utoipa::path(
+    post,
+    path = "/browser/scroll",
+    tag = "browser",
+    summary = "Scroll an element into view or the window by pixels",
+    request_body = ScrollRequest,
+    responses((status = 200, body = ScrollResponse)),
+)]
+pub async fn browser_scroll(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ScrollRequest>,
+) -> Result<Json<ScrollResponse>> {
+    let response = state.browser.scroll(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/hover - Move the mouse over an element
+#[utoipa::path(
+    post,
+    path = "/browser/hover",
+    tag = "browser",
+    summary = "Move the mouse over an element",
+    request_body = ElementRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn browser_hover(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ElementRequest>,
+) -> Result<Json<serde_json::Value>> {
+    state.browser.hover(req).await?;
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+// POST /browser/focus - Focus an element
+#[utoipa::path(
+    post,
+    path = "/browser/focus",
+    tag = "browser",
+    summary = "Focus an element",
+    request_body = ElementRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn browser_focus(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ElementRequest>,
+) -> Result<Json<serde_json::Value>> {
+    state.browser.focus(req).await?;
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+// POST /browser/press - Press a key or key combination
+#[utoipa::path(
+    post,
+    path = "/browser/press",
+    tag = "browser",
+    summary = "Press a key or key combination",
+    request_body = PressRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn browser_press(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<PressRequest>,
+) -> Result<Json<serde_json::Value>> {
+    state.browser.press(req).await?;
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
+// POST /browser/upload - Attach workspace files to a file input
+#[utoipa::path(
+    post,
+    path = "/browser/upload",
+    tag = "browser",
+    summary = "Attach workspace files to a file input",
+    request_body = UploadRequest,
+    responses((status = 200, body = UploadResponse)),
+)]
+pub async fn browser_upload(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<UploadRequest>,
+) -> Result<Json<UploadResponse>> {
+    let response = state.browser.upload(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/query - Count and describe elements matching a selector
+#[utoipa::path(
+    post,
+    path = "/browser/query",
+    tag = "browser",
+    summary = "Count and describe elements matching a selector",
+    request_body = QueryRequest,
+    responses((status = 200, body = QueryResponse)),
+)]
+pub async fn browser_query(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<QueryRequest>,
+) -> Result<Json<QueryResponse>> {
+    let response = state.browser.query(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/select - Choose select options or toggle a checkbox/radio
+#[utoipa::path(
+    post,
+    path = "/browser/select",
+    tag = "browser",
+    summary = "Choose select options or toggle a checkbox/radio",
+    request_body = SelectRequest,
+    responses((status = 200, body = SelectResponse)),
+)]
+pub async fn browser_select(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SelectRequest>,
+) -> Result<Json<SelectResponse>> {
+    let response = state.browser.select(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/fill - Fill several form fields on one page
+#[utoipa::path(
+    post,
+    path = "/browser/fill",
+    tag = "browser",
+    summary = "Fill several form fields on one page",
+    request_body = FillRequest,
+    responses((status = 200, body = FillResponse)),
+)]
+pub async fn browser_fill(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<FillRequest>,
+) -> Result<Json<FillResponse>> {
+    let response = state.browser.fill(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/content - Extract page HTML and readable text
+#[utoipa::path(
+    post,
+    path = "/browser/content",
+    tag = "browser",
+    summary = "Extract page HTML and readable text",
+    request_body = ContentRequest,
+    responses((status = 200, body = ContentResponse)),
+)]
+pub async fn browser_content(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ContentRequest>,
+) -> Result<Json<ContentResponse>> {
+    let response = state.browser.content(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/pdf - Render a page to PDF
+#[utoipa::path(
+    post,
+    path = "/browser/pdf",
+    tag = "browser",
+    summary = "Render a page to PDF",
+    request_body = PdfRequest,
+    responses((status = 200, body = PdfResponse)),
+)]
+pub async fn browser_pdf(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<PdfRequest>,
+) -> Result<Json<PdfResponse>> {
+    let response = state.browser.pdf(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/capture - Save the page as MHTML or single-file HTML
+#[utoipa::path(
+    post,
+    path = "/browser/capture",
+    tag = "browser",
+    summary = "Save the page as MHTML or single-file HTML",
+    request_body = CaptureRequest,
+    responses((status = 200, body = CaptureResponse)),
+)]
+pub async fn browser_capture(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CaptureRequest>,
+) -> Result<Json<CaptureResponse>> {
+    let response = state.browser.capture(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/har/start - Begin recording network activity on a new page
+#[utoipa::path(
+    post,
+    path = "/browser/har/start",
+    tag = "browser",
+    summary = "Begin recording network activity on a new page",
+    request_body = HarStartRequest,
+    responses((status = 200, body = HarStartResponse)),
+)]
+pub async fn browser_har_start(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<HarStartRequest>,
+) -> Result<Json<HarStartResponse>> {
+    let response = state.browser.har_start(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/har/stop - Finish recording and return the HAR
+#[utoipa::path(
+    post,
+    path = "/browser/har/stop",
+    tag = "browser",
+    summary = "Finish recording and return the HAR",
+    request_body = HarStopRequest,
+    responses((status = 200, body = HarStopResponse)),
+)]
+pub async fn browser_har_stop(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<HarStopRequest>,
+) -> Result<Json<HarStopResponse>> {
+    let response = state.browser.har_stop(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/record/start - Begin capturing a page as video
+#[utoipa::path(
+    post,
+    path = "/browser/record/start",
+    tag = "browser",
+    summary = "Begin capturing a page as video",
+    request_body = RecordStartRequest,
+    responses((status = 200, body = RecordStartResponse)),
+)]
+pub async fn browser_record_start(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RecordStartRequest>,
+) -> Result<Json<RecordStartResponse>> {
+    let response = state.browser.record_start(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/record/stop - Stop capturing and encode the video into the workspace
+#[utoipa::path(
+    post,
+    path = "/browser/record/stop",
+    tag = "browser",
+    summary = "Stop capturing and encode the video into the workspace",
+    request_body = RecordStopRequest,
+    responses((status = 200, body = RecordStopResponse)),
+)]
+pub async fn browser_record_stop(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RecordStopRequest>,
+) -> Result<Json<RecordStopResponse>> {
+    let response = state.browser.record_stop(req).await?;
+    Ok(Json(response))
+}
+
+// POST /browser/download - Trigger a download and wait for it to land in the workspace
+#[utoipa::path(
+    post,
+    path = "/browser/download",
+    tag = "browser",
+    summary = "Trigger a download and wait for it to land in the workspace",
+    request_body = DownloadRequest,
+    responses((status = 200, body = DownloadInfo)),
+)]
+pub async fn browser_download(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DownloadRequest>,
+) -> Result<Json<DownloadInfo>> {
+    let response = state.browser.download(req).await?;
+    Ok(Json(response))
+}
+
+// GET /browser/downloads - List downloads and their progress
+#[utoipa::path(
+    get,
+    path = "/browser/downloads",
+    tag = "browser",
+    summary = "List downloads and their progress",
+    responses((status = 200, body = DownloadListResponse)),
+)]
+pub async fn browser_downloads(
+    State(state): State<Arc<AppState>>,
+) -> Json<DownloadListResponse> {
+    Json(DownloadListResponse {
+        downloads: state.browser.list_downloads(),
+    })
+}
+
+// GET /browser/pages - List open tabs
+#[utoipa::path(
+    get,
+    path = "/browser/pages",
+    tag = "browser",
+    summary = "List open tabs",
+    responses((status = 200, body = PageListResponse)),
+)]
+pub async fn browser_pages(
+    State(state): State<Arc<AppState>>,
+) -> Json<PageListResponse> {
+    Json(PageListResponse {
+        pages: state.browser.list_pages().await,
+    })
+}
+
+// GET /browser/pages/:page_id/console - Console output and dialogs seen on a tab
+#[utoipa::path(
+    get,
+    path = "/browser/pages/{page_id}/console",
+    tag = "browser",
+    summary = "Console output and dialogs seen on a tab",
+    params(("page_id" = String, Path, description = "Tab ID")),
+    responses((status = 200, body = ConsoleResponse)),
+)]
+pub async fn browser_page_console(
+    State(state): State<Arc<AppState>>,
+    Path(page_id): Path<String>,
+) -> Result<Json<ConsoleResponse>> {
+    let entries = state.browser.console(&page_id)?;
+    Ok(Json(ConsoleResponse { entries }))
+}
+
+// POST /browser/pages/:page_id/activate - Bring a tab to the foreground
+#[utoipa::path(
+    post,
+    path = "/browser/pages/{page_id}/activate",
+    tag = "browser",
+    summary = "Bring a tab to the foreground",
+    params(("page_id" = String, Path, description = "Tab ID")),
+    responses((status = 200, body = PageInfo)),
+)]
+pub async fn browser_activate_page(
+    State(state): State<Arc<AppState>>,
+    Path(page_id): Path<String>,
+) -> Result<Json<PageInfo>> {
+    let info = state.browser.activate_page(&page_id).await?;
+    Ok(Json(info))
+}
+
+// DELETE /browser/pages/:page_id - Close a tab
+#[utoipa::path(
+    delete,
+    path = "/browser/pages/{page_id}",
+    tag = "browser",
+    summary = "Close a tab",
+    params(("page_id" = String, Path, description = "Tab ID")),
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn browser_close_page(
+    State(state): State<Arc<AppState>>,
+    Path(page_id): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    state.browser.close_page(&page_id).await?;
+    Ok(Json(serde_json::json!({"success": true})))
+}
+
 // GET /browser/status - Get browser status
+#[utoipa::path(
+    get,
+    path = "/browser/status",
+    tag = "browser",
+    summary = "Get browser status",
+    responses((status = 200, body = BrowserStatus)),
+)]
 pub async fn browser_status(
     State(state): State<Arc<AppState>>,
 ) -> Json<BrowserStatus> {
-    Json(state.browser.status())
+    Json(state.browser.status().await)
 }
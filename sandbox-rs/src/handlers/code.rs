@@ -1,5 +1,6 @@
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::fs;
@@ -8,6 +9,9 @@ use tokio::time::{timeout, Duration};
 
 use crate::error::{AppError, Result};
 use crate::state::AppState;
+use crate::tee::receipt::{self, AttestOptions, Receipt};
+use crate::tee::secrets;
+use crate::webhooks;
 
 #[derive(Debug, Clone)]
 struct LangConfig {
@@ -45,38 +49,102 @@ fn get_lang_config(language: &str) -> Option<LangConfig> {
     }
 }
 
-#[derive(Debug, Deserialize)]
+/// Whether snippets in `language` can be run
+pub(crate) fn is_supported(language: &str) -> bool {
+    get_lang_config(language).is_some()
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct CodeExecRequest {
     pub code: String,
     pub language: String,
     #[serde(default = "default_timeout")]
     pub timeout: u64,
+    #[serde(flatten)]
+    pub attestation: AttestOptions,
 }
 
 fn default_timeout() -> u64 {
     30
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CodeExecResponse {
     pub output: String,
     pub error: String,
     pub exit_code: i32,
     pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Receipt>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/code/execute",
+    tag = "code",
+    summary = "Run a code snippet",
+    request_body = CodeExecRequest,
+    responses((status = 200, body = CodeExecResponse)),
+)]
 pub async fn execute_code(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CodeExecRequest>,
 ) -> Result<Json<CodeExecResponse>> {
-    let config = get_lang_config(&req.language)
-        .ok_or_else(|| AppError::BadRequest(format!("Unsupported language: {}", req.language)))?;
-
+    req.attestation.validate()?;
     let start = Instant::now();
+    let ran = run_code(&state, &req.language, &req.code, req.timeout, &[]).await?;
+
+    let mut response = CodeExecResponse {
+        output: ran.stdout,
+        error: ran.stderr,
+        exit_code: ran.exit_code,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+        receipt: None,
+    };
+    webhooks::emit(
+        &state,
+        "job.completed",
+        serde_json::json!({
+            "kind": "code.execute",
+            "language": req.language,
+            "exit_code": response.exit_code,
+            "duration_ms": response.duration_ms,
+        }),
+    );
+
+    if req.attestation.attest {
+        response.receipt = Some(
+            receipt::attest(&state, "code.execute", &req, &response, req.attestation.attest_quote).await?,
+        );
+    }
+
+    Ok(Json(response))
+}
+
+/// What running a snippet printed, with secrets redacted
+pub(crate) struct CodeRun {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+/// Run `code` in the workspace with placeholders expanded, `env` added to its environment
+pub(crate) async fn run_code(
+    state: &AppState,
+    language: &str,
+    code: &str,
+    timeout_secs: u64,
+    env: &[(&str, &str)],
+) -> Result<CodeRun> {
+    let config = get_lang_config(language)
+        .ok_or_else(|| AppError::BadRequest(format!("Unsupported language: {}", language)))?;
+
+    let mut interpolation = state.secrets.interpolation();
+    let code = interpolation.expand(code).await?;
 
     // Create temp file
     let tmp_path = format!("/tmp/code_{}{}", std::process::id(), config.ext);
-    fs::write(&tmp_path, &req.code)
+    fs::write(&tmp_path, &code)
         .await
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
@@ -95,22 +163,23 @@ pub async fn execute_code(
     let mut cmd = Command::new("sh");
     cmd.arg("-c")
         .arg(&full_cmd)
-        .current_dir(&state.config.workspace);
+        .current_dir(&state.config.workspace)
+        .envs(env.iter().copied());
+    secrets::inject(state, &mut cmd);
 
-    let result = timeout(Duration::from_secs(req.timeout), cmd.output()).await;
+    let result = timeout(Duration::from_secs(timeout_secs), cmd.output()).await;
 
     // Cleanup temp file
     let _ = fs::remove_file(&tmp_path).await;
     let _ = fs::remove_file(format!("/tmp/rust_out_{}", std::process::id())).await;
 
     let output = result
-        .map_err(|_| AppError::Timeout("Execution timed out".into()))?
+        .map_err(|_| AppError::exec_timeout("Execution timed out", timeout_secs))?
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    Ok(Json(CodeExecResponse {
-        output: String::from_utf8_lossy(&output.stdout).into_owned(),
-        error: String::from_utf8_lossy(&output.stderr).into_owned(),
+    Ok(CodeRun {
+        stdout: interpolation.redact(String::from_utf8_lossy(&output.stdout).into_owned()),
+        stderr: interpolation.redact(String::from_utf8_lossy(&output.stderr).into_owned()),
         exit_code: output.status.code().unwrap_or(-1),
-        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
-    }))
+    })
 }
@@ -0,0 +1,98 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::convert::{self, Format, Options};
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+
+// POST /convert - Convert a document
+#[derive(Deserialize, ToSchema)]
+pub struct ConvertRequest {
+    /// Workspace file to convert
+    pub input: String,
+    /// `text`, `csv`, `markdown`, `html`, or `pdf`
+    pub to: String,
+    /// Format of `input`, when its extension does not say
+    pub from: Option<String>,
+    /// Where to write the result; `input` with the new extension by default
+    pub output: Option<String>,
+    /// Worksheet to export from an XLSX file; the first by default
+    pub sheet: Option<String>,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    120
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ConvertResponse {
+    pub output: String,
+    pub from: String,
+    pub to: String,
+    pub size: u64,
+    pub duration_ms: f64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/convert",
+    tag = "convert",
+    summary = "Convert a workspace document: PDF, DOCX, or HTML to text, XLSX to CSV, Markdown to PDF",
+    request_body = ConvertRequest,
+    responses((status = 200, body = ConvertResponse)),
+)]
+pub async fn convert_document(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ConvertRequest>,
+) -> Result<Json<ConvertResponse>> {
+    let unknown = |name: &str| AppError::BadRequest(format!("Unknown format: {}", name));
+    let to = Format::parse(&req.to).ok_or_else(|| unknown(&req.to))?;
+    let input = state.resolve(&req.input)?;
+    let from = match &req.from {
+        Some(name) => Format::parse(name).ok_or_else(|| unknown(name))?,
+        None => Format::of(&input).ok_or_else(|| {
+            AppError::BadRequest(format!("Cannot tell the format of '{}'; set `from`", req.input))
+        })?,
+    };
+    if !input.is_file() {
+        return Err(AppError::NotFound(format!("File not found: {}", req.input)));
+    }
+    let output = match &req.output {
+        Some(path) => state.resolve(path)?,
+        None => input.with_extension(to.extension()),
+    };
+    // The tools pick what to write by the extension, so it has to agree with `to`
+    if Format::of(&output) != Some(to) {
+        return Err(AppError::BadRequest(format!(
+            "Output '{}' must have the extension of {} (.{})",
+            output.display(),
+            to.name(),
+            to.extension()
+        )));
+    }
+    if output == input {
+        return Err(AppError::BadRequest("Output must differ from input".into()));
+    }
+
+    let start = Instant::now();
+    let options = Options { sheet: req.sheet };
+    let size = tokio::time::timeout(
+        Duration::from_secs(req.timeout),
+        convert::convert(from, to, &input, &output, &options),
+    )
+    .await
+    .map_err(|_| AppError::exec_timeout("Conversion timed out", req.timeout))??;
+
+    Ok(Json(ConvertResponse {
+        output: output.to_string_lossy().into_owned(),
+        from: from.name().to_string(),
+        to: to.name().to_string(),
+        size,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    }))
+}
@@ -0,0 +1,124 @@
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use super::display::within_limit;
+use crate::data::{Engine, Query};
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+
+// POST /data/query - Run SQL against a database or data files
+#[derive(Deserialize, ToSchema)]
+pub struct DataQueryRequest {
+    pub sql: String,
+    /// SQLite or DuckDB file in the workspace; without one, an in-memory DuckDB that can
+    /// read CSV, Parquet, and JSON files, e.g. `SELECT * FROM 'sales.csv'`
+    pub database: Option<String>,
+    /// `sqlite` or `duckdb`; by default DuckDB for `.duckdb` files and no database, SQLite otherwise
+    pub engine: Option<String>,
+    #[serde(default = "default_read_only")]
+    pub read_only: bool,
+    /// Most rows to return, at most `DATA_MAX_ROWS`
+    pub max_rows: Option<u64>,
+    /// `json` or `arrow`, an Arrow IPC stream
+    #[serde(default = "default_format")]
+    pub format: String,
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_read_only() -> bool {
+    true
+}
+
+fn default_format() -> String {
+    "json".into()
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DataQueryResponse {
+    pub engine: String,
+    pub columns: Vec<String>,
+    /// Values in `columns` order, from the last statement that returned rows
+    #[schema(value_type = Vec<Vec<Object>>)]
+    pub rows: Vec<Vec<serde_json::Value>>,
+    pub row_count: usize,
+    /// Whether rows were left out to stay within `max_rows` or `DATA_MAX_SIZE`
+    pub truncated: bool,
+    pub duration_ms: f64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/data/query",
+    tag = "data",
+    summary = "Run SQL with SQLite or DuckDB against files in the workspace",
+    request_body = DataQueryRequest,
+    responses(
+        (status = 200, body = DataQueryResponse),
+        (status = 200, description = "With `format: arrow`, the rows as an Arrow IPC stream", content_type = "application/vnd.apache.arrow.stream"),
+    ),
+)]
+pub async fn data_query(State(state): State<Arc<AppState>>, Json(req): Json<DataQueryRequest>) -> Result<Response> {
+    if req.format != "json" && req.format != "arrow" {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported format: {} (expected json or arrow)",
+            req.format
+        )));
+    }
+    let database = req.database.as_deref().map(|path| state.resolve(path)).transpose()?;
+    if let Some(database) = &database {
+        // A writable query may create the database, as both engines do
+        if req.read_only && !database.is_file() {
+            return Err(AppError::NotFound(format!("Database '{}' not found", database.display())));
+        }
+    }
+    let engine = Engine::choose(req.engine.as_deref(), database.as_deref())?;
+
+    let start = Instant::now();
+    let query = Query {
+        engine,
+        database: database.as_deref(),
+        sql: &req.sql,
+        read_only: req.read_only,
+        workdir: &state.config.workspace,
+        max_rows: within_limit(req.max_rows, state.config.data_max_rows),
+        max_size: state.config.data_max_size,
+    };
+    let result = tokio::time::timeout(Duration::from_secs(req.timeout), query.run())
+        .await
+        .map_err(|_| AppError::exec_timeout("Query timed out", req.timeout))??;
+
+    if req.format == "arrow" {
+        let stream = result.to_arrow()?;
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "application/vnd.apache.arrow.stream".to_string()),
+                (header::HeaderName::from_static("x-row-count"), result.rows.len().to_string()),
+                (header::HeaderName::from_static("x-truncated"), result.truncated.to_string()),
+            ],
+            stream,
+        )
+            .into_response());
+    }
+    Ok(Json(DataQueryResponse {
+        engine: engine.name().to_string(),
+        columns: result.columns,
+        row_count: result.rows.len(),
+        rows: result.rows,
+        truncated: result.truncated,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    })
+    .into_response())
+}
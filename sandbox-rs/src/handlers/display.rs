@@ -0,0 +1,345 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::display::recording::{Limits, ScreenRecording};
+use crate::display::{Display, Region};
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct DisplayActionResponse {
+    pub success: bool,
+}
+
+// POST /display/screenshot - Capture the desktop
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayScreenshotRequest {
+    /// `png` or `jpeg`
+    #[serde(default = "default_format")]
+    pub format: String,
+    /// Compression quality 0-100 (jpeg only)
+    pub quality: Option<u8>,
+    /// Capture only this part of the screen
+    pub region: Option<Region>,
+}
+
+fn default_format() -> String {
+    "png".into()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DisplayScreenshotResponse {
+    /// Base64-encoded image
+    pub data: String,
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/screenshot",
+    tag = "display",
+    summary = "Take a screenshot of the X display",
+    request_body = DisplayScreenshotRequest,
+    responses((status = 200, body = DisplayScreenshotResponse)),
+)]
+pub async fn display_screenshot(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayScreenshotRequest>,
+) -> Result<Json<DisplayScreenshotResponse>> {
+    let (image, width, height) =
+        Display(&state.config.display).screenshot(&req.format, req.quality, req.region).await?;
+    Ok(Json(DisplayScreenshotResponse {
+        data: base64::engine::general_purpose::STANDARD.encode(image),
+        format: req.format,
+        width,
+        height,
+    }))
+}
+
+// POST /display/click - Click at a point
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayClickRequest {
+    pub x: u32,
+    pub y: u32,
+    /// `left`, `middle`, or `right`
+    #[serde(default = "default_button")]
+    pub button: String,
+    /// Number of clicks, e.g. 2 for a double click
+    #[serde(default = "default_clicks")]
+    pub clicks: u32,
+}
+
+fn default_button() -> String {
+    "left".into()
+}
+
+fn default_clicks() -> u32 {
+    1
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/click",
+    tag = "display",
+    summary = "Move the pointer to a point on the X display and click",
+    request_body = DisplayClickRequest,
+    responses((status = 200, body = DisplayActionResponse)),
+)]
+pub async fn display_click(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayClickRequest>,
+) -> Result<Json<DisplayActionResponse>> {
+    Display(&state.config.display).click(req.x, req.y, &req.button, req.clicks).await?;
+    Ok(Json(DisplayActionResponse { success: true }))
+}
+
+// POST /display/type - Type text
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayTypeRequest {
+    pub text: String,
+    /// Milliseconds between keystrokes
+    #[serde(default = "default_delay")]
+    pub delay_ms: u64,
+}
+
+fn default_delay() -> u64 {
+    12
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/type",
+    tag = "display",
+    summary = "Type text into the focused window",
+    request_body = DisplayTypeRequest,
+    responses((status = 200, body = DisplayActionResponse)),
+)]
+pub async fn display_type(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayTypeRequest>,
+) -> Result<Json<DisplayActionResponse>> {
+    Display(&state.config.display).type_text(&req.text, req.delay_ms).await?;
+    Ok(Json(DisplayActionResponse { success: true }))
+}
+
+// POST /display/keys - Press keys
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayKeysRequest {
+    /// X keysyms or combinations pressed in order, e.g. `["ctrl+l", "Return"]`
+    pub keys: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/keys",
+    tag = "display",
+    summary = "Press key combinations in the focused window",
+    request_body = DisplayKeysRequest,
+    responses((status = 200, body = DisplayActionResponse)),
+)]
+pub async fn display_keys(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayKeysRequest>,
+) -> Result<Json<DisplayActionResponse>> {
+    Display(&state.config.display).keys(&req.keys).await?;
+    Ok(Json(DisplayActionResponse { success: true }))
+}
+
+// GET /display/clipboard - Read the clipboard
+#[derive(Deserialize, IntoParams)]
+pub struct ClipboardQuery {
+    /// `clipboard` (default) or `primary`, the middle-click selection
+    #[serde(default = "default_selection")]
+    pub selection: String,
+}
+
+fn default_selection() -> String {
+    "clipboard".into()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ClipboardResponse {
+    pub selection: String,
+    pub text: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/display/clipboard",
+    tag = "display",
+    summary = "Read text from the X display's clipboard",
+    params(ClipboardQuery),
+    responses((status = 200, body = ClipboardResponse)),
+)]
+pub async fn get_clipboard(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ClipboardQuery>,
+) -> Result<Json<ClipboardResponse>> {
+    let text = Display(&state.config.display).clipboard(&query.selection).await?;
+    Ok(Json(ClipboardResponse { selection: query.selection, text }))
+}
+
+// POST /display/clipboard - Set the clipboard
+#[derive(Deserialize, ToSchema)]
+pub struct SetClipboardRequest {
+    pub text: String,
+    /// `clipboard` (default) or `primary`
+    #[serde(default = "default_selection")]
+    pub selection: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/clipboard",
+    tag = "display",
+    summary = "Put text on the X display's clipboard",
+    request_body = SetClipboardRequest,
+    responses((status = 200, body = DisplayActionResponse)),
+)]
+pub async fn set_clipboard(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SetClipboardRequest>,
+) -> Result<Json<DisplayActionResponse>> {
+    Display(&state.config.display).set_clipboard(&req.selection, &req.text).await?;
+    Ok(Json(DisplayActionResponse { success: true }))
+}
+
+// POST /display/record/start - Start recording the desktop
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayRecordStartRequest {
+    /// `webm` or `mp4`
+    #[serde(default = "default_video_format")]
+    pub format: String,
+    /// Output path; relative paths resolve against the workspace.
+    /// Defaults to `recordings/display-<timestamp>.<format>`.
+    pub path: Option<String>,
+    #[serde(default = "default_framerate")]
+    pub framerate: u32,
+    /// Record only this part of the screen
+    pub region: Option<Region>,
+    /// Stop after this many seconds, at most `DISPLAY_RECORD_MAX_DURATION`
+    pub max_duration: Option<u64>,
+    /// Stop once the file reaches this many bytes, at most `DISPLAY_RECORD_MAX_SIZE`
+    pub max_size: Option<u64>,
+}
+
+fn default_video_format() -> String {
+    "webm".into()
+}
+
+fn default_framerate() -> u32 {
+    10
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DisplayRecordStartResponse {
+    pub recording_id: String,
+    pub path: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/record/start",
+    tag = "display",
+    summary = "Start recording the X display to a video in the workspace",
+    request_body = DisplayRecordStartRequest,
+    responses((status = 200, body = DisplayRecordStartResponse)),
+)]
+pub async fn display_record_start(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayRecordStartRequest>,
+) -> Result<Json<DisplayRecordStartResponse>> {
+    if req.format != "webm" && req.format != "mp4" {
+        return Err(AppError::BadRequest(format!(
+            "Unsupported video format: {} (expected webm or mp4)",
+            req.format
+        )));
+    }
+    let path = req.path.unwrap_or_else(|| {
+        format!("recordings/display-{}.{}", chrono::Utc::now().format("%Y%m%d-%H%M%S%.3f"), req.format)
+    });
+    let path = state.resolve(&path)?;
+    let limits = Limits {
+        max_duration: within_limit(req.max_duration, state.config.display_record_max_duration),
+        max_size: within_limit(req.max_size, state.config.display_record_max_size),
+    };
+
+    let display = Display(&state.config.display);
+    let recording = ScreenRecording::start(&display, &path, req.framerate, req.region, limits).await?;
+    let recording_id = uuid::Uuid::new_v4().to_string();
+    state.display_recordings.insert(recording_id.clone(), recording);
+    Ok(Json(DisplayRecordStartResponse {
+        recording_id,
+        path: path.to_string_lossy().into_owned(),
+    }))
+}
+
+/// The requested limit capped by the configured one, where 0 means none
+pub(super) fn within_limit(requested: Option<u64>, configured: u64) -> u64 {
+    match (requested.filter(|&r| r > 0), configured) {
+        (Some(requested), 0) => requested,
+        (Some(requested), configured) => requested.min(configured),
+        (None, configured) => configured,
+    }
+}
+
+// POST /display/record/stop - Stop a recording
+#[derive(Deserialize, ToSchema)]
+pub struct DisplayRecordStopRequest {
+    pub recording_id: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DisplayRecordStopResponse {
+    pub path: String,
+    pub duration_secs: f64,
+    pub size: u64,
+    /// Whether the duration or size limit ended the recording before it was stopped
+    pub truncated: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/display/record/stop",
+    tag = "display",
+    summary = "Stop recording the X display and finish the video",
+    request_body = DisplayRecordStopRequest,
+    responses((status = 200, body = DisplayRecordStopResponse)),
+)]
+pub async fn display_record_stop(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DisplayRecordStopRequest>,
+) -> Result<Json<DisplayRecordStopResponse>> {
+    let (_, recording) = state.display_recordings.remove(&req.recording_id).ok_or_else(|| {
+        AppError::NotFound(format!("Recording '{}' not found", req.recording_id)).with_code("RECORDING_NOT_FOUND")
+    })?;
+    let recorded = recording.stop().await?;
+    Ok(Json(DisplayRecordStopResponse {
+        path: recorded.path.to_string_lossy().into_owned(),
+        duration_secs: recorded.duration_secs,
+        size: recorded.size,
+        truncated: recorded.truncated,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_within_limit() {
+        assert_eq!(within_limit(None, 600), 600);
+        assert_eq!(within_limit(Some(30), 600), 30);
+        assert_eq!(within_limit(Some(6000), 600), 600);
+        assert_eq!(within_limit(Some(6000), 0), 6000);
+        assert_eq!(within_limit(Some(0), 600), 600);
+    }
+}
@@ -0,0 +1,79 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::nix::{self, InstalledPackage};
+use crate::state::AppState;
+
+/// Seconds `nix profile list` may take
+const LIST_TIMEOUT: u64 = 30;
+
+// POST /env/install - Install packages with Nix
+#[derive(Deserialize, ToSchema)]
+pub struct InstallRequest {
+    /// Attribute names in `PACKAGES_FLAKE`, e.g. `ripgrep` or `python3Packages.requests`
+    pub packages: Vec<String>,
+    #[serde(default = "default_install_timeout")]
+    pub timeout: u64,
+}
+
+fn default_install_timeout() -> u64 {
+    300
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct InstallResponse {
+    /// The requested packages and their store paths
+    pub packages: Vec<InstalledPackage>,
+    /// Requested packages that were not installed before
+    pub added: Vec<String>,
+    /// Bytes of store paths the added packages need
+    pub closure_size: u64,
+    pub duration_ms: f64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/env/install",
+    tag = "env",
+    summary = "Install allowed packages into the sandbox's Nix profile",
+    request_body = InstallRequest,
+    responses((status = 200, body = InstallResponse)),
+)]
+pub async fn install_packages(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<InstallRequest>,
+) -> Result<Json<InstallResponse>> {
+    if req.packages.is_empty() {
+        return Err(AppError::BadRequest("No packages given".into()));
+    }
+    let start = Instant::now();
+    let installed = nix::install(&state.config, &req.packages, Duration::from_secs(req.timeout)).await?;
+    Ok(Json(InstallResponse {
+        packages: installed.packages,
+        added: installed.added,
+        closure_size: installed.closure_size,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    }))
+}
+
+// GET /env/packages - List installed packages
+#[derive(Serialize, ToSchema)]
+pub struct ListPackagesResponse {
+    pub packages: Vec<InstalledPackage>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/env/packages",
+    tag = "env",
+    summary = "List packages in the sandbox's Nix profile",
+    responses((status = 200, body = ListPackagesResponse)),
+)]
+pub async fn list_packages() -> Result<Json<ListPackagesResponse>> {
+    let packages = nix::list(Duration::from_secs(LIST_TIMEOUT)).await?;
+    Ok(Json(ListPackagesResponse { packages }))
+}
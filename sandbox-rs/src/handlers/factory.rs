@@ -1,26 +1,28 @@
 use axum::{extract::State, Json};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::sync::Arc;
 
 use crate::error::{AppError, Result};
 use crate::skills::{check_triggers, SkillSummary};
 use crate::state::AppState;
+use crate::webhooks;
 
 // POST /factory/start
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct StartFactoryRequest {
     pub initial_input: Option<String>,
 }
 
 // POST /factory/continue
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ContinueFactoryRequest {
     pub session_id: String,
     pub input: String,
 }
 
 // Response for start/continue
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct FactoryResponse {
     pub session_id: String,
     pub step: String,
@@ -31,18 +33,26 @@ pub struct FactoryResponse {
 }
 
 // POST /factory/check
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CheckTriggerRequest {
     pub input: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct CheckTriggerResponse {
     pub triggers_factory: bool,
     pub matched_phrases: Vec<String>,
 }
 
 /// POST /factory/start - Begin dialogue
+#[utoipa::path(
+    post,
+    path = "/factory/start",
+    tag = "factory",
+    summary = "Begin dialogue",
+    request_body = StartFactoryRequest,
+    responses((status = 200, body = FactoryResponse)),
+)]
 pub async fn start_factory(
     State(state): State<Arc<AppState>>,
     Json(req): Json<StartFactoryRequest>,
@@ -59,6 +69,14 @@ pub async fn start_factory(
 }
 
 /// POST /factory/continue - Advance step
+#[utoipa::path(
+    post,
+    path = "/factory/continue",
+    tag = "factory",
+    summary = "Advance step",
+    request_body = ContinueFactoryRequest,
+    responses((status = 200, body = FactoryResponse)),
+)]
 pub async fn continue_factory(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ContinueFactoryRequest>,
@@ -92,6 +110,14 @@ pub async fn continue_factory(
         None
     };
 
+    if let Some(skill) = &skill {
+        webhooks::emit(
+            &state,
+            "factory.completed",
+            serde_json::json!({ "session_id": session.id, "skill": skill }),
+        );
+    }
+
     Ok(Json(FactoryResponse {
         session_id: session.id,
         step: format!("{:?}", session.step),
@@ -102,10 +128,19 @@ pub async fn continue_factory(
 }
 
 /// POST /factory/check - Check if input triggers factory
+#[utoipa::path(
+    post,
+    path = "/factory/check",
+    tag = "factory",
+    summary = "Check if input triggers factory",
+    request_body = CheckTriggerRequest,
+    responses((status = 200, body = CheckTriggerResponse)),
+)]
 pub async fn check_trigger(
+    State(state): State<Arc<AppState>>,
     Json(req): Json<CheckTriggerRequest>,
 ) -> Result<Json<CheckTriggerResponse>> {
-    let matched_phrases = check_triggers(&req.input);
+    let matched_phrases = check_triggers(&req.input, &state.live.current().factory_triggers);
     let triggers_factory = !matched_phrases.is_empty();
 
     Ok(Json(CheckTriggerResponse {
@@ -126,8 +161,6 @@ fn sanitize_skill_name(goal: &str) -> String {
         .map(|c| {
             if c.is_alphanumeric() {
                 c
-            } else if c.is_whitespace() || c == '_' {
-                '-'
             } else {
                 '-'
             }
@@ -164,12 +197,17 @@ mod tests {
         assert_eq!(sanitize_skill_name(""), "custom-skill");
     }
 
+    fn test_state() -> Arc<AppState> {
+        let args = crate::config::Args::parse(["--audit-log=".to_string()]).unwrap();
+        AppState::new(crate::config::Config::load(&args).unwrap(), args)
+    }
+
     #[test]
     fn test_check_trigger() {
         let req = CheckTriggerRequest {
             input: "Can you teach me how to do this?".to_string(),
         };
-        let result = tokio_test::block_on(check_trigger(Json(req))).unwrap();
+        let result = tokio_test::block_on(check_trigger(State(test_state()), Json(req))).unwrap();
         assert!(result.0.triggers_factory);
         assert!(result.0.matched_phrases.contains(&"teach me".to_string()));
     }
@@ -179,7 +217,7 @@ mod tests {
         let req = CheckTriggerRequest {
             input: "Just a regular question".to_string(),
         };
-        let result = tokio_test::block_on(check_trigger(Json(req))).unwrap();
+        let result = tokio_test::block_on(check_trigger(State(test_state()), Json(req))).unwrap();
         assert!(!result.0.triggers_factory);
         assert!(result.0.matched_phrases.is_empty());
     }
@@ -1,11 +1,13 @@
 use axum::{
-    extract::{Multipart, Query, State},
+    extract::{multipart::MultipartError, Multipart, Query, State},
     http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+use std::path::{Component, PathBuf};
 use std::sync::Arc;
 use tokio::fs;
 use tokio::io::AsyncReadExt;
@@ -13,16 +15,37 @@ use tokio::io::AsyncReadExt;
 use crate::error::{AppError, Result};
 use crate::state::AppState;
 
-fn resolve_path(base: &str, path: &str) -> PathBuf {
-    if path.starts_with('/') {
+pub(crate) fn resolve_path(base: &str, path: &str, confined: bool) -> Result<PathBuf> {
+    let resolved = if path.starts_with('/') {
         PathBuf::from(path)
     } else {
         PathBuf::from(base).join(path)
+    };
+    if !confined {
+        return Ok(resolved);
     }
+    // Lexically, so `..` cannot climb out; symlinks inside `base` are followed
+    let mut normal = PathBuf::new();
+    for component in resolved.components() {
+        match component {
+            Component::ParentDir => {
+                normal.pop();
+            }
+            Component::CurDir => {}
+            other => normal.push(other),
+        }
+    }
+    if !normal.starts_with(base) {
+        return Err(AppError::Forbidden(format!("{} is outside the session workspace", path))
+            .with_code("PATH_OUTSIDE_WORKSPACE")
+            .with_details(json!({ "path": path, "workspace": base })));
+    }
+    Ok(normal)
 }
 
 // Read file
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FileReadQuery {
     pub path: String,
     #[serde(default = "default_encoding")]
@@ -34,18 +57,26 @@ fn default_encoding() -> String {
     "utf-8".into()
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileReadResponse {
     pub content: String,
     pub size: u64,
     pub mime_type: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/file/read",
+    tag = "file",
+    summary = "Read a workspace file",
+    params(FileReadQuery),
+    responses((status = 200, body = FileReadResponse)),
+)]
 pub async fn read_file(
     State(state): State<Arc<AppState>>,
     Query(query): Query<FileReadQuery>,
 ) -> Result<Json<FileReadResponse>> {
-    let full_path = resolve_path(&state.config.workspace, &query.path);
+    let full_path = state.resolve(&query.path)?;
 
     if !full_path.exists() {
         return Err(AppError::NotFound("File not found".into()));
@@ -67,7 +98,7 @@ pub async fn read_file(
 }
 
 // Write file
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct FileWriteRequest {
     pub path: String,
     pub content: String,
@@ -79,17 +110,25 @@ fn default_mode() -> String {
     "644".into()
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileWriteResponse {
     pub path: String,
     pub size: u64,
 }
 
+#[utoipa::path(
+    post,
+    path = "/file/write",
+    tag = "file",
+    summary = "Write a workspace file",
+    request_body = FileWriteRequest,
+    responses((status = 200, body = FileWriteResponse)),
+)]
 pub async fn write_file(
     State(state): State<Arc<AppState>>,
     Json(req): Json<FileWriteRequest>,
 ) -> Result<Json<FileWriteResponse>> {
-    let full_path = resolve_path(&state.config.workspace, &req.path);
+    let full_path = state.resolve(&req.path)?;
 
     // Create parent directories
     if let Some(parent) = full_path.parent() {
@@ -122,14 +161,15 @@ pub async fn write_file(
 }
 
 // List directory
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FileListQuery {
     pub path: String,
     #[serde(default)]
     pub recursive: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
@@ -139,17 +179,25 @@ pub struct FileEntry {
     pub modified: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct FileListResponse {
     pub path: String,
     pub entries: Vec<FileEntry>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/file/list",
+    tag = "file",
+    summary = "List a directory",
+    params(FileListQuery),
+    responses((status = 200, body = FileListResponse)),
+)]
 pub async fn list_files(
     State(state): State<Arc<AppState>>,
     Query(query): Query<FileListQuery>,
 ) -> Result<Json<FileListResponse>> {
-    let full_path = resolve_path(&state.config.workspace, &query.path);
+    let full_path = state.resolve(&query.path)?;
 
     if !full_path.exists() {
         return Err(AppError::NotFound("Path not found".into()));
@@ -224,6 +272,23 @@ async fn entry_to_file_entry(entry: &fs::DirEntry) -> Option<FileEntry> {
 }
 
 // Upload file (multipart)
+/// Form fields of `/file/upload`, described for the OpenAPI document only
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct FileUploadForm {
+    pub path: String,
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/file/upload",
+    tag = "file",
+    summary = "Upload a file as multipart form data",
+    request_body(content = FileUploadForm, content_type = "multipart/form-data"),
+    responses((status = 200, body = FileWriteResponse)),
+)]
 pub async fn upload_file(
     State(state): State<Arc<AppState>>,
     mut multipart: Multipart,
@@ -231,30 +296,22 @@ pub async fn upload_file(
     let mut file_data: Option<Vec<u8>> = None;
     let mut file_path: Option<String> = None;
 
-    while let Some(field) = multipart
-        .next_field()
-        .await
-        .map_err(|e| AppError::BadRequest(e.to_string()))?
-    {
+    // Running past the upload limit surfaces as a multipart error mid-stream
+    let limit = state.config.max_upload_bytes;
+    let multipart_error = |e: MultipartError| match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(limit),
+        _ => AppError::BadRequest(e.to_string()),
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
         let name = field.name().unwrap_or("").to_string();
 
         match name.as_str() {
             "file" => {
-                file_data = Some(
-                    field
-                        .bytes()
-                        .await
-                        .map_err(|e| AppError::Internal(e.to_string()))?
-                        .to_vec(),
-                );
+                file_data = Some(field.bytes().await.map_err(multipart_error)?.to_vec());
             }
             "path" => {
-                file_path = Some(
-                    field
-                        .text()
-                        .await
-                        .map_err(|e| AppError::Internal(e.to_string()))?,
-                );
+                file_path = Some(field.text().await.map_err(multipart_error)?);
             }
             _ => {}
         }
@@ -263,7 +320,7 @@ pub async fn upload_file(
     let data = file_data.ok_or_else(|| AppError::BadRequest("Missing file field".into()))?;
     let path = file_path.ok_or_else(|| AppError::BadRequest("Missing path field".into()))?;
 
-    let full_path = resolve_path(&state.config.workspace, &path);
+    let full_path = state.resolve(&path)?;
 
     if let Some(parent) = full_path.parent() {
         fs::create_dir_all(parent)
@@ -282,16 +339,25 @@ pub async fn upload_file(
 }
 
 // Download file
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FileDownloadQuery {
     pub path: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/file/download",
+    tag = "file",
+    summary = "Download a file",
+    params(FileDownloadQuery),
+    responses((status = 200, description = "File contents", content_type = "application/octet-stream")),
+)]
 pub async fn download_file(
     State(state): State<Arc<AppState>>,
     Query(query): Query<FileDownloadQuery>,
 ) -> Result<Response> {
-    let full_path = resolve_path(&state.config.workspace, &query.path);
+    let full_path = state.resolve(&query.path)?;
 
     if !full_path.exists() {
         return Err(AppError::NotFound("File not found".into()));
@@ -1,21 +1,51 @@
+use crate::error::AppError;
+use crate::manifest::{self, Manifest};
 use crate::state::AppState;
-use axum::{extract::State, Json};
+use crate::usage::Usage;
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::Serialize;
+use utoipa::ToSchema;
+use std::future::Future;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
 
-#[derive(Serialize)]
+/// Checks `/ready` knows about; `ready_checks` picks which must pass
+pub const READY_CHECKS: &[&str] = &["python3", "node", "skills", "browser", "dstack"];
+
+/// Longest any one readiness check may take
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a failed browser launch is reported before launching is tried again
+const BROWSER_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub uptime: f64,
     pub services: Services,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct Services {
     pub display: bool,
     pub browser: bool,
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    summary = "Liveness and service status",
+    description = "Answers as long as the server is up; `/ready` checks whether it can do its work.",
+    responses((status = 200, body = HealthResponse)),
+)]
 pub async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthResponse> {
     let display_exists = std::path::Path::new("/tmp/.X11-unix/X99").exists();
 
@@ -24,12 +54,138 @@ pub async fn health_check(State(state): State<Arc<AppState>>) -> Json<HealthResp
         uptime: state.uptime_secs(),
         services: Services {
             display: display_exists,
-            browser: false, // Will be updated when browser manager is added
+            browser: state.browser.is_running(),
         },
     })
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+pub struct ReadyResponse {
+    /// Whether every required check passed
+    pub ready: bool,
+    pub checks: Vec<ReadyCheck>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReadyCheck {
+    pub name: String,
+    pub ok: bool,
+    /// Whether a failure makes the sandbox not ready, per `ready_checks`
+    pub required: bool,
+    /// Version found, or why the check failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub duration_ms: f64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    summary = "Readiness: probe interpreters, the skills directory, the browser, and dstack",
+    description = "Runs every check concurrently. The browser check launches Chromium if it is not \
+        running; a failed launch is reported for a minute before it is tried again. The dstack \
+        check only exists in `tee` builds.",
+    responses(
+        (status = 200, description = "All required checks passed", body = ReadyResponse),
+        (status = 503, description = "A required check failed", body = ReadyResponse),
+    ),
+)]
+pub async fn ready_check(State(state): State<Arc<AppState>>) -> Response {
+    let skills_dir = state.config.skills_dir.clone();
+    let browser = state.browser.clone();
+    let (python3, node, skills, browser) = tokio::join!(
+        run_check("python3", version_of("python3")),
+        run_check("node", version_of("node")),
+        run_check("skills", writable(skills_dir)),
+        run_check("browser", async move {
+            browser.check_launch(BROWSER_RETRY_AFTER).await.map_err(|e| e.to_string())?;
+            Ok(None)
+        }),
+    );
+    #[allow(unused_mut)]
+    let mut checks = vec![python3, node, skills, browser];
+
+    #[cfg(feature = "tee")]
+    {
+        let tee = state.tee_service.clone();
+        checks.push(
+            run_check("dstack", async move {
+                let info = tee.info().await.map_err(|e| format!("{:#}", e))?;
+                Ok(Some(info.app_id))
+            })
+            .await,
+        );
+    }
+
+    for check in &mut checks {
+        check.required = state.config.ready_checks.contains(&check.name);
+    }
+    // A draining server is taken out of rotation whatever `ready_checks` says
+    let draining = state.drain.since();
+    checks.push(ReadyCheck {
+        name: "drain".into(),
+        ok: draining.is_none(),
+        required: true,
+        detail: draining.map(|since| format!("Draining since {}", since.to_rfc3339())),
+        duration_ms: 0.0,
+    });
+    let ready = checks.iter().all(|check| check.ok || !check.required);
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(ReadyResponse { ready, checks })).into_response()
+}
+
+/// Time `check`, failing it if it takes longer than `CHECK_TIMEOUT`
+async fn run_check(
+    name: &str,
+    check: impl Future<Output = Result<Option<String>, String>>,
+) -> ReadyCheck {
+    let start = Instant::now();
+    let result = tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("No answer within {}s", CHECK_TIMEOUT.as_secs())));
+    let (ok, detail) = match result {
+        Ok(detail) => (true, detail),
+        Err(error) => (false, Some(error)),
+    };
+    ReadyCheck {
+        name: name.to_string(),
+        ok,
+        required: false,
+        detail,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    }
+}
+
+/// The output of `program --version`
+async fn version_of(program: &str) -> Result<Option<String>, String> {
+    let output = Command::new(program)
+        .arg("--version")
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!("{} --version exited with {}", program, output.status));
+    }
+    // Python 2 printed its version to stderr
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    Ok(Some(String::from_utf8_lossy(&text).trim().to_string()))
+}
+
+/// Whether a file can be created in `dir`, creating `dir` if it is missing
+async fn writable(dir: String) -> Result<Option<String>, String> {
+    let probe = Path::new(&dir).join(format!(".ready-{}", uuid::Uuid::new_v4()));
+    let created = match tokio::fs::create_dir_all(&dir).await {
+        Ok(()) => tokio::fs::write(&probe, b"").await,
+        Err(e) => Err(e),
+    };
+    created.map_err(|e| format!("{}: {}", dir, e))?;
+    tokio::fs::remove_file(&probe).await.ok();
+    Ok(None)
+}
+
+#[derive(Serialize, ToSchema)]
 pub struct SandboxInfo {
     pub hostname: String,
     pub workspace: String,
@@ -38,6 +194,13 @@ pub struct SandboxInfo {
     pub vnc_url: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/sandbox/info",
+    tag = "health",
+    summary = "Sandbox host, workspace, and display details",
+    responses((status = 200, body = SandboxInfo)),
+)]
 pub async fn sandbox_info(State(state): State<Arc<AppState>>) -> Json<SandboxInfo> {
     let hostname = hostname::get()
         .map(|h| h.to_string_lossy().into_owned())
@@ -51,3 +214,119 @@ pub async fn sandbox_info(State(state): State<Arc<AppState>>) -> Json<SandboxInf
         vnc_url: "vnc://localhost:5900".into(),
     })
 }
+
+/// Cargo features this build was compiled with
+const FEATURES: &[(&str, bool)] = &[
+    ("tee", cfg!(feature = "tee")),
+    ("grpc", cfg!(feature = "grpc")),
+    ("sqlite", cfg!(feature = "sqlite")),
+];
+
+fn features() -> Vec<String> {
+    FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VersionResponse {
+    /// Crate version
+    pub version: String,
+    /// Commit the server was built from, or `unknown`
+    pub git_commit: String,
+    pub build_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub rustc: String,
+    /// Enabled optional features, e.g. `tee`
+    pub features: Vec<String>,
+    /// API version served under `/v1`
+    pub api_version: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    tag = "health",
+    summary = "Server version, commit, build time, and enabled features",
+    responses((status = 200, body = VersionResponse)),
+)]
+pub async fn version_info() -> Json<VersionResponse> {
+    let build_time = env!("BUILD_EPOCH")
+        .parse()
+        .ok()
+        .and_then(|epoch| chrono::DateTime::from_timestamp(epoch, 0));
+
+    Json(VersionResponse {
+        version: env!("CARGO_PKG_VERSION").into(),
+        git_commit: env!("BUILD_GIT_COMMIT").into(),
+        build_time,
+        rustc: env!("BUILD_RUSTC").into(),
+        features: features(),
+        api_version: crate::versioning::API_VERSION.into(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/sandbox/manifest",
+    tag = "health",
+    summary = "Tool versions, Nix closure, features, and config digest, to reproduce a run later",
+    description = "Probes the tools and walks the Nix closure on every call, so it can take a few \
+        seconds. `nix` is absent when no tool is in the Nix store or Nix cannot be queried.",
+    responses((status = 200, body = Manifest)),
+)]
+pub async fn sandbox_manifest(State(state): State<Arc<AppState>>) -> Result<Json<Manifest>, AppError> {
+    Ok(Json(manifest::collect(&state.config, features()).await?))
+}
+
+/// Seconds a client is asked to wait when no usage sample has been taken yet
+const USAGE_RETRY_AFTER: u64 = 1;
+
+#[utoipa::path(
+    get,
+    path = "/sandbox/usage",
+    tag = "health",
+    summary = "CPU, memory, disk, file descriptor, process, and browser memory usage",
+    description = "Sampled in the background every `usage_interval` seconds; `sampled_at` says when.",
+    responses((status = 200, body = Usage)),
+)]
+pub async fn sandbox_usage(State(state): State<Arc<AppState>>) -> Result<Json<Usage>, AppError> {
+    state.usage.latest().map(Json).ok_or_else(|| {
+        AppError::ServiceUnavailable("Resource usage has not been sampled yet".into(), USAGE_RETRY_AFTER)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let skills = dir.path().join("skills");
+        assert!(writable(skills.display().to_string()).await.is_ok());
+        assert_eq!(std::fs::read_dir(&skills).unwrap().count(), 0);
+
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        assert!(writable(file.display().to_string()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_version_info() {
+        let Json(version) = version_info().await;
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.api_version, "1");
+        assert!(version.build_time.is_some());
+        assert!(!version.git_commit.is_empty());
+        assert_eq!(version.features.contains(&"tee".to_string()), cfg!(feature = "tee"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_check_keeps_its_reason() {
+        let check = run_check("node", version_of("no-such-program-for-ready")).await;
+        assert!(!check.ok);
+        assert!(check.detail.unwrap().starts_with("no-such-program-for-ready"));
+    }
+}
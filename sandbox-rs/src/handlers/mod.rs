@@ -1,20 +1,44 @@
+pub mod admin;
 pub mod browser;
 pub mod code;
+pub mod convert;
+pub mod data;
+pub mod display;
+pub mod env;
 pub mod factory;
 pub mod file;
 pub mod health;
+pub mod net;
+pub mod notebook;
+pub mod secrets;
+pub mod services;
+pub mod sessions;
 pub mod shell;
 pub mod skills;
+pub mod webhooks;
+pub mod ws;
 
 #[cfg(feature = "tee")]
 pub mod tee;
 
+pub use admin::*;
 pub use browser::*;
 pub use code::*;
+pub use convert::*;
+pub use data::*;
+pub use display::*;
+pub use env::*;
 pub use factory::*;
 pub use file::*;
 pub use health::*;
+pub use net::*;
+pub use notebook::*;
+pub use secrets::*;
+pub use services::*;
+pub use sessions::*;
 pub use shell::*;
 pub use skills::*;
+pub use webhooks::*;
+pub use ws::*;
 
 // Note: TEE handlers are imported explicitly via handlers::tee::{...} in main.rs
@@ -0,0 +1,157 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::net::FetchRequest;
+use crate::state::AppState;
+
+// POST /net/fetch - Make an HTTP request from the sandbox
+#[derive(Deserialize, ToSchema)]
+pub struct NetFetchRequest {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Request body, sent as is
+    pub body: Option<String>,
+    /// Redirects to follow; 0 returns the redirect itself
+    #[serde(default = "default_max_redirects")]
+    pub max_redirects: usize,
+    /// Largest response body in bytes, at most `NET_MAX_SIZE`
+    #[serde(default)]
+    pub max_size: u64,
+    /// Seconds until the response, including its body unless streamed, has arrived
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Save the body to this workspace path instead of returning it
+    pub save_to: Option<String>,
+    /// Return the body as it arrives, as the response itself
+    #[serde(default)]
+    pub stream: bool,
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+fn default_max_redirects() -> usize {
+    5
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NetFetchResponse {
+    pub status: u16,
+    /// The URL answered, after redirects
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    /// The body, unless saved; text as is and anything else base64-encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// `utf8` or `base64`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Where the body was saved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub size: u64,
+    pub redirects: Vec<String>,
+    pub duration_ms: f64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/net/fetch",
+    tag = "net",
+    summary = "Make an HTTP request under the egress policy",
+    request_body = NetFetchRequest,
+    responses(
+        (status = 200, body = NetFetchResponse),
+        (status = 200, description = "With `stream`, the body itself, with the upstream status in `X-Fetch-Status`", content_type = "application/octet-stream"),
+    ),
+)]
+pub async fn net_fetch(State(state): State<Arc<AppState>>, Json(req): Json<NetFetchRequest>) -> Result<Response> {
+    let start = Instant::now();
+    let save_to = req.save_to.as_deref().map(|path| state.resolve(path)).transpose()?;
+    let mut interpolation = state.secrets.interpolation();
+    let fetch = FetchRequest {
+        url: interpolation.expand(&req.url).await?,
+        method: req.method.clone(),
+        headers: interpolation.expand_env(&req.headers).await?,
+        body: match &req.body {
+            Some(body) => Some(interpolation.expand(body).await?.into_bytes()),
+            None => None,
+        },
+        max_redirects: req.max_redirects,
+        max_size: req.max_size,
+    };
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(req.timeout);
+    let timed_out = |_| AppError::exec_timeout("Fetch timed out", req.timeout);
+    let fetched = tokio::time::timeout_at(deadline, state.egress.fetch(fetch)).await.map_err(timed_out)??;
+    let status = fetched.response.status();
+    let url = interpolation.redact(fetched.response.url().to_string());
+    tracing::info!(method = %req.method, %url, status = status.as_u16(), "Fetched");
+
+    if req.stream {
+        let content_type = fetched.response.headers().get(header::CONTENT_TYPE).cloned();
+        let body = Body::from_stream(fetched.stream()?);
+        let mut response = (StatusCode::OK, body).into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            header::CONTENT_TYPE,
+            content_type.unwrap_or(HeaderValue::from_static("application/octet-stream")),
+        );
+        headers.insert("x-fetch-status", HeaderValue::from(status.as_u16()));
+        if let Ok(url) = HeaderValue::from_str(&url) {
+            headers.insert("x-fetch-url", url);
+        }
+        return Ok(response);
+    }
+
+    let headers = fetched.headers();
+    let redirects = fetched.redirects.iter().map(|url| interpolation.redact(url.clone())).collect();
+    let (body, encoding, path, size) = match save_to {
+        Some(path) => {
+            let size = tokio::time::timeout_at(deadline, fetched.save(&path)).await.map_err(timed_out)??;
+            (None, None, Some(path.to_string_lossy().into_owned()), size)
+        }
+        None => {
+            let bytes = tokio::time::timeout_at(deadline, fetched.bytes()).await.map_err(timed_out)??;
+            let size = bytes.len() as u64;
+            let (body, encoding) = match String::from_utf8(bytes) {
+                Ok(text) => (interpolation.redact(text), "utf8"),
+                Err(e) => (base64::engine::general_purpose::STANDARD.encode(e.into_bytes()), "base64"),
+            };
+            (Some(body), Some(encoding.to_string()), None, size)
+        }
+    };
+
+    Ok(Json(NetFetchResponse {
+        status: status.as_u16(),
+        url,
+        headers,
+        body,
+        encoding,
+        path,
+        size,
+        redirects,
+        duration_ms: start.elapsed().as_secs_f64() * 1000.0,
+    })
+    .into_response())
+}
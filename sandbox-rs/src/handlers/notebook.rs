@@ -0,0 +1,445 @@
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+use super::code::{is_supported, run_code};
+use crate::error::{AppError, Result};
+use crate::notebook::{cell_outputs, Cell, CellType, Notebook, NotebookStore, OUTPUT_DIR_ENV};
+use crate::state::AppState;
+use crate::webhooks;
+
+/// A cell to add to a notebook
+#[derive(Deserialize, ToSchema)]
+pub struct NewCell {
+    #[serde(default = "default_cell_type")]
+    pub cell_type: CellType,
+    pub source: String,
+    /// Language of a code cell, when not the notebook's
+    pub language: Option<String>,
+}
+
+fn default_cell_type() -> CellType {
+    CellType::Code
+}
+
+impl NewCell {
+    fn into_cell(self) -> Result<Cell> {
+        check_language(self.language.as_deref())?;
+        Ok(Cell::new(self.cell_type, self.source, self.language))
+    }
+}
+
+fn check_language(language: Option<&str>) -> Result<()> {
+    match language {
+        Some(language) if !is_supported(language) => {
+            Err(AppError::BadRequest(format!("Unsupported language: {}", language)))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn store(state: &AppState) -> NotebookStore {
+    NotebookStore::new(&state.config.workspace)
+}
+
+// POST /notebook - Create a notebook
+#[derive(Deserialize, ToSchema)]
+pub struct CreateNotebookRequest {
+    #[serde(default = "default_title")]
+    pub title: String,
+    /// Language of code cells that do not name their own
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub cells: Vec<NewCell>,
+}
+
+fn default_title() -> String {
+    "Untitled".into()
+}
+
+fn default_language() -> String {
+    "python".into()
+}
+
+#[utoipa::path(
+    post,
+    path = "/notebook",
+    tag = "notebook",
+    summary = "Create a notebook in the workspace",
+    request_body = CreateNotebookRequest,
+    responses((status = 200, body = Notebook)),
+)]
+pub async fn create_notebook(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateNotebookRequest>,
+) -> Result<Json<Notebook>> {
+    check_language(Some(&req.language))?;
+    let cells = req.cells.into_iter().map(NewCell::into_cell).collect::<Result<_>>()?;
+    let notebook = Notebook::new(req.title, req.language.to_lowercase(), cells);
+    store(&state).create(&notebook).await?;
+    Ok(Json(notebook))
+}
+
+// GET /notebook - List notebooks
+#[derive(Serialize, ToSchema)]
+pub struct NotebookSummary {
+    pub id: String,
+    pub title: String,
+    pub language: String,
+    pub cells: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListNotebooksResponse {
+    pub notebooks: Vec<NotebookSummary>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/notebook",
+    tag = "notebook",
+    summary = "List notebooks, most recently updated first",
+    responses((status = 200, body = ListNotebooksResponse)),
+)]
+pub async fn list_notebooks(State(state): State<Arc<AppState>>) -> Result<Json<ListNotebooksResponse>> {
+    let notebooks = store(&state)
+        .list()
+        .await?
+        .into_iter()
+        .map(|notebook| NotebookSummary {
+            id: notebook.id,
+            title: notebook.title,
+            language: notebook.language,
+            cells: notebook.cells.len(),
+            updated_at: notebook.updated_at,
+        })
+        .collect();
+    Ok(Json(ListNotebooksResponse { notebooks }))
+}
+
+// GET /notebook/{id} - Get a notebook
+#[utoipa::path(
+    get,
+    path = "/notebook/{id}",
+    tag = "notebook",
+    summary = "Get a notebook with its cells and their outputs",
+    params(("id" = String, Path, description = "Notebook ID")),
+    responses((status = 200, body = Notebook)),
+)]
+pub async fn get_notebook(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Json<Notebook>> {
+    Ok(Json(store(&state).get(&id).await?))
+}
+
+// DELETE /notebook/{id} - Delete a notebook
+#[derive(Serialize, ToSchema)]
+pub struct NotebookResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/notebook/{id}",
+    tag = "notebook",
+    summary = "Delete a notebook",
+    params(("id" = String, Path, description = "Notebook ID")),
+    responses((status = 200, body = NotebookResponse)),
+)]
+pub async fn delete_notebook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<NotebookResponse>> {
+    store(&state).delete(&id).await?;
+    Ok(Json(NotebookResponse {
+        success: true,
+        message: format!("Notebook '{}' deleted", id),
+    }))
+}
+
+// POST /notebook/{id}/cells - Add a cell
+#[derive(Deserialize, ToSchema)]
+pub struct AddCellRequest {
+    #[serde(flatten)]
+    pub cell: NewCell,
+    /// Position to insert at; appended by default
+    pub index: Option<usize>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/notebook/{id}/cells",
+    tag = "notebook",
+    summary = "Add a cell to a notebook",
+    params(("id" = String, Path, description = "Notebook ID")),
+    request_body = AddCellRequest,
+    responses((status = 200, body = Cell)),
+)]
+pub async fn add_cell(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<AddCellRequest>,
+) -> Result<Json<Cell>> {
+    let cell = req.cell.into_cell()?;
+    let (_, cell) = store(&state)
+        .update(&id, |notebook| {
+            let index = req.index.unwrap_or(notebook.cells.len()).min(notebook.cells.len());
+            notebook.cells.insert(index, cell.clone());
+            Ok(cell)
+        })
+        .await?;
+    Ok(Json(cell))
+}
+
+// PUT /notebook/{id}/cells/{cell_id} - Edit or move a cell
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateCellRequest {
+    /// New source; clears the cell's outputs
+    pub source: Option<String>,
+    pub cell_type: Option<CellType>,
+    pub language: Option<String>,
+    /// Move the cell to this position
+    pub index: Option<usize>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/notebook/{id}/cells/{cell_id}",
+    tag = "notebook",
+    summary = "Change a cell's source, type, language, or position",
+    params(
+        ("id" = String, Path, description = "Notebook ID"),
+        ("cell_id" = String, Path, description = "Cell ID"),
+    ),
+    request_body = UpdateCellRequest,
+    responses((status = 200, body = Cell)),
+)]
+pub async fn update_cell(
+    State(state): State<Arc<AppState>>,
+    Path((id, cell_id)): Path<(String, String)>,
+    Json(req): Json<UpdateCellRequest>,
+) -> Result<Json<Cell>> {
+    check_language(req.language.as_deref())?;
+    let (_, cell) = store(&state)
+        .update(&id, |notebook| {
+            let cell = notebook.cell_mut(&cell_id)?;
+            if let Some(source) = req.source {
+                cell.source = source;
+                cell.outputs.clear();
+                cell.execution_count = None;
+                cell.duration_ms = None;
+            }
+            if let Some(cell_type) = req.cell_type {
+                cell.cell_type = cell_type;
+            }
+            if req.language.is_some() {
+                cell.language = req.language;
+            }
+            let cell = cell.clone();
+            if let Some(index) = req.index {
+                notebook.cells.retain(|c| c.id != cell_id);
+                let index = index.min(notebook.cells.len());
+                notebook.cells.insert(index, cell.clone());
+            }
+            Ok(cell)
+        })
+        .await?;
+    Ok(Json(cell))
+}
+
+// DELETE /notebook/{id}/cells/{cell_id} - Remove a cell
+#[utoipa::path(
+    delete,
+    path = "/notebook/{id}/cells/{cell_id}",
+    tag = "notebook",
+    summary = "Remove a cell from a notebook",
+    params(
+        ("id" = String, Path, description = "Notebook ID"),
+        ("cell_id" = String, Path, description = "Cell ID"),
+    ),
+    responses((status = 200, body = NotebookResponse)),
+)]
+pub async fn delete_cell(
+    State(state): State<Arc<AppState>>,
+    Path((id, cell_id)): Path<(String, String)>,
+) -> Result<Json<NotebookResponse>> {
+    store(&state)
+        .update(&id, |notebook| {
+            notebook.cell(&cell_id)?;
+            notebook.cells.retain(|cell| cell.id != cell_id);
+            Ok(())
+        })
+        .await?;
+    Ok(Json(NotebookResponse {
+        success: true,
+        message: format!("Cell '{}' removed", cell_id),
+    }))
+}
+
+// POST /notebook/{id}/cells/{cell_id}/run - Run one cell
+#[derive(Deserialize, ToSchema)]
+pub struct RunCellRequest {
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+#[utoipa::path(
+    post,
+    path = "/notebook/{id}/cells/{cell_id}/run",
+    tag = "notebook",
+    summary = "Run a code cell and store its outputs",
+    params(
+        ("id" = String, Path, description = "Notebook ID"),
+        ("cell_id" = String, Path, description = "Cell ID"),
+    ),
+    request_body = RunCellRequest,
+    responses((status = 200, body = Cell)),
+)]
+pub async fn run_cell(
+    State(state): State<Arc<AppState>>,
+    Path((id, cell_id)): Path<(String, String)>,
+    Json(req): Json<RunCellRequest>,
+) -> Result<Json<Cell>> {
+    let notebook = store(&state).get(&id).await?;
+    let cell = notebook.cell(&cell_id)?;
+    if cell.cell_type != CellType::Code {
+        return Err(AppError::BadRequest(format!("Cell '{}' is not a code cell", cell_id)));
+    }
+    let (cell, _) = execute(&state, &notebook, cell, req.timeout).await?;
+    Ok(Json(cell))
+}
+
+// POST /notebook/{id}/run - Run every code cell in order
+#[derive(Deserialize, ToSchema)]
+pub struct RunNotebookRequest {
+    /// Seconds each cell may run
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Stop at the first cell that exits unsuccessfully
+    #[serde(default = "default_stop_on_error")]
+    pub stop_on_error: bool,
+}
+
+fn default_stop_on_error() -> bool {
+    true
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RunNotebookResponse {
+    pub notebook: Notebook,
+    /// IDs of the cells that ran, in order
+    pub executed: Vec<String>,
+    /// The cell that stopped the run, with `stop_on_error`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/notebook/{id}/run",
+    tag = "notebook",
+    summary = "Run a notebook's code cells top to bottom, storing each cell's outputs as it finishes",
+    params(("id" = String, Path, description = "Notebook ID")),
+    request_body = RunNotebookRequest,
+    responses((status = 200, body = RunNotebookResponse)),
+)]
+pub async fn run_notebook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Json(req): Json<RunNotebookRequest>,
+) -> Result<Json<RunNotebookResponse>> {
+    let mut notebook = store(&state).get(&id).await?;
+    let (mut executed, mut failed) = (Vec::new(), None);
+    let cells: Vec<Cell> = notebook.cells.iter().filter(|c| c.cell_type == CellType::Code).cloned().collect();
+    for cell in &cells {
+        let (ran, exit_code) = execute(&state, &notebook, cell, req.timeout).await?;
+        notebook = store(&state).get(&id).await?;
+        executed.push(ran.id);
+        if exit_code != 0 && req.stop_on_error {
+            failed = Some(cell.id.clone());
+            break;
+        }
+    }
+    Ok(Json(RunNotebookResponse { notebook, executed, failed }))
+}
+
+/// Run `cell` of `notebook`, save its outputs, and return the updated cell and exit code
+async fn execute(state: &AppState, notebook: &Notebook, cell: &Cell, timeout: u64) -> Result<(Cell, i32)> {
+    let language = notebook.language_of(cell).to_string();
+    let output_dir = std::env::temp_dir().join(format!("notebook-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&output_dir).await?;
+
+    let start = Instant::now();
+    let env = [(OUTPUT_DIR_ENV, output_dir.to_str().unwrap_or_default())];
+    let ran = run_code(state, &language, &cell.source, timeout, &env).await;
+    let duration_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let ran = match ran {
+        Ok(ran) => ran,
+        Err(e) => {
+            tokio::fs::remove_dir_all(&output_dir).await.ok();
+            return Err(e);
+        }
+    };
+    let outputs = cell_outputs(ran.stdout, ran.stderr, ran.exit_code, &output_dir);
+    tokio::fs::remove_dir_all(&output_dir).await.ok();
+
+    let (_, updated) = store(state)
+        .update(&notebook.id, |notebook| {
+            notebook.execution_count += 1;
+            let count = notebook.execution_count;
+            let cell = notebook.cell_mut(&cell.id)?;
+            cell.outputs = outputs;
+            cell.execution_count = Some(count);
+            cell.duration_ms = Some(duration_ms);
+            Ok(cell.clone())
+        })
+        .await?;
+    webhooks::emit(
+        state,
+        "job.completed",
+        serde_json::json!({
+            "kind": "notebook.cell",
+            "notebook_id": notebook.id,
+            "cell_id": cell.id,
+            "language": language,
+            "exit_code": ran.exit_code,
+            "duration_ms": duration_ms,
+        }),
+    );
+    Ok((updated, ran.exit_code))
+}
+
+// GET /notebook/{id}/export - Export as .ipynb
+#[utoipa::path(
+    get,
+    path = "/notebook/{id}/export",
+    tag = "notebook",
+    summary = "Download a notebook as a Jupyter .ipynb document",
+    params(("id" = String, Path, description = "Notebook ID")),
+    responses((status = 200, description = "nbformat 4.5 document", content_type = "application/x-ipynb+json")),
+)]
+pub async fn export_notebook(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Response> {
+    let notebook = store(&state).get(&id).await?;
+    let body = serde_json::to_string_pretty(&notebook.to_ipynb()).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-ipynb+json".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.ipynb\"", notebook.id)),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -0,0 +1,119 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::secrets::SecretInfo;
+use crate::state::AppState;
+
+// POST /secrets - Store a secret
+#[derive(Deserialize, ToSchema)]
+pub struct CreateSecretRequest {
+    /// Environment-variable style name, referenced as `{{secret:NAME}}`
+    pub name: String,
+    /// The value; it is never returned and is sealed before it is stored
+    pub secret: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/secrets",
+    tag = "secrets",
+    summary = "Store a secret, sealed at rest, for use as {{secret:NAME}}",
+    request_body = CreateSecretRequest,
+    responses((status = 200, body = SecretInfo)),
+)]
+pub async fn create_secret(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateSecretRequest>,
+) -> Result<Json<SecretInfo>> {
+    let info = state.secrets.set(&req.name, req.secret, false).await?;
+    Ok(Json(info))
+}
+
+// GET /secrets - List secrets
+#[derive(Serialize, ToSchema)]
+pub struct ListSecretsResponse {
+    pub secrets: Vec<SecretInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/secrets",
+    tag = "secrets",
+    summary = "List secret names and timestamps, never their values",
+    responses((status = 200, body = ListSecretsResponse)),
+)]
+pub async fn list_secrets(State(state): State<Arc<AppState>>) -> Result<Json<ListSecretsResponse>> {
+    Ok(Json(ListSecretsResponse { secrets: state.secrets.list().await? }))
+}
+
+// GET /secrets/{name} - Get a secret's metadata
+#[utoipa::path(
+    get,
+    path = "/secrets/{name}",
+    tag = "secrets",
+    summary = "Get a secret's name and timestamps",
+    params(("name" = String, Path, description = "Secret name")),
+    responses((status = 200, body = SecretInfo)),
+)]
+pub async fn get_secret(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<SecretInfo>> {
+    Ok(Json(state.secrets.get(&name).await?))
+}
+
+// PUT /secrets/{name} - Replace a secret's value
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateSecretRequest {
+    pub secret: String,
+}
+
+#[utoipa::path(
+    put,
+    path = "/secrets/{name}",
+    tag = "secrets",
+    summary = "Replace a secret's value",
+    params(("name" = String, Path, description = "Secret name")),
+    request_body = UpdateSecretRequest,
+    responses((status = 200, body = SecretInfo)),
+)]
+pub async fn update_secret(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<UpdateSecretRequest>,
+) -> Result<Json<SecretInfo>> {
+    let info = state.secrets.set(&name, req.secret, true).await?;
+    Ok(Json(info))
+}
+
+// DELETE /secrets/{name} - Delete a secret
+#[derive(Serialize, ToSchema)]
+pub struct SecretResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/secrets/{name}",
+    tag = "secrets",
+    summary = "Delete a secret",
+    params(("name" = String, Path, description = "Secret name")),
+    responses((status = 200, body = SecretResponse)),
+)]
+pub async fn delete_secret(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<SecretResponse>> {
+    state.secrets.remove(&name).await?;
+    Ok(Json(SecretResponse {
+        success: true,
+        message: format!("Secret '{}' deleted", name),
+    }))
+}
@@ -0,0 +1,286 @@
+use axum::{
+    body::Body,
+    extract::{OriginalUri, Path, Request, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::services::{Health, Service};
+use crate::state::AppState;
+use crate::versioning;
+
+/// Headers that describe one connection and are not forwarded past it
+const HOP_BY_HOP: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+#[derive(Serialize, ToSchema)]
+pub struct ServiceInfo {
+    #[serde(flatten)]
+    pub service: Service,
+    /// Where the service is reachable through the API, when proxied
+    pub proxy_url: Option<String>,
+    pub health: Health,
+}
+
+async fn info(state: &AppState, service: Service) -> ServiceInfo {
+    let health = state.services.check(&service).await;
+    let proxy_url = service.proxy.then(|| format!("{}/proxy/{}/", versioning::PREFIX, service.name));
+    ServiceInfo { service, proxy_url, health }
+}
+
+// POST /services - Declare a service
+#[derive(Deserialize, ToSchema)]
+pub struct DeclareServiceRequest {
+    /// Lowercase letters, digits, and dashes; declaring a name again replaces it
+    pub name: String,
+    /// Port the process listens on at 127.0.0.1
+    pub port: u16,
+    /// Path to GET for health checks, e.g. `/health`; a TCP connect when unset
+    pub health_path: Option<String>,
+    /// Forward `/proxy/{name}/` to the service
+    #[serde(default = "default_proxy")]
+    pub proxy: bool,
+}
+
+fn default_proxy() -> bool {
+    true
+}
+
+#[utoipa::path(
+    post,
+    path = "/services",
+    tag = "services",
+    summary = "Declare that a process in the sandbox listens on a port",
+    request_body = DeclareServiceRequest,
+    responses((status = 200, body = ServiceInfo)),
+)]
+pub async fn declare_service(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DeclareServiceRequest>,
+) -> Result<Json<ServiceInfo>> {
+    let service = Service {
+        name: req.name,
+        port: req.port,
+        health_path: req.health_path,
+        proxy: req.proxy,
+        created_at: Utc::now(),
+    };
+    state.services.declare(service.clone(), state.config.port)?;
+    Ok(Json(info(&state, service).await))
+}
+
+// GET /services - List services
+#[derive(Serialize, ToSchema)]
+pub struct ListServicesResponse {
+    pub services: Vec<ServiceInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/services",
+    tag = "services",
+    summary = "List declared services, checking the health of each",
+    responses((status = 200, body = ListServicesResponse)),
+)]
+pub async fn list_services(State(state): State<Arc<AppState>>) -> Json<ListServicesResponse> {
+    let checks = state.services.list().into_iter().map(|service| info(&state, service));
+    Json(ListServicesResponse { services: futures::future::join_all(checks).await })
+}
+
+// GET /services/{name} - Get a service
+#[utoipa::path(
+    get,
+    path = "/services/{name}",
+    tag = "services",
+    summary = "Get a service and check its health",
+    params(("name" = String, Path, description = "Service name")),
+    responses((status = 200, body = ServiceInfo)),
+)]
+pub async fn get_service(State(state): State<Arc<AppState>>, Path(name): Path<String>) -> Result<Json<ServiceInfo>> {
+    let service = state.services.get(&name)?;
+    Ok(Json(info(&state, service).await))
+}
+
+// DELETE /services/{name} - Forget a service
+#[derive(Serialize, ToSchema)]
+pub struct ServiceResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/services/{name}",
+    tag = "services",
+    summary = "Forget a service and stop proxying to it; the process keeps running",
+    params(("name" = String, Path, description = "Service name")),
+    responses((status = 200, body = ServiceResponse)),
+)]
+pub async fn delete_service(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ServiceResponse>> {
+    state.services.remove(&name)?;
+    Ok(Json(ServiceResponse {
+        success: true,
+        message: format!("Service '{}' removed", name),
+    }))
+}
+
+// ANY /proxy/{service}/{path} - Forward a request to a service
+#[utoipa::path(
+    method(get, post, put, patch, delete),
+    path = "/proxy/{service}/{path}",
+    tag = "services",
+    summary = "Forward a request to a service's port, streaming both ways",
+    params(
+        ("service" = String, Path, description = "Service name"),
+        ("path" = String, Path, description = "Path on the service"),
+    ),
+    responses((status = 200, description = "Whatever the service answers")),
+)]
+pub async fn proxy_service(
+    State(state): State<Arc<AppState>>,
+    OriginalUri(original): OriginalUri,
+    request: Request,
+) -> Result<Response> {
+    let (parts, body) = request.into_parts();
+    // Split from the raw path rather than extracted, so escapes such as %2F reach the service as sent
+    let proxied = versioning::unprefixed(parts.uri.path()).strip_prefix("/proxy/").unwrap_or_default();
+    let (name, path) = proxied.find('/').map_or((proxied, ""), |slash| proxied.split_at(slash));
+    let service = state.services.get(name)?;
+    if !service.proxy {
+        return Err(AppError::NotFound(format!("Service '{}' is not proxied", name)).with_code("SERVICE_NOT_PROXIED"));
+    }
+
+    if path.is_empty() {
+        // Relative links in the service's pages only resolve below a trailing slash
+        let mut location = format!("{}/", original.path());
+        if let Some(query) = original.query() {
+            location = format!("{}?{}", location, query);
+        }
+        return Ok(Redirect::temporary(&location).into_response());
+    }
+    let prefix = original.path().strip_suffix(path).unwrap_or(original.path()).to_string();
+    let mut url = format!("http://127.0.0.1:{}{}", service.port, path);
+    if let Some(query) = parts.uri.query() {
+        url = format!("{}?{}", url, query);
+    }
+
+    let mut headers = forwarded(&parts.headers);
+    headers.remove(header::HOST);
+    // Credentials for this API, whichever auth mode issued them, are not the service's business
+    headers.remove(header::AUTHORIZATION);
+    headers.remove("x-api-key");
+    if let Ok(value) = HeaderValue::from_str(&prefix) {
+        headers.insert("x-forwarded-prefix", value);
+    }
+    if let Some(host) = parts.headers.get(header::HOST) {
+        headers.insert("x-forwarded-host", host.clone());
+    }
+
+    let upstream = state
+        .services
+        .client()
+        .request(parts.method, url)
+        .headers(headers)
+        .body(reqwest::Body::wrap_stream(body.into_data_stream()))
+        .send()
+        .await
+        .map_err(|e| {
+            AppError::ServiceUnavailable(
+                format!("Service '{}' did not answer on port {}: {}", name, service.port, e),
+                5,
+            )
+            .with_code("SERVICE_UNREACHABLE")
+        })?;
+
+    let mut response = Response::builder().status(upstream.status());
+    let mut response_headers = forwarded(upstream.headers());
+    let location = response_headers.get(header::LOCATION).and_then(|l| l.to_str().ok());
+    if let Some(location) = location.and_then(|l| rewrite_location(l, service.port, &prefix)) {
+        if let Ok(value) = HeaderValue::from_str(&location) {
+            response_headers.insert(header::LOCATION, value);
+        }
+    }
+    if let Some(headers) = response.headers_mut() {
+        *headers = response_headers;
+    }
+    response
+        .body(Body::from_stream(upstream.bytes_stream()))
+        .map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// `headers` without the hop-by-hop ones, nor those `Connection` names
+fn forwarded(headers: &HeaderMap) -> HeaderMap {
+    let named: Vec<String> = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(',').map(|name| name.trim().to_ascii_lowercase()))
+        .collect();
+    let mut forwarded = headers.clone();
+    for name in HOP_BY_HOP.iter().copied().chain(named.iter().map(String::as_str)) {
+        forwarded.remove(name);
+    }
+    forwarded
+}
+
+/// A redirect to the service's own root-relative or loopback URL, moved under `prefix`
+fn rewrite_location(location: &str, port: u16, prefix: &str) -> Option<String> {
+    if location.starts_with('/') && !location.starts_with("//") {
+        return Some(format!("{}{}", prefix, location));
+    }
+    let url = url::Url::parse(location).ok()?;
+    let local = matches!(url.host_str(), Some("127.0.0.1" | "localhost")) && url.port() == Some(port);
+    if !local {
+        return None;
+    }
+    let mut rewritten = format!("{}{}", prefix, url.path());
+    if let Some(query) = url.query() {
+        rewritten = format!("{}?{}", rewritten, query);
+    }
+    Some(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_location() {
+        let prefix = "/v1/proxy/web";
+        assert_eq!(rewrite_location("/login?next=1", 3000, prefix).unwrap(), "/v1/proxy/web/login?next=1");
+        assert_eq!(rewrite_location("http://localhost:3000/a", 3000, prefix).unwrap(), "/v1/proxy/web/a");
+        assert_eq!(rewrite_location("http://localhost:4000/a", 3000, prefix), None);
+        assert_eq!(rewrite_location("https://example.com/", 3000, prefix), None);
+        assert_eq!(rewrite_location("//example.com/", 3000, prefix), None);
+        assert_eq!(rewrite_location("next", 3000, prefix), None);
+    }
+
+    #[test]
+    fn test_forwarded_drops_hop_by_hop_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::CONNECTION, HeaderValue::from_static("keep-alive, x-trace"));
+        headers.insert("x-trace", HeaderValue::from_static("1"));
+        headers.insert(header::TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/html"));
+        let forwarded = forwarded(&headers);
+        assert_eq!(forwarded.len(), 1);
+        assert!(forwarded.contains_key(header::ACCEPT));
+    }
+}
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::sessions::SessionInfo;
+use crate::state::AppState;
+
+// POST /sessions - Create a session
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CreateSessionRequest {
+    /// Letters, digits, '-', and '_'; a UUID when omitted
+    pub id: Option<String>,
+    /// Defaults to `rate_limit_rpm`
+    pub rate_limit_rpm: Option<u32>,
+    /// Defaults to `rate_limit_concurrent`
+    pub rate_limit_concurrent: Option<usize>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions",
+    tag = "sessions",
+    summary = "Create a session with its own workspace, skills, env, browser, and quotas",
+    request_body = CreateSessionRequest,
+    responses((status = 200, body = SessionInfo)),
+)]
+pub async fn create_session(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<CreateSessionRequest>,
+) -> Result<Json<SessionInfo>> {
+    if state.config.max_sessions == 0 {
+        return Err(AppError::Forbidden("Sessions are disabled".into()));
+    }
+    let info = state
+        .sessions
+        .create(&state, req.id, req.rate_limit_rpm, req.rate_limit_concurrent)
+        .await?;
+    Ok(Json(info))
+}
+
+// GET /sessions - List sessions
+#[derive(Serialize, ToSchema)]
+pub struct ListSessionsResponse {
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions",
+    tag = "sessions",
+    summary = "List sessions, oldest first",
+    responses((status = 200, body = ListSessionsResponse)),
+)]
+pub async fn list_sessions(State(state): State<Arc<AppState>>) -> Json<ListSessionsResponse> {
+    let sessions = state.sessions.list().iter().map(|session| session.info.clone()).collect();
+    Json(ListSessionsResponse { sessions })
+}
+
+// GET /sessions/{id} - Get a session
+#[utoipa::path(
+    get,
+    path = "/sessions/{id}",
+    tag = "sessions",
+    summary = "Get a session",
+    params(("id" = String, Path, description = "Session ID")),
+    responses((status = 200, body = SessionInfo)),
+)]
+pub async fn get_session(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Result<Json<SessionInfo>> {
+    let session = state
+        .sessions
+        .get(&id)
+        .ok_or_else(|| AppError::NotFound(format!("Session '{}' not found", id)).with_code("SESSION_NOT_FOUND"))?;
+    Ok(Json(session.info.clone()))
+}
+
+// DELETE /sessions/{id} - Delete a session
+#[derive(Serialize, ToSchema)]
+pub struct DeleteSessionResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/sessions/{id}",
+    tag = "sessions",
+    summary = "Close a session's browser and delete its workspace, skills, and profile",
+    params(("id" = String, Path, description = "Session ID")),
+    responses((status = 200, body = DeleteSessionResponse)),
+)]
+pub async fn delete_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<DeleteSessionResponse>> {
+    state.sessions.remove(&id).await?;
+    Ok(Json(DeleteSessionResponse {
+        success: true,
+        message: format!("Session '{}' deleted", id),
+    }))
+}
@@ -1,7 +1,8 @@
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::{extract::State, Json};
-use futures::stream::Stream;
+use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use std::collections::HashMap;
 use std::convert::Infallible;
 use std::sync::Arc;
@@ -12,79 +13,166 @@ use tokio::time::{timeout, Duration};
 
 use crate::error::{AppError, Result};
 use crate::state::AppState;
+use crate::tee::receipt::{self, AttestOptions, Receipt};
+use crate::tee::secrets;
+use crate::webhooks;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct ShellExecRequest {
     pub command: String,
     pub cwd: Option<String>,
     #[serde(default = "default_timeout")]
     pub timeout: u64,
     pub env: Option<HashMap<String, String>>,
+    #[serde(flatten)]
+    pub attestation: AttestOptions,
 }
 
 fn default_timeout() -> u64 {
     30
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ShellExecResponse {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
     pub duration_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Receipt>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/shell/exec",
+    tag = "shell",
+    summary = "Run a shell command and wait for it",
+    request_body = ShellExecRequest,
+    responses((status = 200, body = ShellExecResponse)),
+)]
 pub async fn exec_command(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ShellExecRequest>,
 ) -> Result<Json<ShellExecResponse>> {
+    req.attestation.validate()?;
+
     let start = Instant::now();
-    let cwd = req.cwd.unwrap_or_else(|| state.config.workspace.clone());
+    let cwd = state.resolve(req.cwd.as_deref().unwrap_or_default())?;
+    let mut interpolation = state.secrets.interpolation();
+    let command = interpolation.expand(&req.command).await?;
+    let env = interpolation.expand_env(&req.env.clone().unwrap_or_default()).await?;
 
     let mut cmd = Command::new("sh");
-    cmd.arg("-c").arg(&req.command).current_dir(&cwd);
+    cmd.arg("-c").arg(&command).current_dir(&cwd);
+    secrets::inject(&state, &mut cmd);
 
     // Merge environment
-    if let Some(env) = req.env {
-        for (key, value) in env {
-            cmd.env(key, value);
-        }
+    for (key, value) in &env {
+        cmd.env(key, value);
     }
 
     let output = timeout(Duration::from_secs(req.timeout), cmd.output())
         .await
-        .map_err(|_| AppError::Timeout("Command timed out".into()))?
+        .map_err(|_| AppError::exec_timeout("Command timed out", req.timeout))?
         .map_err(|e| AppError::Internal(e.to_string()))?;
 
-    Ok(Json(ShellExecResponse {
-        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
-        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    let mut response = ShellExecResponse {
+        stdout: interpolation.redact(String::from_utf8_lossy(&output.stdout).into_owned()),
+        stderr: interpolation.redact(String::from_utf8_lossy(&output.stderr).into_owned()),
         exit_code: output.status.code().unwrap_or(-1),
         duration_ms: start.elapsed().as_secs_f64() * 1000.0,
-    }))
+        receipt: None,
+    };
+    webhooks::emit(
+        &state,
+        "job.completed",
+        serde_json::json!({
+            "kind": "shell.exec",
+            "command": req.command,
+            "exit_code": response.exit_code,
+            "duration_ms": response.duration_ms,
+        }),
+    );
+
+    if req.attestation.attest {
+        response.receipt = Some(
+            receipt::attest(&state, "shell.exec", &req, &response, req.attestation.attest_quote).await?,
+        );
+    }
+
+    Ok(Json(response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/shell/stream",
+    tag = "shell",
+    summary = "Run a shell command, streaming stdout",
+    request_body = ShellExecRequest,
+    responses((status = 200, description = "stdout lines as server-sent events, then `[exit_code:N]`", content_type = "text/event-stream")),
+)]
 pub async fn stream_command(
     State(state): State<Arc<AppState>>,
     Json(req): Json<ShellExecRequest>,
 ) -> Sse<impl Stream<Item = std::result::Result<Event, Infallible>>> {
-    let cwd = req.cwd.unwrap_or_else(|| state.config.workspace.clone());
+    let events = stream_output(state, req).map(|output| {
+        let data = match output {
+            StreamOutput::Line(line) => line,
+            StreamOutput::Exit(code) => format!("[exit_code:{}]", code),
+            StreamOutput::Error(e) => format!("[error:{}]", e),
+        };
+        Ok(Event::default().data(data))
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// What a streamed command reports: its stdout lines, then its exit code or an error
+pub enum StreamOutput {
+    Line(String),
+    Exit(i32),
+    Error(String),
+}
+
+/// Run a command, yielding its stdout line by line
+pub fn stream_output(state: Arc<AppState>, req: ShellExecRequest) -> impl Stream<Item = StreamOutput> {
+    let cwd = state.resolve(req.cwd.as_deref().unwrap_or_default());
 
-    let stream = async_stream::stream! {
+    async_stream::stream! {
+        let cwd = match cwd {
+            Ok(cwd) => cwd,
+            Err(e) => {
+                yield StreamOutput::Error(e.to_string());
+                return;
+            }
+        };
+        let mut interpolation = state.secrets.interpolation();
+        let expanded = async {
+            let command = interpolation.expand(&req.command).await?;
+            let env = interpolation.expand_env(&req.env.clone().unwrap_or_default()).await?;
+            Ok::<_, AppError>((command, env))
+        };
+        let (command, env) = match expanded.await {
+            Ok(expanded) => expanded,
+            Err(e) => {
+                yield StreamOutput::Error(e.to_string());
+                return;
+            }
+        };
         let mut cmd = Command::new("sh");
         cmd.arg("-c")
-            .arg(&req.command)
+            .arg(&command)
             .current_dir(&cwd)
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
+        secrets::inject(&state, &mut cmd);
 
         // Merge environment
-        if let Some(env) = &req.env {
-            for (key, value) in env {
-                cmd.env(key, value);
-            }
+        for (key, value) in &env {
+            cmd.env(key, value);
         }
 
+        let start = Instant::now();
         match cmd.spawn() {
             Ok(mut child) => {
                 let stdout = child.stdout.take();
@@ -93,25 +181,33 @@ pub async fn stream_command(
                 if let Some(stdout) = stdout {
                     let mut reader = BufReader::new(stdout).lines();
                     while let Ok(Some(line)) = reader.next_line().await {
-                        yield Ok(Event::default().data(line));
+                        yield StreamOutput::Line(interpolation.redact(line));
                     }
                 }
 
                 match child.wait().await {
                     Ok(status) => {
-                        let code = status.code().unwrap_or(-1);
-                        yield Ok(Event::default().data(format!("[exit_code:{}]", code)));
+                        let exit_code = status.code().unwrap_or(-1);
+                        webhooks::emit(
+                            &state,
+                            "job.completed",
+                            serde_json::json!({
+                                "kind": "shell.stream",
+                                "command": req.command,
+                                "exit_code": exit_code,
+                                "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+                            }),
+                        );
+                        yield StreamOutput::Exit(exit_code);
                     }
                     Err(e) => {
-                        yield Ok(Event::default().data(format!("[error:{}]", e)));
+                        yield StreamOutput::Error(e.to_string());
                     }
                 }
             }
             Err(e) => {
-                yield Ok(Event::default().data(format!("[error:{}]", e)));
+                yield StreamOutput::Error(e.to_string());
             }
         }
-    };
-
-    Sse::new(stream).keep_alive(KeepAlive::default())
+    }
 }
@@ -3,33 +3,54 @@ use axum::{
     Json,
 };
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 use tokio::time::timeout;
 
 use crate::error::{AppError, Result};
 use crate::skills::{CreateSkillRequest, Skill, SkillSummary, UpdateSkillRequest};
 use crate::state::AppState;
+use crate::tee::measure;
+use crate::tee::receipt::{self, AttestOptions, Receipt};
+use crate::tee::secrets;
+use crate::webhooks;
 
 // GET /skills - List all skills
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ListSkillsResponse {
     pub skills: Vec<SkillSummary>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/skills",
+    tag = "skills",
+    summary = "List all skills",
+    responses((status = 200, body = ListSkillsResponse)),
+)]
 pub async fn list_skills(State(state): State<Arc<AppState>>) -> Result<Json<ListSkillsResponse>> {
     let skills = state.skills.list().await?;
     Ok(Json(ListSkillsResponse { skills }))
 }
 
 // GET /skills/search - Search skills by query
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SearchQuery {
     pub q: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/skills/search",
+    tag = "skills",
+    summary = "Search skills by query",
+    params(SearchQuery),
+    responses((status = 200, body = ListSkillsResponse)),
+)]
 pub async fn search_skills(
     State(state): State<Arc<AppState>>,
     Query(query): Query<SearchQuery>,
@@ -39,6 +60,14 @@ pub async fn search_skills(
 }
 
 // GET /skills/:name - Get a specific skill
+#[utoipa::path(
+    get,
+    path = "/skills/{name}",
+    tag = "skills",
+    summary = "Get a specific skill",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, body = Skill)),
+)]
 pub async fn get_skill(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
@@ -48,7 +77,7 @@ pub async fn get_skill(
 }
 
 // POST /skills - Create a new skill
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct CreateSkillRequestJson {
     pub name: String,
     pub description: String,
@@ -61,6 +90,14 @@ pub struct CreateSkillRequestJson {
     pub assets: HashMap<String, String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/skills",
+    tag = "skills",
+    summary = "Create a skill",
+    request_body = CreateSkillRequestJson,
+    responses((status = 200, body = Skill)),
+)]
 pub async fn create_skill(
     State(state): State<Arc<AppState>>,
     Json(req): Json<CreateSkillRequestJson>,
@@ -75,11 +112,13 @@ pub async fn create_skill(
     };
 
     let skill = state.skills.create(create_req).await?;
+    measure::measure_skills(&state).await;
+    webhooks::emit(&state, "skill.created", serde_json::json!({ "skill": skill }));
     Ok(Json(skill))
 }
 
 // PUT /skills/:name - Update an existing skill
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateSkillRequestJson {
     pub description: Option<String>,
     pub body: Option<String>,
@@ -88,6 +127,15 @@ pub struct UpdateSkillRequestJson {
     pub assets: Option<HashMap<String, String>>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/skills/{name}",
+    tag = "skills",
+    summary = "Update a skill",
+    params(("name" = String, Path, description = "Skill name")),
+    request_body = UpdateSkillRequestJson,
+    responses((status = 200, body = Skill)),
+)]
 pub async fn update_skill(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
@@ -102,21 +150,31 @@ pub async fn update_skill(
     };
 
     let skill = state.skills.update(&name, update_req).await?;
+    measure::measure_skills(&state).await;
     Ok(Json(skill))
 }
 
 // DELETE /skills/:name - Delete a skill
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct DeleteSkillResponse {
     pub success: bool,
     pub message: String,
 }
 
+#[utoipa::path(
+    delete,
+    path = "/skills/{name}",
+    tag = "skills",
+    summary = "Delete a skill",
+    params(("name" = String, Path, description = "Skill name")),
+    responses((status = 200, body = DeleteSkillResponse)),
+)]
 pub async fn delete_skill(
     State(state): State<Arc<AppState>>,
     Path(name): Path<String>,
 ) -> Result<Json<DeleteSkillResponse>> {
     state.skills.delete(&name).await?;
+    measure::measure_skills(&state).await;
     Ok(Json(DeleteSkillResponse {
         success: true,
         message: format!("Skill '{}' deleted successfully", name),
@@ -124,26 +182,41 @@ pub async fn delete_skill(
 }
 
 // POST /skills/:name/scripts/:script - Execute a script
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct ExecuteScriptRequest {
     #[serde(default)]
     pub args: Vec<String>,
     #[serde(default)]
     pub env: HashMap<String, String>,
+    #[serde(flatten)]
+    pub attestation: AttestOptions,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct ExecuteScriptResponse {
     pub stdout: String,
     pub stderr: String,
     pub exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<Receipt>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/skills/{name}/scripts/{script}",
+    tag = "skills",
+    summary = "Run a skill script",
+    params(("name" = String, Path, description = "Skill name"), ("script" = String, Path, description = "Script file name")),
+    request_body = ExecuteScriptRequest,
+    responses((status = 200, body = ExecuteScriptResponse)),
+)]
 pub async fn execute_script(
     State(state): State<Arc<AppState>>,
     Path((skill_name, script_name)): Path<(String, String)>,
     Json(req): Json<ExecuteScriptRequest>,
 ) -> Result<Json<ExecuteScriptResponse>> {
+    req.attestation.validate()?;
+
     // Get the skill to verify it exists
     let skill = state.skills.get(&skill_name).await?;
 
@@ -152,12 +225,12 @@ pub async fn execute_script(
         return Err(AppError::NotFound(format!(
             "Script '{}' not found in skill '{}'",
             script_name, skill_name
-        )));
+        ))
+        .with_code("SCRIPT_NOT_FOUND"));
     }
 
-    // Build the script path using the registry's internal path
-    // The registry knows where skills are stored
-    let skill_dir = state.skills.skill_dir(&skill_name);
+    // Scripts run from a directory, so skills kept elsewhere are copied out first
+    let skill_dir = state.skills.checkout(&skill_name).await?;
     let scripts_dir = skill_dir.join("scripts");
     let script_path = scripts_dir.join(&script_name);
 
@@ -181,6 +254,10 @@ pub async fn execute_script(
         (script_path_str.as_str(), vec![])
     };
 
+    let mut interpolation = state.secrets.interpolation();
+    let user_args = interpolation.expand_all(&req.args).await?;
+    let env = interpolation.expand_env(&req.env).await?;
+
     // Build the command with user-provided args
     let mut cmd = Command::new(command);
     cmd.current_dir(&scripts_dir);
@@ -189,24 +266,53 @@ pub async fn execute_script(
     for arg in args {
         cmd.arg(arg);
     }
-    for arg in &req.args {
+    for arg in &user_args {
         cmd.arg(arg);
     }
 
-    // Add environment variables
-    for (key, value) in &req.env {
+    // Add environment variables, injected secrets first so request values win
+    secrets::inject(&state, &mut cmd);
+    for (key, value) in &env {
         cmd.env(key, value);
     }
 
     // Execute the command with timeout
+    let start = Instant::now();
     let output = timeout(Duration::from_secs(30), cmd.output())
         .await
-        .map_err(|_| AppError::Timeout("Script execution timed out".into()))?
+        .map_err(|_| AppError::exec_timeout("Script execution timed out", 30))?
         .map_err(|e| AppError::Internal(format!("Failed to execute script: {}", e)))?;
 
-    Ok(Json(ExecuteScriptResponse {
-        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
-        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    let mut response = ExecuteScriptResponse {
+        stdout: interpolation.redact(String::from_utf8_lossy(&output.stdout).to_string()),
+        stderr: interpolation.redact(String::from_utf8_lossy(&output.stderr).to_string()),
         exit_code: output.status.code().unwrap_or(-1),
-    }))
+        receipt: None,
+    };
+    webhooks::emit(
+        &state,
+        "job.completed",
+        serde_json::json!({
+            "kind": "skills.script",
+            "skill": skill_name,
+            "script": script_name,
+            "exit_code": response.exit_code,
+            "duration_ms": start.elapsed().as_secs_f64() * 1000.0,
+        }),
+    );
+
+    if req.attestation.attest {
+        // The script is identified by skill and name, which come from the path
+        let request = serde_json::json!({
+            "skill": skill_name,
+            "script": script_name,
+            "args": req.args,
+            "env": req.env,
+        });
+        response.receipt = Some(
+            receipt::attest(&state, "skills.script", &request, &response, req.attestation.attest_quote).await?,
+        );
+    }
+
+    Ok(Json(response))
 }
@@ -1,32 +1,51 @@
-use axum::{extract::State, Json};
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use dstack_sdk::dstack_client::{
     GetKeyResponse, GetQuoteResponse, InfoResponse, SignResponse, VerifyResponse,
 };
-use serde::Deserialize;
+use ring::signature::KeyPair;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::fs;
 
 use crate::error::{AppError, Result};
 use crate::state::AppState;
+use crate::tee::auth::{Challenge, Proof, Token};
+use crate::tee::ecies::{self, EciesKey};
+use crate::tee::eventlog::{self, Event};
+use crate::tee::keys::{check_client_path, derive_ed25519, skill_key_path, KeyInfo, RegisterKeyRequest};
+use crate::tee::measure::{self, Measurements};
+use crate::tee::receipt;
+use crate::tee::sealed::SealingKey;
+use crate::tee::secrets::{self, SECRETS_KEY_PATH};
+use crate::tee::token::{IssuedToken, Jwk, TokenRequest};
 
 // Request types
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct GenerateQuoteRequest {
     pub report_data: String, // hex-encoded
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct DeriveKeyRequest {
     pub path: Option<String>,
     pub purpose: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct SignRequest {
-    pub algorithm: String, // "secp256k1"
-    pub data: String,      // hex-encoded
+    pub algorithm: Option<String>, // "secp256k1"; required unless `key` is set
+    pub data: String,              // hex-encoded
+    /// Sign with a registered key instead of the app key
+    pub key: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct VerifyRequest {
     pub algorithm: String,
     pub data: String,
@@ -34,29 +53,217 @@ pub struct VerifyRequest {
     pub public_key: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct EmitEventRequest {
     pub event: String,
     pub payload: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct SealRequest {
+    pub path: String,
+    pub content: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // "utf-8" or "base64"
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UnsealRequest {
+    pub path: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // "utf-8" or "base64"
+}
+
+fn default_encoding() -> String {
+    "utf-8".into()
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SealResponse {
+    pub path: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UnsealResponse {
+    pub content: String,
+    pub size: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EventLogResponse {
+    pub events: Vec<Event>,
+    /// RTMR0-3 recomputed from `events`
+    pub replayed_rtmrs: Vec<String>,
+    /// RTMR0-3 as reported by a fresh quote
+    pub quote_rtmrs: Vec<String>,
+    /// Whether the replay reproduces the quoted registers
+    pub matches: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NamedSignResponse {
+    pub key: String,
+    pub algorithm: &'static str,
+    pub signature: String,
+    pub public_key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum SignDataResponse {
+    #[schema(value_type = Object)]
+    Tee(SignResponse),
+    Named(NamedSignResponse),
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct KeyListResponse {
+    pub keys: Vec<KeyInfo>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TeeInfoResponse {
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub info: InfoResponse,
+    /// Sandbox digests extended into RTMR3; absent if startup measurement failed
+    pub sandbox_measurements: Option<Measurements>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetEnvRequest {
+    /// Variable name to base64 ECIES ciphertext of its value
+    pub secrets: HashMap<String, String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EnvResponse {
+    /// Hex X25519 public key to encrypt secrets to
+    pub public_key: String,
+    /// Names of injected variables; values are never returned
+    pub names: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ProveRequest {
+    pub nonce: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AuthRequest {
+    pub nonce: String,
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SignOutputRequest {
+    /// Workspace file to sign; alternatively pass `content`
+    pub path: Option<String>,
+    pub content: Option<String>,
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // "utf-8" or "base64"
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SignOutputResponse {
+    pub skill: String,
+    /// Digest of the skill's files when it signed, as in `/tee/info` measurements
+    pub skill_sha256: String,
+    pub output_sha256: String,
+    pub algorithm: &'static str,
+    pub public_key: String,
+    /// Signature over the canonical JSON of `{output_sha256, skill, skill_sha256}`
+    pub signature: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AttestPayloadRequest {
+    pub payload: serde_json::Value,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AttestPayloadResponse {
+    pub payload: serde_json::Value,
+    /// SHA-256 of the payload's canonical JSON; the first 32 bytes of `report_data`
+    pub payload_sha256: String,
+    #[schema(value_type = Object)]
+    pub quote: GetQuoteResponse,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct JwksResponse {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct EncryptRequest {
+    /// Recipient's hex X25519 public key
+    pub public_key: String,
+    pub plaintext: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // "utf-8" or "base64"
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DecryptRequest {
+    /// Base64 ECIES ciphertext encrypted to `/tee/encryption-key`
+    pub ciphertext: String,
+    #[serde(default = "default_encoding")]
+    pub encoding: String, // "utf-8" or "base64"
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EncryptionKeyResponse {
+    pub public_key: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EncryptResponse {
+    pub ciphertext: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct DecryptResponse {
+    pub plaintext: String,
+}
+
 // Helper function to decode hex strings
 fn decode_hex(s: &str) -> Result<Vec<u8>> {
     hex::decode(s).map_err(|e| AppError::BadRequest(format!("Invalid hex string: {}", e)))
 }
 
 // GET /tee/info - CVM instance metadata
-pub async fn tee_info(State(state): State<Arc<AppState>>) -> Result<Json<InfoResponse>> {
+#[utoipa::path(
+    get,
+    path = "/tee/info",
+    tag = "tee",
+    summary = "CVM instance metadata",
+    responses((status = 200, body = TeeInfoResponse)),
+)]
+pub async fn tee_info(State(state): State<Arc<AppState>>) -> Result<Json<TeeInfoResponse>> {
     let info = state
         .tee_service
         .info()
         .await
         .map_err(|e| AppError::Internal(format!("Failed to get TEE info: {}", e)))?;
 
-    Ok(Json(info))
+    Ok(Json(TeeInfoResponse {
+        info,
+        sandbox_measurements: state.measurements.lock().await.clone(),
+    }))
 }
 
 // POST /tee/quote - TDX attestation quote
+#[utoipa::path(
+    post,
+    path = "/tee/quote",
+    tag = "tee",
+    summary = "TDX attestation quote",
+    request_body = GenerateQuoteRequest,
+    responses((status = 200, body = Object)),
+)]
 pub async fn generate_quote(
     State(state): State<Arc<AppState>>,
     Json(req): Json<GenerateQuoteRequest>,
@@ -73,10 +280,21 @@ pub async fn generate_quote(
 }
 
 // POST /tee/derive-key - Derive key with path/purpose
+#[utoipa::path(
+    post,
+    path = "/tee/derive-key",
+    tag = "tee",
+    summary = "Derive key with path/purpose",
+    request_body = DeriveKeyRequest,
+    responses((status = 200, body = Object)),
+)]
 pub async fn derive_key(
     State(state): State<Arc<AppState>>,
     Json(req): Json<DeriveKeyRequest>,
 ) -> Result<Json<GetKeyResponse>> {
+    if let Some(path) = &req.path {
+        check_client_path(path)?;
+    }
     let key = state
         .tee_service
         .derive_key(req.path.as_deref(), req.purpose.as_deref())
@@ -87,22 +305,101 @@ pub async fn derive_key(
 }
 
 // POST /tee/sign - Sign with derived key
+#[utoipa::path(
+    post,
+    path = "/tee/sign",
+    tag = "tee",
+    summary = "Sign with derived key",
+    request_body = SignRequest,
+    responses((status = 200, body = SignDataResponse)),
+)]
 pub async fn sign_data(
     State(state): State<Arc<AppState>>,
     Json(req): Json<SignRequest>,
-) -> Result<Json<SignResponse>> {
+) -> Result<Json<SignDataResponse>> {
     let data = decode_hex(&req.data)?;
 
+    if let Some(name) = req.key {
+        if req.algorithm.as_deref().is_some_and(|a| a != "ed25519") {
+            return Err(AppError::BadRequest("Registered keys sign with ed25519".into()));
+        }
+        let (signature, info) = state.tee_keys.sign(&name, &data)?;
+        return Ok(Json(SignDataResponse::Named(NamedSignResponse {
+            key: info.name,
+            algorithm: info.algorithm,
+            signature: hex::encode(signature),
+            public_key: info.public_key,
+        })));
+    }
+
+    let algorithm = req
+        .algorithm
+        .ok_or_else(|| AppError::BadRequest("algorithm is required unless key is set".into()))?;
     let signature = state
         .tee_service
-        .sign(&req.algorithm, &data)
+        .sign(&algorithm, &data)
         .await
         .map_err(|e| AppError::Internal(format!("Failed to sign data: {}", e)))?;
 
-    Ok(Json(signature))
+    Ok(Json(SignDataResponse::Tee(signature)))
+}
+
+// POST /tee/keys - Register a named key with a fixed derivation path
+#[utoipa::path(
+    post,
+    path = "/tee/keys",
+    tag = "tee",
+    summary = "Register a named key with a fixed derivation path",
+    request_body = RegisterKeyRequest,
+    responses((status = 200, body = KeyInfo)),
+)]
+pub async fn register_key(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RegisterKeyRequest>,
+) -> Result<Json<KeyInfo>> {
+    Ok(Json(state.tee_keys.register(req).await?))
+}
+
+// GET /tee/keys - List registered keys and their public keys
+#[utoipa::path(
+    get,
+    path = "/tee/keys",
+    tag = "tee",
+    summary = "List registered keys and their public keys",
+    responses((status = 200, body = KeyListResponse)),
+)]
+pub async fn list_keys(State(state): State<Arc<AppState>>) -> Json<KeyListResponse> {
+    Json(KeyListResponse {
+        keys: state.tee_keys.list(),
+    })
+}
+
+// DELETE /tee/keys/{name} - Forget a registered key
+#[utoipa::path(
+    delete,
+    path = "/tee/keys/{name}",
+    tag = "tee",
+    summary = "Forget a registered key",
+    params(("name" = String, Path, description = "Key name")),
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn remove_key(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    state.tee_keys.remove(&name)?;
+    Ok(Json(serde_json::json!({ "success": true })))
 }
 
 // POST /tee/verify - Verify signature
+#[utoipa::path(
+    post,
+    path = "/tee/verify",
+    tag = "tee",
+    summary = "Verify signature",
+    request_body = VerifyRequest,
+    responses((status = 200, body = Object)),
+)]
 pub async fn verify_signature(
     State(state): State<Arc<AppState>>,
     Json(req): Json<VerifyRequest>,
@@ -121,6 +418,14 @@ pub async fn verify_signature(
 }
 
 // POST /tee/emit-event - Emit runtime event
+#[utoipa::path(
+    post,
+    path = "/tee/emit-event",
+    tag = "tee",
+    summary = "Emit runtime event",
+    request_body = EmitEventRequest,
+    responses((status = 200, body = serde_json::Value)),
+)]
 pub async fn emit_event(
     State(state): State<Arc<AppState>>,
     Json(req): Json<EmitEventRequest>,
@@ -136,3 +441,474 @@ pub async fn emit_event(
         "message": format!("Event '{}' emitted successfully", req.event)
     })))
 }
+
+// POST /tee/attest-payload - Quote over the hash of a JSON payload
+#[utoipa::path(
+    post,
+    path = "/tee/attest-payload",
+    tag = "tee",
+    summary = "Quote over the hash of a JSON payload",
+    request_body = AttestPayloadRequest,
+    responses((status = 200, body = AttestPayloadResponse)),
+)]
+pub async fn attest_payload(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AttestPayloadRequest>,
+) -> Result<Json<AttestPayloadResponse>> {
+    let digest = receipt::sha256(receipt::canonical_json(&req.payload)?.as_bytes());
+
+    // Same convention as receipts: the digest, zero-padded to 64 bytes
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(&digest);
+    let quote = state
+        .tee_service
+        .get_quote(&report_data)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to generate quote: {}", e)))?;
+
+    Ok(Json(AttestPayloadResponse {
+        payload: req.payload,
+        payload_sha256: hex::encode(digest),
+        quote,
+    }))
+}
+
+// GET /tee/event-log - Runtime event log with replayed RTMRs
+#[utoipa::path(
+    get,
+    path = "/tee/event-log",
+    tag = "tee",
+    summary = "Runtime event log with replayed RTMRs",
+    responses((status = 200, body = EventLogResponse)),
+)]
+pub async fn event_log(State(state): State<Arc<AppState>>) -> Result<Json<EventLogResponse>> {
+    // The event log only comes back with a quote; report_data is irrelevant here
+    let quote = state
+        .tee_service
+        .get_quote(&[0u8; 64])
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to get event log: {}", e)))?;
+
+    let events = eventlog::parse(&quote.event_log)
+        .map_err(|e| AppError::Internal(format!("Failed to parse event log: {}", e)))?;
+    let replayed = eventlog::replay(&events)
+        .map_err(|e| AppError::Internal(format!("Failed to replay event log: {}", e)))?;
+    let quote_bytes = hex::decode(&quote.quote)
+        .map_err(|e| AppError::Internal(format!("Quote is not valid hex: {}", e)))?;
+    let quoted = eventlog::quote_rtmrs(&quote_bytes)
+        .map_err(|e| AppError::Internal(format!("Failed to read quote RTMRs: {}", e)))?;
+
+    Ok(Json(EventLogResponse {
+        events,
+        replayed_rtmrs: replayed.iter().map(hex::encode).collect(),
+        quote_rtmrs: quoted.iter().map(hex::encode).collect(),
+        matches: replayed == quoted,
+    }))
+}
+
+// POST /file/seal - Encrypt content with a TEE-derived key and write it
+#[utoipa::path(
+    post,
+    path = "/file/seal",
+    tag = "tee",
+    summary = "Encrypt content with a TEE-derived key and write it",
+    request_body = SealRequest,
+    responses((status = 200, body = SealResponse)),
+)]
+pub async fn seal_file(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SealRequest>,
+) -> Result<Json<SealResponse>> {
+    let plaintext = match req.encoding.as_str() {
+        "utf-8" => req.content.into_bytes(),
+        "base64" => BASE64
+            .decode(&req.content)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 content: {}", e)))?,
+        other => return Err(AppError::BadRequest(format!("Unsupported encoding: {}", other))),
+    };
+
+    let key = SealingKey::derive(&state.tee_service)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to derive sealing key: {}", e)))?;
+    let sealed = key
+        .seal(&plaintext)
+        .map_err(|e| AppError::Internal(format!("Failed to seal: {}", e)))?;
+
+    let full_path = state.resolve(&req.path)?;
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    fs::write(&full_path, &sealed).await?;
+
+    Ok(Json(SealResponse {
+        path: full_path.to_string_lossy().into_owned(),
+        size: sealed.len() as u64,
+    }))
+}
+
+// POST /file/unseal - Decrypt a sealed file; the plaintext is returned, never written
+#[utoipa::path(
+    post,
+    path = "/file/unseal",
+    tag = "tee",
+    summary = "Decrypt a sealed file; the plaintext is returned, never written",
+    request_body = UnsealRequest,
+    responses((status = 200, body = UnsealResponse)),
+)]
+pub async fn unseal_file(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<UnsealRequest>,
+) -> Result<Json<UnsealResponse>> {
+    if req.encoding != "utf-8" && req.encoding != "base64" {
+        return Err(AppError::BadRequest(format!("Unsupported encoding: {}", req.encoding)));
+    }
+
+    let full_path = state.resolve(&req.path)?;
+    if !full_path.exists() {
+        return Err(AppError::NotFound("File not found".into()));
+    }
+    let sealed = fs::read(&full_path).await?;
+
+    let key = SealingKey::derive(&state.tee_service)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to derive sealing key: {}", e)))?;
+    let plaintext = key
+        .unseal(&sealed)
+        .map_err(|e| AppError::BadRequest(format!("Failed to unseal: {}", e)))?;
+
+    let size = plaintext.len() as u64;
+    let content = if req.encoding == "base64" {
+        BASE64.encode(&plaintext)
+    } else {
+        String::from_utf8(plaintext).map_err(|_| {
+            AppError::BadRequest("Content is not valid UTF-8; request encoding \"base64\"".into())
+        })?
+    };
+
+    Ok(Json(UnsealResponse { content, size }))
+}
+
+async fn secrets_key(state: &AppState) -> Result<EciesKey> {
+    EciesKey::derive(&state.tee_service, SECRETS_KEY_PATH)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to derive secrets key: {}", e)))
+}
+
+// GET /tee/env - Public key for encrypting secrets, and the names already injected
+#[utoipa::path(
+    get,
+    path = "/tee/env",
+    tag = "tee",
+    summary = "Public key for encrypting secrets, and the names already injected",
+    responses((status = 200, body = EnvResponse)),
+)]
+pub async fn get_env(State(state): State<Arc<AppState>>) -> Result<Json<EnvResponse>> {
+    let key = secrets_key(&state).await?;
+    Ok(Json(EnvResponse {
+        public_key: hex::encode(key.public_key()),
+        names: state.secret_env.names(),
+    }))
+}
+
+// POST /tee/env - Decrypt secrets in the enclave and inject them into exec/code/skill runs
+#[utoipa::path(
+    post,
+    path = "/tee/env",
+    tag = "tee",
+    summary = "Decrypt secrets in the enclave and inject them into exec/code/skill runs",
+    request_body = SetEnvRequest,
+    responses((status = 200, body = EnvResponse)),
+)]
+pub async fn set_env(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<SetEnvRequest>,
+) -> Result<Json<EnvResponse>> {
+    let key = secrets_key(&state).await?;
+
+    // Decrypt everything before storing anything, so a bad entry changes nothing
+    let mut decrypted = Vec::with_capacity(req.secrets.len());
+    for (name, ciphertext) in req.secrets {
+        secrets::validate_name(&name).map_err(AppError::BadRequest)?;
+        let ciphertext = BASE64
+            .decode(&ciphertext)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 for {}: {}", name, e)))?;
+        let value = key
+            .decrypt(&ciphertext)
+            .ok()
+            .and_then(|plaintext| String::from_utf8(plaintext).ok())
+            .ok_or_else(|| AppError::BadRequest(format!("Failed to decrypt {}", name)))?;
+        decrypted.push((name, value));
+    }
+    for (name, value) in decrypted {
+        state.secret_env.set(name, value);
+    }
+
+    Ok(Json(EnvResponse {
+        public_key: hex::encode(key.public_key()),
+        names: state.secret_env.names(),
+    }))
+}
+
+// DELETE /tee/env/{name} - Stop injecting a secret
+#[utoipa::path(
+    delete,
+    path = "/tee/env/{name}",
+    tag = "tee",
+    summary = "Stop injecting a secret",
+    params(("name" = String, Path, description = "Secret name")),
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn remove_env(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    if !state.secret_env.remove(&name) {
+        return Err(AppError::NotFound(format!("Secret '{}' not set", name)));
+    }
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+// POST /tee/auth/challenge - Issue a nonce to be signed with a trusted key
+#[utoipa::path(
+    post,
+    path = "/tee/auth/challenge",
+    tag = "tee",
+    summary = "Issue a nonce to be signed with a trusted key",
+    responses((status = 200, body = Challenge)),
+)]
+pub async fn auth_challenge(State(state): State<Arc<AppState>>) -> Result<Json<Challenge>> {
+    Ok(Json(state.tee_auth.challenge()?))
+}
+
+// POST /tee/auth/prove - Sign another sandbox's challenge with this app's auth key
+#[utoipa::path(
+    post,
+    path = "/tee/auth/prove",
+    tag = "tee",
+    summary = "Sign another sandbox's challenge with this app's auth key",
+    request_body = ProveRequest,
+    responses((status = 200, body = Proof)),
+)]
+pub async fn auth_prove(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<ProveRequest>,
+) -> Result<Json<Proof>> {
+    Ok(Json(state.tee_auth.prove(&req.nonce).await?))
+}
+
+// POST /tee/auth - Exchange a signed challenge for a bearer token
+#[utoipa::path(
+    post,
+    path = "/tee/auth",
+    tag = "tee",
+    summary = "Exchange a signed challenge for a bearer token",
+    request_body = AuthRequest,
+    responses((status = 200, body = Token)),
+)]
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<AuthRequest>,
+) -> Result<Json<Token>> {
+    Ok(Json(
+        state
+            .tee_auth
+            .authenticate(&req.nonce, &req.public_key, &req.signature)
+            .await?,
+    ))
+}
+
+// POST /skills/{name}/sign-output - Sign an artifact with the skill's own derived key
+#[utoipa::path(
+    post,
+    path = "/skills/{name}/sign-output",
+    tag = "tee",
+    summary = "Sign an artifact with the skill's own derived key",
+    params(("name" = String, Path, description = "Skill name")),
+    request_body = SignOutputRequest,
+    responses((status = 200, body = SignOutputResponse)),
+)]
+pub async fn sign_skill_output(
+    State(state): State<Arc<AppState>>,
+    Path(name): Path<String>,
+    Json(req): Json<SignOutputRequest>,
+) -> Result<Json<SignOutputResponse>> {
+    state.skills.get(&name).await?;
+
+    let output = match (req.path, req.content) {
+        (Some(path), None) => {
+            let full_path = state.resolve(&path)?;
+            if !full_path.exists() {
+                return Err(AppError::NotFound("File not found".into()));
+            }
+            fs::read(&full_path).await?
+        }
+        (None, Some(content)) => match req.encoding.as_str() {
+            "utf-8" => content.into_bytes(),
+            "base64" => BASE64
+                .decode(&content)
+                .map_err(|e| AppError::BadRequest(format!("Invalid base64 content: {}", e)))?,
+            other => return Err(AppError::BadRequest(format!("Unsupported encoding: {}", other))),
+        },
+        _ => return Err(AppError::BadRequest("Provide exactly one of path or content".into())),
+    };
+
+    let skill_dir = state.skills.checkout(&name).await?;
+    let skill_sha256 = measure::skills_digest(&skill_dir.to_string_lossy())
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to hash skill: {}", e)))?;
+    let output_sha256 = hex::encode(receipt::sha256(&output));
+    let message = receipt::canonical_json(&serde_json::json!({
+        "skill": name,
+        "skill_sha256": skill_sha256,
+        "output_sha256": output_sha256,
+    }))?;
+
+    // Path includes the skill name, so every skill signs with its own stable key
+    let key = derive_ed25519(&state.tee_service, &skill_key_path(&name), "signing").await?;
+    let signature = key.sign(message.as_bytes());
+
+    Ok(Json(SignOutputResponse {
+        skill: name,
+        skill_sha256,
+        output_sha256,
+        algorithm: "ed25519",
+        public_key: hex::encode(key.public_key().as_ref()),
+        signature: hex::encode(signature.as_ref()),
+    }))
+}
+
+// POST /tee/token - Issue a short-lived JWT signed by the TEE-derived token key
+#[utoipa::path(
+    post,
+    path = "/tee/token",
+    tag = "tee",
+    summary = "Issue a short-lived JWT signed by the TEE-derived token key",
+    request_body = TokenRequest,
+    responses((status = 200, body = IssuedToken)),
+)]
+pub async fn issue_token(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<TokenRequest>,
+) -> Result<Json<IssuedToken>> {
+    Ok(Json(state.token_issuer.issue(req).await?))
+}
+
+// GET /.well-known/jwks.json - Token signing key with a quote binding it to this CVM
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "tee",
+    summary = "Token signing key with a quote binding it to this CVM",
+    responses((status = 200, body = JwksResponse)),
+)]
+pub async fn jwks(State(state): State<Arc<AppState>>) -> Result<Json<JwksResponse>> {
+    Ok(Json(JwksResponse {
+        keys: vec![state.token_issuer.jwk().await?.clone()],
+    }))
+}
+
+// GET /.well-known/openid-configuration - OIDC discovery for token consumers
+#[utoipa::path(
+    get,
+    path = "/.well-known/openid-configuration",
+    tag = "tee",
+    summary = "OIDC discovery for token consumers",
+    responses((status = 200, body = serde_json::Value)),
+)]
+pub async fn openid_configuration(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let issuer = state.token_issuer.issuer();
+    Json(serde_json::json!({
+        "issuer": issuer,
+        "jwks_uri": format!("{}/.well-known/jwks.json", issuer.trim_end_matches('/')),
+        "id_token_signing_alg_values_supported": ["EdDSA"],
+        "response_types_supported": ["id_token"],
+        "subject_types_supported": ["public"],
+    }))
+}
+
+/// Derivation path of the key clients encrypt one-shot data to
+const ECIES_KEY_PATH: &str = "sandbox/ecies";
+
+async fn ecies_key(state: &AppState) -> Result<EciesKey> {
+    EciesKey::derive(&state.tee_service, ECIES_KEY_PATH)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to derive encryption key: {}", e)))
+}
+
+// GET /tee/encryption-key - X25519 public key that only this enclave can decrypt for
+#[utoipa::path(
+    get,
+    path = "/tee/encryption-key",
+    tag = "tee",
+    summary = "X25519 public key that only this enclave can decrypt for",
+    responses((status = 200, body = EncryptionKeyResponse)),
+)]
+pub async fn encryption_key(State(state): State<Arc<AppState>>) -> Result<Json<EncryptionKeyResponse>> {
+    let key = ecies_key(&state).await?;
+    Ok(Json(EncryptionKeyResponse {
+        public_key: hex::encode(key.public_key()),
+    }))
+}
+
+// POST /tee/encrypt - Encrypt data to a client's X25519 public key
+#[utoipa::path(
+    post,
+    path = "/tee/encrypt",
+    tag = "tee",
+    summary = "Encrypt data to a client's X25519 public key",
+    request_body = EncryptRequest,
+    responses((status = 200, body = EncryptResponse)),
+)]
+pub async fn encrypt(Json(req): Json<EncryptRequest>) -> Result<Json<EncryptResponse>> {
+    let public_key: [u8; ecies::KEY_LEN] = decode_hex(&req.public_key)?
+        .try_into()
+        .map_err(|_| AppError::BadRequest("public_key must be 32 bytes".into()))?;
+    let plaintext = match req.encoding.as_str() {
+        "utf-8" => req.plaintext.into_bytes(),
+        "base64" => BASE64
+            .decode(&req.plaintext)
+            .map_err(|e| AppError::BadRequest(format!("Invalid base64 plaintext: {}", e)))?,
+        other => return Err(AppError::BadRequest(format!("Unsupported encoding: {}", other))),
+    };
+
+    let ciphertext = ecies::encrypt(&public_key, &plaintext)
+        .map_err(|e| AppError::Internal(format!("Failed to encrypt: {}", e)))?;
+    Ok(Json(EncryptResponse {
+        ciphertext: BASE64.encode(ciphertext),
+    }))
+}
+
+// POST /tee/decrypt - Decrypt data sent to this enclave's encryption key
+#[utoipa::path(
+    post,
+    path = "/tee/decrypt",
+    tag = "tee",
+    summary = "Decrypt data sent to this enclave's encryption key",
+    request_body = DecryptRequest,
+    responses((status = 200, body = DecryptResponse)),
+)]
+pub async fn decrypt(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<DecryptRequest>,
+) -> Result<Json<DecryptResponse>> {
+    if req.encoding != "utf-8" && req.encoding != "base64" {
+        return Err(AppError::BadRequest(format!("Unsupported encoding: {}", req.encoding)));
+    }
+    let ciphertext = BASE64
+        .decode(&req.ciphertext)
+        .map_err(|e| AppError::BadRequest(format!("Invalid base64 ciphertext: {}", e)))?;
+
+    let key = ecies_key(&state).await?;
+    let plaintext = key
+        .decrypt(&ciphertext)
+        .map_err(|e| AppError::BadRequest(format!("Failed to decrypt: {}", e)))?;
+
+    let plaintext = if req.encoding == "base64" {
+        BASE64.encode(&plaintext)
+    } else {
+        String::from_utf8(plaintext).map_err(|_| {
+            AppError::BadRequest("Plaintext is not valid UTF-8; request encoding \"base64\"".into())
+        })?
+    };
+    Ok(Json(DecryptResponse { plaintext }))
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::error::Result;
+use crate::state::AppState;
+use crate::webhooks::{RegisterWebhookRequest, WebhookInfo};
+
+// POST /webhooks - Register a webhook
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    tag = "webhooks",
+    summary = "Register a URL to receive signed event notifications",
+    request_body = RegisterWebhookRequest,
+    responses((status = 200, body = WebhookInfo)),
+)]
+pub async fn register_webhook(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<RegisterWebhookRequest>,
+) -> Result<Json<WebhookInfo>> {
+    let info = state.webhooks.register(&state, req)?;
+    Ok(Json(info))
+}
+
+// GET /webhooks - List webhooks
+#[derive(Serialize, ToSchema)]
+pub struct ListWebhooksResponse {
+    pub webhooks: Vec<WebhookInfo>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    tag = "webhooks",
+    summary = "List webhooks and their delivery counts, oldest first",
+    responses((status = 200, body = ListWebhooksResponse)),
+)]
+pub async fn list_webhooks(State(state): State<Arc<AppState>>) -> Json<ListWebhooksResponse> {
+    Json(ListWebhooksResponse { webhooks: state.webhooks.list() })
+}
+
+// DELETE /webhooks/{id} - Remove a webhook
+#[derive(Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    tag = "webhooks",
+    summary = "Remove a webhook and stop its file watch",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses((status = 200, body = WebhookResponse)),
+)]
+pub async fn delete_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookResponse>> {
+    state.webhooks.remove(&id)?;
+    Ok(Json(WebhookResponse {
+        success: true,
+        message: format!("Webhook '{}' removed", id),
+    }))
+}
+
+// POST /webhooks/{id}/test - Send a ping
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/test",
+    tag = "webhooks",
+    summary = "Send a signed ping event once, without retrying, and report whether it was accepted",
+    params(("id" = String, Path, description = "Webhook ID")),
+    responses((status = 200, body = WebhookResponse)),
+)]
+pub async fn test_webhook(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookResponse>> {
+    state.webhooks.ping(&id).await?;
+    Ok(Json(WebhookResponse {
+        success: true,
+        message: format!("Webhook '{}' accepted a ping", id),
+    }))
+}
@@ -0,0 +1,501 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Request, State,
+    },
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method},
+    response::Response,
+    Extension, Router,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use futures::{SinkExt, StreamExt};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tower::ServiceExt;
+use utoipa::ToSchema;
+
+use crate::auth::{Claims, WATCH_SCOPE};
+use crate::handlers::file::resolve_path;
+use crate::request_id::{RequestId, X_REQUEST_ID};
+use crate::state::AppState;
+
+/// Headers of the upgrade request that every call on the connection carries, so
+/// calls are authenticated, limited, and audited as the client that connected
+const FORWARDED_HEADERS: &[&str] = &["authorization", "x-api-key"];
+
+/// Watches one connection may hold at once
+const MAX_WATCHES: usize = 32;
+
+/// Largest response body passed back over the socket; fetch bigger ones over HTTP
+const MAX_RESPONSE_BYTES: usize = 32 * 1024 * 1024;
+
+/// Messages queued for a slow client before calls wait for it
+const OUTBOX_SIZE: usize = 256;
+
+/// The API that `/ws` calls are dispatched into, with all of its middleware
+#[derive(Clone)]
+pub struct WsApi(pub Router);
+
+/// A frame sent by the client
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum ClientMessage {
+    /// Call an endpoint as over HTTP, e.g. `{"op": "call", "id": "1", "method": "POST",
+    /// "path": "/shell/exec", "body": {"command": "ls"}}`
+    Call {
+        /// Chosen by the client and echoed on every message about this call
+        id: String,
+        #[serde(default = "default_method")]
+        method: String,
+        /// Path and query string
+        path: String,
+        /// JSON request body
+        #[serde(default)]
+        #[schema(value_type = Option<Object>)]
+        body: Option<Value>,
+    },
+    /// Push changes under a file or directory, relative to the workspace unless absolute
+    Watch {
+        id: String,
+        path: String,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Stop the call or watch `id`
+    Cancel { id: String },
+}
+
+fn default_method() -> String {
+    "GET".into()
+}
+
+/// A frame sent by the server
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ServerMessage {
+    /// A call finished. Every call ends with exactly one `response` or `error`.
+    Response {
+        id: String,
+        status: u16,
+        /// `X-Request-Id` of the call, as in the server logs and audit log
+        request_id: String,
+        /// JSON or text body; absent for empty bodies and after a stream
+        #[serde(skip_serializing_if = "Option::is_none")]
+        #[schema(value_type = Option<Object>)]
+        body: Option<Value>,
+        /// `base64` when the body was binary
+        #[serde(skip_serializing_if = "Option::is_none")]
+        encoding: Option<String>,
+    },
+    /// One server-sent event of a streaming call, such as a `/shell/stream` line
+    Event { id: String, data: String },
+    /// A watch was set up
+    Watching { id: String, path: String },
+    /// A file under a watched path was created, modified, or removed
+    File {
+        id: String,
+        kind: String,
+        paths: Vec<String>,
+    },
+    /// A call or watch was stopped by `cancel`
+    Cancelled { id: String },
+    /// A frame could not be handled, or a call or watch failed
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        error: String,
+    },
+}
+
+// GET /ws - Multiplexed API channel
+#[utoipa::path(
+    get,
+    path = "/ws",
+    tag = "ws",
+    summary = "Open a WebSocket that multiplexes API calls and pushes file changes",
+    description = "Each text frame is a JSON `ClientMessage`; the server answers with `ServerMessage`s \
+        carrying the client's `id`. Calls run concurrently and go through the same authentication, \
+        limits, and audit log as HTTP requests, using the `Authorization` or `X-API-Key` header of \
+        the upgrade request.",
+    responses((status = 101, description = "Switching to the WebSocket protocol")),
+)]
+pub async fn websocket(
+    State(state): State<Arc<AppState>>,
+    Extension(WsApi(api)): Extension<WsApi>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    claims: Option<Extension<Arc<Claims>>>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let forwarded = FORWARDED_HEADERS
+        .iter()
+        .filter_map(|name| Some((HeaderName::from_static(name), headers.get(*name)?.clone())))
+        .collect();
+    let connection = Connection {
+        api,
+        workspace: state.config.workspace.clone(),
+        confined: state.session.is_some(),
+        // Set by `auth::require_scope`, which has already refused the upgrade without a token
+        can_watch: claims.is_none_or(|Extension(claims)| claims.allows(WATCH_SCOPE)),
+        headers: forwarded,
+        peer: connect_info.map(|Extension(info)| info),
+    };
+    upgrade
+        .max_message_size(state.config.max_body_bytes)
+        .on_upgrade(move |socket| connection.run(socket))
+}
+
+/// What a connection needs to make calls on behalf of its client
+struct Connection {
+    api: Router,
+    workspace: String,
+    /// Whether watches must stay inside the workspace, as in a session
+    confined: bool,
+    /// Whether the client's token allows watching files
+    can_watch: bool,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+}
+
+impl Connection {
+    async fn run(self, socket: WebSocket) {
+        let (mut sink, mut frames) = socket.split();
+        let (outbox, mut outgoing) = mpsc::channel::<ServerMessage>(OUTBOX_SIZE);
+        let writer = tokio::spawn(async move {
+            while let Some(message) = outgoing.recv().await {
+                let text = serde_json::to_string(&message).expect("server messages serialize");
+                if sink.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut calls: HashMap<String, AbortHandle> = HashMap::new();
+        let mut watches: HashMap<String, RecommendedWatcher> = HashMap::new();
+        while let Some(Ok(frame)) = frames.next().await {
+            let text = match frame {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                // Pings are answered by axum
+                Message::Ping(_) | Message::Pong(_) => continue,
+                Message::Binary(_) => {
+                    let _ = outbox.send(error(None, "Frames must be JSON text")).await;
+                    continue;
+                }
+            };
+            calls.retain(|_, task| !task.is_finished());
+
+            let message = match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(message) => message,
+                Err(e) => {
+                    let _ = outbox.send(error(None, format!("Invalid message: {}", e))).await;
+                    continue;
+                }
+            };
+            let reply = match message {
+                ClientMessage::Call { id, .. } | ClientMessage::Watch { id, .. }
+                    if calls.contains_key(&id) || watches.contains_key(&id) =>
+                {
+                    error(Some(id), "id is already in use on this connection")
+                }
+                ClientMessage::Call { id, method, path, body } => match self.request(&method, &path, body) {
+                    Ok((request, request_id)) => {
+                        let task = tokio::spawn(call(self.api.clone(), request, id.clone(), request_id, outbox.clone()));
+                        calls.insert(id, task.abort_handle());
+                        continue;
+                    }
+                    Err(e) => error(Some(id), e),
+                },
+                ClientMessage::Watch { id, .. } if !self.can_watch => {
+                    error(Some(id), format!("Token lacks the {} scope", WATCH_SCOPE))
+                }
+                ClientMessage::Watch { id, .. } if watches.len() >= MAX_WATCHES => {
+                    error(Some(id), format!("At most {} watches per connection", MAX_WATCHES))
+                }
+                ClientMessage::Watch { id, path, recursive } => {
+                    match resolve_path(&self.workspace, &path, self.confined) {
+                        Ok(path) => match watch(&id, &path, recursive, outbox.clone()) {
+                            Ok(watcher) => {
+                                watches.insert(id.clone(), watcher);
+                                ServerMessage::Watching { id, path: path.display().to_string() }
+                            }
+                            Err(e) => error(Some(id), format!("Failed to watch {}: {}", path.display(), e)),
+                        },
+                        Err(e) => error(Some(id), e.to_string()),
+                    }
+                }
+                ClientMessage::Cancel { id } => {
+                    if let Some(task) = calls.remove(&id) {
+                        task.abort();
+                        ServerMessage::Cancelled { id }
+                    } else if watches.remove(&id).is_some() {
+                        ServerMessage::Cancelled { id }
+                    } else {
+                        error(Some(id), "No running call or watch with this id")
+                    }
+                }
+            };
+            if outbox.send(reply).await.is_err() {
+                break;
+            }
+        }
+
+        // Dropping a call's future stops it, the same as an HTTP client disconnecting
+        for task in calls.values() {
+            task.abort();
+        }
+        drop(watches);
+        writer.abort();
+    }
+
+    /// Build the request for a call, as the client that opened the connection
+    fn request(&self, method: &str, path: &str, body: Option<Value>) -> Result<(Request, String), String> {
+        let method = Method::from_bytes(method.to_ascii_uppercase().as_bytes())
+            .map_err(|_| format!("Invalid method: {}", method))?;
+        if !path.starts_with('/') {
+            return Err(format!("Path must start with /: {}", path));
+        }
+
+        let request_id = uuid::Uuid::new_v4().to_string();
+        let mut builder = Request::builder().method(method).uri(path);
+        for (name, value) in &self.headers {
+            builder = builder.header(name.clone(), value.clone());
+        }
+        builder = builder.header(X_REQUEST_ID.clone(), request_id.as_str());
+        let body = match body {
+            Some(body) => {
+                builder = builder.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+
+        let mut request = builder.body(body).map_err(|e| format!("Invalid request: {}", e))?;
+        request.extensions_mut().insert(RequestId(request_id.clone()));
+        if let Some(peer) = self.peer {
+            request.extensions_mut().insert(peer);
+        }
+        Ok((request, request_id))
+    }
+}
+
+fn error(id: Option<String>, error: impl Into<String>) -> ServerMessage {
+    ServerMessage::Error { id, error: error.into() }
+}
+
+/// Run one call, forwarding a streamed body event by event
+async fn call(api: Router, request: Request, id: String, request_id: String, outbox: mpsc::Sender<ServerMessage>) {
+    let response = match api.oneshot(request).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    };
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    if content_type.starts_with("text/event-stream") {
+        let mut events = SseParser::default();
+        let mut chunks = response.into_body().into_data_stream();
+        while let Some(chunk) = chunks.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    let _ = outbox.send(error(Some(id), format!("Stream ended early: {}", e))).await;
+                    return;
+                }
+            };
+            for data in events.push(&chunk) {
+                if outbox.send(ServerMessage::Event { id: id.clone(), data }).await.is_err() {
+                    return;
+                }
+            }
+        }
+        let _ = outbox
+            .send(ServerMessage::Response { id, status, request_id, body: None, encoding: None })
+            .await;
+        return;
+    }
+
+    let message = match to_bytes(response.into_body(), MAX_RESPONSE_BYTES).await {
+        Ok(bytes) => {
+            let (body, encoding) = decode_body(&content_type, &bytes);
+            ServerMessage::Response { id, status, request_id, body, encoding }
+        }
+        Err(_) => error(
+            Some(id),
+            format!("Response is over {} bytes; fetch it over HTTP", MAX_RESPONSE_BYTES),
+        ),
+    };
+    let _ = outbox.send(message).await;
+}
+
+/// A response body as JSON, text, or base64
+fn decode_body(content_type: &str, bytes: &[u8]) -> (Option<Value>, Option<String>) {
+    if bytes.is_empty() {
+        return (None, None);
+    }
+    if content_type.starts_with("application/json") {
+        if let Ok(value) = serde_json::from_slice(bytes) {
+            return (Some(value), None);
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) if content_type.starts_with("text/") || content_type.starts_with("application/json") => {
+            (Some(json!(text)), None)
+        }
+        _ => (Some(json!(BASE64.encode(bytes))), Some("base64".into())),
+    }
+}
+
+/// Collects the `data` of server-sent events from body chunks
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Add a chunk, returning the data of every event it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|w| w == b"\n\n") {
+            let block: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let block = String::from_utf8_lossy(&block);
+            let data: Vec<&str> = block
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.strip_prefix(' ').unwrap_or(data))
+                .collect();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+}
+
+/// Push changes under `path` to the client until the watcher is dropped
+fn watch(
+    id: &str,
+    path: &std::path::Path,
+    recursive: bool,
+    outbox: mpsc::Sender<ServerMessage>,
+) -> notify::Result<RecommendedWatcher> {
+    let id = id.to_string();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let message = match event {
+            Ok(event) => {
+                let kind = match event.kind {
+                    EventKind::Create(_) => "create",
+                    EventKind::Modify(_) => "modify",
+                    EventKind::Remove(_) => "remove",
+                    // Reads are not changes
+                    EventKind::Access(_) => return,
+                    EventKind::Any | EventKind::Other => "other",
+                };
+                ServerMessage::File {
+                    id: id.clone(),
+                    kind: kind.into(),
+                    paths: event.paths.iter().map(|p| p.display().to_string()).collect(),
+                }
+            }
+            Err(e) => error(Some(id.clone()), e.to_string()),
+        };
+        // Called on the watcher's own thread, so waiting for a slow client only delays this watch
+        let _ = outbox.blocking_send(message);
+    })?;
+    let mode = if recursive { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+    watcher.watch(path, mode)?;
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        response::sse::{Event, Sse},
+        routing::{get, post},
+        Json,
+    };
+    use std::convert::Infallible;
+
+    fn api() -> Router {
+        Router::new()
+            .route("/echo", post(|Json(body): Json<Value>| async move { Json(body) }))
+            .route(
+                "/lines",
+                get(|| async {
+                    let events = futures::stream::iter(["one", "two"])
+                        .map(|line| Ok::<_, Infallible>(Event::default().data(line)));
+                    Sse::new(events)
+                }),
+            )
+    }
+
+    fn connection() -> Connection {
+        Connection {
+            api: api(),
+            workspace: "/tmp".into(),
+            confined: false,
+            can_watch: true,
+            headers: Vec::new(),
+            peer: None,
+        }
+    }
+
+    async fn messages(method: &str, path: &str, body: Option<Value>) -> Vec<Value> {
+        let (outbox, mut outgoing) = mpsc::channel(16);
+        let (request, request_id) = connection().request(method, path, body).unwrap();
+        call(api(), request, "7".into(), request_id, outbox).await;
+        let mut messages = Vec::new();
+        while let Ok(message) = outgoing.try_recv() {
+            messages.push(serde_json::to_value(message).unwrap());
+        }
+        messages
+    }
+
+    #[tokio::test]
+    async fn test_call_returns_json_body() {
+        let messages = messages("post", "/echo", Some(json!({"a": 1}))).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["type"], "response");
+        assert_eq!(messages[0]["id"], "7");
+        assert_eq!(messages[0]["status"], 200);
+        assert_eq!(messages[0]["body"], json!({"a": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_streamed_call_forwards_events() {
+        let messages = messages("GET", "/lines", None).await;
+        let types: Vec<_> = messages.iter().map(|m| m["type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["event", "event", "response"]);
+        assert_eq!(messages[0]["data"], "one");
+        assert_eq!(messages[1]["data"], "two");
+    }
+
+    #[test]
+    fn test_sse_events_split_across_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"data: hel").is_empty());
+        assert_eq!(parser.push(b"lo\n\ndata: a\ndata: b\n\n:keep-alive\n\n"), ["hello", "a\nb"]);
+    }
+
+    #[test]
+    fn test_decode_binary_body() {
+        let (body, encoding) = decode_body("image/png", &[0x89, 0x50]);
+        assert_eq!(body, Some(json!("iVA=")));
+        assert_eq!(encoding.as_deref(), Some("base64"));
+        assert_eq!(decode_body("text/plain", b"hi"), (Some(json!("hi")), None));
+    }
+}
@@ -0,0 +1,49 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Routes that take multipart uploads and get the larger limit
+pub const UPLOAD_PATHS: &[&str] = &["/file/upload"];
+
+/// The body limit that applies to `path`
+pub fn limit_for(state: &AppState, path: &str) -> usize {
+    if UPLOAD_PATHS.contains(&path) {
+        state.config.max_upload_bytes
+    } else {
+        state.config.max_body_bytes
+    }
+}
+
+/// Answer oversized bodies with a JSON 413 naming the limit.
+///
+/// A declared `Content-Length` is checked before the body is read. Chunked
+/// bodies are cut off by `DefaultBodyLimit` while extracting, and the plain
+/// text rejection that produces is rewritten here.
+pub async fn enforce(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let limit = limit_for(&state, request.uri().path());
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if declared.is_some_and(|len| len > limit as u64) {
+        return AppError::PayloadTooLarge(limit).into_response();
+    }
+
+    let response = next.run(request).await;
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|v| v.as_bytes().starts_with(b"application/json"));
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        return AppError::PayloadTooLarge(limit).into_response();
+    }
+    response
+}
@@ -0,0 +1,357 @@
+use anyhow::{bail, Context};
+use axum::serve::Listener;
+use axum::Router;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::{TcpListener, UnixListener};
+
+use crate::shutdown::Shutdown;
+use crate::tls;
+
+/// Where the API accepts connections
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenAddr {
+    Tcp(SocketAddr),
+    /// Unix domain socket path; a stale socket file is replaced
+    Unix(PathBuf),
+    /// vsock context ID and port, for VMs that expose no network interface
+    Vsock { cid: u32, port: u32 },
+}
+
+impl ListenAddr {
+    /// Parse `unix:PATH` or `vsock:CID:PORT` (CID may be `any`); without `listen`, TCP on `port`
+    pub fn parse(listen: Option<&str>, port: u16) -> anyhow::Result<Self> {
+        let Some(listen) = listen else {
+            return Ok(Self::Tcp(SocketAddr::from(([0, 0, 0, 0], port))));
+        };
+
+        if let Some(path) = listen.strip_prefix("unix:") {
+            if path.is_empty() {
+                bail!("unix: needs a socket path");
+            }
+            return Ok(Self::Unix(path.into()));
+        }
+
+        if let Some(addr) = listen.strip_prefix("vsock:") {
+            if !cfg!(target_os = "linux") {
+                bail!("vsock is only supported on Linux");
+            }
+            let (cid, port) = addr.split_once(':').context("vsock address must be CID:PORT")?;
+            let cid = match cid {
+                "any" => u32::MAX,
+                cid => cid.parse().with_context(|| format!("invalid vsock CID '{}'", cid))?,
+            };
+            let port = port.parse().with_context(|| format!("invalid vsock port '{}'", port))?;
+            return Ok(Self::Vsock { cid, port });
+        }
+
+        bail!("expected unix:PATH or vsock:CID:PORT, not '{}'", listen)
+    }
+}
+
+impl fmt::Display for ListenAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "{}", addr),
+            Self::Unix(path) => write!(f, "unix:{}", path.display()),
+            Self::Vsock { cid, port } if *cid == u32::MAX => write!(f, "vsock:any:{}", port),
+            Self::Vsock { cid, port } => write!(f, "vsock:{}:{}", cid, port),
+        }
+    }
+}
+
+/// A bound listener of any supported kind
+pub enum Bound {
+    Tcp(TcpListener),
+    Unix(UnixListener, PathBuf),
+    #[cfg(target_os = "linux")]
+    Vsock(vsock::VsockListener),
+}
+
+pub async fn bind(addr: &ListenAddr) -> anyhow::Result<Bound> {
+    match addr {
+        ListenAddr::Tcp(addr) => Ok(Bound::Tcp(TcpListener::bind(addr).await?)),
+        ListenAddr::Unix(path) => {
+            // Left behind by an unclean exit; binding fails while it exists
+            if std::fs::symlink_metadata(path).is_ok_and(|m| {
+                std::os::unix::fs::FileTypeExt::is_socket(&m.file_type())
+            }) {
+                std::fs::remove_file(path)?;
+            }
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            Ok(Bound::Unix(UnixListener::bind(path)?, path.clone()))
+        }
+        #[cfg(target_os = "linux")]
+        ListenAddr::Vsock { cid, port } => Ok(Bound::Vsock(vsock::VsockListener::bind(*cid, *port)?)),
+        #[cfg(not(target_os = "linux"))]
+        ListenAddr::Vsock { .. } => bail!("vsock is only supported on Linux"),
+    }
+}
+
+/// Serve `app` until `shutdown` fires, over TLS when `tls_config` is set. Only TCP
+/// peers have an address, so clients on other listeners are told apart by API key alone.
+pub async fn serve(listener: Bound, app: Router, tls_config: Option<Arc<rustls::ServerConfig>>, shutdown: Shutdown) {
+    match listener {
+        Bound::Tcp(listener) => match tls_config {
+            Some(tls_config) => tls::serve(listener, app, tls_config, shutdown).await,
+            None => axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown.triggered())
+                .await
+                .unwrap(),
+        },
+        Bound::Unix(listener, path) => {
+            serve_on(listener, app, tls_config, shutdown).await;
+            std::fs::remove_file(path).ok();
+        }
+        #[cfg(target_os = "linux")]
+        Bound::Vsock(listener) => serve_on(listener, app, tls_config, shutdown).await,
+    }
+}
+
+async fn serve_on<L>(listener: L, app: Router, tls_config: Option<Arc<rustls::ServerConfig>>, shutdown: Shutdown)
+where
+    L: Listener,
+    L::Addr: Clone + Send + Sync + fmt::Debug + 'static,
+{
+    match tls_config {
+        Some(tls_config) => tls::serve(listener, app, tls_config, shutdown).await,
+        None => axum::serve(listener, app)
+            .with_graceful_shutdown(shutdown.triggered())
+            .await
+            .unwrap(),
+    }
+}
+
+/// Minimal async vsock sockets over libc; tokio has no vsock support of its own
+#[cfg(target_os = "linux")]
+mod vsock {
+    use std::io;
+    use std::mem::{size_of, zeroed};
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::pin::Pin;
+    use std::task::{ready, Context, Poll};
+    use std::time::Duration;
+    use tokio::io::unix::AsyncFd;
+    use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+    /// Peer of a vsock connection
+    #[derive(Debug, Clone, Copy)]
+    #[allow(dead_code)]
+    pub struct VsockAddr {
+        pub cid: u32,
+        pub port: u32,
+    }
+
+    fn sockaddr(cid: u32, port: u32) -> libc::sockaddr_vm {
+        // SAFETY: sockaddr_vm is plain data, valid when zeroed
+        let mut addr: libc::sockaddr_vm = unsafe { zeroed() };
+        addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+        addr.svm_cid = cid;
+        addr.svm_port = port;
+        addr
+    }
+
+    fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+        if result < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(result)
+        }
+    }
+
+    pub struct VsockListener {
+        fd: AsyncFd<OwnedFd>,
+    }
+
+    impl VsockListener {
+        pub fn bind(cid: u32, port: u32) -> io::Result<Self> {
+            // SAFETY: plain socket syscalls on a descriptor this function owns
+            unsafe {
+                let fd = check(libc::socket(
+                    libc::AF_VSOCK,
+                    libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                    0,
+                ))?;
+                let fd = OwnedFd::from_raw_fd(fd);
+                let addr = sockaddr(cid, port);
+                check(libc::bind(
+                    fd.as_raw_fd(),
+                    &addr as *const libc::sockaddr_vm as *const libc::sockaddr,
+                    size_of::<libc::sockaddr_vm>() as libc::socklen_t,
+                ))?;
+                check(libc::listen(fd.as_raw_fd(), libc::SOMAXCONN))?;
+                Ok(Self { fd: AsyncFd::new(fd)? })
+            }
+        }
+
+        async fn accept_once(&self) -> io::Result<(VsockStream, VsockAddr)> {
+            loop {
+                let mut guard = self.fd.readable().await?;
+                let accepted = guard.try_io(|fd| {
+                    let mut addr = sockaddr(0, 0);
+                    let mut len = size_of::<libc::sockaddr_vm>() as libc::socklen_t;
+                    // SAFETY: addr and len describe a valid sockaddr_vm buffer
+                    let conn = check(unsafe {
+                        libc::accept4(
+                            fd.as_raw_fd(),
+                            &mut addr as *mut libc::sockaddr_vm as *mut libc::sockaddr,
+                            &mut len,
+                            libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+                        )
+                    })?;
+                    // SAFETY: accept4 returned a new descriptor that nothing else owns
+                    Ok((unsafe { OwnedFd::from_raw_fd(conn) }, addr))
+                });
+                match accepted {
+                    Ok(Ok((conn, addr))) => {
+                        let peer = VsockAddr { cid: addr.svm_cid, port: addr.svm_port };
+                        return Ok((VsockStream { fd: AsyncFd::new(conn)? }, peer));
+                    }
+                    Ok(Err(e)) => return Err(e),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    impl axum::serve::Listener for VsockListener {
+        type Io = VsockStream;
+        type Addr = VsockAddr;
+
+        async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+            loop {
+                match self.accept_once().await {
+                    Ok(conn) => return conn,
+                    Err(e) => {
+                        tracing::warn!("Failed to accept vsock connection: {}", e);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+        }
+
+        fn local_addr(&self) -> io::Result<Self::Addr> {
+            let mut addr = sockaddr(0, 0);
+            let mut len = size_of::<libc::sockaddr_vm>() as libc::socklen_t;
+            // SAFETY: addr and len describe a valid sockaddr_vm buffer
+            check(unsafe {
+                libc::getsockname(
+                    self.fd.as_raw_fd(),
+                    &mut addr as *mut libc::sockaddr_vm as *mut libc::sockaddr,
+                    &mut len,
+                )
+            })?;
+            Ok(VsockAddr { cid: addr.svm_cid, port: addr.svm_port })
+        }
+    }
+
+    pub struct VsockStream {
+        fd: AsyncFd<OwnedFd>,
+    }
+
+    impl AsyncRead for VsockStream {
+        fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                let read = guard.try_io(|fd| {
+                    // SAFETY: unfilled is a valid, initialized buffer of this length
+                    let n = unsafe {
+                        libc::read(fd.as_raw_fd(), unfilled.as_mut_ptr() as *mut libc::c_void, unfilled.len())
+                    };
+                    if n < 0 { Err(io::Error::last_os_error()) } else { Ok(n as usize) }
+                });
+                match read {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(Err(e)) => return Poll::Ready(Err(e)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    impl AsyncWrite for VsockStream {
+        fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+            loop {
+                let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+                let written = guard.try_io(|fd| {
+                    // SAFETY: data is a valid buffer of this length
+                    let n = unsafe { libc::write(fd.as_raw_fd(), data.as_ptr() as *const libc::c_void, data.len()) };
+                    if n < 0 { Err(io::Error::last_os_error()) } else { Ok(n as usize) }
+                });
+                match written {
+                    Ok(result) => return Poll::Ready(result),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            // SAFETY: shutdown on a descriptor this stream owns
+            Poll::Ready(check(unsafe { libc::shutdown(self.fd.as_raw_fd(), libc::SHUT_WR) }).map(|_| ()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_parse_listen_addr() {
+        assert_eq!(ListenAddr::parse(None, 8080).unwrap(), ListenAddr::Tcp(([0, 0, 0, 0], 8080).into()));
+        assert_eq!(
+            ListenAddr::parse(Some("unix:/run/sandbox.sock"), 8080).unwrap(),
+            ListenAddr::Unix("/run/sandbox.sock".into())
+        );
+        assert!(ListenAddr::parse(Some("unix:"), 8080).is_err());
+        assert!(ListenAddr::parse(Some("0.0.0.0:80"), 8080).is_err());
+        if cfg!(target_os = "linux") {
+            assert_eq!(
+                ListenAddr::parse(Some("vsock:any:5000"), 8080).unwrap(),
+                ListenAddr::Vsock { cid: u32::MAX, port: 5000 }
+            );
+            assert_eq!(ListenAddr::parse(Some("vsock:3:5000"), 8080).unwrap().to_string(), "vsock:3:5000");
+            assert!(ListenAddr::parse(Some("vsock:3"), 8080).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_serve_on_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+        // A stale socket from an earlier run is replaced
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+
+        let listener = bind(&ListenAddr::Unix(path.clone())).await.unwrap();
+        let app = Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+        let (trigger, shutdown) = Shutdown::channel();
+        let server = tokio::spawn(serve(listener, app, None, shutdown));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+        assert!(response.ends_with("ok"));
+
+        trigger.send_replace(true);
+        server.await.unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -1,34 +1,88 @@
+mod audit;
+mod auth;
 mod browser;
 mod config;
+mod convert;
+mod data;
+mod display;
+mod drain;
 mod error;
+#[cfg(feature = "grpc")]
+mod grpc;
 mod handlers;
+mod limits;
+mod listen;
+mod manifest;
+mod net;
+mod nix;
+mod notebook;
+mod openapi;
+mod overload;
+mod panics;
+mod ratelimit;
+mod reload;
+mod request_id;
+mod secrets;
+mod services;
+mod shutdown;
+mod sessions;
 mod skills;
 mod state;
+mod storage;
 
-#[cfg(feature = "tee")]
 mod tee;
+mod tls;
+mod usage;
+mod versioning;
+mod webhooks;
 
 use axum::{
-    routing::{get, post},
-    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{any, delete, get, post, put},
+    Extension, Router,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
 };
-use std::net::SocketAddr;
+use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use utoipa_swagger_ui::SwaggerUi;
 
-use config::Config;
+use config::{Args, Config};
 use handlers::{
-    browser_click, browser_evaluate, browser_goto, browser_screenshot, browser_status,
-    browser_type, check_trigger, continue_factory, create_skill, delete_skill, download_file,
-    exec_command, execute_code, execute_script, get_skill, health_check, list_files, list_skills,
-    read_file, sandbox_info, search_skills, start_factory, stream_command, update_skill,
-    upload_file, write_file,
+    add_cell, admin_status, audit_log, browser_activate_page, browser_capture, browser_click,
+    browser_close_page, browser_content, browser_download, browser_downloads, browser_evaluate,
+    browser_fill, browser_focus, browser_goto, browser_har_start, browser_har_stop, browser_hover,
+    browser_page_console, browser_pages, browser_pdf, browser_press, browser_query,
+    browser_record_start, browser_record_stop, browser_screenshot, browser_scroll, browser_select,
+    browser_status, browser_type, browser_upload, check_trigger, continue_factory, convert_document,
+    create_notebook, create_secret, create_session, create_skill, data_query, declare_service,
+    delete_cell, delete_notebook, delete_secret, delete_service, delete_session, delete_skill,
+    delete_webhook, display_click, display_keys, display_record_start, display_record_stop,
+    display_screenshot, display_type, download_file, exec_command, execute_code, execute_script,
+    export_notebook, get_clipboard, get_config, get_notebook, get_secret, get_service, get_session,
+    get_skill, health_check, install_packages, list_files, list_notebooks, list_packages,
+    list_secrets, list_services, list_sessions, list_skills, list_webhooks, net_fetch,
+    proxy_service, read_file, ready_check, register_webhook, reload_config, run_cell, run_notebook,
+    sandbox_info, sandbox_manifest, sandbox_usage, search_skills, set_clipboard, start_drain,
+    start_factory, stop_drain, stream_command, test_webhook, update_cell, update_secret,
+    update_skill, upload_file, version_info, websocket, write_file, WsApi,
 };
 
 #[cfg(feature = "tee")]
 use handlers::tee::{
-    derive_key, emit_event, generate_quote, sign_data, tee_info, verify_signature,
+    attest_payload, auth_challenge, auth_prove, authenticate, decrypt, derive_key, emit_event,
+    encrypt, encryption_key, event_log, generate_quote, get_env, issue_token, jwks, list_keys,
+    openid_configuration, register_key, remove_env, remove_key, seal_file, set_env, sign_data,
+    sign_skill_output, tee_info, unseal_file, verify_signature,
 };
+use listen::ListenAddr;
+use shutdown::Shutdown;
 use state::AppState;
 
 #[tokio::main]
@@ -40,25 +94,177 @@ async fn main() {
         )
         .with(tracing_subscriber::fmt::layer())
         .init();
+    panics::install_hook();
+
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, config::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        print!("{}", config::USAGE);
+        return;
+    }
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: invalid configuration: {:#}", e);
+            std::process::exit(2);
+        }
+    };
+    if args.print_config {
+        print!("{}", config.to_toml());
+        return;
+    }
+    if let Some(path) = &config.config_file {
+        tracing::info!("Loaded configuration from {}", path.display());
+    }
+
+    let addr = ListenAddr::parse(config.listen.as_deref(), config.port).expect("validated by Config::load");
+    let tls_files = config.tls_cert.clone().zip(config.tls_key.clone());
+    let tls_redirect_port = config.tls_redirect_port;
+    let https_port = config.port;
+    #[cfg(feature = "tee")]
+    let ratls_hostnames = config.tee_ratls.then(|| config.tee_ratls_hostnames.clone());
+    let state = AppState::new(config, args);
+    state.browser.spawn_reaper();
+    reload::spawn_watcher(state.clone());
+    usage::spawn_collector(state.clone());
+    #[cfg(feature = "tee")]
+    tee::measure::measure_startup(&state).await;
+
+    #[cfg(feature = "tee")]
+    if state.config.tee_auth {
+        tracing::info!("Mutating endpoints require a /tee/auth token");
+    }
+    #[cfg(feature = "tee")]
+    let tee_service = state.tee_service.clone();
+
+    match state.webhooks.restore() {
+        0 => {}
+        restored => tracing::info!("Restored {} webhooks", restored),
+    }
+    match state.services.restore() {
+        0 => {}
+        restored => tracing::info!("Restored {} services", restored),
+    }
+    match state.sessions.restore(&state) {
+        0 => {}
+        restored => tracing::info!("Restored {} sessions", restored),
+    }
+
+    // Requests naming a session are handed to that session's own router
+    let app = router(&state)
+        .layer(middleware::from_fn_with_state(state.clone(), sessions::dispatch))
+        .layer(TraceLayer::new_for_http().make_span_with(request_id::make_span))
+        .layer(middleware::from_fn(request_id::assign))
+        // Outermost, so error bodies have their request ID added before they are compressed
+        .layer(compression(state.config.compress_min_size));
+
+    let listener = listen::bind(&addr)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to listen on {}: {:#}", addr, e));
+    let shutdown = Shutdown::listen();
+
+    let mut tls_config = None;
+    #[cfg(feature = "tee")]
+    if let Some(hostnames) = ratls_hostnames {
+        tls_config = Some(
+            tee::ratls::server_config(&tee_service, hostnames)
+                .await
+                .expect("Failed to create RA-TLS certificate"),
+        );
+        tracing::info!("listening on {} (RA-TLS)", addr);
+    }
+    if let Some((cert, key)) = tls_files {
+        tls_config = Some(tls::reloading_config(cert.into(), key.into()).expect("Failed to load TLS certificate"));
+        if let Some(port) = tls_redirect_port {
+            tokio::spawn(tls::serve_redirect(port, https_port));
+        }
+        tracing::info!("listening on {} (TLS)", addr);
+    }
+
+    if tls_config.is_none() {
+        tracing::info!("listening on {}", addr);
+    }
+
+    let server = listen::serve(listener, app, tls_config, shutdown.clone());
+    let deadline = Duration::from_secs(state.config.shutdown_timeout);
+    if !shutdown.drain(server, deadline).await {
+        tracing::warn!("Requests still running after {}s, abandoning them", deadline.as_secs());
+    }
+
+    match state.factory.save(&state.state_store) {
+        Ok(0) => {}
+        Ok(saved) => tracing::info!("Saved {} factory sessions", saved),
+        Err(e) => tracing::warn!("Failed to save factory sessions: {}", e),
+    }
+    state.browser.restart().await;
+    state.sessions.close_all().await;
+    tracing::info!("Shutdown complete");
+}
 
-    let config = Config::from_env();
-    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
-    let state = AppState::new(config);
+/// gzip or brotli for responses of at least `min_size` bytes, as the client's Accept-Encoding
+/// allows; 0 disables. Images, gRPC, and event streams are left alone.
+fn compression(min_size: u16) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(min_size)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE);
+    let layer = CompressionLayer::new();
+    let layer = if min_size == 0 { layer.no_gzip().no_br() } else { layer };
+    layer.compress_when(predicate)
+}
 
+/// Every endpoint with its middleware, for `state`. Sessions get a router of their own.
+fn router(state: &Arc<AppState>) -> Router {
     let app = Router::new()
         // Health
         .route("/health", get(health_check))
+        .route("/ready", get(ready_check))
+        .route("/version", get(version_info))
         .route("/sandbox/info", get(sandbox_info))
+        .route("/sandbox/usage", get(sandbox_usage))
+        .route("/sandbox/manifest", get(sandbox_manifest))
         // Shell
         .route("/shell/exec", post(exec_command))
         .route("/shell/stream", post(stream_command))
         // Code
         .route("/code/execute", post(execute_code))
+        // Notebook
+        .route("/notebook", get(list_notebooks).post(create_notebook))
+        .route("/notebook/{id}", get(get_notebook).delete(delete_notebook))
+        .route("/notebook/{id}/cells", post(add_cell))
+        .route("/notebook/{id}/cells/{cell_id}", put(update_cell).delete(delete_cell))
+        .route("/notebook/{id}/cells/{cell_id}/run", post(run_cell))
+        .route("/notebook/{id}/run", post(run_notebook))
+        .route("/notebook/{id}/export", get(export_notebook))
+        // Display
+        .route("/display/screenshot", post(display_screenshot))
+        .route("/display/click", post(display_click))
+        .route("/display/type", post(display_type))
+        .route("/display/keys", post(display_keys))
+        .route("/display/clipboard", get(get_clipboard).post(set_clipboard))
+        .route("/display/record/start", post(display_record_start))
+        .route("/display/record/stop", post(display_record_stop))
+        // Network
+        .route("/net/fetch", post(net_fetch))
+        // Data
+        .route("/data/query", post(data_query))
+        .route("/convert", post(convert_document))
+        // Environment
+        .route("/env/install", post(install_packages))
+        .route("/env/packages", get(list_packages))
         // Files
         .route("/file/read", get(read_file))
         .route("/file/write", post(write_file))
         .route("/file/list", get(list_files))
-        .route("/file/upload", post(upload_file))
+        .route(
+            "/file/upload",
+            post(upload_file).layer(DefaultBodyLimit::max(state.config.max_upload_bytes)),
+        )
         .route("/file/download", get(download_file))
         // Skills routes
         .route("/skills", get(list_skills).post(create_skill))
@@ -78,21 +284,163 @@ async fn main() {
         .route("/browser/evaluate", post(browser_evaluate))
         .route("/browser/click", post(browser_click))
         .route("/browser/type", post(browser_type))
-        .route("/browser/status", get(browser_status));
+        .route("/browser/scroll", post(browser_scroll))
+        .route("/browser/hover", post(browser_hover))
+        .route("/browser/focus", post(browser_focus))
+        .route("/browser/press", post(browser_press))
+        .route("/browser/fill", post(browser_fill))
+        .route("/browser/select", post(browser_select))
+        .route("/browser/query", post(browser_query))
+        .route("/browser/upload", post(browser_upload))
+        .route("/browser/pdf", post(browser_pdf))
+        .route("/browser/content", post(browser_content))
+        .route("/browser/capture", post(browser_capture))
+        .route("/browser/har/start", post(browser_har_start))
+        .route("/browser/har/stop", post(browser_har_stop))
+        .route("/browser/record/start", post(browser_record_start))
+        .route("/browser/record/stop", post(browser_record_stop))
+        .route("/browser/download", post(browser_download))
+        .route("/browser/downloads", get(browser_downloads))
+        .route("/browser/pages", get(browser_pages))
+        .route("/browser/pages/{page_id}", delete(browser_close_page))
+        .route("/browser/pages/{page_id}/activate", post(browser_activate_page))
+        .route("/browser/pages/{page_id}/console", get(browser_page_console))
+        .route("/browser/status", get(browser_status))
+        // Admin
+        .route("/admin/config", get(get_config))
+        .route("/admin/reload", post(reload_config))
+        .route("/admin/status", get(admin_status))
+        .route("/admin/drain", post(start_drain).delete(stop_drain))
+        .route("/audit", get(audit_log))
+        // Secrets
+        .route("/secrets", get(list_secrets).post(create_secret))
+        .route("/secrets/{name}", get(get_secret).put(update_secret).delete(delete_secret))
+        // Services
+        .route("/services", get(list_services).post(declare_service))
+        .route("/services/{name}", get(get_service).delete(delete_service))
+        .route("/proxy/{service}", any(proxy_service))
+        .route("/proxy/{service}/", any(proxy_service))
+        .route("/proxy/{service}/{*path}", any(proxy_service))
+        // Sessions
+        .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/{id}", get(get_session).delete(delete_session))
+        // Webhooks
+        .route("/webhooks", get(list_webhooks).post(register_webhook))
+        .route("/webhooks/{id}", delete(delete_webhook))
+        .route("/webhooks/{id}/test", post(test_webhook))
+        // API documentation
+        .merge(SwaggerUi::new("/swagger-ui").url("/openapi.json", openapi::spec()));
 
     #[cfg(feature = "tee")]
     let app = app
         .route("/tee/info", get(tee_info))
         .route("/tee/quote", post(generate_quote))
+        .route("/tee/attest-payload", post(attest_payload))
         .route("/tee/derive-key", post(derive_key))
         .route("/tee/sign", post(sign_data))
+        .route("/tee/keys", get(list_keys).post(register_key))
+        .route("/tee/keys/{name}", delete(remove_key))
         .route("/tee/verify", post(verify_signature))
-        .route("/tee/emit-event", post(emit_event));
+        .route("/tee/encryption-key", get(encryption_key))
+        .route("/tee/encrypt", post(encrypt))
+        .route("/tee/decrypt", post(decrypt))
+        .route("/tee/emit-event", post(emit_event))
+        .route("/tee/event-log", get(event_log))
+        .route("/tee/env", get(get_env).post(set_env))
+        .route("/tee/env/{name}", delete(remove_env))
+        .route("/file/seal", post(seal_file))
+        .route("/file/unseal", post(unseal_file))
+        .route("/tee/auth", post(authenticate))
+        .route("/tee/auth/challenge", post(auth_challenge))
+        .route("/tee/auth/prove", post(auth_prove))
+        .route("/skills/{name}/sign-output", post(sign_skill_output))
+        .route("/tee/token", post(issue_token))
+        .route("/.well-known/jwks.json", get(jwks))
+        .route("/.well-known/openid-configuration", get(openid_configuration));
+
+    #[cfg(feature = "grpc")]
+    let app = grpc::mount(app, state);
+
+    let app = require_auth(app, state);
+
+    // A panicking handler is answered with a 500 that is audited and reported like any other
+    let app = app
+        .layer(CatchPanicLayer::custom(panics::respond))
+        .layer(middleware::from_fn(panics::track));
+
+    // Outside authentication, so rejected attempts are logged too, and outside the
+    // timeout, so timed out requests are
+    let app = app
+        .layer(middleware::from_fn_with_state(state.clone(), overload::timeout))
+        .layer(middleware::from_fn_with_state(state.clone(), webhooks::notify_errors))
+        .layer(middleware::from_fn_with_state(state.clone(), audit::record))
+        .layer(DefaultBodyLimit::max(state.config.max_body_bytes))
+        .layer(middleware::from_fn_with_state(state.clone(), limits::enforce));
 
-    let app = app.with_state(state).layer(TraceLayer::new_for_http());
+    // Outside the body limit, so throttled clients are turned away before any other work
+    let app = limit(app, state);
+
+    // Every route is served under /v1, and at its old path unless legacy routes are off
+    let negotiate = middleware::from_fn_with_state(state.clone(), versioning::negotiate);
+    let app = versioning::prefixed(app.with_state(state.clone())).layer(negotiate.clone());
+
+    // Calls made over /ws are dispatched into everything above, so they are authenticated,
+    // limited, and audited like HTTP requests. The upgrade itself is authenticated and
+    // limited too, since watches are served by the socket directly.
+    let ws = Router::new().route("/ws", get(websocket)).layer(Extension(WsApi(app.clone())));
+    let ws = limit(require_auth(ws, state), state).with_state(state.clone());
+    versioning::prefixed(ws).layer(negotiate).merge(app)
+}
+
+/// Bearer JWT and TEE token checks, for whichever are configured
+fn require_auth(app: Router<Arc<AppState>>, state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    let app = if state.jwt.is_some() {
+        app.layer(middleware::from_fn_with_state(state.clone(), auth::require_scope))
+    } else {
+        app
+    };
+
+    #[cfg(feature = "tee")]
+    let app = if state.config.tee_auth {
+        app.layer(middleware::from_fn_with_state(state.clone(), tee::auth::require_token))
+    } else {
+        app
+    };
+    app
+}
+
+/// Per-client quotas, draining, and the global limit. Always installed, since a reload can
+/// enable limits.
+fn limit(app: Router<Arc<AppState>>, state: &Arc<AppState>) -> Router<Arc<AppState>> {
+    app.layer(middleware::from_fn_with_state(state.clone(), ratelimit::enforce))
+        // Outside the quotas, so executions refused while draining are not charged to the client
+        .layer(middleware::from_fn_with_state(state.clone(), drain::enforce))
+        // Past the global limit nothing else is worth doing, not even per-client accounting
+        .layer(middleware::from_fn_with_state(state.clone(), overload::shed))
+}
 
-    tracing::info!("listening on {}", addr);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    #[tokio::test]
+    async fn test_ws_upgrade_needs_a_token() {
+        let args = Args::parse(["--audit-log=".to_string(), format!("--jwt-secret={}", "0".repeat(32))]).unwrap();
+        let state = AppState::new(Config::load(&args).unwrap(), args);
+        for (path, authorization) in [("/ws", None), ("/v1/ws", None), ("/ws", Some("Bearer not-a-jwt"))] {
+            let mut request = Request::get(path)
+                .header("connection", "upgrade")
+                .header("upgrade", "websocket")
+                .header("sec-websocket-version", "13")
+                .header("sec-websocket-key", "dGhlIHNhbXBsZSBub25jZQ==");
+            if let Some(authorization) = authorization {
+                request = request.header("authorization", authorization);
+            }
+            let response = router(&state).oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{} with {:?}", path, authorization);
+        }
+    }
 }
@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::nix;
+use crate::tee::receipt;
+
+/// Longest a tool may take to print its version
+const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest `nix path-info` may take to walk the closures
+const CLOSURE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The system a NixOS host booted into
+const CURRENT_SYSTEM: &str = "/run/current-system";
+
+/// Tools reported, as `(name, programs to try in order, version argument)`
+const TOOLS: &[(&str, &[&str], &str)] = &[
+    ("python", &["python3"], "--version"),
+    ("node", &["node"], "--version"),
+    ("go", &["go"], "version"),
+    ("rustc", &["rustc"], "--version"),
+    ("chromium", &["chromium", "chromium-browser", "google-chrome"], "--version"),
+];
+
+/// What is needed to stand up an identical sandbox later
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Manifest {
+    /// Crate version
+    pub version: String,
+    /// Commit the server was built from, or `unknown`
+    pub git_commit: String,
+    /// Enabled optional features, e.g. `tee`
+    pub features: Vec<String>,
+    /// SHA-256 of the redacted configuration as canonical JSON, as measured in `tee` builds
+    pub config_sha256: String,
+    pub tools: Vec<Tool>,
+    /// The Nix closure of the tools and the booted system; absent without Nix
+    pub nix: Option<Closure>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Tool {
+    pub name: String,
+    /// First line the tool printed for its version, or absent when it is not installed
+    pub version: Option<String>,
+    /// Executable found on `PATH`, symlinks resolved
+    pub path: Option<String>,
+    /// Store path the executable belongs to, e.g. `/nix/store/<hash>-python3-3.12.8`
+    pub store_path: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Closure {
+    /// Store paths the closure was taken of: the tools' and the current system's
+    pub roots: Vec<String>,
+    /// Number of store paths in the closure
+    pub paths: usize,
+    /// SHA-256 of the sorted store paths, one per line
+    pub sha256: String,
+}
+
+/// Describe the sandbox as it is now
+pub async fn collect(config: &Config, features: Vec<String>) -> Result<Manifest> {
+    let tools = futures::future::join_all(TOOLS.iter().map(|(name, programs, arg)| tool(name, programs, arg))).await;
+
+    let mut roots: Vec<String> = tools.iter().filter_map(|tool| tool.store_path.clone()).collect();
+    if let Some(system) = std::fs::canonicalize(CURRENT_SYSTEM).ok().as_deref().and_then(store_path) {
+        roots.push(system);
+    }
+    roots.sort();
+    roots.dedup();
+    let nix = match roots.is_empty() {
+        true => None,
+        false => match nix::closure(&roots, CLOSURE_TIMEOUT).await {
+            Ok(mut paths) => {
+                paths.sort();
+                let sha256 = hex_digest(paths.join("\n").as_bytes());
+                Some(Closure { roots, paths: paths.len(), sha256 })
+            }
+            Err(e) => {
+                tracing::warn!("Could not take the Nix closure for the manifest: {}", e);
+                None
+            }
+        },
+    };
+
+    Ok(Manifest {
+        version: env!("CARGO_PKG_VERSION").into(),
+        git_commit: env!("BUILD_GIT_COMMIT").into(),
+        features,
+        config_sha256: config_digest(config)?,
+        tools,
+        nix,
+        generated_at: Utc::now(),
+    })
+}
+
+/// SHA-256 of the configuration with credentials removed, as canonical JSON; publishing a hash
+/// of a low-entropy password would let it be brute-forced
+pub fn config_digest(config: &Config) -> Result<String> {
+    Ok(hex_digest(receipt::canonical_json(&config.redacted())?.as_bytes()))
+}
+
+fn hex_digest(data: &[u8]) -> String {
+    receipt::sha256(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The first of `programs` on `PATH`, and its version
+async fn tool(name: &str, programs: &[&str], arg: &str) -> Tool {
+    let mut tool = Tool { name: name.into(), version: None, path: None, store_path: None, error: None };
+    let Some(found) = programs.iter().find_map(|program| which(program)) else {
+        tool.error = Some(format!("{} not found on PATH", programs.join(", ")));
+        return tool;
+    };
+    let resolved = std::fs::canonicalize(&found).unwrap_or(found.clone());
+    tool.store_path = store_path(&resolved);
+    tool.path = Some(resolved.display().to_string());
+
+    let output = Command::new(&found).arg(arg).kill_on_drop(true).output();
+    match tokio::time::timeout(VERSION_TIMEOUT, output).await {
+        Ok(Ok(output)) if output.status.success() => {
+            // Python 2 printed its version to stderr
+            let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+            tool.version = String::from_utf8_lossy(&text).lines().next().map(|line| line.trim().to_string());
+        }
+        Ok(Ok(output)) => tool.error = Some(format!("{} {} exited with {}", found.display(), arg, output.status)),
+        Ok(Err(e)) => tool.error = Some(format!("{}: {}", found.display(), e)),
+        Err(_) => tool.error = Some(format!("No answer within {}s", VERSION_TIMEOUT.as_secs())),
+    }
+    tool
+}
+
+/// Where `program` would be run from
+fn which(program: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(program)).find(|candidate| {
+        use std::os::unix::fs::PermissionsExt;
+        candidate.metadata().is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    })
+}
+
+/// The top-level store path `path` is inside, if it is in the Nix store
+fn store_path(path: &Path) -> Option<String> {
+    let entry = path.strip_prefix("/nix/store").ok()?.components().next()?;
+    Some(Path::new("/nix/store").join(entry).display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_path() {
+        let binary = Path::new("/nix/store/abc123-python3-3.12.8/bin/python3.12");
+        assert_eq!(store_path(binary).unwrap(), "/nix/store/abc123-python3-3.12.8");
+        assert_eq!(store_path(Path::new("/usr/bin/python3")), None);
+    }
+
+    #[test]
+    fn test_config_digest_ignores_secrets() {
+        let args = crate::config::Args::parse(Vec::<String>::new()).unwrap();
+        let config = Config::load(&args).unwrap();
+        let digest = config_digest(&config).unwrap();
+        assert_eq!(digest.len(), 64);
+
+        let mut with_secret = config.clone();
+        with_secret.jwt_secret = Some("hunter2".into());
+        let mut other_secret = config.clone();
+        other_secret.jwt_secret = Some("correct horse".into());
+        assert_eq!(config_digest(&with_secret).unwrap(), config_digest(&other_secret).unwrap());
+
+        let mut changed = config;
+        changed.port += 1;
+        assert_ne!(config_digest(&changed).unwrap(), digest);
+    }
+
+    #[tokio::test]
+    async fn test_missing_tool() {
+        let tool = tool("nothing", &["no-such-program-for-manifest"], "--version").await;
+        assert!(tool.version.is_none() && tool.path.is_none());
+        assert!(tool.error.unwrap().contains("not found"));
+    }
+}
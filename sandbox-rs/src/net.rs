@@ -0,0 +1,282 @@
+use axum::body::Bytes;
+use futures::Stream;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, LOCATION};
+use reqwest::{Method, StatusCode};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use tokio::io::AsyncWriteExt;
+use url::Url;
+
+use crate::browser::UrlPolicy;
+use crate::config::Config;
+use crate::error::{AppError, Result};
+
+/// A request to make on an agent's behalf, with placeholders already expanded
+pub struct FetchRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body: Option<Vec<u8>>,
+    pub max_redirects: usize,
+    pub max_size: u64,
+}
+
+/// The response after any redirects, before its body is read
+pub struct Fetched {
+    pub response: reqwest::Response,
+    /// URLs redirected through, in order
+    pub redirects: Vec<String>,
+    pub max_size: u64,
+}
+
+/// Outbound HTTP for `/net/fetch`, held to the egress policy on every hop
+pub struct Egress {
+    client: reqwest::Client,
+    policy: UrlPolicy,
+    max_size: u64,
+}
+
+impl Egress {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            client: Self::builder().build().expect("TLS backend is available"),
+            policy: UrlPolicy::new(&config.net_allow, &config.net_deny).expect("validated by Config::load"),
+            max_size: config.net_max_size,
+        }
+    }
+
+    fn builder() -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            // Followed by hand, so each hop is checked against the policy
+            .redirect(reqwest::redirect::Policy::none())
+            .user_agent(concat!("sandbox-api/", env!("CARGO_PKG_VERSION")))
+    }
+
+    /// Send `req`, following up to `max_redirects` redirects the policy allows
+    pub async fn fetch(&self, req: FetchRequest) -> Result<Fetched> {
+        let mut method = Method::from_bytes(req.method.to_uppercase().as_bytes())
+            .map_err(|_| AppError::BadRequest(format!("Invalid method '{}'", req.method)))?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in &req.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| AppError::BadRequest(format!("Invalid header name '{}'", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| AppError::BadRequest(format!("Invalid value for header '{}'", name)))?;
+            headers.insert(name, value);
+        }
+        let mut url = Url::parse(&req.url).map_err(|e| AppError::BadRequest(format!("Invalid URL: {}", e)))?;
+        let mut body = req.body;
+        let mut redirects = Vec::new();
+
+        loop {
+            let client = self.client_for(&url, self.check(&url).await?)?;
+            let mut request = client.request(method.clone(), url.clone()).headers(headers.clone());
+            if let Some(body) = &body {
+                request = request.body(body.clone());
+            }
+            let response = request.send().await.map_err(|e| {
+                AppError::Internal(format!("Fetching {} failed: {}", url, e)).with_code("FETCH_FAILED")
+            })?;
+
+            let location = response.headers().get(LOCATION).and_then(|l| l.to_str().ok());
+            let next = match location {
+                Some(location) if response.status().is_redirection() && req.max_redirects > 0 => {
+                    url.join(location).ok()
+                }
+                _ => None,
+            };
+            let Some(next) = next else {
+                return Ok(Fetched { response, redirects, max_size: self.limit(req.max_size) });
+            };
+            if redirects.len() == req.max_redirects {
+                return Err(AppError::BadRequest(format!("More than {} redirects", req.max_redirects))
+                    .with_code("TOO_MANY_REDIRECTS"));
+            }
+            // As browsers do: 303, and 301/302 after a POST, continue as a GET without the body
+            let status = response.status();
+            if status == StatusCode::SEE_OTHER
+                || (method == Method::POST && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND))
+            {
+                method = Method::GET;
+                body = None;
+            }
+            // Credentials are not handed to another host
+            if next.host_str() != url.host_str() {
+                headers.remove(reqwest::header::AUTHORIZATION);
+                headers.remove(reqwest::header::COOKIE);
+            }
+            redirects.push(next.to_string());
+            url = next;
+        }
+    }
+
+    /// Refuse URLs that are not http(s) or that the policy blocks; an empty allow list blocks all.
+    /// Returns the addresses the host was checked at, if network rules needed it resolved
+    async fn check(&self, url: &Url) -> Result<Vec<IpAddr>> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::BadRequest(format!("Only http and https URLs can be fetched, not {}", url.scheme())));
+        }
+        let blocked = |reason: String| {
+            tracing::warn!(%url, "Blocked fetch: {}", reason);
+            AppError::Forbidden(format!("URL blocked by egress policy: {}", reason))
+                .with_code("EGRESS_BLOCKED")
+                .with_details(json!({ "url": url.as_str() }))
+        };
+        if !self.policy.has_allow_rules() {
+            return Err(blocked("net_allow is empty".into()));
+        }
+        self.policy.vet(url.as_str()).await.map_err(blocked)
+    }
+
+    /// A client that connects only to the checked `addrs`, so the host cannot resolve
+    /// somewhere else between the check and the connection
+    fn client_for(&self, url: &Url, addrs: Vec<IpAddr>) -> Result<reqwest::Client> {
+        let (Some(host), false) = (url.host_str(), addrs.is_empty()) else {
+            return Ok(self.client.clone());
+        };
+        let port = url.port_or_known_default().unwrap_or(80);
+        let addrs: Vec<_> = addrs.into_iter().map(|addr| SocketAddr::new(addr, port)).collect();
+        Self::builder()
+            .resolve_to_addrs(host, &addrs)
+            .build()
+            .map_err(|e| AppError::Internal(format!("Building HTTP client failed: {}", e)))
+    }
+
+    /// The requested size limit capped by `net_max_size`, where 0 means none
+    fn limit(&self, requested: u64) -> u64 {
+        match (requested, self.max_size) {
+            (0, max) => max,
+            (requested, 0) => requested,
+            (requested, max) => requested.min(max),
+        }
+    }
+}
+
+impl Fetched {
+    pub fn headers(&self) -> BTreeMap<String, String> {
+        let mut headers = BTreeMap::<String, String>::new();
+        for (name, value) in self.response.headers() {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            headers
+                .entry(name.to_string())
+                .and_modify(|joined| {
+                    joined.push_str(", ");
+                    joined.push_str(&value);
+                })
+                .or_insert_with(|| value.into_owned());
+        }
+        headers
+    }
+
+    /// Refuse a body that has announced it is over the limit
+    fn check_length(&self) -> Result<()> {
+        match self.response.content_length() {
+            Some(length) if self.max_size > 0 && length > self.max_size => Err(too_large(self.max_size)),
+            _ => Ok(()),
+        }
+    }
+
+    /// The whole body, refused once it passes `max_size`
+    pub async fn bytes(mut self) -> Result<Vec<u8>> {
+        self.check_length()?;
+        let mut body = Vec::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            body.extend_from_slice(&chunk);
+            if self.max_size > 0 && body.len() as u64 > self.max_size {
+                return Err(too_large(self.max_size));
+            }
+        }
+        Ok(body)
+    }
+
+    /// Write the body to `path`, removing the partial file if it passes `max_size`
+    pub async fn save(mut self, path: &Path) -> Result<u64> {
+        self.check_length()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut size = 0u64;
+        while let Some(chunk) = self.next_chunk().await? {
+            size += chunk.len() as u64;
+            if self.max_size > 0 && size > self.max_size {
+                drop(file);
+                tokio::fs::remove_file(path).await.ok();
+                return Err(too_large(self.max_size));
+            }
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(size)
+    }
+
+    /// The body as it arrives, ending early at `max_size`
+    pub fn stream(self) -> Result<impl Stream<Item = std::result::Result<Bytes, std::io::Error>>> {
+        self.check_length()?;
+        Ok(async_stream::stream! {
+            let (mut fetched, mut sent) = (self, 0u64);
+            loop {
+                match fetched.next_chunk().await {
+                    Ok(Some(chunk)) => {
+                        sent += chunk.len() as u64;
+                        if fetched.max_size > 0 && sent > fetched.max_size {
+                            yield Err(std::io::Error::other(format!("body exceeds {} bytes", fetched.max_size)));
+                            break;
+                        }
+                        yield Ok(chunk);
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        yield Err(std::io::Error::other(e.to_string()));
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    async fn next_chunk(&mut self) -> Result<Option<Bytes>> {
+        self.response
+            .chunk()
+            .await
+            .map_err(|e| AppError::Internal(format!("Reading the response failed: {}", e)).with_code("FETCH_FAILED"))
+    }
+}
+
+fn too_large(limit: u64) -> AppError {
+    AppError::BadRequest(format!("Response body exceeds the limit of {} bytes", limit))
+        .with_code("RESPONSE_TOO_LARGE")
+        .with_details(json!({ "limit": limit }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn egress(allow: &[&str], max_size: u64) -> Egress {
+        let allow: Vec<String> = allow.iter().map(|rule| rule.to_string()).collect();
+        Egress { client: reqwest::Client::new(), policy: UrlPolicy::new(&allow, &[]).unwrap(), max_size }
+    }
+
+    #[tokio::test]
+    async fn test_check_needs_allow_list_and_http() {
+        let url = |url: &str| Url::parse(url).unwrap();
+        assert!(egress(&[], 0).check(&url("https://example.com/")).await.is_err());
+
+        let egress = egress(&["example.com"], 0);
+        assert!(egress.check(&url("https://api.example.com/v1")).await.is_ok());
+        let err = egress.check(&url("https://example.org/")).await.unwrap_err();
+        assert_eq!(err.status(), axum::http::StatusCode::FORBIDDEN);
+        assert!(matches!(egress.check(&url("ftp://example.com/")).await, Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_limit_caps_requested_size() {
+        assert_eq!(egress(&[], 1000).limit(0), 1000);
+        assert_eq!(egress(&[], 1000).limit(10), 10);
+        assert_eq!(egress(&[], 1000).limit(5000), 1000);
+        assert_eq!(egress(&[], 0).limit(5000), 5000);
+    }
+}
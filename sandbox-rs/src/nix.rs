@@ -0,0 +1,230 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::error::{AppError, Result};
+
+/// Binary cache asked for closure sizes, so nothing is downloaded before the size check
+const SIZE_STORE: &str = "https://cache.nixos.org";
+
+/// `nix profile` rewrites the whole manifest, so concurrent installs could lose one another
+static PROFILE: Mutex<()> = Mutex::const_new(());
+
+/// A package in the sandbox's Nix profile
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct InstalledPackage {
+    pub name: String,
+    /// Attribute path within the flake, e.g. `legacyPackages.x86_64-linux.ripgrep`
+    pub attr_path: Option<String>,
+    pub store_paths: Vec<String>,
+}
+
+impl InstalledPackage {
+    fn is(&self, name: &str) -> bool {
+        self.name == name
+            || self.attr_path.as_deref().is_some_and(|path| path.ends_with(&format!(".{}", name)))
+    }
+}
+
+/// What an install did
+#[derive(Debug)]
+pub struct Installed {
+    /// The requested packages as they now are in the profile
+    pub packages: Vec<InstalledPackage>,
+    /// Requested packages that were not installed before
+    pub added: Vec<String>,
+    /// Bytes of store paths the added packages need, including ones already present
+    pub closure_size: u64,
+}
+
+/// Refuse names that are not plain attribute paths or that `packages_allow` does not list
+pub fn check_allowed(allow: &[String], name: &str) -> Result<()> {
+    let plain = !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "._+-".contains(c));
+    if !plain {
+        return Err(AppError::BadRequest(format!("Invalid package name '{}'", name)));
+    }
+    if !allow.iter().any(|allowed| allowed == "*" || allowed == name) {
+        return Err(AppError::Forbidden(format!("Package '{}' is not in packages_allow", name))
+            .with_code("PACKAGE_NOT_ALLOWED")
+            .with_details(json!({ "package": name })));
+    }
+    Ok(())
+}
+
+/// Install `names` from `packages_flake` into the profile, skipping ones already there
+pub async fn install(config: &Config, names: &[String], timeout: Duration) -> Result<Installed> {
+    for name in names {
+        check_allowed(&config.packages_allow, name)?;
+    }
+    let _profile = PROFILE.lock().await;
+    let present = list(timeout).await?;
+    let mut added: Vec<String> = Vec::new();
+    for name in names {
+        if !added.contains(name) && !present.iter().any(|package| package.is(name)) {
+            added.push(name.clone());
+        }
+    }
+
+    let mut closure_size = 0;
+    if !added.is_empty() {
+        let installables: Vec<String> =
+            added.iter().map(|name| format!("{}#{}", config.packages_flake, name)).collect();
+        closure_size = closure_size_of(&installables, timeout).await?;
+        if config.packages_max_size > 0 && closure_size > config.packages_max_size {
+            return Err(AppError::BadRequest(format!(
+                "Installing {} needs {} bytes, over the limit of {}",
+                added.join(", "),
+                closure_size,
+                config.packages_max_size
+            ))
+            .with_code("PACKAGE_TOO_LARGE")
+            .with_details(json!({ "size": closure_size, "limit": config.packages_max_size })));
+        }
+        let mut args = vec!["profile", "install"];
+        args.extend(installables.iter().map(String::as_str));
+        nix(&args, timeout).await?;
+        tracing::info!("Installed packages: {}", added.join(", "));
+    }
+
+    let profile = list(timeout).await?;
+    let packages = profile.into_iter().filter(|package| names.iter().any(|name| package.is(name))).collect();
+    Ok(Installed { packages, added, closure_size })
+}
+
+/// Packages in the profile
+pub async fn list(timeout: Duration) -> Result<Vec<InstalledPackage>> {
+    let output = nix(&["profile", "list", "--json"], timeout).await?;
+    let profile: Value = serde_json::from_slice(&output)
+        .map_err(|e| AppError::Internal(format!("Unreadable nix profile list output: {}", e)))?;
+    Ok(parse_profile(&profile))
+}
+
+/// Every store path the `paths` depend on, themselves included
+pub async fn closure(paths: &[String], timeout: Duration) -> Result<Vec<String>> {
+    let mut args = vec!["path-info", "--recursive"];
+    args.extend(paths.iter().map(String::as_str));
+    let output = nix(&args, timeout).await?;
+    Ok(String::from_utf8_lossy(&output).lines().map(String::from).collect())
+}
+
+/// Bytes in the union of the closures of `installables`, according to the binary cache
+async fn closure_size_of(installables: &[String], timeout: Duration) -> Result<u64> {
+    let mut args = vec!["path-info", "--json", "--recursive", "--store", SIZE_STORE];
+    args.extend(installables.iter().map(String::as_str));
+    let output = nix(&args, timeout).await.map_err(|e| match e {
+        AppError::Internal(msg) if msg.contains("does not provide attribute") => {
+            AppError::NotFound(msg).with_code("PACKAGE_NOT_FOUND")
+        }
+        AppError::Internal(msg) => {
+            AppError::BadRequest(format!("Size unknown, not in {}: {}", SIZE_STORE, msg))
+                .with_code("PACKAGE_SIZE_UNKNOWN")
+        }
+        e => e,
+    })?;
+    let info: Value = serde_json::from_slice(&output)
+        .map_err(|e| AppError::Internal(format!("Unreadable nix path-info output: {}", e)))?;
+    Ok(nar_sizes(&info).values().sum())
+}
+
+/// Run `nix` with flakes enabled, returning its stdout
+async fn nix(args: &[&str], timeout: Duration) -> Result<Vec<u8>> {
+    let output = Command::new("nix")
+        .args(["--extra-experimental-features", "nix-command flakes"])
+        .args(args)
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(timeout, output)
+        .await
+        .map_err(|_| AppError::exec_timeout("nix timed out", timeout.as_secs()))?
+        .map_err(|e| AppError::Internal(format!("Could not run nix: {}", e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Internal(format!("nix {} failed: {}", args[..2].join(" "), stderr.trim())));
+    }
+    Ok(output.stdout)
+}
+
+/// Profile elements from `nix profile list --json`: an array before Nix 2.20, keyed by name since
+fn parse_profile(profile: &Value) -> Vec<InstalledPackage> {
+    let element = |name: Option<&str>, element: &Value| {
+        let attr_path = element["attrPath"].as_str().map(String::from);
+        let name = name
+            .or_else(|| attr_path.as_deref().and_then(|path| path.rsplit('.').next()))
+            .unwrap_or_default()
+            .to_string();
+        let store_paths = element["storePaths"]
+            .as_array()
+            .map(|paths| paths.iter().filter_map(|p| p.as_str().map(String::from)).collect())
+            .unwrap_or_default();
+        InstalledPackage { name, attr_path, store_paths }
+    };
+    match &profile["elements"] {
+        Value::Object(elements) => elements.iter().map(|(name, e)| element(Some(name), e)).collect(),
+        Value::Array(elements) => elements.iter().map(|e| element(None, e)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// NAR size of each path in `nix path-info --json` output: an array before Nix 2.19, keyed by path since
+fn nar_sizes(info: &Value) -> BTreeMap<String, u64> {
+    let entries: Vec<(String, &Value)> = match info {
+        Value::Object(paths) => paths.iter().map(|(path, entry)| (path.clone(), entry)).collect(),
+        Value::Array(entries) => entries
+            .iter()
+            .filter_map(|entry| Some((entry["path"].as_str()?.to_string(), entry)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    entries
+        .into_iter()
+        .filter_map(|(path, entry)| Some((path, entry["narSize"].as_u64()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_profile_both_formats() {
+        let keyed = json!({"version": 3, "elements": {"ripgrep": {
+            "attrPath": "legacyPackages.x86_64-linux.ripgrep",
+            "storePaths": ["/nix/store/abc-ripgrep-14.1.0"],
+        }}});
+        let listed = json!({"version": 2, "elements": [{
+            "attrPath": "legacyPackages.x86_64-linux.ripgrep",
+            "storePaths": ["/nix/store/abc-ripgrep-14.1.0"],
+        }]});
+        let packages = parse_profile(&keyed);
+        assert_eq!(packages, parse_profile(&listed));
+        assert_eq!(packages[0].name, "ripgrep");
+        assert!(packages[0].is("ripgrep"));
+        assert!(!packages[0].is("grep"));
+    }
+
+    #[test]
+    fn test_nar_sizes_count_each_path_once() {
+        let keyed = json!({"/nix/store/a": {"narSize": 10}, "/nix/store/b": {"narSize": 5}});
+        let listed = json!([{"path": "/nix/store/a", "narSize": 10}, {"path": "/nix/store/b", "narSize": 5}]);
+        assert_eq!(nar_sizes(&keyed).values().sum::<u64>(), 15);
+        assert_eq!(nar_sizes(&keyed), nar_sizes(&listed));
+    }
+
+    #[test]
+    fn test_check_allowed() {
+        let allow = vec!["ripgrep".to_string()];
+        assert!(check_allowed(&allow, "ripgrep").is_ok());
+        assert_eq!(check_allowed(&allow, "jq").unwrap_err().status(), axum::http::StatusCode::FORBIDDEN);
+        assert!(matches!(check_allowed(&allow, "nixpkgs#jq"), Err(AppError::BadRequest(_))));
+        assert!(matches!(check_allowed(&allow, "--impure"), Err(AppError::BadRequest(_))));
+        assert!(check_allowed(&["*".to_string()], "python3Packages.requests").is_ok());
+        assert!(check_allowed(&[], "ripgrep").is_err());
+    }
+}
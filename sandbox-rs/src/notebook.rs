@@ -0,0 +1,366 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+
+/// Where notebooks are kept, relative to the workspace
+pub const NOTEBOOK_DIR: &str = "notebooks";
+
+/// Environment variable naming the directory a cell writes rich outputs to
+pub const OUTPUT_DIR_ENV: &str = "NOTEBOOK_OUTPUT_DIR";
+
+/// Serializes read-modify-write of notebook files; execution happens outside it
+static FILES: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CellType {
+    Code,
+    Markdown,
+}
+
+/// An output in nbformat's shape, so cells export to .ipynb as they are
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "output_type", rename_all = "snake_case")]
+pub enum Output {
+    /// Text the cell printed; `name` is `stdout` or `stderr`
+    Stream { name: String, text: String },
+    /// A rich output keyed by MIME type, e.g. `image/png` as base64
+    DisplayData {
+        #[schema(value_type = Object)]
+        data: BTreeMap<String, Value>,
+        #[schema(value_type = Object)]
+        metadata: Map<String, Value>,
+    },
+    /// The cell exited unsuccessfully
+    Error { ename: String, evalue: String, traceback: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Cell {
+    pub id: String,
+    pub cell_type: CellType,
+    pub source: String,
+    /// Language of a code cell when it differs from the notebook's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+    /// Which run of the notebook last executed the cell
+    #[serde(default)]
+    pub execution_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Notebook {
+    pub id: String,
+    pub title: String,
+    /// Language of code cells that do not name their own
+    pub language: String,
+    pub cells: Vec<Cell>,
+    /// Cell executions so far, numbering each run as Jupyter does
+    pub execution_count: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Cell {
+    pub fn new(cell_type: CellType, source: String, language: Option<String>) -> Self {
+        Self {
+            id: new_id(),
+            cell_type,
+            source,
+            language,
+            outputs: Vec::new(),
+            execution_count: None,
+            duration_ms: None,
+        }
+    }
+}
+
+impl Notebook {
+    pub fn new(title: String, language: String, cells: Vec<Cell>) -> Self {
+        let now = Utc::now();
+        Self { id: new_id(), title, language, cells, execution_count: 0, created_at: now, updated_at: now }
+    }
+
+    pub fn cell(&self, cell_id: &str) -> Result<&Cell> {
+        self.cells.iter().find(|cell| cell.id == cell_id).ok_or_else(|| cell_not_found(cell_id))
+    }
+
+    pub fn cell_mut(&mut self, cell_id: &str) -> Result<&mut Cell> {
+        self.cells.iter_mut().find(|cell| cell.id == cell_id).ok_or_else(|| cell_not_found(cell_id))
+    }
+
+    /// The language `cell` runs in
+    pub fn language_of<'a>(&'a self, cell: &'a Cell) -> &'a str {
+        cell.language.as_deref().unwrap_or(&self.language)
+    }
+
+    /// The notebook as an nbformat 4.5 document
+    pub fn to_ipynb(&self) -> Value {
+        let cells: Vec<Value> = self
+            .cells
+            .iter()
+            .map(|cell| {
+                let mut metadata = Map::new();
+                if let Some(language) = &cell.language {
+                    metadata.insert("language".into(), json!(language));
+                }
+                match cell.cell_type {
+                    CellType::Markdown => json!({
+                        "cell_type": "markdown",
+                        "id": cell.id,
+                        "metadata": metadata,
+                        "source": cell.source,
+                    }),
+                    CellType::Code => json!({
+                        "cell_type": "code",
+                        "id": cell.id,
+                        "metadata": metadata,
+                        "source": cell.source,
+                        "outputs": cell.outputs,
+                        "execution_count": cell.execution_count,
+                    }),
+                }
+            })
+            .collect();
+        let (kernel, display_name) = match self.language.as_str() {
+            "python" => ("python3", "Python 3".to_string()),
+            language => (language, language.to_string()),
+        };
+        json!({
+            "nbformat": 4,
+            "nbformat_minor": 5,
+            "metadata": {
+                "title": self.title,
+                "kernelspec": { "name": kernel, "display_name": display_name, "language": self.language },
+                "language_info": { "name": self.language },
+            },
+            "cells": cells,
+        })
+    }
+}
+
+/// Notebooks stored as JSON files under `notebooks/` in a workspace
+pub struct NotebookStore {
+    dir: PathBuf,
+}
+
+impl NotebookStore {
+    pub fn new(workspace: &str) -> Self {
+        Self { dir: Path::new(workspace).join(NOTEBOOK_DIR) }
+    }
+
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(not_found(id));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Notebook> {
+        let data = match tokio::fs::read(self.path(id)?).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(not_found(id)),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&data)
+            .map_err(|e| AppError::Internal(format!("Notebook '{}' is not valid: {}", id, e)))
+    }
+
+    pub async fn create(&self, notebook: &Notebook) -> Result<()> {
+        let _guard = FILES.lock().await;
+        self.save(notebook).await
+    }
+
+    /// Apply `change` to the stored notebook and save it, returning what `change` returns
+    pub async fn update<T>(&self, id: &str, change: impl FnOnce(&mut Notebook) -> Result<T>) -> Result<(Notebook, T)> {
+        let _guard = FILES.lock().await;
+        let mut notebook = self.get(id).await?;
+        let value = change(&mut notebook)?;
+        notebook.updated_at = Utc::now();
+        self.save(&notebook).await?;
+        Ok((notebook, value))
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        let _guard = FILES.lock().await;
+        match tokio::fs::remove_file(self.path(id)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(not_found(id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every notebook, most recently updated first; unreadable files are skipped
+    pub async fn list(&self) -> Result<Vec<Notebook>> {
+        let mut entries = match tokio::fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut notebooks = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            let Some(id) = name.to_str().and_then(|name| name.strip_suffix(".json")) else {
+                continue;
+            };
+            match self.get(id).await {
+                Ok(notebook) => notebooks.push(notebook),
+                Err(e) => tracing::warn!(id, "Skipping notebook: {}", e),
+            }
+        }
+        notebooks.sort_by_key(|notebook| std::cmp::Reverse(notebook.updated_at));
+        Ok(notebooks)
+    }
+
+    async fn save(&self, notebook: &Notebook) -> Result<()> {
+        let path = self.path(&notebook.id)?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let data = serde_json::to_vec_pretty(notebook).map_err(|e| AppError::Internal(e.to_string()))?;
+        // Written whole and renamed, so a reader never sees half a notebook
+        let tmp = path.with_extension("json.tmp");
+        tokio::fs::write(&tmp, data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+}
+
+/// The outputs of a finished code cell: its streams, what it wrote to the output directory, and its exit
+pub fn cell_outputs(stdout: String, stderr: String, exit_code: i32, output_dir: &Path) -> Vec<Output> {
+    let mut outputs = Vec::new();
+    if !stdout.is_empty() {
+        outputs.push(Output::Stream { name: "stdout".into(), text: stdout });
+    }
+    if !stderr.is_empty() {
+        outputs.push(Output::Stream { name: "stderr".into(), text: stderr });
+    }
+    outputs.extend(display_outputs(output_dir));
+    if exit_code != 0 {
+        outputs.push(Output::Error {
+            ename: "ExitCode".into(),
+            evalue: format!("Cell exited with code {}", exit_code),
+            traceback: Vec::new(),
+        });
+    }
+    outputs
+}
+
+/// A display output for each file in `dir` with a known type, in name order
+fn display_outputs(dir: &Path) -> Vec<Output> {
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|entry| Some(entry.ok()?.path())).filter(|path| path.is_file()).collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+            let mime = match extension.as_str() {
+                "png" => "image/png",
+                "jpg" | "jpeg" => "image/jpeg",
+                "gif" => "image/gif",
+                "svg" => "image/svg+xml",
+                "html" => "text/html",
+                "md" => "text/markdown",
+                "json" => "application/json",
+                "txt" => "text/plain",
+                _ => {
+                    tracing::debug!(path = %path.display(), "Ignoring notebook output of unknown type");
+                    return None;
+                }
+            };
+            let bytes = std::fs::read(&path).ok()?;
+            let value = match mime {
+                "image/png" | "image/jpeg" | "image/gif" => {
+                    json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+                }
+                "application/json" => serde_json::from_slice(&bytes).ok()?,
+                _ => json!(String::from_utf8_lossy(&bytes)),
+            };
+            Some(Output::DisplayData { data: BTreeMap::from([(mime.to_string(), value)]), metadata: Map::new() })
+        })
+        .collect()
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Notebook '{}' not found", id)).with_code("NOTEBOOK_NOT_FOUND")
+}
+
+fn cell_not_found(id: &str) -> AppError {
+    AppError::NotFound(format!("Cell '{}' not found", id)).with_code("CELL_NOT_FOUND")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_outputs_collect_streams_files_and_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("b.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        std::fs::write(dir.path().join("a.json"), r#"{"rows": 2}"#).unwrap();
+        std::fs::write(dir.path().join("c.bin"), [0]).unwrap();
+
+        let outputs = cell_outputs("hi\n".into(), String::new(), 1, dir.path());
+        let outputs: Vec<Value> = outputs.iter().map(|o| serde_json::to_value(o).unwrap()).collect();
+        assert_eq!(outputs.len(), 4);
+        assert_eq!(outputs[0], json!({ "output_type": "stream", "name": "stdout", "text": "hi\n" }));
+        assert_eq!(outputs[1]["data"]["application/json"], json!({ "rows": 2 }));
+        assert_eq!(outputs[2]["data"]["image/png"], json!("iVBORw=="));
+        assert_eq!(outputs[3]["output_type"], "error");
+    }
+
+    #[test]
+    fn test_to_ipynb() {
+        let mut code = Cell::new(CellType::Code, "print(1)".into(), None);
+        code.outputs = vec![Output::Stream { name: "stdout".into(), text: "1\n".into() }];
+        code.execution_count = Some(1);
+        let markdown = Cell::new(CellType::Markdown, "# Title".into(), None);
+        let notebook = Notebook::new("Demo".into(), "python".into(), vec![markdown, code]);
+
+        let ipynb = notebook.to_ipynb();
+        assert_eq!(ipynb["nbformat"], 4);
+        assert_eq!(ipynb["metadata"]["kernelspec"]["name"], "python3");
+        assert!(ipynb["cells"][0].get("outputs").is_none());
+        assert_eq!(ipynb["cells"][1]["execution_count"], 1);
+        assert_eq!(ipynb["cells"][1]["outputs"][0]["output_type"], "stream");
+    }
+
+    #[tokio::test]
+    async fn test_store_round_trip_and_ids() {
+        let workspace = tempfile::tempdir().unwrap();
+        let store = NotebookStore::new(workspace.path().to_str().unwrap());
+        let notebook = Notebook::new("Demo".into(), "python".into(), Vec::new());
+        store.create(&notebook).await.unwrap();
+
+        let (updated, ()) = store
+            .update(&notebook.id, |nb| {
+                nb.cells.push(Cell::new(CellType::Code, "1".into(), None));
+                Ok(())
+            })
+            .await
+            .unwrap();
+        assert_eq!(store.get(&notebook.id).await.unwrap().cells.len(), 1);
+        assert_eq!(store.list().await.unwrap()[0].id, updated.id);
+        assert!(store.get("../notebooks").await.is_err());
+
+        store.delete(&notebook.id).await.unwrap();
+        assert_eq!(store.get(&notebook.id).await.unwrap_err().status(), axum::http::StatusCode::NOT_FOUND);
+    }
+}
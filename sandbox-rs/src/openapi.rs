@@ -0,0 +1,218 @@
+use utoipa::openapi::{self, RefOr, Response};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::handlers;
+
+/// Body of every error response
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct ErrorResponse {
+    pub error: String,
+    /// Stable identifier of the failure, such as `SKILL_EXISTS` or `EXEC_TIMEOUT`
+    pub code: String,
+    /// Structured context for some codes, such as the `timeout` that was exceeded
+    pub details: Option<serde_json::Value>,
+    /// The request's `X-Request-Id`, for finding it in the server logs
+    pub request_id: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Sandbox API", description = "Shell, code, files, browser automation, and skills for AI agents"),
+    servers((url = "/v1", description = "API version 1")),
+    paths(
+        handlers::health_check,
+        handlers::ready_check,
+        handlers::sandbox_info,
+        handlers::version_info,
+        handlers::sandbox_usage,
+        handlers::sandbox_manifest,
+        handlers::exec_command,
+        handlers::stream_command,
+        handlers::execute_code,
+        handlers::create_notebook,
+        handlers::list_notebooks,
+        handlers::get_notebook,
+        handlers::delete_notebook,
+        handlers::add_cell,
+        handlers::update_cell,
+        handlers::delete_cell,
+        handlers::run_cell,
+        handlers::run_notebook,
+        handlers::export_notebook,
+        handlers::display_screenshot,
+        handlers::display_click,
+        handlers::display_type,
+        handlers::display_keys,
+        handlers::get_clipboard,
+        handlers::set_clipboard,
+        handlers::display_record_start,
+        handlers::display_record_stop,
+        handlers::net_fetch,
+        handlers::data_query,
+        handlers::convert_document,
+        handlers::install_packages,
+        handlers::list_packages,
+        handlers::read_file,
+        handlers::write_file,
+        handlers::list_files,
+        handlers::upload_file,
+        handlers::download_file,
+        handlers::list_skills,
+        handlers::search_skills,
+        handlers::get_skill,
+        handlers::create_skill,
+        handlers::update_skill,
+        handlers::delete_skill,
+        handlers::execute_script,
+        handlers::start_factory,
+        handlers::continue_factory,
+        handlers::check_trigger,
+        handlers::browser_goto,
+        handlers::browser_screenshot,
+        handlers::browser_evaluate,
+        handlers::browser_click,
+        handlers::browser_type,
+        handlers::browser_scroll,
+        handlers::browser_hover,
+        handlers::browser_focus,
+        handlers::browser_press,
+        handlers::browser_fill,
+        handlers::browser_select,
+        handlers::browser_query,
+        handlers::browser_upload,
+        handlers::browser_pdf,
+        handlers::browser_content,
+        handlers::browser_capture,
+        handlers::browser_har_start,
+        handlers::browser_har_stop,
+        handlers::browser_record_start,
+        handlers::browser_record_stop,
+        handlers::browser_download,
+        handlers::browser_downloads,
+        handlers::browser_pages,
+        handlers::browser_close_page,
+        handlers::browser_activate_page,
+        handlers::browser_page_console,
+        handlers::browser_status,
+        handlers::get_config,
+        handlers::reload_config,
+        handlers::admin_status,
+        handlers::start_drain,
+        handlers::stop_drain,
+        handlers::audit_log,
+        handlers::create_secret,
+        handlers::list_secrets,
+        handlers::get_secret,
+        handlers::update_secret,
+        handlers::delete_secret,
+        handlers::declare_service,
+        handlers::list_services,
+        handlers::get_service,
+        handlers::delete_service,
+        handlers::proxy_service,
+        handlers::create_session,
+        handlers::list_sessions,
+        handlers::get_session,
+        handlers::delete_session,
+        handlers::register_webhook,
+        handlers::list_webhooks,
+        handlers::delete_webhook,
+        handlers::test_webhook,
+        handlers::websocket,
+    ),
+    components(schemas(ErrorResponse, handlers::ClientMessage, handlers::ServerMessage)),
+    modifiers(&ErrorResponses)
+)]
+struct ApiDoc;
+
+#[cfg(feature = "tee")]
+#[derive(OpenApi)]
+#[openapi(paths(
+    handlers::tee::tee_info,
+    handlers::tee::generate_quote,
+    handlers::tee::attest_payload,
+    handlers::tee::derive_key,
+    handlers::tee::sign_data,
+    handlers::tee::list_keys,
+    handlers::tee::register_key,
+    handlers::tee::remove_key,
+    handlers::tee::verify_signature,
+    handlers::tee::encryption_key,
+    handlers::tee::encrypt,
+    handlers::tee::decrypt,
+    handlers::tee::emit_event,
+    handlers::tee::event_log,
+    handlers::tee::get_env,
+    handlers::tee::set_env,
+    handlers::tee::remove_env,
+    handlers::tee::seal_file,
+    handlers::tee::unseal_file,
+    handlers::tee::authenticate,
+    handlers::tee::auth_challenge,
+    handlers::tee::auth_prove,
+    handlers::tee::sign_skill_output,
+    handlers::tee::issue_token,
+    handlers::tee::jwks,
+    handlers::tee::openid_configuration,
+))]
+struct TeeApiDoc;
+
+/// Documents the `{"error": ..., "code": ...}` body as the default response of every operation
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let error = Response::builder()
+            .description("Error")
+            .content(
+                "application/json",
+                openapi::Content::new(Some(openapi::Ref::from_schema_name("ErrorResponse"))),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.post,
+                &mut item.put,
+                &mut item.delete,
+            ];
+            for operation in operations.into_iter().flatten() {
+                operation
+                    .responses
+                    .responses
+                    .entry("default".into())
+                    .or_insert_with(|| RefOr::T(error.clone()));
+            }
+        }
+    }
+}
+
+/// The OpenAPI document for every route this build serves
+pub fn spec() -> openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut doc = ApiDoc::openapi();
+    #[cfg(feature = "tee")]
+    {
+        let mut tee = TeeApiDoc::openapi();
+        ErrorResponses.modify(&mut tee);
+        doc.merge(tee);
+    }
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_operation_documents_errors() {
+        let doc = spec();
+        assert!(!doc.paths.paths.is_empty());
+        for (path, item) in &doc.paths.paths {
+            for operation in [&item.get, &item.post, &item.put, &item.delete].into_iter().flatten() {
+                assert!(operation.responses.responses.contains_key("default"), "{}", path);
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{bail, Context as _};
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use http_body::{Frame, SizeHint};
+use serde_json::json;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::{Instant, Sleep};
+
+use crate::error::AppError;
+use crate::ratelimit::hold_until_sent;
+use crate::state::AppState;
+
+/// Route timeouts used when `route_timeouts` is not set
+pub const DEFAULT_ROUTE_TIMEOUTS: &[&str] = &["/health=5", "/shell/stream=3600"];
+
+/// Seconds a shed client is asked to wait before retrying
+const SHED_RETRY_AFTER: u64 = 1;
+
+/// Time limits per path prefix, with a default for everything else. Zero means no limit.
+#[derive(Debug, Clone)]
+pub struct RouteTimeouts {
+    default: u64,
+    /// `(prefix, seconds)`, longest prefix first
+    routes: Vec<(String, u64)>,
+}
+
+impl RouteTimeouts {
+    /// Parse `PREFIX=SECONDS` entries, e.g. `/shell/stream=3600`
+    pub fn new(default: u64, entries: &[String]) -> anyhow::Result<Self> {
+        let mut routes = Vec::new();
+        for entry in entries {
+            let Some((prefix, secs)) = entry.split_once('=') else {
+                bail!("\"{}\" is not PREFIX=SECONDS", entry);
+            };
+            let prefix = prefix.trim();
+            if !prefix.starts_with('/') {
+                bail!("\"{}\": route prefix must start with /", entry);
+            }
+            let secs = secs
+                .trim()
+                .parse()
+                .with_context(|| format!("\"{}\": invalid number of seconds", entry))?;
+            routes.push((prefix.to_string(), secs));
+        }
+        routes.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        Ok(Self { default, routes })
+    }
+
+    /// The limit for `path`, or `None` when it may run forever
+    pub fn for_path(&self, path: &str) -> Option<Duration> {
+        let secs = self
+            .routes
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map_or(self.default, |(_, secs)| *secs);
+        (secs > 0).then(|| Duration::from_secs(secs))
+    }
+}
+
+/// Bound a request by its route's timeout.
+///
+/// A handler that has not produced response headers in time is dropped and
+/// answered with 408. A streaming body that is still running at the deadline is
+/// cut off, so a client sees the connection end rather than a clean finish.
+pub async fn timeout(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(limit) = state.route_timeouts.for_path(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let deadline = Instant::now() + limit;
+    match tokio::time::timeout_at(deadline, next.run(request)).await {
+        Ok(response) => cut_off_at(response, deadline),
+        Err(_) => AppError::Timeout(format!("Request did not complete within {}s", limit.as_secs()))
+            .with_code("REQUEST_TIMEOUT")
+            .with_details(json!({ "timeout": limit.as_secs() }))
+            .into_response(),
+    }
+}
+
+/// End the response body with an error once `deadline` passes
+fn cut_off_at(response: Response, deadline: Instant) -> Response {
+    response.map(|body| {
+        Body::new(Deadline {
+            body,
+            sleep: Box::pin(tokio::time::sleep_until(deadline)),
+            expired: false,
+        })
+    })
+}
+
+/// A body that fails once its deadline passes
+struct Deadline {
+    body: Body,
+    sleep: Pin<Box<Sleep>>,
+    expired: bool,
+}
+
+impl HttpBody for Deadline {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        if self.expired {
+            return Poll::Ready(None);
+        }
+        if let Poll::Ready(frame) = Pin::new(&mut self.body).poll_frame(cx) {
+            return Poll::Ready(frame);
+        }
+        if self.sleep.as_mut().poll(cx).is_ready() {
+            self.expired = true;
+            return Poll::Ready(Some(Err(axum::Error::new("request timeout reached"))));
+        }
+        Poll::Pending
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.expired || self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+/// Cap on requests being handled at once across all clients. Zero disables.
+pub struct LoadShedder {
+    permits: Option<Arc<Semaphore>>,
+    limit: usize,
+}
+
+impl LoadShedder {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            permits: (limit > 0).then(|| Arc::new(Semaphore::new(limit))),
+            limit,
+        }
+    }
+}
+
+/// Answer 503 with `Retry-After` when the server is already handling as many
+/// requests as it allows, instead of queueing work it cannot keep up with.
+/// Streaming responses hold their slot until the body ends. Health checks are
+/// exempt so an overloaded server is not also reported as dead.
+pub async fn shed(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(permits) = state.load_shedder.permits.clone() else {
+        return next.run(request).await;
+    };
+    if request.uri().path() == "/health" {
+        return next.run(request).await;
+    }
+
+    let Ok(permit) = permits.try_acquire_owned() else {
+        tracing::warn!(
+            "Shedding {} {}: {} requests in flight",
+            request.method(),
+            request.uri().path(),
+            state.load_shedder.limit
+        );
+        return AppError::ServiceUnavailable(
+            format!("Server is at its limit of {} concurrent requests", state.load_shedder.limit),
+            SHED_RETRY_AFTER,
+        )
+        .into_response();
+    };
+    hold_until_sent(next.run(request).await, permit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let timeouts = RouteTimeouts::new(60, &entries(&["/shell=120", "/shell/stream=0", "/health=5"])).unwrap();
+        assert_eq!(timeouts.for_path("/health"), Some(Duration::from_secs(5)));
+        assert_eq!(timeouts.for_path("/shell/exec"), Some(Duration::from_secs(120)));
+        assert_eq!(timeouts.for_path("/shell/stream"), None);
+        assert_eq!(timeouts.for_path("/file/read"), Some(Duration::from_secs(60)));
+        assert_eq!(RouteTimeouts::new(0, &[]).unwrap().for_path("/file/read"), None);
+    }
+
+    #[test]
+    fn test_invalid_route_timeouts() {
+        assert!(RouteTimeouts::new(60, &entries(&["/health"])).is_err());
+        assert!(RouteTimeouts::new(60, &entries(&["health=5"])).is_err());
+        assert!(RouteTimeouts::new(60, &entries(&["/health=soon"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_cut_off_at_deadline() {
+        use futures::StreamExt;
+
+        let chunks = futures::stream::iter(0..).then(|i| async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, std::io::Error>(format!("{}\n", i))
+        });
+        let response = Response::new(Body::from_stream(chunks));
+        let response = cut_off_at(response, Instant::now() + Duration::from_millis(100));
+        assert!(axum::body::to_bytes(response.into_body(), usize::MAX).await.is_err());
+    }
+}
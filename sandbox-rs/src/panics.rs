@@ -0,0 +1,89 @@
+use axum::{
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::any::Any;
+use std::backtrace::Backtrace;
+
+use crate::error::AppError;
+use crate::request_id::RequestId;
+
+tokio::task_local! {
+    /// ID of the request being handled, for the panic hook
+    static REQUEST_ID: String;
+}
+
+/// Log panics inside requests with their request ID and a backtrace. Panics
+/// elsewhere, such as at startup, are left to the default hook.
+pub fn install_hook() {
+    let default = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let Ok(request_id) = REQUEST_ID.try_with(Clone::clone) else {
+            return default(info);
+        };
+        let location = info.location().map(ToString::to_string).unwrap_or_default();
+        tracing::error!(
+            %request_id,
+            %location,
+            "Handler panicked: {}\n{}",
+            message(info.payload()),
+            Backtrace::force_capture()
+        );
+    }));
+}
+
+/// Make the request's ID visible to the panic hook while it is handled
+pub async fn track(request: Request, next: Next) -> Response {
+    let Some(RequestId(id)) = request.extensions().get::<RequestId>().cloned() else {
+        return next.run(request).await;
+    };
+    REQUEST_ID.scope(id, next.run(request)).await
+}
+
+/// The 500 answered for a request whose handler panicked, for `CatchPanicLayer`
+pub fn respond(payload: Box<dyn Any + Send + 'static>) -> Response {
+    AppError::Internal(format!("Handler panicked: {}", message(payload.as_ref())))
+        .with_code("PANIC")
+        .into_response()
+}
+
+fn message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+    use tower_http::catch_panic::CatchPanicLayer;
+
+    async fn boom() -> &'static str {
+        panic!("index out of range")
+    }
+
+    #[tokio::test]
+    async fn test_panic_becomes_structured_500() {
+        let app = Router::new()
+            .route("/boom", get(boom))
+            .layer(CatchPanicLayer::custom(respond))
+            .layer(middleware::from_fn(track))
+            .layer(middleware::from_fn(crate::request_id::assign));
+
+        let request = Request::get("/boom").header("x-request-id", "step-9").body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["code"], "PANIC");
+        assert_eq!(body["error"], "Handler panicked: index out of range");
+        assert_eq!(body["request_id"], "step-9");
+    }
+}
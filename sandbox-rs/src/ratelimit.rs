@@ -0,0 +1,352 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{ConnectInfo, Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use http_body::{Frame, SizeHint};
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Idle clients are forgotten once this many are tracked
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Token bucket and in-flight count for one client
+struct ClientQuota {
+    tokens: f64,
+    updated: Instant,
+    in_flight: usize,
+}
+
+/// Per-client request rate and concurrent execution quotas.
+///
+/// The request rate is a token bucket holding a minute's worth of requests,
+/// so short bursts are allowed but the sustained rate is capped. Zero
+/// disables either quota. Both can be changed while running with `set_limits`.
+pub struct RateLimiter {
+    requests_per_minute: AtomicU32,
+    max_concurrent: AtomicUsize,
+    clients: DashMap<String, ClientQuota>,
+}
+
+/// Holds one of a client's concurrent execution slots until dropped
+pub struct Permit {
+    limiter: Arc<RateLimiter>,
+    client: String,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(mut quota) = self.limiter.clients.get_mut(&self.client) {
+            quota.in_flight -= 1;
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, max_concurrent: usize) -> Self {
+        Self {
+            requests_per_minute: AtomicU32::new(requests_per_minute),
+            max_concurrent: AtomicUsize::new(max_concurrent),
+            clients: DashMap::new(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.requests_per_minute() > 0 || self.max_concurrent() > 0
+    }
+
+    /// Change the quotas; buckets refill toward the new rate and running executions keep their slots
+    pub fn set_limits(&self, requests_per_minute: u32, max_concurrent: usize) {
+        self.requests_per_minute.store(requests_per_minute, Ordering::Relaxed);
+        self.max_concurrent.store(max_concurrent, Ordering::Relaxed);
+    }
+
+    fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute.load(Ordering::Relaxed)
+    }
+
+    fn max_concurrent(&self) -> usize {
+        self.max_concurrent.load(Ordering::Relaxed)
+    }
+
+    /// Charge one request to `client`, taking an execution slot if `execution` is set
+    pub fn check(self: &Arc<Self>, client: &str, execution: bool) -> Result<Option<Permit>, AppError> {
+        let now = Instant::now();
+        if self.clients.len() >= MAX_TRACKED_CLIENTS && !self.clients.contains_key(client) {
+            self.prune(now);
+        }
+
+        let requests_per_minute = self.requests_per_minute();
+        let max_concurrent = self.max_concurrent();
+        let capacity = requests_per_minute as f64;
+        let mut quota = self.clients.entry(client.to_string()).or_insert_with(|| ClientQuota {
+            tokens: capacity,
+            updated: now,
+            in_flight: 0,
+        });
+
+        if requests_per_minute > 0 {
+            let rate = capacity / 60.0;
+            quota.tokens = (quota.tokens + now.duration_since(quota.updated).as_secs_f64() * rate).min(capacity);
+            quota.updated = now;
+            if quota.tokens < 1.0 {
+                let retry_after = ((1.0 - quota.tokens) / rate).ceil() as u64;
+                return Err(AppError::TooManyRequests(
+                    format!("Rate limit of {} requests per minute exceeded", requests_per_minute),
+                    retry_after.max(1),
+                ));
+            }
+        }
+
+        let limit_concurrency = execution && max_concurrent > 0;
+        if limit_concurrency && quota.in_flight >= max_concurrent {
+            return Err(AppError::TooManyRequests(
+                format!("Limit of {} concurrent executions reached", max_concurrent),
+                1,
+            ));
+        }
+
+        if requests_per_minute > 0 {
+            quota.tokens -= 1.0;
+        }
+        if !limit_concurrency {
+            return Ok(None);
+        }
+        quota.in_flight += 1;
+        Ok(Some(Permit {
+            limiter: self.clone(),
+            client: client.to_string(),
+        }))
+    }
+
+    /// Drop clients with a full bucket and nothing running, which are indistinguishable from
+    /// new ones. If that is not enough, drop the longest idle of the rest until a tenth of the
+    /// room is free, so a flood of new clients does not sort the map on every request.
+    fn prune(&self, now: Instant) {
+        let capacity = self.requests_per_minute() as f64;
+        let rate = capacity / 60.0;
+        self.clients.retain(|_, quota| {
+            let tokens = quota.tokens + now.duration_since(quota.updated).as_secs_f64() * rate;
+            quota.in_flight > 0 || tokens < capacity
+        });
+
+        if self.clients.len() < MAX_TRACKED_CLIENTS {
+            return;
+        }
+        let excess = self.clients.len() - MAX_TRACKED_CLIENTS * 9 / 10;
+        // Running executions keep their entry, so their slots stay counted
+        let mut idle: Vec<_> = self
+            .clients
+            .iter()
+            .filter(|quota| quota.in_flight == 0)
+            .map(|quota| (quota.updated, quota.key().clone()))
+            .collect();
+        idle.sort_unstable();
+        for (_, client) in idle.into_iter().take(excess) {
+            self.clients.remove(&client);
+        }
+    }
+}
+
+/// Endpoints that run commands, code, or a browser and count against the concurrency quota
+pub fn is_execution(method: &Method, path: &str) -> bool {
+    if *method != Method::POST {
+        return false;
+    }
+    path.starts_with("/shell/")
+        || path.starts_with("/code/")
+        || (path.starts_with("/notebook/") && path.ends_with("/run"))
+        || path.starts_with("/browser/")
+        || (path.starts_with("/skills/") && path.contains("/scripts/"))
+        || path == "/env/install"
+        || path == "/net/fetch"
+        || path == "/data/query"
+        || path == "/convert"
+        || path.starts_with("/sandbox.v1.Shell/")
+        || path.starts_with("/sandbox.v1.Code/")
+        || path == "/sandbox.v1.Skills/RunScript"
+}
+
+/// The bearer JWT when it verifies, otherwise the peer's IP address, or its /64 for IPv6,
+/// which one host usually holds whole. This runs before authentication, so unverified
+/// credentials are ignored: a client could send a fresh one with every request.
+fn client_id(state: &AppState, request: &Request) -> String {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if let (Some(jwt), Some(token)) = (&state.jwt, token) {
+        if jwt.verify(token).is_ok() {
+            return format!("token:{}", token);
+        }
+    }
+    match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => match addr.ip().to_canonical() {
+            IpAddr::V4(ip) => format!("ip:{}", ip),
+            IpAddr::V6(ip) => {
+                let [a, b, c, d, ..] = ip.segments();
+                format!("ip:{:x}:{:x}:{:x}:{:x}::/64", a, b, c, d)
+            }
+        },
+        None => "unknown".into(),
+    }
+}
+
+/// Enforce the configured quotas, answering 429 with `Retry-After` when one is exceeded.
+/// Health and readiness checks are exempt so probes keep working while a client is throttled.
+pub async fn enforce(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if !state.rate_limiter.enabled() || matches!(request.uri().path(), "/health" | "/ready") {
+        return next.run(request).await;
+    }
+
+    let execution = is_execution(request.method(), request.uri().path());
+    let permit = match state.rate_limiter.check(&client_id(&state, &request), execution) {
+        Ok(permit) => permit,
+        Err(e) => return e.into_response(),
+    };
+
+    let response = next.run(request).await;
+    match permit {
+        Some(permit) => hold_until_sent(response, permit),
+        None => response,
+    }
+}
+
+/// Streaming responses keep executing after the handler returns; hold `guard`
+/// (a quota slot) until the body ends
+pub fn hold_until_sent<T: Send + Unpin + 'static>(response: Response, guard: T) -> Response {
+    response.map(|body| Body::new(Guarded { body, _guard: guard }))
+}
+
+/// A body that keeps `_guard` alive until it is dropped. Trailers, which carry
+/// the status of gRPC calls, pass through.
+struct Guarded<T> {
+    body: Body,
+    _guard: T,
+}
+
+impl<T: Unpin> HttpBody for Guarded<T> {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+        Pin::new(&mut self.body).poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limit_retry_after() {
+        let limiter = Arc::new(RateLimiter::new(2, 0));
+        assert!(limiter.check("a", false).is_ok());
+        assert!(limiter.check("a", false).is_ok());
+        match limiter.check("a", false) {
+            Err(AppError::TooManyRequests(_, retry_after)) => assert!((1..=30).contains(&retry_after)),
+            _ => panic!("expected rate limit"),
+        }
+        // Other clients have their own bucket
+        assert!(limiter.check("b", false).is_ok());
+    }
+
+    #[test]
+    fn test_set_limits_applies_to_existing_clients() {
+        let limiter = Arc::new(RateLimiter::new(1, 0));
+        assert!(limiter.check("a", false).is_ok());
+        assert!(limiter.check("a", false).is_err());
+        limiter.set_limits(0, 0);
+        assert!(!limiter.enabled());
+        assert!(limiter.check("a", false).is_ok());
+    }
+
+    #[test]
+    fn test_concurrency_permits_are_released() {
+        let limiter = Arc::new(RateLimiter::new(0, 1));
+        let permit = limiter.check("a", true).unwrap();
+        assert!(permit.is_some());
+        assert!(limiter.check("a", true).is_err());
+        // Non-execution requests are not limited by concurrency
+        assert!(limiter.check("a", false).unwrap().is_none());
+        drop(permit);
+        assert!(limiter.check("a", true).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_permit_held_until_body_consumed() {
+        let limiter = Arc::new(RateLimiter::new(0, 1));
+        let permit = limiter.check("a", true).unwrap().unwrap();
+        let response = hold_until_sent("output".into_response(), permit);
+        assert!(limiter.check("a", true).is_err());
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"output");
+        assert!(limiter.check("a", true).is_ok());
+    }
+
+    #[test]
+    fn test_tracked_clients_are_capped() {
+        let limiter = Arc::new(RateLimiter::new(10, 1));
+        let running = limiter.check("running", true).unwrap();
+        for client in 0..MAX_TRACKED_CLIENTS + 10 {
+            // Each leaves a partly used bucket, which pruning alone would keep
+            assert!(limiter.check(&client.to_string(), false).is_ok());
+        }
+        assert!(limiter.clients.len() <= MAX_TRACKED_CLIENTS);
+        // The running execution kept its slot
+        assert!(limiter.check("running", true).is_err());
+        drop(running);
+    }
+
+    #[test]
+    fn test_client_id_ignores_unverified_credentials() {
+        let args = crate::config::Args::parse(["--audit-log=".to_string()]).unwrap();
+        let state = AppState::new(crate::config::Config::load(&args).unwrap(), args);
+        let request = |ip: &str, key: &str| {
+            let mut request = Request::builder()
+                .header("x-api-key", key)
+                .header(header::AUTHORIZATION, format!("Bearer {}", key))
+                .body(Body::empty())
+                .unwrap();
+            request.extensions_mut().insert(ConnectInfo(SocketAddr::new(ip.parse().unwrap(), 443)));
+            request
+        };
+        assert_eq!(client_id(&state, &request("203.0.113.7", "a")), "ip:203.0.113.7");
+        assert_eq!(client_id(&state, &request("203.0.113.7", "b")), "ip:203.0.113.7");
+        assert_eq!(client_id(&state, &request("::ffff:203.0.113.7", "c")), "ip:203.0.113.7");
+        assert_eq!(client_id(&state, &request("2001:db8:1:2:3::9", "d")), "ip:2001:db8:1:2::/64");
+    }
+
+    #[test]
+    fn test_is_execution() {
+        assert!(is_execution(&Method::POST, "/shell/exec"));
+        assert!(is_execution(&Method::POST, "/browser/screenshot"));
+        assert!(is_execution(&Method::POST, "/skills/demo/scripts/run.sh"));
+        assert!(!is_execution(&Method::POST, "/skills"));
+        assert!(is_execution(&Method::POST, "/notebook/abc/cells/def/run"));
+        assert!(!is_execution(&Method::POST, "/notebook/abc/cells"));
+        assert!(!is_execution(&Method::GET, "/browser/status"));
+        assert!(is_execution(&Method::POST, "/sandbox.v1.Shell/Stream"));
+        assert!(!is_execution(&Method::POST, "/sandbox.v1.Files/Read"));
+    }
+}
@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, SystemTime};
+use utoipa::ToSchema;
+
+use crate::browser::UrlPolicy;
+use crate::config::{Args, Config};
+use crate::state::{self, AppState};
+
+/// How often the config file is checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Settings a reload applies to the running server. Changes to any other
+/// setting are reported and take effect on the next restart.
+pub const TUNABLE: &[&str] = &[
+    "browser_url_allow",
+    "browser_url_deny",
+    "browser_dialog_action",
+    "browser_max_pages",
+    "browser_page_idle_timeout",
+    "browser_page_max_lifetime",
+    "browser_max_memory_mb",
+    "factory_triggers",
+    "rate_limit_rpm",
+    "rate_limit_concurrent",
+];
+
+/// The configuration in effect, and the arguments it is reloaded from
+pub struct LiveConfig {
+    args: Args,
+    current: RwLock<Arc<Config>>,
+    // Serializes reloads from the endpoint and the file watcher
+    reloading: tokio::sync::Mutex<()>,
+}
+
+impl LiveConfig {
+    pub fn new(args: Args, config: Config) -> Self {
+        Self {
+            args,
+            current: RwLock::new(Arc::new(config)),
+            reloading: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn current(&self) -> Arc<Config> {
+        self.current.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReloadReport {
+    /// Tunable settings that changed and are now in effect
+    pub applied: Vec<String>,
+    /// Settings that changed but only take effect after a restart
+    pub restart_required: Vec<String>,
+    /// Whether Chromium was restarted to start enforcing a URL policy
+    pub browser_restarted: bool,
+}
+
+/// Re-read the config file, environment, and flags, and apply changed tunable
+/// settings. An invalid configuration is rejected whole, keeping the current one.
+pub async fn reload(state: &AppState) -> anyhow::Result<ReloadReport> {
+    let _reloading = state.live.reloading.lock().await;
+    let loaded = Config::load(&state.live.args)?;
+    let current = state.live.current();
+
+    let old = serde_json::to_value(&*current)?;
+    let new = serde_json::to_value(&loaded)?;
+    let (applied, restart_required): (Vec<String>, Vec<String>) = new
+        .as_object()
+        .expect("config serializes to an object")
+        .iter()
+        .filter(|(key, value)| old.get(key.as_str()) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .partition(|key| TUNABLE.contains(&key.as_str()));
+
+    let mut config = (*current).clone();
+    config.browser_url_allow = loaded.browser_url_allow;
+    config.browser_url_deny = loaded.browser_url_deny;
+    config.browser_dialog_action = loaded.browser_dialog_action;
+    config.browser_max_pages = loaded.browser_max_pages;
+    config.browser_page_idle_timeout = loaded.browser_page_idle_timeout;
+    config.browser_page_max_lifetime = loaded.browser_page_max_lifetime;
+    config.browser_max_memory_mb = loaded.browser_max_memory_mb;
+    config.factory_triggers = loaded.factory_triggers;
+    config.rate_limit_rpm = loaded.rate_limit_rpm;
+    config.rate_limit_concurrent = loaded.rate_limit_concurrent;
+
+    let mut browser_restarted = false;
+    if applied.iter().any(|key| key.starts_with("browser_")) {
+        // Already checked by Config::load
+        let url_policy = UrlPolicy::new(&config.browser_url_allow, &config.browser_url_deny)
+            .map_err(anyhow::Error::msg)?;
+        browser_restarted = state
+            .browser
+            .reconfigure(state::browser_limits(&config), state::dialog_policy(&config), url_policy)
+            .await;
+        // Sessions run browsers of their own under the same policies
+        for session in state.sessions.list() {
+            let url_policy = UrlPolicy::new(&config.browser_url_allow, &config.browser_url_deny)
+                .map_err(anyhow::Error::msg)?;
+            session
+                .state
+                .browser
+                .reconfigure(state::browser_limits(&config), state::dialog_policy(&config), url_policy)
+                .await;
+        }
+    }
+    state.rate_limiter.set_limits(config.rate_limit_rpm, config.rate_limit_concurrent);
+    *state.live.current.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(config);
+
+    if !applied.is_empty() {
+        tracing::info!("Reloaded configuration: {}", applied.join(", "));
+    }
+    if !restart_required.is_empty() {
+        tracing::warn!("Restart to apply changed settings: {}", restart_required.join(", "));
+    }
+    Ok(ReloadReport { applied, restart_required, browser_restarted })
+}
+
+/// Reload whenever the config file's modification time changes
+pub fn spawn_watcher(state: Arc<AppState>) {
+    let Some(path) = state.live.current().config_file.clone() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let mut last = modified(&path);
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+            let current = modified(&path);
+            if current == last {
+                continue;
+            }
+            last = current;
+            if let Err(e) = reload(&state).await {
+                tracing::warn!("Ignoring change to {}: {:#}", path.display(), e);
+            }
+        }
+    });
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reload_applies_tunable_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sandbox.toml");
+        std::fs::write(&path, "port = 9000\nrate_limit_rpm = 0\naudit_log = \"\"\n").unwrap();
+        let args = Args::parse(["--config".to_string(), path.to_string_lossy().into_owned()]).unwrap();
+        let state = AppState::new(Config::load(&args).unwrap(), args);
+
+        std::fs::write(&path, "port = 9001\nrate_limit_rpm = 60\nfactory_triggers = [\"show me\"]\naudit_log = \"\"\n").unwrap();
+        let report = reload(&state).await.unwrap();
+        assert_eq!(report.applied, vec!["factory_triggers", "rate_limit_rpm"]);
+        assert_eq!(report.restart_required, vec!["port"]);
+        assert!(state.rate_limiter.enabled());
+        let current = state.live.current();
+        assert_eq!(current.factory_triggers, vec!["show me"]);
+        assert_eq!(current.port, 9000);
+
+        // An invalid file is rejected and the running settings are kept
+        std::fs::write(&path, "rate_limit_rpm = \"lots\"\n").unwrap();
+        assert!(reload(&state).await.is_err());
+        assert_eq!(state.live.current().rate_limit_rpm, 60);
+    }
+}
@@ -0,0 +1,182 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::{json, Value};
+use tracing::Span;
+
+use crate::error::code_for_status;
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied ID that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Largest error body rewritten to carry the request ID
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// The request's ID, available to handlers and inner middleware as an extension
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Keep the client's `X-Request-Id` if it is sensible, otherwise assign a UUID, and
+/// return it in the response header and in the body of every error.
+pub async fn assign(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let value = HeaderValue::from_str(&id).expect("request IDs are visible ASCII");
+    request.headers_mut().insert(X_REQUEST_ID.clone(), value.clone());
+    request.extensions_mut().insert(RequestId(id.clone()));
+
+    let response = next.run(request).await;
+    let mut response = if response.status().is_client_error() || response.status().is_server_error() {
+        with_request_id(response, &id).await
+    } else {
+        response
+    };
+    response.headers_mut().insert(X_REQUEST_ID.clone(), value);
+    response
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+/// Span for `TraceLayer`, tagged with the request ID so every log line of a request carries it
+pub fn make_span(request: &Request) -> Span {
+    let id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.as_str())
+        .unwrap_or_default();
+    tracing::debug_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+        request_id = %id,
+    )
+}
+
+/// Add `request_id` to a JSON error body, and a `code` from the status if it has none.
+/// Plain-text errors, such as rejected JSON from an extractor, become
+/// `{"error": ..., "code": ..., "request_id": ...}` too.
+async fn with_request_id(response: Response, id: &str) -> Response {
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let is_json = content_type.starts_with("application/json");
+    if !is_json && !content_type.starts_with("text/plain") {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_ERROR_BODY).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let body = if is_json {
+        match serde_json::from_slice::<Value>(&bytes) {
+            Ok(Value::Object(mut fields)) => {
+                fields.entry("code").or_insert_with(|| code_for_status(parts.status).into());
+                fields.insert("request_id".into(), id.into());
+                Value::Object(fields)
+            }
+            _ => return Response::from_parts(parts, Body::from(bytes)),
+        }
+    } else {
+        json!({
+            "error": String::from_utf8_lossy(&bytes).trim(),
+            "code": code_for_status(parts.status),
+            "request_id": id,
+        })
+    };
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Response::from_parts(parts, Body::from(body.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, response::IntoResponse, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/fail", get(|| async { crate::error::AppError::NotFound("gone".into()) }))
+            .route(
+                "/coded",
+                get(|| async {
+                    crate::error::AppError::BadRequest("taken".into())
+                        .with_code("SKILL_EXISTS")
+                        .with_details(json!({ "name": "pdf" }))
+                }),
+            )
+            .route("/text", get(|| async { (StatusCode::UNPROCESSABLE_ENTITY, "bad field").into_response() }))
+            .layer(axum::middleware::from_fn(assign))
+    }
+
+    async fn call(path: &str, id: Option<&str>) -> (Response, Value) {
+        let mut request = Request::get(path);
+        if let Some(id) = id {
+            request = request.header(&X_REQUEST_ID, id);
+        }
+        let response = app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let json = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (Response::from_parts(parts, Body::empty()), json)
+    }
+
+    #[tokio::test]
+    async fn test_client_id_is_propagated() {
+        let (response, _) = call("/ok", Some("agent-step-42")).await;
+        assert_eq!(response.headers()[&X_REQUEST_ID], "agent-step-42");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_client_id_is_replaced() {
+        let long = "x".repeat(200);
+        let (response, _) = call("/ok", Some(&long)).await;
+        let id = response.headers()[&X_REQUEST_ID].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_error_bodies_carry_request_id() {
+        let (response, body) = call("/fail", Some("abc")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "gone");
+        assert_eq!(body["code"], "NOT_FOUND");
+        assert_eq!(body["request_id"], "abc");
+
+        let (_, body) = call("/text", Some("def")).await;
+        assert_eq!(body["error"], "bad field");
+        assert_eq!(body["code"], "INVALID_BODY");
+        assert_eq!(body["request_id"], "def");
+    }
+
+    #[tokio::test]
+    async fn test_error_codes_and_details() {
+        let (response, body) = call("/coded", None).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "taken");
+        assert_eq!(body["code"], "SKILL_EXISTS");
+        assert_eq!(body["details"]["name"], "pdf");
+        assert!(body["request_id"].is_string());
+    }
+}
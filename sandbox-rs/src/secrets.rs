@@ -0,0 +1,360 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard, OnceCell};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::storage::Storage;
+use crate::tee::sealed::SealingKey;
+use crate::tee::secrets::validate_name;
+
+/// Key in the state store of the sealed secrets
+const SAVED_KEY: &str = "secrets.json";
+
+/// Derivation path of the key secrets are sealed with in a TEE
+#[cfg(feature = "tee")]
+const SECRETS_KEY_PATH: &str = "sandbox/secret-store";
+
+/// Start of a reference to a secret in a command, code, or env value
+const PLACEHOLDER: &str = "{{secret:";
+
+/// What replaces a secret's value in command output
+const REDACTED: &str = "[redacted]";
+
+/// A secret's name and timestamps; its value is never returned
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+struct Secret {
+    value: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedSecret {
+    /// Base64 of the value sealed with the secrets key
+    value: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Saved {
+    /// `tee` or `local`, so a store sealed in a TEE is not opened with the local key
+    sealed_with: String,
+    secrets: BTreeMap<String, SavedSecret>,
+}
+
+/// The key secrets are sealed with: derived from the TEE when it answers, otherwise
+/// a random key kept in a file beside the server's state. Shared by every session.
+pub struct SecretsKey {
+    file: PathBuf,
+    #[cfg(feature = "tee")]
+    tee: crate::tee::TeeService,
+    key: OnceCell<(&'static str, SealingKey)>,
+}
+
+impl SecretsKey {
+    pub fn new(file: PathBuf, #[cfg(feature = "tee")] tee: crate::tee::TeeService) -> Self {
+        Self {
+            file,
+            #[cfg(feature = "tee")]
+            tee,
+            key: OnceCell::new(),
+        }
+    }
+
+    async fn get(&self) -> Result<&(&'static str, SealingKey)> {
+        self.key.get_or_try_init(|| self.resolve()).await
+    }
+
+    async fn resolve(&self) -> Result<(&'static str, SealingKey)> {
+        #[cfg(feature = "tee")]
+        match SealingKey::derive_at(&self.tee, SECRETS_KEY_PATH).await {
+            Ok(key) => return Ok(("tee", key)),
+            Err(e) => tracing::warn!("Sealing secrets with a local key, the TEE is unavailable: {:#}", e),
+        }
+        let file = self.file.clone();
+        let secret = tokio::task::spawn_blocking(move || local_key(&file))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+        let key = SealingKey::from_secret(&secret).map_err(|e| AppError::Internal(e.to_string()))?;
+        Ok(("local", key))
+    }
+}
+
+/// The random key in `file`, created readable by this user only if it does not exist
+fn local_key(file: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut secret = [0u8; 32];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut secret)
+        .map_err(|_| std::io::Error::other("failed to generate secrets key"))?;
+    match std::fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(file) {
+        Ok(mut created) => {
+            created.write_all(&secret)?;
+            Ok(secret.to_vec())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => std::fs::read(file),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write-only secrets, sealed at rest, that requests reference as `{{secret:NAME}}`
+pub struct Secrets {
+    store: Storage,
+    key: Arc<SecretsKey>,
+    /// Loaded from the store on first use
+    values: Mutex<Option<BTreeMap<String, Secret>>>,
+}
+
+impl Secrets {
+    pub fn new(store: Storage, key: Arc<SecretsKey>) -> Self {
+        Self { store, key, values: Mutex::new(None) }
+    }
+
+    pub fn key(&self) -> Arc<SecretsKey> {
+        self.key.clone()
+    }
+
+    /// All secrets, by name
+    pub async fn list(&self) -> Result<Vec<SecretInfo>> {
+        let values = self.values().await?;
+        Ok(values.iter().map(|(name, secret)| info(name, secret)).collect())
+    }
+
+    pub async fn get(&self, name: &str) -> Result<SecretInfo> {
+        let values = self.values().await?;
+        values.get(name).map(|secret| info(name, secret)).ok_or_else(|| not_found(name))
+    }
+
+    /// Add a secret, or replace its value when `replace` is set
+    pub async fn set(&self, name: &str, value: String, replace: bool) -> Result<SecretInfo> {
+        validate_name(name).map_err(AppError::BadRequest)?;
+        if value.is_empty() {
+            return Err(AppError::BadRequest("Secret value must not be empty".into()));
+        }
+        let mut values = self.values().await?;
+        let now = Utc::now();
+        let created_at = match (values.get(name), replace) {
+            (Some(_), false) => {
+                return Err(AppError::BadRequest(format!("Secret '{}' already exists", name))
+                    .with_code("SECRET_EXISTS"))
+            }
+            (None, true) => return Err(not_found(name)),
+            (existing, _) => existing.map_or(now, |secret| secret.created_at),
+        };
+        values.insert(name.to_string(), Secret { value, created_at, updated_at: now });
+        self.save(&values).await?;
+        Ok(info(name, &values[name]))
+    }
+
+    pub async fn remove(&self, name: &str) -> Result<()> {
+        let mut values = self.values().await?;
+        if values.remove(name).is_none() {
+            return Err(not_found(name));
+        }
+        self.save(&values).await
+    }
+
+    /// Substitutions for one request
+    pub fn interpolation(&self) -> Interpolation<'_> {
+        Interpolation { secrets: self, used: Vec::new() }
+    }
+
+    async fn values(&self) -> Result<MappedMutexGuard<'_, BTreeMap<String, Secret>>> {
+        let mut values = self.values.lock().await;
+        if values.is_none() {
+            *values = Some(self.load().await?);
+        }
+        Ok(MutexGuard::map(values, |values| values.get_or_insert_default()))
+    }
+
+    async fn load(&self) -> Result<BTreeMap<String, Secret>> {
+        let Some(saved) = self.store.read(SAVED_KEY)? else {
+            return Ok(BTreeMap::new());
+        };
+        let saved: Saved = serde_json::from_slice(&saved)
+            .map_err(|e| AppError::Internal(format!("Failed to read saved secrets: {}", e)))?;
+        let (sealed_with, key) = self.key.get().await?;
+        if saved.sealed_with != *sealed_with {
+            return Err(AppError::Internal(format!(
+                "Secrets were sealed with the {} key, but only the {} key is available",
+                saved.sealed_with, sealed_with
+            )));
+        }
+        let mut values = BTreeMap::new();
+        for (name, secret) in saved.secrets {
+            let value = BASE64
+                .decode(&secret.value)
+                .map_err(anyhow::Error::from)
+                .and_then(|sealed| key.unseal(&sealed))
+                .and_then(|value| Ok(String::from_utf8(value)?))
+                .map_err(|e| AppError::Internal(format!("Failed to unseal secret '{}': {}", name, e)))?;
+            values.insert(name, Secret { value, created_at: secret.created_at, updated_at: secret.updated_at });
+        }
+        Ok(values)
+    }
+
+    async fn save(&self, values: &BTreeMap<String, Secret>) -> Result<()> {
+        let (sealed_with, key) = self.key.get().await?;
+        let mut secrets = BTreeMap::new();
+        for (name, secret) in values {
+            let sealed = key.seal(secret.value.as_bytes()).map_err(|e| AppError::Internal(e.to_string()))?;
+            secrets.insert(
+                name.clone(),
+                SavedSecret {
+                    value: BASE64.encode(sealed),
+                    created_at: secret.created_at,
+                    updated_at: secret.updated_at,
+                },
+            );
+        }
+        let saved = Saved { sealed_with: sealed_with.to_string(), secrets };
+        let saved = serde_json::to_vec_pretty(&saved).map_err(|e| AppError::Internal(e.to_string()))?;
+        self.store.write(SAVED_KEY, &saved)?;
+        Ok(())
+    }
+}
+
+/// Replaces `{{secret:NAME}}` in a request's command, code, args, and env, and
+/// remembers the values it put in so they can be masked in the output
+pub struct Interpolation<'a> {
+    secrets: &'a Secrets,
+    used: Vec<String>,
+}
+
+impl Interpolation<'_> {
+    pub async fn expand(&mut self, text: &str) -> Result<String> {
+        if !text.contains(PLACEHOLDER) {
+            return Ok(text.to_string());
+        }
+        let values = self.secrets.values().await?;
+
+        let mut expanded = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(PLACEHOLDER) {
+            let after = &rest[start + PLACEHOLDER.len()..];
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let name = &after[..end];
+            let secret = values.get(name).ok_or_else(|| not_found(name))?;
+            expanded.push_str(&rest[..start]);
+            expanded.push_str(&secret.value);
+            if !self.used.contains(&secret.value) {
+                self.used.push(secret.value.clone());
+            }
+            rest = &after[end + 2..];
+        }
+        expanded.push_str(rest);
+        Ok(expanded)
+    }
+
+    pub async fn expand_all(&mut self, texts: &[String]) -> Result<Vec<String>> {
+        let mut expanded = Vec::with_capacity(texts.len());
+        for text in texts {
+            expanded.push(self.expand(text).await?);
+        }
+        Ok(expanded)
+    }
+
+    pub async fn expand_env(&mut self, env: &HashMap<String, String>) -> Result<HashMap<String, String>> {
+        let mut expanded = HashMap::with_capacity(env.len());
+        for (name, value) in env {
+            expanded.insert(name.clone(), self.expand(value).await?);
+        }
+        Ok(expanded)
+    }
+
+    /// `output` with every secret value this request used replaced by `[redacted]`
+    pub fn redact(&self, output: String) -> String {
+        redact(output, &self.used)
+    }
+}
+
+fn redact(mut output: String, values: &[String]) -> String {
+    let mut values: Vec<&String> = values.iter().collect();
+    // Longest first, so a value containing another is masked whole
+    values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+    for value in values {
+        if output.contains(value.as_str()) {
+            output = output.replace(value.as_str(), REDACTED);
+        }
+    }
+    output
+}
+
+fn info(name: &str, secret: &Secret) -> SecretInfo {
+    SecretInfo { name: name.to_string(), created_at: secret.created_at, updated_at: secret.updated_at }
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::NotFound(format!("Secret '{}' not found", name))
+        .with_code("SECRET_NOT_FOUND")
+        .with_details(json!({ "name": name }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets(dir: &std::path::Path) -> Secrets {
+        let key = SecretsKey::new(
+            dir.join("secrets.key"),
+            #[cfg(feature = "tee")]
+            crate::tee::TeeService::new(None),
+        );
+        Secrets::new(Storage::fs(dir.to_path_buf()), Arc::new(key))
+    }
+
+    #[tokio::test]
+    async fn test_secrets_are_sealed_and_restored() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = secrets(dir.path());
+        store.set("API_KEY", "sk-live-123".into(), false).await.unwrap();
+        assert!(store.set("API_KEY", "other".into(), false).await.is_err());
+        assert!(store.set("MISSING", "x".into(), true).await.is_err());
+
+        let saved = std::fs::read_to_string(dir.path().join(SAVED_KEY)).unwrap();
+        assert!(!saved.contains("sk-live-123"));
+
+        let restored = secrets(dir.path());
+        let mut interpolation = restored.interpolation();
+        let command = interpolation.expand("curl -H 'Authorization: {{secret:API_KEY}}' x").await.unwrap();
+        assert_eq!(command, "curl -H 'Authorization: sk-live-123' x");
+        assert_eq!(interpolation.redact("token sk-live-123 ok".into()), "token [redacted] ok");
+        assert_eq!(restored.list().await.unwrap()[0].name, "API_KEY");
+    }
+
+    #[tokio::test]
+    async fn test_expand_without_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = secrets(dir.path());
+        let mut interpolation = store.interpolation();
+        assert_eq!(interpolation.expand("echo {{secret:").await.unwrap(), "echo {{secret:");
+        assert_eq!(interpolation.expand("echo {{other}}").await.unwrap(), "echo {{other}}");
+        assert!(interpolation.expand("echo {{secret:NOPE}}").await.is_err());
+        assert!(!dir.path().join("secrets.key").exists());
+    }
+
+    #[test]
+    fn test_redact_longest_first() {
+        let values = ["abc".to_string(), "abcdef".to_string()];
+        assert_eq!(redact("x abcdef abc".into(), &values), "x [redacted] [redacted]");
+    }
+}
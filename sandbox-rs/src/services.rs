@@ -0,0 +1,217 @@
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::storage::Storage;
+
+/// Time a service gets to answer a health check
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Time a service gets to accept a proxied connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Key under which services are saved across restarts
+const SAVED_KEY: &str = "services.json";
+
+/// A process in the sandbox that an agent has said listens on a port
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Service {
+    pub name: String,
+    pub port: u16,
+    /// Path whose answer decides health; a TCP connect when unset
+    pub health_path: Option<String>,
+    /// Whether `/proxy/{name}/` forwards to it
+    pub proxy: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The outcome of one health check
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Health {
+    /// Accepting connections, and with `health_path`, answering it without a 4xx or 5xx
+    pub healthy: bool,
+    /// Status `health_path` answered with
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub latency_ms: f64,
+}
+
+/// Declared services, saved to `store` whenever they change, and the client that reaches them
+pub struct Services {
+    store: Storage,
+    services: DashMap<String, Service>,
+    client: reqwest::Client,
+}
+
+impl Services {
+    pub fn new(store: Storage) -> Self {
+        let client = reqwest::Client::builder()
+            // Redirects are for the client on the other side of the proxy to follow
+            .redirect(reqwest::redirect::Policy::none())
+            .connect_timeout(CONNECT_TIMEOUT)
+            .build()
+            .expect("TLS backend is available");
+        Self { store, services: DashMap::new(), client }
+    }
+
+    /// Client for requests to services, which are all on loopback
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Declare a service, replacing any of the same name
+    pub fn declare(&self, service: Service, api_port: u16) -> Result<()> {
+        let valid_name = !service.name.is_empty()
+            && service.name.len() <= 63
+            && service.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            && !service.name.starts_with('-');
+        if !valid_name {
+            return Err(AppError::BadRequest(format!(
+                "Invalid service name \"{}\": use up to 63 lowercase letters, digits, and dashes",
+                service.name
+            )));
+        }
+        if service.port == 0 || service.port == api_port {
+            return Err(AppError::BadRequest(format!("Port {} cannot be a service", service.port)));
+        }
+        if let Some(path) = &service.health_path {
+            if !path.starts_with('/') {
+                return Err(AppError::BadRequest("health_path must start with /".into()));
+            }
+        }
+        self.services.insert(service.name.clone(), service);
+        self.save();
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<Service> {
+        self.services.get(name).map(|entry| entry.value().clone()).ok_or_else(|| not_found(name))
+    }
+
+    pub fn list(&self) -> Vec<Service> {
+        let mut services: Vec<Service> = self.services.iter().map(|entry| entry.value().clone()).collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        services
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        self.services.remove(name).ok_or_else(|| not_found(name))?;
+        self.save();
+        Ok(())
+    }
+
+    pub async fn check(&self, service: &Service) -> Health {
+        let start = Instant::now();
+        let outcome = match &service.health_path {
+            Some(path) => {
+                let url = format!("http://127.0.0.1:{}{}", service.port, path);
+                match self.client.get(url).timeout(HEALTH_TIMEOUT).send().await {
+                    Ok(response) => {
+                        let status = response.status();
+                        let error = (status.is_client_error() || status.is_server_error())
+                            .then(|| format!("{} answered {}", path, status));
+                        (Some(status.as_u16()), error)
+                    }
+                    Err(e) => (None, Some(e.to_string())),
+                }
+            }
+            None => {
+                let connect = tokio::net::TcpStream::connect(("127.0.0.1", service.port));
+                match tokio::time::timeout(HEALTH_TIMEOUT, connect).await {
+                    Ok(Ok(_)) => (None, None),
+                    Ok(Err(e)) => (None, Some(e.to_string())),
+                    Err(_) => (None, Some("Timed out connecting".into())),
+                }
+            }
+        };
+        Health {
+            healthy: outcome.1.is_none(),
+            status: outcome.0,
+            error: outcome.1,
+            latency_ms: start.elapsed().as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// Bring back the services declared before a restart
+    pub fn restore(&self) -> usize {
+        let saved: Vec<Service> = match self.store.read(SAVED_KEY) {
+            Ok(Some(data)) => match serde_json::from_slice(&data) {
+                Ok(services) => services,
+                Err(e) => {
+                    tracing::warn!("Ignoring saved services: {}", e);
+                    return 0;
+                }
+            },
+            Ok(None) => return 0,
+            Err(e) => {
+                tracing::warn!("Failed to read saved services: {}", e);
+                return 0;
+            }
+        };
+        let restored = saved.len();
+        for service in saved {
+            self.services.insert(service.name.clone(), service);
+        }
+        restored
+    }
+
+    fn save(&self) {
+        let written = serde_json::to_vec_pretty(&self.list())
+            .map_err(std::io::Error::other)
+            .and_then(|data| self.store.write(SAVED_KEY, &data));
+        if let Err(e) = written {
+            tracing::warn!("Failed to save services: {}", e);
+        }
+    }
+}
+
+fn not_found(name: &str) -> AppError {
+    AppError::NotFound(format!("Service '{}' not found", name)).with_code("SERVICE_NOT_FOUND")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(name: &str, port: u16, health_path: Option<&str>) -> Service {
+        Service {
+            name: name.into(),
+            port,
+            health_path: health_path.map(Into::into),
+            proxy: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_declare_validates_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Storage::fs(dir.path().to_path_buf());
+        let services = Services::new(store.clone());
+        assert!(services.declare(service("Web App", 3000, None), 8080).is_err());
+        assert!(services.declare(service("web", 8080, None), 8080).is_err());
+        assert!(services.declare(service("web", 3000, Some("health")), 8080).is_err());
+        services.declare(service("web", 3000, Some("/health")), 8080).unwrap();
+        services.declare(service("web", 3001, None), 8080).unwrap();
+
+        let restored = Services::new(store);
+        assert_eq!(restored.restore(), 1);
+        assert_eq!(restored.get("web").unwrap().port, 3001);
+        restored.remove("web").unwrap();
+        assert!(restored.get("web").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_connects_to_the_port() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let services = Services::new(Storage::fs(std::env::temp_dir()));
+        assert!(services.check(&service("up", port, None)).await.healthy);
+        drop(listener);
+        let health = services.check(&service("down", port, None)).await;
+        assert!(!health.healthy && health.error.is_some());
+    }
+}
@@ -0,0 +1,284 @@
+use axum::{
+    extract::{Request, State},
+    http::HeaderName,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tower::ServiceExt;
+use utoipa::ToSchema;
+
+use crate::error::AppError;
+use crate::state::AppState;
+use crate::versioning;
+
+/// Header naming the session a request runs in
+pub static X_SESSION_ID: HeaderName = HeaderName::from_static("x-session-id");
+
+/// Longest session ID a client may choose
+const MAX_ID_LEN: usize = 64;
+
+/// Settings and paths of one session, saved as `session.json` in its directory
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SessionInfo {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// Relative paths resolve here, and file endpoints refuse paths outside it
+    pub workspace: String,
+    /// Requests per minute for this session; 0 disables
+    pub rate_limit_rpm: u32,
+    /// Concurrent executions for this session; 0 disables
+    pub rate_limit_concurrent: usize,
+}
+
+/// A tenant: its own workspace, skills, env, browser, and quotas, behind its own router
+pub struct Session {
+    pub info: SessionInfo,
+    pub state: Arc<AppState>,
+    router: Router,
+    reaper: JoinHandle<()>,
+}
+
+/// Sessions by ID, each stored under `dir/<id>`
+pub struct Sessions {
+    dir: PathBuf,
+    max: usize,
+    sessions: DashMap<String, Arc<Session>>,
+}
+
+impl Sessions {
+    pub fn new(dir: PathBuf, max: usize) -> Self {
+        Self { dir, max, sessions: DashMap::new() }
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<Session>> {
+        self.sessions.get(id).map(|session| session.clone())
+    }
+
+    /// All sessions, oldest first
+    pub fn list(&self) -> Vec<Arc<Session>> {
+        let mut sessions: Vec<_> = self.sessions.iter().map(|entry| entry.value().clone()).collect();
+        sessions.sort_by_key(|session| session.info.created_at);
+        sessions
+    }
+
+    /// Create a session with quotas that default to the server's
+    pub async fn create(
+        &self,
+        parent: &Arc<AppState>,
+        id: Option<String>,
+        rate_limit_rpm: Option<u32>,
+        rate_limit_concurrent: Option<usize>,
+    ) -> Result<SessionInfo, AppError> {
+        let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        if !is_valid_id(&id) {
+            return Err(AppError::BadRequest(format!(
+                "Session ID must be 1-{} letters, digits, '-', or '_'",
+                MAX_ID_LEN
+            )));
+        }
+        if self.sessions.contains_key(&id) {
+            return Err(AppError::BadRequest(format!("Session '{}' already exists", id)).with_code("SESSION_EXISTS"));
+        }
+        if self.sessions.len() >= self.max {
+            return Err(AppError::Forbidden(format!("Limit of {} sessions reached", self.max))
+                .with_code("SESSION_LIMIT")
+                .with_details(json!({ "limit": self.max })));
+        }
+
+        let dir = self.dir.join(&id);
+        let info = SessionInfo {
+            workspace: dir.join("workspace").display().to_string(),
+            id,
+            created_at: Utc::now(),
+            rate_limit_rpm: rate_limit_rpm.unwrap_or(parent.config.rate_limit_rpm),
+            rate_limit_concurrent: rate_limit_concurrent.unwrap_or(parent.config.rate_limit_concurrent),
+        };
+        tokio::fs::create_dir_all(&info.workspace).await?;
+        let saved = serde_json::to_vec_pretty(&info).map_err(|e| AppError::Internal(e.to_string()))?;
+        tokio::fs::write(dir.join("session.json"), saved).await?;
+
+        self.insert(parent, info.clone(), &dir);
+        tracing::info!("Created session {}", info.id);
+        Ok(info)
+    }
+
+    fn insert(&self, parent: &Arc<AppState>, info: SessionInfo, dir: &Path) {
+        let state = parent.for_session(&info, dir);
+        let session = Session {
+            router: crate::router(&state),
+            reaper: state.browser.spawn_reaper(),
+            state,
+            info,
+        };
+        self.sessions.insert(session.info.id.clone(), Arc::new(session));
+    }
+
+    /// Close a session's browser and delete its directory, workspace included
+    pub async fn remove(&self, id: &str) -> Result<(), AppError> {
+        let Some((_, session)) = self.sessions.remove(id) else {
+            return Err(AppError::NotFound(format!("Session '{}' not found", id)).with_code("SESSION_NOT_FOUND"));
+        };
+        session.close().await;
+        tokio::fs::remove_dir_all(self.dir.join(id)).await?;
+        // Skills kept in a shared backend live outside the session's directory
+        let store = session.state.state_store.clone();
+        tokio::task::spawn_blocking(move || store.clear())
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+        tracing::info!("Removed session {}", id);
+        Ok(())
+    }
+
+    /// Bring back the sessions saved under `dir` by a previous run
+    pub fn restore(&self, parent: &Arc<AppState>) -> usize {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return 0;
+        };
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            let restored = std::fs::read(dir.join("session.json"))
+                .map_err(|e| e.to_string())
+                .and_then(|saved| serde_json::from_slice::<SessionInfo>(&saved).map_err(|e| e.to_string()));
+            match restored {
+                Ok(info) => self.insert(parent, info, &dir),
+                Err(e) => tracing::warn!("Skipping session in {}: {}", dir.display(), e),
+            }
+        }
+        self.sessions.len()
+    }
+
+    /// Close every session's browser, for shutdown
+    pub async fn close_all(&self) {
+        for session in self.list() {
+            session.close().await;
+        }
+    }
+}
+
+impl Session {
+    async fn close(&self) {
+        self.reaper.abort();
+        self.state.browser.restart().await;
+    }
+}
+
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_ID_LEN && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
+
+/// Endpoints that act on the whole server and are refused inside a session
+fn is_server_wide(path: &str) -> bool {
+    let path = versioning::unprefixed(path);
+    ["/sessions", "/admin/", "/audit", "/webhooks"]
+        .iter()
+        .any(|prefix| path.starts_with(prefix))
+}
+
+/// Endpoints usable without a session when `require_session` is on
+fn is_sessionless(path: &str) -> bool {
+    let path = versioning::unprefixed(path);
+    matches!(path, "/health" | "/ready" | "/version" | "/openapi.json")
+        || path.starts_with("/swagger-ui")
+        || path.starts_with("/.well-known/")
+        || is_server_wide(path)
+}
+
+/// Run requests that carry `X-Session-Id` in that session, and requests without
+/// one in the shared workspace, unless `require_session` says they must have one.
+pub async fn dispatch(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(id) = request.headers().get(&X_SESSION_ID) else {
+        if state.config.require_session && !is_sessionless(request.uri().path()) {
+            return AppError::Forbidden("Requests must name a session with X-Session-Id".into())
+                .with_code("SESSION_REQUIRED")
+                .into_response();
+        }
+        return next.run(request).await;
+    };
+    let id = id.to_str().unwrap_or_default();
+    let Some(session) = state.sessions.get(id) else {
+        return AppError::NotFound(format!("Session '{}' not found", id))
+            .with_code("SESSION_NOT_FOUND")
+            .into_response();
+    };
+    if is_server_wide(request.uri().path()) {
+        return AppError::Forbidden(format!("{} is not available within a session", request.uri().path()))
+            .into_response();
+    }
+    match session.router.clone().oneshot(request).await {
+        Ok(response) => response,
+        Err(infallible) => match infallible {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_ids() {
+        assert!(is_valid_id("agent-7_b"));
+        assert!(!is_valid_id(""));
+        assert!(!is_valid_id("../etc"));
+        assert!(!is_valid_id(&"a".repeat(MAX_ID_LEN + 1)));
+    }
+
+    #[test]
+    fn test_server_wide_paths() {
+        assert!(is_server_wide("/sessions"));
+        assert!(is_server_wide("/v1/admin/reload"));
+        assert!(is_server_wide("/audit"));
+        assert!(!is_server_wide("/shell/exec"));
+        assert!(is_sessionless("/v1/health"));
+        assert!(!is_sessionless("/v1/file/read"));
+    }
+
+    #[test]
+    fn test_paths_confined_to_session_workspace() {
+        use crate::handlers::file::resolve_path;
+
+        let workspace = "/data/sessions/a/workspace";
+        assert_eq!(
+            resolve_path(workspace, "src/../main.py", true).unwrap(),
+            PathBuf::from("/data/sessions/a/workspace/main.py")
+        );
+        assert!(resolve_path(workspace, "/data/sessions/a/workspace/x", true).is_ok());
+        assert!(resolve_path(workspace, "../../b/workspace/x", true).is_err());
+        assert!(resolve_path(workspace, "/etc/passwd", true).is_err());
+        assert!(resolve_path(workspace, "/etc/passwd", false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_browser_paths_confined_to_session_workspace() {
+        use crate::browser::{BrowserError, UploadRequest};
+
+        let args = crate::config::Args::parse(["--audit-log=".to_string()]).unwrap();
+        let state = AppState::new(crate::config::Config::load(&args).unwrap(), args);
+        let dir = std::env::temp_dir().join(format!("session-test-{}", uuid::Uuid::new_v4()));
+        let info = SessionInfo {
+            id: "a".into(),
+            created_at: Utc::now(),
+            workspace: dir.join("workspace").display().to_string(),
+            rate_limit_rpm: 0,
+            rate_limit_concurrent: 0,
+        };
+        let session = state.for_session(&info, &dir);
+
+        let request = UploadRequest {
+            page_id: None,
+            url: None,
+            selector: "input[type=file]".into(),
+            files: vec!["/etc/passwd".into()],
+        };
+        let error = session.browser.upload(request).await.unwrap_err();
+        assert!(matches!(error, BrowserError::OutsideWorkspace { .. }), "{:?}", error);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,97 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Fires once on SIGINT or SIGTERM. Servers stop accepting connections when it
+/// does and finish the requests already in flight.
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    /// Start listening for the shutdown signals
+    pub fn listen() -> Self {
+        let (tx, triggered) = watch::channel(false);
+        tokio::spawn(async move {
+            signal().await;
+            tracing::info!("Shutting down: no longer accepting requests");
+            tx.send_replace(true);
+            // Keep the sender alive so receivers never see a closed channel
+            std::future::pending::<()>().await;
+        });
+        Self { triggered }
+    }
+
+    /// A shutdown fired by sending `true`, for tests
+    #[cfg(test)]
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (tx, triggered) = watch::channel(false);
+        (tx, Self { triggered })
+    }
+
+    /// Resolves once shutdown has begun
+    pub async fn triggered(mut self) {
+        // The sender is never dropped, so this only errs if the runtime is going away
+        let _ = self.triggered.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Run `server` until it has drained, or until `deadline` after shutdown began.
+    /// Returns whether everything in flight finished in time.
+    pub async fn drain(&self, server: impl Future<Output = ()>, deadline: Duration) -> bool {
+        let expired = async {
+            self.clone().triggered().await;
+            tokio::time::sleep(deadline).await;
+        };
+        tokio::select! {
+            _ = server => true,
+            _ = expired => false,
+        }
+    }
+}
+
+async fn signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_gives_up_after_deadline() {
+        let (tx, shutdown) = Shutdown::channel();
+
+        // A server that finishes on its own is waited for, signal or not
+        assert!(shutdown.drain(async {}, Duration::from_millis(10)).await);
+
+        tx.send_replace(true);
+        let stuck = std::future::pending::<()>();
+        assert!(!shutdown.drain(stuck, Duration::from_millis(10)).await);
+    }
+}
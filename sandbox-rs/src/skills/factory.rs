@@ -1,7 +1,13 @@
 use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
 use std::time::Instant;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::storage::Storage;
+
+/// Key under which sessions are saved across restarts
+const SAVED_KEY: &str = "factory-sessions.json";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FactoryStep {
     Goal,
     Trigger,
@@ -40,7 +46,7 @@ impl FactoryStep {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct FactoryAnswers {
     pub goal: Option<String>,
     pub triggers: Option<Vec<String>>,
@@ -50,18 +56,20 @@ pub struct FactoryAnswers {
     pub edge_cases: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Complexity {
     Simple,
     Complex,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FactorySession {
     pub id: String,
     pub step: FactoryStep,
     pub answers: FactoryAnswers,
+    /// Not persisted; a restored session counts its age from the restore
     #[allow(dead_code)] // Used by cleanup_expired
+    #[serde(skip, default = "Instant::now")]
     pub created_at: Instant,
 }
 
@@ -246,6 +254,31 @@ impl FactorySessions {
         self.sessions.get(id).map(|s| s.clone())
     }
 
+    /// Write all sessions to `store` so a restarted server can resume them.
+    /// Returns how many were saved; nothing is written when there are none.
+    pub fn save(&self, store: &Storage) -> anyhow::Result<usize> {
+        let sessions: Vec<FactorySession> = self.sessions.iter().map(|s| s.clone()).collect();
+        if sessions.is_empty() {
+            return Ok(0);
+        }
+        store.write(SAVED_KEY, &serde_json::to_vec(&sessions)?)?;
+        Ok(sessions.len())
+    }
+
+    /// Restore sessions written by `save`, deleting them so they are resumed only once
+    pub fn load(store: &Storage) -> anyhow::Result<Self> {
+        let factory = Self::new();
+        let Some(data) = store.read(SAVED_KEY)? else {
+            return Ok(factory);
+        };
+        let sessions: Vec<FactorySession> = serde_json::from_slice(&data)?;
+        for session in sessions {
+            factory.sessions.insert(session.id.clone(), session);
+        }
+        store.remove(SAVED_KEY)?;
+        Ok(factory)
+    }
+
     /// Remove expired sessions
     #[allow(dead_code)] // Reserved for background cleanup task
     pub fn cleanup_expired(&self, max_age_secs: u64) {
@@ -262,23 +295,24 @@ impl Default for FactorySessions {
     }
 }
 
-/// Check if input contains trigger phrases for the factory skill
-pub fn check_triggers(input: &str) -> Vec<String> {
+/// Phrases that suggest starting the factory, unless `FACTORY_TRIGGERS` replaces them
+pub const DEFAULT_TRIGGERS: &[&str] = &[
+    "teach me",
+    "teach you",
+    "learn this",
+    "learn how",
+    "create a skill",
+    "remember how to",
+    "automate this",
+];
+
+/// Check if input contains any of the trigger phrases for the factory skill
+pub fn check_triggers(input: &str, trigger_phrases: &[String]) -> Vec<String> {
     let normalized = input.to_lowercase();
     let mut triggers = Vec::new();
 
-    let trigger_phrases = [
-        "teach me",
-        "teach you",
-        "learn this",
-        "learn how",
-        "create a skill",
-        "remember how to",
-        "automate this",
-    ];
-
-    for phrase in &trigger_phrases {
-        if normalized.contains(phrase) {
+    for phrase in trigger_phrases {
+        if normalized.contains(&phrase.to_lowercase()) {
             triggers.push(phrase.to_string());
         }
     }
@@ -313,18 +347,45 @@ mod tests {
         assert_eq!(FactoryStep::Done.next(), FactoryStep::Done);
     }
 
+    #[test]
+    fn test_save_and_load_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Storage::fs(dir.path().join("state"));
+        let path = dir.path().join("state/factory-sessions.json");
+
+        let factory = FactorySessions::new();
+        assert_eq!(factory.save(&store).unwrap(), 0);
+        assert!(!path.exists());
+
+        let session = factory.start(Some("Summarize PDFs".into()));
+        factory.continue_session(&session.id, "summarize this").unwrap();
+        assert_eq!(factory.save(&store).unwrap(), 1);
+
+        let restored = FactorySessions::load(&store).unwrap();
+        let resumed = restored.get(&session.id).unwrap();
+        assert_eq!(resumed.step, FactoryStep::Example);
+        assert_eq!(resumed.answers.goal.as_deref(), Some("Summarize PDFs"));
+        // Loaded once, then the file is gone
+        assert!(!path.exists());
+        assert!(FactorySessions::load(&store).unwrap().get(&session.id).is_none());
+    }
+
+    fn default_triggers() -> Vec<String> {
+        DEFAULT_TRIGGERS.iter().map(|t| t.to_string()).collect()
+    }
+
     #[test]
     fn test_check_triggers() {
-        let triggers = check_triggers("Can you teach me how to do this?");
+        let triggers = check_triggers("Can you teach me how to do this?", &default_triggers());
         assert!(triggers.contains(&"teach me".to_string()));
 
-        let triggers = check_triggers("I want to create a skill for this");
+        let triggers = check_triggers("I want to create a skill for this", &default_triggers());
         assert!(triggers.contains(&"create a skill".to_string()));
 
-        let triggers = check_triggers("Please automate this task");
+        let triggers = check_triggers("Please automate this task", &default_triggers());
         assert!(triggers.contains(&"automate this".to_string()));
 
-        let triggers = check_triggers("Just a regular message");
+        let triggers = check_triggers("Just a regular message", &default_triggers());
         assert!(triggers.is_empty());
     }
 
@@ -5,5 +5,5 @@ pub mod factory;
 pub use types::{Skill, SkillSummary};
 pub use registry::{SkillRegistry, CreateSkillRequest, UpdateSkillRequest};
 pub use factory::{
-    FactorySessions, check_triggers
+    FactorySessions, check_triggers, DEFAULT_TRIGGERS
 };
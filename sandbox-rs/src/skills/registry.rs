@@ -1,7 +1,7 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::fs;
+use std::path::{Path, PathBuf};
 use crate::error::{AppError, Result};
+use crate::storage::Storage;
 use super::types::{Skill, SkillMeta, SkillSummary, validate_skill_name, validate_description};
 
 /// Request to create a new skill
@@ -25,10 +25,12 @@ pub struct UpdateSkillRequest {
     pub assets: Option<HashMap<String, String>>,
 }
 
-/// Registry for managing skills in the filesystem
+/// Registry of skills, each a `SKILL.md` with scripts, references, and assets beside it
 #[derive(Clone)]
 pub struct SkillRegistry {
-    skills_dir: PathBuf,
+    store: Storage,
+    /// Where skills are copied to be run or measured, when the store does not keep them as files
+    cache_dir: PathBuf,
 }
 
 /// Validate that a filename doesn't contain path traversal sequences
@@ -42,112 +44,135 @@ fn validate_filename(filename: &str) -> Result<()> {
     Ok(())
 }
 
+/// The directories a skill's files live in, besides SKILL.md
+const FILE_KINDS: [&str; 3] = ["scripts", "references", "assets"];
+
 impl SkillRegistry {
-    /// Create a new skill registry
-    pub fn new(skills_dir: PathBuf) -> Self {
-        Self { skills_dir }
+    /// Create a registry of skills kept in `store`, copied to `cache_dir` when they must be files
+    pub fn with_store(store: Storage, cache_dir: PathBuf) -> Self {
+        Self { store, cache_dir }
     }
 
-    /// Ensure the skills directory exists
-    async fn ensure_skills_dir(&self) -> Result<()> {
-        fs::create_dir_all(&self.skills_dir).await?;
-        Ok(())
+    /// Run `f` on a blocking thread, since storage backends do blocking I/O
+    async fn blocking<T: Send + 'static>(&self, f: impl FnOnce(&Self) -> Result<T> + Send + 'static) -> Result<T> {
+        let registry = self.clone();
+        tokio::task::spawn_blocking(move || f(&registry))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?
     }
 
-    /// Get the path to a skill directory
-    fn skill_path(&self, name: &str) -> PathBuf {
-        self.skills_dir.join(name)
+    /// The directory holding a skill's files, copying them out of the store if needed
+    pub async fn checkout(&self, name: &str) -> Result<PathBuf> {
+        validate_skill_name(name).map_err(AppError::BadRequest)?;
+        let name = name.to_string();
+        self.blocking(move |registry| {
+            if let Some(dir) = registry.store.local_dir() {
+                return Ok(dir.join(&name));
+            }
+            registry.copy_out(&format!("{}/", name), &registry.cache_dir.join(&name))?;
+            Ok(registry.cache_dir.join(&name))
+        })
+        .await
     }
 
-    /// Get the path to a skill directory (public accessor)
-    pub fn skill_dir(&self, name: &str) -> PathBuf {
-        self.skill_path(name)
+    /// The directory holding every skill's files, copying them out of the store if needed
+    #[cfg_attr(not(feature = "tee"), allow(dead_code))]
+    pub async fn checkout_all(&self) -> Result<PathBuf> {
+        self.blocking(|registry| {
+            if let Some(dir) = registry.store.local_dir() {
+                return Ok(dir);
+            }
+            registry.copy_out("", &registry.cache_dir)?;
+            Ok(registry.cache_dir.clone())
+        })
+        .await
     }
 
-    /// Get the path to a skill's SKILL.md file
-    fn skill_md_path(&self, name: &str) -> PathBuf {
-        self.skill_path(name).join("SKILL.md")
+    /// Replace `dir` with the files under `prefix`
+    fn copy_out(&self, prefix: &str, dir: &Path) -> Result<()> {
+        if dir.exists() {
+            std::fs::remove_dir_all(dir)?;
+        }
+        std::fs::create_dir_all(dir)?;
+        for key in self.store.list(prefix)? {
+            let path = dir.join(&key[prefix.len()..]);
+            if let (Some(parent), Some(data)) = (path.parent(), self.store.read(&key)?) {
+                std::fs::create_dir_all(parent)?;
+                std::fs::write(&path, data)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the key of a skill's SKILL.md file
+    fn skill_md_key(name: &str) -> String {
+        format!("{}/SKILL.md", name)
     }
 
     /// List all skills
     pub async fn list(&self) -> Result<Vec<SkillSummary>> {
-        self.ensure_skills_dir().await?;
-
-        let mut entries = fs::read_dir(&self.skills_dir).await?;
-        let mut summaries = Vec::new();
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if !path.is_dir() {
-                continue;
-            }
-
-            let name = match path.file_name().and_then(|n| n.to_str()) {
-                Some(name) => name.to_string(),
-                None => continue,
-            };
-
-            // Try to read the skill to get its description
-            match self.get(&name).await {
-                Ok(skill) => {
+        self.blocking(|registry| {
+            let mut summaries = Vec::new();
+            for key in registry.store.list("")? {
+                let Some(name) = key.strip_suffix("/SKILL.md").filter(|name| !name.contains('/')) else {
+                    continue;
+                };
+                // Skip invalid skills
+                if let Ok(skill) = registry.get_now(name) {
                     summaries.push(SkillSummary {
                         name: skill.meta.name,
                         description: skill.meta.description,
                     });
                 }
-                Err(_) => {
-                    // Skip invalid skills
-                    continue;
-                }
             }
-        }
-
-        summaries.sort_by(|a, b| a.name.cmp(&b.name));
-        Ok(summaries)
+            summaries.sort_by(|a, b| a.name.cmp(&b.name));
+            Ok(summaries)
+        })
+        .await
     }
 
     /// Get a skill by name
     pub async fn get(&self, name: &str) -> Result<Skill> {
-        validate_skill_name(name).map_err(|e| AppError::BadRequest(e))?;
+        let name = name.to_string();
+        self.blocking(move |registry| registry.get_now(&name)).await
+    }
 
-        let skill_md_path = self.skill_md_path(name);
-        if !skill_md_path.exists() {
-            return Err(AppError::NotFound(format!("Skill '{}' not found", name)));
-        }
+    fn get_now(&self, name: &str) -> Result<Skill> {
+        validate_skill_name(name).map_err(AppError::BadRequest)?;
 
-        let content = fs::read_to_string(&skill_md_path).await?;
-        let (meta, body) = self.parse_skill_md(&content)?;
+        let content = self
+            .store
+            .read(&Self::skill_md_key(name))?
+            .ok_or_else(|| AppError::NotFound(format!("Skill '{}' not found", name)).with_code("SKILL_NOT_FOUND"))?;
+        let (meta, body) = self.parse_skill_md(&String::from_utf8_lossy(&content))?;
 
         // List scripts, references, and assets
-        let skill_dir = self.skill_path(name);
-        let scripts = self.list_dir_files(&skill_dir.join("scripts")).await?;
-        let references = self.list_dir_files(&skill_dir.join("references")).await?;
-        let assets = self.list_dir_files(&skill_dir.join("assets")).await?;
+        let [scripts, references, assets] = FILE_KINDS.map(|kind| self.list_files(name, kind));
 
         Ok(Skill {
             meta,
             body,
-            scripts,
-            references,
-            assets,
+            scripts: scripts?,
+            references: references?,
+            assets: assets?,
         })
     }
 
     /// Create a new skill
     pub async fn create(&self, req: CreateSkillRequest) -> Result<Skill> {
-        validate_skill_name(&req.name).map_err(|e| AppError::BadRequest(e))?;
-        validate_description(&req.description).map_err(|e| AppError::BadRequest(e))?;
+        self.blocking(move |registry| registry.create_now(req)).await
+    }
 
-        let skill_dir = self.skill_path(&req.name);
-        if skill_dir.exists() {
-            return Err(AppError::BadRequest(format!("Skill '{}' already exists", req.name)));
+    fn create_now(&self, req: CreateSkillRequest) -> Result<Skill> {
+        validate_skill_name(&req.name).map_err(AppError::BadRequest)?;
+        validate_description(&req.description).map_err(AppError::BadRequest)?;
+        for filename in req.scripts.keys().chain(req.references.keys()).chain(req.assets.keys()) {
+            validate_filename(filename)?;
         }
 
-        // Create skill directory structure
-        fs::create_dir_all(&skill_dir).await?;
-        fs::create_dir_all(skill_dir.join("scripts")).await?;
-        fs::create_dir_all(skill_dir.join("references")).await?;
-        fs::create_dir_all(skill_dir.join("assets")).await?;
+        if self.store.read(&Self::skill_md_key(&req.name))?.is_some() {
+            return Err(AppError::BadRequest(format!("Skill '{}' already exists", req.name)).with_code("SKILL_EXISTS"));
+        }
 
         // Create metadata
         let meta = SkillMeta {
@@ -158,45 +183,31 @@ impl SkillRegistry {
             metadata: None,
         };
 
-        // Write SKILL.md
+        // Write SKILL.md, then scripts, references, and assets
         let skill_md = self.format_skill_md(&meta, &req.body);
-        fs::write(self.skill_md_path(&req.name), skill_md).await?;
-
-        // Write scripts
-        for (filename, content) in &req.scripts {
-            validate_filename(filename)?;
-            let script_path = skill_dir.join("scripts").join(filename);
-            fs::write(script_path, content).await?;
+        self.store.write(&Self::skill_md_key(&req.name), skill_md.as_bytes())?;
+        for (kind, files) in FILE_KINDS.iter().zip([&req.scripts, &req.references, &req.assets]) {
+            self.write_files(&req.name, kind, files)?;
         }
 
-        // Write references
-        for (filename, content) in &req.references {
-            validate_filename(filename)?;
-            let ref_path = skill_dir.join("references").join(filename);
-            fs::write(ref_path, content).await?;
-        }
-
-        // Write assets
-        for (filename, content) in &req.assets {
-            validate_filename(filename)?;
-            let asset_path = skill_dir.join("assets").join(filename);
-            fs::write(asset_path, content).await?;
-        }
-
-        self.get(&req.name).await
+        self.get_now(&req.name)
     }
 
     /// Update an existing skill
     pub async fn update(&self, name: &str, req: UpdateSkillRequest) -> Result<Skill> {
-        validate_skill_name(name).map_err(|e| AppError::BadRequest(e))?;
+        let name = name.to_string();
+        self.blocking(move |registry| registry.update_now(&name, req)).await
+    }
+
+    fn update_now(&self, name: &str, req: UpdateSkillRequest) -> Result<Skill> {
+        validate_skill_name(name).map_err(AppError::BadRequest)?;
 
         // Get existing skill
-        let mut skill = self.get(name).await?;
-        let skill_dir = self.skill_path(name);
+        let mut skill = self.get_now(name)?;
 
         // Update metadata if description changed
         if let Some(description) = &req.description {
-            validate_description(description).map_err(|e| AppError::BadRequest(e))?;
+            validate_description(description).map_err(AppError::BadRequest)?;
             skill.meta.description = description.clone();
         }
 
@@ -205,72 +216,37 @@ impl SkillRegistry {
             skill.body = body.clone();
         }
 
+        let replaced = [&req.scripts, &req.references, &req.assets];
+        for filename in replaced.iter().flat_map(|files| files.iter().flat_map(|files| files.keys())) {
+            validate_filename(filename)?;
+        }
+
         // Write updated SKILL.md
         let skill_md = self.format_skill_md(&skill.meta, &skill.body);
-        fs::write(self.skill_md_path(name), skill_md).await?;
-
-        // Update scripts if provided
-        if let Some(scripts) = &req.scripts {
-            let scripts_dir = skill_dir.join("scripts");
-            // Remove old scripts
-            if scripts_dir.exists() {
-                fs::remove_dir_all(&scripts_dir).await?;
-            }
-            fs::create_dir_all(&scripts_dir).await?;
-            // Write new scripts
-            for (filename, content) in scripts {
-                validate_filename(filename)?;
-                let script_path = scripts_dir.join(filename);
-                fs::write(script_path, content).await?;
-            }
-        }
+        self.store.write(&Self::skill_md_key(name), skill_md.as_bytes())?;
 
-        // Update references if provided
-        if let Some(references) = &req.references {
-            let references_dir = skill_dir.join("references");
-            // Remove old references
-            if references_dir.exists() {
-                fs::remove_dir_all(&references_dir).await?;
-            }
-            fs::create_dir_all(&references_dir).await?;
-            // Write new references
-            for (filename, content) in references {
-                validate_filename(filename)?;
-                let ref_path = references_dir.join(filename);
-                fs::write(ref_path, content).await?;
+        // Replace scripts, references, and assets where provided
+        for (kind, files) in FILE_KINDS.iter().zip(replaced) {
+            if let Some(files) = files {
+                self.store.remove(&format!("{}/{}", name, kind))?;
+                self.write_files(name, kind, files)?;
             }
         }
 
-        // Update assets if provided
-        if let Some(assets) = &req.assets {
-            let assets_dir = skill_dir.join("assets");
-            // Remove old assets
-            if assets_dir.exists() {
-                fs::remove_dir_all(&assets_dir).await?;
-            }
-            fs::create_dir_all(&assets_dir).await?;
-            // Write new assets
-            for (filename, content) in assets {
-                validate_filename(filename)?;
-                let asset_path = assets_dir.join(filename);
-                fs::write(asset_path, content).await?;
-            }
-        }
-
-        self.get(name).await
+        self.get_now(name)
     }
 
     /// Delete a skill
     pub async fn delete(&self, name: &str) -> Result<()> {
-        validate_skill_name(name).map_err(|e| AppError::BadRequest(e))?;
-
-        let skill_dir = self.skill_path(name);
-        if !skill_dir.exists() {
-            return Err(AppError::NotFound(format!("Skill '{}' not found", name)));
-        }
-
-        fs::remove_dir_all(&skill_dir).await?;
-        Ok(())
+        validate_skill_name(name).map_err(AppError::BadRequest)?;
+        let name = name.to_string();
+        self.blocking(move |registry| {
+            if !registry.store.remove(&name)? {
+                return Err(AppError::NotFound(format!("Skill '{}' not found", name)).with_code("SKILL_NOT_FOUND"));
+            }
+            Ok(())
+        })
+        .await
     }
 
     /// Search for skills by query (searches name and description)
@@ -317,25 +293,23 @@ impl SkillRegistry {
         format!("---\n{}---\n\n{}", frontmatter, body)
     }
 
-    /// List files in a directory
-    async fn list_dir_files(&self, dir: &PathBuf) -> Result<Vec<String>> {
-        if !dir.exists() {
-            return Ok(Vec::new());
-        }
-
-        let mut entries = fs::read_dir(dir).await?;
-        let mut files = Vec::new();
-
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    files.push(name.to_string());
-                }
-            }
+    fn write_files(&self, name: &str, kind: &str, files: &HashMap<String, String>) -> Result<()> {
+        for (filename, content) in files {
+            self.store.write(&format!("{}/{}/{}", name, kind, filename), content.as_bytes())?;
         }
+        Ok(())
+    }
 
-        files.sort();
+    /// List the files of one kind in a skill, sorted
+    fn list_files(&self, name: &str, kind: &str) -> Result<Vec<String>> {
+        let prefix = format!("{}/{}/", name, kind);
+        let files = self
+            .store
+            .list(&prefix)?
+            .into_iter()
+            .filter_map(|key| Some(key.strip_prefix(&prefix)?.to_string()))
+            .filter(|file| !file.contains('/'))
+            .collect();
         Ok(files)
     }
 }
@@ -347,7 +321,8 @@ mod tests {
 
     async fn create_test_registry() -> (SkillRegistry, TempDir) {
         let temp_dir = TempDir::new().unwrap();
-        let registry = SkillRegistry::new(temp_dir.path().to_path_buf());
+        let dir = temp_dir.path().to_path_buf();
+        let registry = SkillRegistry::with_store(Storage::fs(dir.clone()), dir);
         (registry, temp_dir)
     }
 
@@ -483,6 +458,31 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[tokio::test]
+    #[cfg(feature = "sqlite")]
+    async fn test_checkout_copies_skill_out_of_store() {
+        let temp = TempDir::new().unwrap();
+        let db = crate::storage::SqliteBackend::open(&temp.path().join("state.db")).unwrap();
+        let store = Storage::shared(std::sync::Arc::new(db), "skills/");
+        let registry = SkillRegistry::with_store(store, temp.path().join("cache"));
+
+        let req = CreateSkillRequest {
+            name: "runner".to_string(),
+            description: "Runs things".to_string(),
+            body: "Body".to_string(),
+            scripts: HashMap::from([("run.sh".to_string(), "echo hi".to_string())]),
+            references: HashMap::new(),
+            assets: HashMap::new(),
+        };
+        registry.create(req).await.unwrap();
+        assert!(!temp.path().join("cache").exists());
+
+        let dir = registry.checkout("runner").await.unwrap();
+        assert_eq!(dir, temp.path().join("cache/runner"));
+        assert_eq!(std::fs::read_to_string(dir.join("scripts/run.sh")).unwrap(), "echo hi");
+        assert_eq!(registry.get("runner").await.unwrap().scripts, vec!["run.sh"]);
+    }
+
     #[tokio::test]
     async fn test_parse_skill_md() {
         let (registry, _temp) = create_test_registry().await;
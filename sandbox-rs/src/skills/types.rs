@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use regex::Regex;
 use std::sync::OnceLock;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SkillMeta {
     pub name: String,
     pub description: String,
@@ -14,7 +15,7 @@ pub struct SkillMeta {
     pub metadata: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct Skill {
     #[serde(flatten)]
     pub meta: SkillMeta,
@@ -24,7 +25,7 @@ pub struct Skill {
     pub assets: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct SkillSummary {
     pub name: String,
     pub description: String,
@@ -1,52 +1,302 @@
-use crate::config::Config;
+use crate::audit::AuditLog;
+use crate::auth::JwtAuth;
+use crate::config::{Args, Config};
+use crate::display::recording::ScreenRecording;
+use crate::drain::Drain;
+use crate::net::Egress;
+use crate::overload::{LoadShedder, RouteTimeouts};
+use crate::ratelimit::RateLimiter;
+use crate::secrets::{Secrets, SecretsKey};
+use crate::services::Services;
+use crate::error::AppError;
+use crate::handlers::file::resolve_path;
+use crate::reload::LiveConfig;
+use crate::sessions::{SessionInfo, Sessions};
+use crate::storage::{Storage, Stores};
+use crate::usage::UsageCollector;
+use crate::webhooks::Webhooks;
 use crate::skills::{SkillRegistry, FactorySessions};
-use crate::browser::{BrowserService, BrowserServiceConfig};
-use std::path::PathBuf;
+use crate::browser::{
+    BrowserLimits, BrowserService, BrowserServiceConfig, DialogAction, DialogPolicy, ProxyConfig,
+    UrlPolicy,
+};
+use dashmap::DashMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "tee")]
-use crate::tee::TeeService;
+use crate::tee::{
+    auth::TeeAuth, measure::Measurements, secrets::SecretEnv, token::TokenIssuer, KeyStore,
+    TeeService,
+};
 
 #[derive(Clone)]
 pub struct AppState {
+    /// Settings as loaded at startup; `live` has any tunable settings reloaded since
     pub config: Config,
+    pub live: Arc<LiveConfig>,
     pub start_time: Instant,
     pub skills: SkillRegistry,
     pub factory: FactorySessions,
+    pub stores: Stores,
+    /// Where factory sessions and webhooks are saved
+    pub state_store: Storage,
     pub browser: BrowserService,
+    /// Outbound HTTP for `/net/fetch` under the egress policy
+    pub egress: Arc<Egress>,
+    /// Recordings of the X display in progress, which all sessions share
+    pub display_recordings: Arc<DashMap<String, ScreenRecording>>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub route_timeouts: Arc<RouteTimeouts>,
+    pub load_shedder: Arc<LoadShedder>,
+    pub drain: Arc<Drain>,
+    pub usage: Arc<UsageCollector>,
+    pub sessions: Arc<Sessions>,
+    pub webhooks: Arc<Webhooks>,
+    /// Ports declared as services, shared by all sessions
+    pub services: Arc<Services>,
+    /// Sealed secrets for `{{secret:NAME}}` references, kept per session
+    pub secrets: Arc<Secrets>,
+    /// The session this state belongs to; `None` for the shared workspace
+    pub session: Option<String>,
+    /// Log of mutating requests, unless disabled
+    pub audit: Option<Arc<AuditLog>>,
+    /// Bearer JWT verification, when a key is configured
+    pub jwt: Option<Arc<JwtAuth>>,
     #[cfg(feature = "tee")]
     pub tee_service: TeeService,
+    #[cfg(feature = "tee")]
+    pub tee_keys: KeyStore,
+    /// Latest digests extended into the RTMR, once startup measurement succeeds
+    #[cfg(feature = "tee")]
+    pub measurements: Arc<tokio::sync::Mutex<Option<Measurements>>>,
+    #[cfg(feature = "tee")]
+    pub secret_env: SecretEnv,
+    #[cfg(feature = "tee")]
+    pub tee_auth: Arc<TeeAuth>,
+    #[cfg(feature = "tee")]
+    pub token_issuer: Arc<TokenIssuer>,
 }
 
 impl AppState {
-    pub fn new(config: Config) -> Arc<Self> {
-        let skills = SkillRegistry::new(PathBuf::from(&config.skills_dir));
-        let factory = FactorySessions::new();
-
-        let browser_config = BrowserServiceConfig {
-            headless: config.browser_headless,
-            executable_path: config.browser_executable.clone(),
-            viewport_width: config.browser_viewport_width,
-            viewport_height: config.browser_viewport_height,
-            timeout: config.browser_timeout,
-        };
+    pub fn new(config: Config, args: Args) -> Arc<Self> {
+        // Losing track of where durable state lives would silently start from scratch
+        let stores = Stores::open(&config).unwrap_or_else(|e| panic!("Failed to open STORAGE: {:#}", e));
+        let skills_dir = PathBuf::from(&config.skills_dir);
+        let skills = SkillRegistry::with_store(stores.at(&skills_dir, "skills/"), skills_dir);
+        let state_store = stores.at(Path::new(&config.state_dir), "state/");
+        let factory = FactorySessions::load(&state_store).unwrap_or_else(|e| {
+            tracing::warn!("Failed to restore factory sessions: {}", e);
+            FactorySessions::new()
+        });
+
+        let rate_limiter = Arc::new(RateLimiter::new(
+            config.rate_limit_rpm,
+            config.rate_limit_concurrent,
+        ));
+
+        let route_timeouts = Arc::new(
+            RouteTimeouts::new(config.request_timeout, &config.route_timeouts)
+                .unwrap_or_else(|e| panic!("Invalid ROUTE_TIMEOUTS: {}", e)),
+        );
+
+        let load_shedder = Arc::new(LoadShedder::new(config.max_concurrent_requests));
+        let drain = Arc::new(Drain::new(config.drain));
+        let browser = BrowserService::new(browser_config(&config));
+        let sessions = Arc::new(Sessions::new(config.sessions_dir(), config.max_sessions));
+        let webhooks = Arc::new(Webhooks::new(state_store.clone()));
+        let services = Arc::new(Services::new(state_store.clone()));
+        let egress = Arc::new(Egress::new(&config));
+
+        // Running without the log that was asked for would defeat its purpose
+        let audit = Some(config.audit_log.as_str()).filter(|path| !path.is_empty()).map(|path| {
+            Arc::new(
+                AuditLog::open(std::path::Path::new(path))
+                    .unwrap_or_else(|e| panic!("Failed to open AUDIT_LOG {}: {}", path, e)),
+            )
+        });
+
+        let jwt = JwtAuth::from_config(&config)
+            .unwrap_or_else(|e| panic!("Invalid JWT settings: {:#}", e))
+            .map(Arc::new);
 
         #[cfg(feature = "tee")]
         let tee_service = TeeService::new(None);
+        #[cfg(feature = "tee")]
+        let tee_keys = KeyStore::new(tee_service.clone());
+        #[cfg(feature = "tee")]
+        let tee_auth = Arc::new(
+            TeeAuth::new(
+                tee_service.clone(),
+                &config.tee_auth_trusted_keys,
+                Duration::from_secs(config.tee_auth_token_ttl),
+            )
+            .unwrap_or_else(|e| panic!("Invalid TEE_AUTH_TRUSTED_KEYS: {}", e)),
+        );
+        #[cfg(feature = "tee")]
+        let token_issuer = Arc::new(TokenIssuer::new(
+            tee_service.clone(),
+            config.tee_token_issuer.clone(),
+        ));
+
+        let secrets_key = SecretsKey::new(
+            Path::new(&config.state_dir).join("secrets.key"),
+            #[cfg(feature = "tee")]
+            tee_service.clone(),
+        );
+        let secrets = Arc::new(Secrets::new(state_store.clone(), Arc::new(secrets_key)));
 
         Arc::new(Self {
+            live: Arc::new(LiveConfig::new(args, config.clone())),
             config,
             start_time: Instant::now(),
             skills,
             factory,
-            browser: BrowserService::new(browser_config),
+            stores,
+            state_store,
+            browser,
+            egress,
+            display_recordings: Arc::new(DashMap::new()),
+            rate_limiter,
+            route_timeouts,
+            load_shedder,
+            drain,
+            usage: Arc::new(UsageCollector::new()),
+            sessions,
+            webhooks,
+            services,
+            secrets,
+            session: None,
+            audit,
+            jwt,
             #[cfg(feature = "tee")]
             tee_service,
+            #[cfg(feature = "tee")]
+            tee_keys,
+            #[cfg(feature = "tee")]
+            measurements: Arc::new(tokio::sync::Mutex::new(None)),
+            #[cfg(feature = "tee")]
+            secret_env: SecretEnv::default(),
+            #[cfg(feature = "tee")]
+            tee_auth,
+            #[cfg(feature = "tee")]
+            token_issuer,
         })
     }
 
     pub fn uptime_secs(&self) -> f64 {
         self.start_time.elapsed().as_secs_f64()
     }
+
+    /// State for a session stored in `dir`: its own workspace, skills, factory
+    /// sessions, browser, env, and quotas, sharing everything server-wide
+    pub fn for_session(self: &Arc<Self>, info: &SessionInfo, dir: &Path) -> Arc<Self> {
+        let mut config = self.config.clone();
+        config.workspace = info.workspace.clone();
+        config.skills_dir = dir.join("skills").display().to_string();
+        config.state_dir = dir.display().to_string();
+        // Browser policies may have been reloaded since startup
+        let mut browser_config = browser_config(&self.live.current());
+        browser_config.workspace = config.workspace.clone();
+        browser_config.confined = true;
+        browser_config.user_data_dir = Some(dir.join("browser"));
+
+        let prefix = format!("sessions/{}/", info.id);
+        let skills_dir = dir.join("skills");
+
+        Arc::new(Self {
+            skills: SkillRegistry::with_store(self.stores.at(&skills_dir, &format!("{}skills/", prefix)), skills_dir),
+            factory: FactorySessions::new(),
+            stores: self.stores.clone(),
+            state_store: self.stores.at(dir, &prefix),
+            secrets: Arc::new(Secrets::new(self.stores.at(dir, &prefix), self.secrets.key())),
+            browser: BrowserService::new(browser_config),
+            egress: self.egress.clone(),
+            display_recordings: self.display_recordings.clone(),
+            rate_limiter: Arc::new(RateLimiter::new(info.rate_limit_rpm, info.rate_limit_concurrent)),
+            session: Some(info.id.clone()),
+            config,
+            live: self.live.clone(),
+            start_time: self.start_time,
+            route_timeouts: self.route_timeouts.clone(),
+            load_shedder: self.load_shedder.clone(),
+            drain: self.drain.clone(),
+            usage: self.usage.clone(),
+            sessions: self.sessions.clone(),
+            webhooks: self.webhooks.clone(),
+            services: self.services.clone(),
+            audit: self.audit.clone(),
+            jwt: self.jwt.clone(),
+            #[cfg(feature = "tee")]
+            tee_service: self.tee_service.clone(),
+            #[cfg(feature = "tee")]
+            tee_keys: self.tee_keys.clone(),
+            #[cfg(feature = "tee")]
+            measurements: self.measurements.clone(),
+            #[cfg(feature = "tee")]
+            secret_env: SecretEnv::default(),
+            #[cfg(feature = "tee")]
+            tee_auth: self.tee_auth.clone(),
+            #[cfg(feature = "tee")]
+            token_issuer: self.token_issuer.clone(),
+        })
+    }
+
+    /// `path` relative to the workspace unless absolute. Within a session it must
+    /// stay inside the session's workspace.
+    pub fn resolve(&self, path: &str) -> Result<PathBuf, AppError> {
+        resolve_path(&self.config.workspace, path, self.session.is_some())
+    }
+}
+
+/// Chromium settings from the configuration, with its output under `config.workspace`
+pub fn browser_config(config: &Config) -> BrowserServiceConfig {
+    BrowserServiceConfig {
+        headless: config.browser_headless,
+        executable_path: config.browser_executable.clone(),
+        viewport_width: config.browser_viewport_width,
+        viewport_height: config.browser_viewport_height,
+        timeout: config.browser_timeout,
+        workspace: config.workspace.clone(),
+        confined: false,
+        proxy: config.browser_proxy.as_deref().map(|url| {
+            ProxyConfig::from_url(url, config.browser_proxy_bypass.clone())
+        }),
+        limits: browser_limits(config),
+        dialog: dialog_policy(config),
+        // A typo in a deny rule must not silently open up access
+        url_policy: Arc::new(
+            UrlPolicy::new(&config.browser_url_allow, &config.browser_url_deny)
+                .unwrap_or_else(|e| panic!("Invalid BROWSER_URL_ALLOW/BROWSER_URL_DENY: {}", e)),
+        ),
+        user_data_dir: None,
+    }
+}
+
+/// Browser tab limits from the configuration; zero disables a limit
+pub fn browser_limits(config: &Config) -> BrowserLimits {
+    BrowserLimits {
+        max_pages: config.browser_max_pages,
+        idle_timeout: Some(config.browser_page_idle_timeout)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+        max_lifetime: Some(config.browser_page_max_lifetime)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+        max_memory_bytes: Some(config.browser_max_memory_mb)
+            .filter(|mb| *mb > 0)
+            .map(|mb| mb * 1024 * 1024),
+    }
+}
+
+/// Dialog policy for tabs that don't set their own
+pub fn dialog_policy(config: &Config) -> DialogPolicy {
+    DialogPolicy {
+        action: match config.browser_dialog_action.as_str() {
+            "dismiss" => DialogAction::Dismiss,
+            _ => DialogAction::Accept,
+        },
+        prompt_text: None,
+    }
 }
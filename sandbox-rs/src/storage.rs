@@ -0,0 +1,276 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::config::Config;
+
+/// Durable key-value storage for skills and server state. Keys are `/`-separated
+/// relative paths such as `pdf/scripts/run.sh`.
+pub trait StorageBackend: Send + Sync {
+    /// The value of `key`, or `None` when it does not exist
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()>;
+    /// Remove `key` and every key under `key/`, returning whether anything existed
+    fn remove(&self, key: &str) -> io::Result<bool>;
+    /// Every key under `prefix`, which is empty or ends with `/`, sorted
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>>;
+    /// The directory holding key `k` as the file `<dir>/k`, for backends that are plain files
+    fn local_dir(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Keys as files under a directory, the layout skills and state have always had
+pub struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+}
+
+impl StorageBackend for FsBackend {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.root.join(key)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, data)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<bool> {
+        let path = self.root.join(key);
+        let removed = match std::fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_dir() => std::fs::remove_dir_all(&path),
+            Ok(_) => std::fs::remove_file(&path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        removed.map(|()| true)
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut pending = vec![self.root.join(prefix)];
+        while let Some(dir) = pending.pop() {
+            let entries = match std::fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let entry = entry?;
+                let path = entry.path();
+                if entry.file_type()?.is_dir() {
+                    pending.push(path);
+                } else if let Some(key) = path.strip_prefix(&self.root).ok().and_then(Path::to_str) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+
+    fn local_dir(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
+
+/// All keys in one sqlite table, so state can live on a volume outside the container
+#[cfg(feature = "sqlite")]
+pub struct SqliteBackend {
+    db: std::sync::Mutex<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteBackend {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let db = rusqlite::Connection::open(path)?;
+        db.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS objects (key TEXT PRIMARY KEY, data BLOB NOT NULL);",
+        )?;
+        Ok(Self { db: std::sync::Mutex::new(db) })
+    }
+
+    fn db(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.db.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StorageBackend for SqliteBackend {
+    fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        use rusqlite::OptionalExtension;
+
+        self.db()
+            .query_row("SELECT data FROM objects WHERE key = ?1", [key], |row| row.get(0))
+            .optional()
+            .map_err(io::Error::other)
+    }
+
+    fn write(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.db()
+            .execute(
+                "INSERT INTO objects (key, data) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET data = excluded.data",
+                rusqlite::params![key, data],
+            )
+            .map(|_| ())
+            .map_err(io::Error::other)
+    }
+
+    fn remove(&self, key: &str) -> io::Result<bool> {
+        let under = format!("{}/", key);
+        self.db()
+            .execute(
+                "DELETE FROM objects WHERE key = ?1 OR substr(key, 1, length(?2)) = ?2",
+                [key, under.as_str()],
+            )
+            .map(|deleted| deleted > 0)
+            .map_err(io::Error::other)
+    }
+
+    fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let db = self.db();
+        let mut query = db
+            .prepare("SELECT key FROM objects WHERE substr(key, 1, length(?1)) = ?1 ORDER BY key")
+            .map_err(io::Error::other)?;
+        let keys = query
+            .query_map([prefix], |row| row.get(0))
+            .and_then(|rows| rows.collect())
+            .map_err(io::Error::other);
+        keys
+    }
+}
+
+/// A backend seen from under a key prefix, so several stores can share one database
+#[derive(Clone)]
+pub struct Storage {
+    backend: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+impl Storage {
+    /// Keys as files under `dir`
+    pub fn fs(dir: PathBuf) -> Self {
+        Self { backend: Arc::new(FsBackend::new(dir)), prefix: String::new() }
+    }
+
+    /// Keys under `prefix` in a backend shared with other stores
+    pub fn shared(backend: Arc<dyn StorageBackend>, prefix: &str) -> Self {
+        Self { backend, prefix: prefix.to_string() }
+    }
+
+    pub fn read(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        self.backend.read(&self.key(key))
+    }
+
+    pub fn write(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.backend.write(&self.key(key), data)
+    }
+
+    pub fn remove(&self, key: &str) -> io::Result<bool> {
+        self.backend.remove(&self.key(key))
+    }
+
+    /// Keys under `prefix`, relative to this store
+    pub fn list(&self, prefix: &str) -> io::Result<Vec<String>> {
+        let keys = self.backend.list(&self.key(prefix))?;
+        Ok(keys.into_iter().map(|key| key[self.prefix.len()..].to_string()).collect())
+    }
+
+    /// Remove every key in this store
+    pub fn clear(&self) -> io::Result<bool> {
+        self.backend.remove(self.prefix.trim_end_matches('/'))
+    }
+
+    pub fn local_dir(&self) -> Option<PathBuf> {
+        self.backend.local_dir().map(|dir| dir.join(&self.prefix))
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+/// Where skills and state are stored: as files in their configured directories, or
+/// in one shared backend named by `storage`
+#[derive(Clone)]
+pub struct Stores {
+    shared: Option<Arc<dyn StorageBackend>>,
+}
+
+impl Stores {
+    pub fn open(config: &Config) -> anyhow::Result<Self> {
+        let shared: Option<Arc<dyn StorageBackend>> = match config.storage.split_once(':') {
+            None if config.storage == "fs" => None,
+            #[cfg(feature = "sqlite")]
+            Some(("sqlite", path)) => Some(Arc::new(SqliteBackend::open(Path::new(path))?)),
+            _ => anyhow::bail!("unsupported storage \"{}\"", config.storage),
+        };
+        Ok(Self { shared })
+    }
+
+    /// The store for files otherwise kept in `dir`, or under `prefix` in the shared backend
+    pub fn at(&self, dir: &Path, prefix: &str) -> Storage {
+        match &self.shared {
+            Some(backend) => Storage::shared(backend.clone(), prefix),
+            None => Storage::fs(dir.to_path_buf()),
+        }
+    }
+}
+
+/// Whether `storage` names a backend this build supports
+pub fn is_supported(storage: &str) -> bool {
+    storage == "fs" || (cfg!(feature = "sqlite") && storage.strip_prefix("sqlite:").is_some_and(|path| !path.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exercise(backend: Arc<dyn StorageBackend>) {
+        let store = Storage::shared(backend, "skills/");
+        store.write("pdf/SKILL.md", b"body").unwrap();
+        store.write("pdf/scripts/run.sh", b"echo").unwrap();
+        store.write("pdfx/SKILL.md", b"other").unwrap();
+
+        assert_eq!(store.read("pdf/SKILL.md").unwrap().as_deref(), Some(&b"body"[..]));
+        assert_eq!(store.read("missing").unwrap(), None);
+        assert_eq!(store.list("pdf/").unwrap(), vec!["pdf/SKILL.md", "pdf/scripts/run.sh"]);
+        assert_eq!(store.list("").unwrap().len(), 3);
+
+        assert!(store.remove("pdf").unwrap());
+        assert!(!store.remove("pdf").unwrap());
+        assert_eq!(store.list("").unwrap(), vec!["pdfx/SKILL.md"]);
+    }
+
+    #[test]
+    fn test_fs_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        exercise(Arc::new(FsBackend::new(dir.path().to_path_buf())));
+        assert!(dir.path().join("skills/pdfx/SKILL.md").is_file());
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_sqlite_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        exercise(Arc::new(SqliteBackend::open(&dir.path().join("state.db")).unwrap()));
+    }
+}
@@ -0,0 +1,188 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+use crate::tee::keys::derive_ed25519;
+use crate::tee::{jwt, TeeService};
+
+/// Derivation path of the key that proves challenges and signs tokens.
+/// Every instance of the same app derives the same key, and the KMS only
+/// releases it to CVMs whose attestation passed, so holding it is the proof.
+const AUTH_KEY_PATH: &str = "sandbox/auth";
+/// Domain separation for challenge proofs, so they can never double as token signatures
+const PROOF_PREFIX: &[u8] = b"sandbox-auth:";
+const CHALLENGE_TTL: Duration = Duration::from_secs(60);
+/// Challenges are handed out unauthenticated, so bound how many can be pending
+const MAX_PENDING_CHALLENGES: usize = 10_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// Hex public key that answered the challenge
+    pub sub: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Challenge {
+    pub nonce: String,
+    pub expires_in: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Proof {
+    pub public_key: String,
+    pub signature: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Token {
+    pub token: String,
+    pub expires_at: String,
+}
+
+/// Challenge-response authentication and the tokens it issues
+pub struct TeeAuth {
+    tee: TeeService,
+    key: OnceCell<Ed25519KeyPair>,
+    /// Extra Ed25519 public keys allowed to answer challenges, e.g. operators or other apps
+    trusted_keys: Vec<Vec<u8>>,
+    token_ttl: Duration,
+    challenges: DashMap<String, Instant>,
+}
+
+impl TeeAuth {
+    pub fn new(tee: TeeService, trusted_keys: &[String], token_ttl: Duration) -> std::result::Result<Self, String> {
+        let trusted_keys = trusted_keys
+            .iter()
+            .map(|k| {
+                hex::decode(k)
+                    .ok()
+                    .filter(|k| k.len() == 32)
+                    .ok_or_else(|| format!("invalid Ed25519 public key: {}", k))
+            })
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self {
+            tee,
+            key: OnceCell::new(),
+            trusted_keys,
+            token_ttl,
+            challenges: DashMap::new(),
+        })
+    }
+
+    async fn key(&self) -> Result<&Ed25519KeyPair> {
+        self.key
+            .get_or_try_init(|| derive_ed25519(&self.tee, AUTH_KEY_PATH, "signing"))
+            .await
+    }
+
+    pub fn challenge(&self) -> Result<Challenge> {
+        let now = Instant::now();
+        self.challenges.retain(|_, issued| now.duration_since(*issued) < CHALLENGE_TTL);
+        if self.challenges.len() >= MAX_PENDING_CHALLENGES {
+            return Err(AppError::Forbidden("Too many pending challenges".into()));
+        }
+
+        let mut nonce = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| AppError::Internal("Failed to generate nonce".into()))?;
+        let nonce = hex::encode(nonce);
+        self.challenges.insert(nonce.clone(), now);
+        Ok(Challenge { nonce, expires_in: CHALLENGE_TTL.as_secs() })
+    }
+
+    /// Answer a peer's challenge with this app's key
+    pub async fn prove(&self, nonce: &str) -> Result<Proof> {
+        let nonce = hex::decode(nonce).map_err(|e| AppError::BadRequest(format!("Invalid nonce: {}", e)))?;
+        let key = self.key().await?;
+        let signature = key.sign(&[PROOF_PREFIX, &nonce].concat());
+        Ok(Proof {
+            public_key: hex::encode(key.public_key().as_ref()),
+            signature: hex::encode(signature.as_ref()),
+        })
+    }
+
+    /// Check a proof for an outstanding challenge and issue a token
+    pub async fn authenticate(&self, nonce: &str, public_key: &str, signature: &str) -> Result<Token> {
+        let unauthorized = |msg: &str| AppError::Unauthorized(msg.to_string());
+
+        let (_, issued) = self
+            .challenges
+            .remove(nonce)
+            .ok_or_else(|| unauthorized("Unknown or already used challenge"))?;
+        if issued.elapsed() >= CHALLENGE_TTL {
+            return Err(unauthorized("Challenge expired"));
+        }
+
+        let key = self.key().await?;
+        let public_key = hex::decode(public_key).map_err(|_| unauthorized("Invalid public key"))?;
+        if public_key != key.public_key().as_ref() && !self.trusted_keys.contains(&public_key) {
+            return Err(unauthorized("Public key is not trusted"));
+        }
+        let signature = hex::decode(signature).map_err(|_| unauthorized("Invalid signature"))?;
+        let nonce_bytes = hex::decode(nonce).map_err(|_| unauthorized("Invalid nonce"))?;
+        UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(&[PROOF_PREFIX, &nonce_bytes].concat(), &signature)
+            .map_err(|_| unauthorized("Invalid signature"))?;
+
+        let now = chrono::Utc::now();
+        let expires_at = now + self.token_ttl;
+        let claims = Claims {
+            sub: hex::encode(&public_key),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = jwt::sign(key, None, &claims).map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
+        Ok(Token { token, expires_at: expires_at.to_rfc3339() })
+    }
+
+    /// Validate a bearer token issued by this app
+    pub async fn verify(&self, token: &str) -> Result<Claims> {
+        let key = self.key().await?;
+        let claims: Claims = jwt::verify(key.public_key().as_ref(), token)
+            .map_err(|e| AppError::Unauthorized(format!("Invalid token: {}", e)))?;
+        if claims.exp <= chrono::Utc::now().timestamp() {
+            return Err(AppError::Unauthorized("Token expired".into()));
+        }
+        Ok(claims)
+    }
+}
+
+/// Require a token on mutating requests. Reads and the challenge handshake stay open;
+/// `/tee/auth/prove` does not, or anyone could answer challenges as this sandbox.
+pub async fn require_token(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let open = matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || matches!(request.uri().path(), "/tee/auth" | "/tee/auth/challenge");
+    if open {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let Some(token) = token else {
+        return AppError::Unauthorized("Missing bearer token; obtain one via /tee/auth".into()).into_response();
+    };
+
+    match state.tee_auth.verify(token).await {
+        Ok(_) => next.run(request).await,
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::{anyhow, bail, Context};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use crate::tee::TeeService;
+
+/// HKDF info string; bump the version if the format changes
+const INFO: &[u8] = b"sandbox-ecies-v1";
+pub const KEY_LEN: usize = 32;
+
+/// X25519 key pair for data encrypted to the enclave.
+///
+/// Ciphertext layout: `ephemeral public key (32) || nonce (12) || ciphertext || tag (16)`.
+/// The AES-256-GCM key is HKDF-SHA256 over the shared secret, salted with the
+/// ephemeral and recipient public keys so a ciphertext is bound to its recipient.
+pub struct EciesKey {
+    secret: StaticSecret,
+    public: PublicKey,
+}
+
+impl EciesKey {
+    /// Derive the key pair for `path`; the same app always gets the same key
+    pub async fn derive(tee: &TeeService, path: &str) -> anyhow::Result<Self> {
+        let key = tee
+            .derive_key(Some(path), Some("encryption"))
+            .await
+            .context("failed to derive encryption key")?;
+        let secret = hex::decode(&key.key).context("derived key is not valid hex")?;
+        let seed: [u8; KEY_LEN] = secret
+            .get(..KEY_LEN)
+            .and_then(|s| s.try_into().ok())
+            .ok_or_else(|| anyhow!("derived key is shorter than 32 bytes"))?;
+        Ok(Self::from_seed(seed))
+    }
+
+    fn from_seed(seed: [u8; KEY_LEN]) -> Self {
+        let secret = StaticSecret::from(seed);
+        let public = PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    pub fn public_key(&self) -> [u8; KEY_LEN] {
+        self.public.to_bytes()
+    }
+
+    pub fn decrypt(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if data.len() < KEY_LEN + NONCE_LEN + AES_256_GCM.tag_len() {
+            bail!("ciphertext is too short");
+        }
+        let (ephemeral, rest) = data.split_at(KEY_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+        let ephemeral: [u8; KEY_LEN] = ephemeral.try_into().expect("split at key length");
+        let ephemeral = PublicKey::from(ephemeral);
+        let shared = self.secret.diffie_hellman(&ephemeral);
+        let key = aead_key(shared.as_bytes(), ephemeral.as_bytes(), self.public.as_bytes())?;
+
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| anyhow!("decryption failed: wrong recipient key or corrupted data"))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+/// Encrypt `plaintext` to an X25519 public key with a fresh ephemeral key
+pub fn encrypt(recipient: &[u8; KEY_LEN], plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let rng = SystemRandom::new();
+    let mut seed = [0u8; KEY_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut seed).map_err(|_| anyhow!("failed to generate ephemeral key"))?;
+    rng.fill(&mut nonce).map_err(|_| anyhow!("failed to generate nonce"))?;
+
+    let ephemeral = StaticSecret::from(seed);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+    let recipient = PublicKey::from(*recipient);
+    let shared = ephemeral.diffie_hellman(&recipient);
+    let key = aead_key(shared.as_bytes(), ephemeral_public.as_bytes(), recipient.as_bytes())?;
+
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut in_out)
+        .map_err(|_| anyhow!("encryption failed"))?;
+
+    let mut out = Vec::with_capacity(KEY_LEN + NONCE_LEN + in_out.len());
+    out.extend_from_slice(ephemeral_public.as_bytes());
+    out.extend_from_slice(&nonce);
+    out.extend(in_out);
+    Ok(out)
+}
+
+fn aead_key(shared: &[u8], ephemeral: &[u8], recipient: &[u8]) -> anyhow::Result<LessSafeKey> {
+    let salt = [ephemeral, recipient].concat();
+    let prk = Salt::new(HKDF_SHA256, &salt).extract(shared);
+    let okm = prk
+        .expand(&[INFO], &AES_256_GCM)
+        .map_err(|_| anyhow!("failed to expand encryption key"))?;
+    Ok(LessSafeKey::new(UnboundKey::from(okm)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let key = EciesKey::from_seed([3u8; 32]);
+        let ciphertext = encrypt(&key.public_key(), b"sk-live-123").unwrap();
+        assert_eq!(key.decrypt(&ciphertext).unwrap(), b"sk-live-123");
+    }
+
+    #[test]
+    fn test_decrypt_rejects_wrong_key_and_truncation() {
+        let key = EciesKey::from_seed([3u8; 32]);
+        let other = EciesKey::from_seed([4u8; 32]);
+        let ciphertext = encrypt(&other.public_key(), b"secret").unwrap();
+        assert!(key.decrypt(&ciphertext).is_err());
+        assert!(other.decrypt(&ciphertext[..40]).is_err());
+    }
+}
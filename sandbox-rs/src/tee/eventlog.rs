@@ -0,0 +1,124 @@
+use anyhow::{bail, Context};
+use ring::digest::{Context as Digest, SHA384, SHA384_OUTPUT_LEN};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// TDX has four runtime measurement registers
+pub const RTMR_COUNT: usize = 4;
+
+/// Offset of RTMR0 in a TDX v4 quote: 48-byte header, then the TD report
+/// body fields before it (TEE_TCB_SVN, MRSEAM, MRSIGNERSEAM, SEAMATTRIBUTES,
+/// TDATTRIBUTES, XFAM, MRTD, MRCONFIGID, MROWNER, MROWNERCONFIG).
+const QUOTE_RTMR0_OFFSET: usize = 48 + 16 + 48 + 48 + 8 + 8 + 8 + 48 + 48 + 48 + 48;
+
+/// One entry of the dstack event log, as returned alongside a quote
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Event {
+    /// Index of the RTMR this event extended
+    pub imr: u32,
+    #[serde(default)]
+    pub event_type: u32,
+    /// Hex SHA-384 the register was extended with
+    pub digest: String,
+    /// Event name, e.g. an `emit_event` name; empty for boot-time events
+    #[serde(default)]
+    pub event: String,
+    /// Hex payload the digest was computed over, when the log carries it
+    #[serde(default)]
+    pub event_payload: String,
+}
+
+pub fn parse(event_log: &str) -> anyhow::Result<Vec<Event>> {
+    serde_json::from_str(event_log).context("event log is not valid JSON")
+}
+
+/// Recompute the RTMRs from zero by extending each register with its events in
+/// order: `rtmr = SHA384(rtmr || digest)`, digests zero-padded to 48 bytes.
+pub fn replay(events: &[Event]) -> anyhow::Result<[[u8; SHA384_OUTPUT_LEN]; RTMR_COUNT]> {
+    let mut rtmrs = [[0u8; SHA384_OUTPUT_LEN]; RTMR_COUNT];
+    for (index, event) in events.iter().enumerate() {
+        let Some(rtmr) = rtmrs.get_mut(event.imr as usize) else {
+            bail!("event {} targets unknown RTMR {}", index, event.imr);
+        };
+        let digest = hex::decode(&event.digest).with_context(|| format!("event {} digest is not valid hex", index))?;
+        if digest.len() > SHA384_OUTPUT_LEN {
+            bail!("event {} digest is longer than 48 bytes", index);
+        }
+        let mut padded = [0u8; SHA384_OUTPUT_LEN];
+        padded[..digest.len()].copy_from_slice(&digest);
+
+        let mut ctx = Digest::new(&SHA384);
+        ctx.update(rtmr.as_slice());
+        ctx.update(&padded);
+        rtmr.copy_from_slice(ctx.finish().as_ref());
+    }
+    Ok(rtmrs)
+}
+
+/// Read the RTMRs a TDX quote reports, to compare against a replay
+pub fn quote_rtmrs(quote: &[u8]) -> anyhow::Result<[[u8; SHA384_OUTPUT_LEN]; RTMR_COUNT]> {
+    let end = QUOTE_RTMR0_OFFSET + RTMR_COUNT * SHA384_OUTPUT_LEN;
+    if quote.len() < end {
+        bail!("quote is too short to contain RTMRs");
+    }
+    let mut rtmrs = [[0u8; SHA384_OUTPUT_LEN]; RTMR_COUNT];
+    for (i, chunk) in quote[QUOTE_RTMR0_OFFSET..end].chunks_exact(SHA384_OUTPUT_LEN).enumerate() {
+        rtmrs[i].copy_from_slice(chunk);
+    }
+    Ok(rtmrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(imr: u32, digest: &[u8]) -> Event {
+        Event {
+            imr,
+            event_type: 0,
+            digest: hex::encode(digest),
+            event: String::new(),
+            event_payload: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_replay_extends_registers_in_order() {
+        let a = [1u8; SHA384_OUTPUT_LEN];
+        let b = [2u8; SHA384_OUTPUT_LEN];
+        let rtmrs = replay(&[event(3, &a), event(3, &b)]).unwrap();
+
+        let extend = |rtmr: &[u8], digest: &[u8]| {
+            let mut ctx = Digest::new(&SHA384);
+            ctx.update(rtmr);
+            ctx.update(digest);
+            ctx.finish().as_ref().to_vec()
+        };
+        let expected = extend(&extend(&[0u8; SHA384_OUTPUT_LEN], &a), &b);
+        assert_eq!(rtmrs[3].to_vec(), expected);
+        assert_eq!(rtmrs[0], [0u8; SHA384_OUTPUT_LEN]);
+    }
+
+    #[test]
+    fn test_replay_rejects_bad_events() {
+        assert!(replay(&[event(4, &[0u8; 48])]).is_err());
+        assert!(replay(&[event(0, &[0u8; 49])]).is_err());
+    }
+
+    #[test]
+    fn test_parse_dstack_log() {
+        let log = r#"[{"imr":3,"event_type":134217729,"digest":"00ff","event":"app-id","event_payload":"abcd"}]"#;
+        let events = parse(log).unwrap();
+        assert_eq!(events[0].imr, 3);
+        assert_eq!(events[0].event, "app-id");
+    }
+
+    #[test]
+    fn test_quote_rtmrs_offsets() {
+        let mut quote = vec![0u8; QUOTE_RTMR0_OFFSET + 4 * 48 + 64];
+        quote[QUOTE_RTMR0_OFFSET + 48] = 0xaa;
+        let rtmrs = quote_rtmrs(&quote).unwrap();
+        assert_eq!(rtmrs[1][0], 0xaa);
+        assert!(quote_rtmrs(&quote[..100]).is_err());
+    }
+}
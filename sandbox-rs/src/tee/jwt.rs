@@ -0,0 +1,75 @@
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use ring::signature::{Ed25519KeyPair, UnparsedPublicKey, ED25519};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Compact JWS header for Ed25519 tokens
+#[derive(Serialize, serde::Deserialize)]
+struct Header {
+    alg: String,
+    typ: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kid: Option<String>,
+}
+
+/// Sign `claims` as an EdDSA JWT, naming the key with `kid` when it is published in a JWKS
+pub fn sign(key_pair: &Ed25519KeyPair, kid: Option<&str>, claims: &impl Serialize) -> anyhow::Result<String> {
+    let header = Header {
+        alg: "EdDSA".into(),
+        typ: "JWT".into(),
+        kid: kid.map(str::to_string),
+    };
+    let signing_input = format!(
+        "{}.{}",
+        BASE64URL.encode(serde_json::to_vec(&header)?),
+        BASE64URL.encode(serde_json::to_vec(claims)?)
+    );
+    let signature = key_pair.sign(signing_input.as_bytes());
+    Ok(format!("{}.{}", signing_input, BASE64URL.encode(signature.as_ref())))
+}
+
+/// Verify an EdDSA JWT against a raw Ed25519 public key and decode its claims.
+/// Expiry is checked by the caller, which knows which claims it expects.
+pub fn verify<T: DeserializeOwned>(public_key: &[u8], token: &str) -> anyhow::Result<T> {
+    let mut parts = token.split('.');
+    let (Some(header), Some(claims), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        bail!("malformed token");
+    };
+
+    let decoded: Header = serde_json::from_slice(&BASE64URL.decode(header).context("malformed header")?)
+        .context("malformed header")?;
+    if decoded.alg != "EdDSA" {
+        bail!("unsupported algorithm {}", decoded.alg);
+    }
+
+    let signature = BASE64URL.decode(signature).context("malformed signature")?;
+    let signing_input = &token[..header.len() + 1 + claims.len()];
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(signing_input.as_bytes(), &signature)
+        .map_err(|_| anyhow!("invalid signature"))?;
+
+    serde_json::from_slice(&BASE64URL.decode(claims).context("malformed claims")?).context("malformed claims")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::KeyPair;
+
+    #[test]
+    fn test_sign_verify_roundtrip() {
+        let key_pair = Ed25519KeyPair::from_seed_unchecked(&[9u8; 32]).unwrap();
+        let token = sign(&key_pair, Some("k1"), &serde_json::json!({"sub": "peer", "exp": 10})).unwrap();
+
+        let claims: serde_json::Value = verify(key_pair.public_key().as_ref(), &token).unwrap();
+        assert_eq!(claims["sub"], "peer");
+
+        let other = Ed25519KeyPair::from_seed_unchecked(&[1u8; 32]).unwrap();
+        assert!(verify::<serde_json::Value>(other.public_key().as_ref(), &token).is_err());
+
+        let tampered = token.replacen('.', ".e30", 1);
+        assert!(verify::<serde_json::Value>(key_pair.public_key().as_ref(), &tampered).is_err());
+    }
+}
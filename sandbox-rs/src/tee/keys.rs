@@ -0,0 +1,210 @@
+use dashmap::DashMap;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use std::sync::Arc;
+
+use crate::error::{AppError, Result};
+use crate::tee::TeeService;
+
+/// Purpose passed to `derive_key` when a registration names none
+const DEFAULT_PURPOSE: &str = "signing";
+
+/// Derivation paths of the server's own keys (auth tokens, OIDC, sealing, skills), which
+/// clients can neither derive nor register
+pub const RESERVED_PATH_PREFIX: &str = "sandbox/";
+
+/// Whether `path` is under `RESERVED_PATH_PREFIX`, ignoring leading slashes
+fn is_reserved_path(path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    path == RESERVED_PATH_PREFIX.trim_end_matches('/') || path.starts_with(RESERVED_PATH_PREFIX)
+}
+
+/// Where a skill's signing key is derived: under `RESERVED_PATH_PREFIX`, so only
+/// `sign-output` can sign with it
+pub fn skill_key_path(skill: &str) -> String {
+    format!("{}skills/{}", RESERVED_PATH_PREFIX, skill)
+}
+
+/// Refuse a client-chosen derivation path under `RESERVED_PATH_PREFIX`
+pub fn check_client_path(path: &str) -> Result<()> {
+    if !is_reserved_path(path) {
+        return Ok(());
+    }
+    Err(AppError::Forbidden(format!("Key paths under '{}' are reserved for the server", RESERVED_PATH_PREFIX))
+        .with_code("KEY_PATH_RESERVED")
+        .with_details(serde_json::json!({ "path": path })))
+}
+
+/// Public description of a registered key
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct KeyInfo {
+    pub name: String,
+    pub path: String,
+    pub purpose: String,
+    pub algorithm: &'static str,
+    /// Hex-encoded raw Ed25519 public key
+    pub public_key: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterKeyRequest {
+    pub name: String,
+    pub path: String,
+    pub purpose: Option<String>,
+}
+
+/// Derive an Ed25519 key pair seeded from `derive_key(path, purpose)`
+pub async fn derive_ed25519(tee: &TeeService, path: &str, purpose: &str) -> Result<Ed25519KeyPair> {
+    let derived = tee
+        .derive_key(Some(path), Some(purpose))
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to derive key: {}", e)))?;
+    let seed = hex::decode(&derived.key)
+        .map_err(|e| AppError::Internal(format!("Derived key is not valid hex: {}", e)))?;
+    Ed25519KeyPair::from_seed_unchecked(&seed)
+        .map_err(|_| AppError::Internal("Derived key is not a valid Ed25519 seed".into()))
+}
+
+/// A named key's derivation parameters and its cached key pair
+struct NamedKey {
+    info: KeyInfo,
+    key_pair: Ed25519KeyPair,
+}
+
+/// Named keys with fixed derivation paths, derived once and kept in memory.
+///
+/// The TEE derives the same secret for the same path and purpose, so a key
+/// registered again after a restart (or in another replica of the app) is
+/// the same key.
+#[derive(Clone)]
+pub struct KeyStore {
+    tee: TeeService,
+    keys: Arc<DashMap<String, Arc<NamedKey>>>,
+}
+
+impl KeyStore {
+    pub fn new(tee: TeeService) -> Self {
+        Self {
+            tee,
+            keys: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Register a key, deriving it now. Registering an existing name with the
+    /// same path and purpose is a no-op; with different ones it is an error.
+    pub async fn register(&self, req: RegisterKeyRequest) -> Result<KeyInfo> {
+        validate_key_name(&req.name).map_err(AppError::BadRequest)?;
+        if req.path.is_empty() {
+            return Err(AppError::BadRequest("Key path cannot be empty".into()));
+        }
+        check_client_path(&req.path)?;
+        let purpose = req.purpose.unwrap_or_else(|| DEFAULT_PURPOSE.to_string());
+
+        if let Some(existing) = self.keys.get(&req.name) {
+            if existing.info.path == req.path && existing.info.purpose == purpose {
+                return Ok(existing.info.clone());
+            }
+            return Err(AppError::BadRequest(format!(
+                "Key '{}' is already registered with a different path or purpose",
+                req.name
+            )));
+        }
+
+        let key_pair = derive_ed25519(&self.tee, &req.path, &purpose).await?;
+
+        let info = KeyInfo {
+            name: req.name.clone(),
+            path: req.path,
+            purpose,
+            algorithm: "ed25519",
+            public_key: hex::encode(key_pair.public_key().as_ref()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        // A concurrent registration of the same name keeps whichever landed first
+        let entry = self
+            .keys
+            .entry(req.name)
+            .or_insert_with(|| Arc::new(NamedKey { info, key_pair }));
+        Ok(entry.info.clone())
+    }
+
+    pub fn list(&self) -> Vec<KeyInfo> {
+        let mut keys: Vec<_> = self.keys.iter().map(|k| k.info.clone()).collect();
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        keys
+    }
+
+    pub fn remove(&self, name: &str) -> Result<()> {
+        self.keys
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("Key '{}' not registered", name)))
+    }
+
+    /// Sign with a registered key, returning the signature and the key's description
+    pub fn sign(&self, name: &str, data: &[u8]) -> Result<(Vec<u8>, KeyInfo)> {
+        let key = self
+            .keys
+            .get(name)
+            .map(|k| k.clone())
+            .ok_or_else(|| AppError::NotFound(format!("Key '{}' not registered", name)))?;
+        let signature = key.key_pair.sign(data);
+        Ok((signature.as_ref().to_vec(), key.info.clone()))
+    }
+}
+
+fn validate_key_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Key name must be 1-64 characters".into());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err("Key name may only contain letters, digits, '-', '_' and '.'".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key_name() {
+        assert!(validate_key_name("wallet-0").is_ok());
+        assert!(validate_key_name("signing").is_ok());
+        assert!(validate_key_name("").is_err());
+        assert!(validate_key_name("a/b").is_err());
+        assert!(validate_key_name(&"k".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn test_reserved_paths() {
+        assert!(is_reserved_path("sandbox/auth"));
+        assert!(is_reserved_path("/sandbox/skills/pdf"));
+        assert!(is_reserved_path("sandbox"));
+        assert!(!is_reserved_path("sandboxes/auth"));
+        assert!(!is_reserved_path("wallet/0"));
+    }
+
+    #[tokio::test]
+    async fn test_register_refuses_reserved_paths() {
+        let keys = KeyStore::new(TeeService::new(None));
+        for path in ["sandbox/auth", "sandbox/oidc", "/sandbox/skills/pdf"] {
+            let req = RegisterKeyRequest { name: "forged".into(), path: path.into(), purpose: None };
+            let err = keys.register(req).await.unwrap_err();
+            assert!(matches!(err, AppError::Coded { code: "KEY_PATH_RESERVED", .. }), "{}: {:?}", path, err);
+        }
+        assert!(keys.list().is_empty());
+    }
+
+    #[test]
+    fn test_skill_keys_unreachable_by_clients() {
+        let path = skill_key_path("summarize");
+        assert_eq!(path, "sandbox/skills/summarize");
+        assert!(check_client_path(&path).is_err());
+    }
+}
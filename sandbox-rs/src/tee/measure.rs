@@ -0,0 +1,149 @@
+#[cfg(feature = "tee")]
+use serde::Serialize;
+#[cfg(feature = "tee")]
+use utoipa::ToSchema;
+
+use crate::state::AppState;
+
+/// Digests of what the sandbox will actually run, extended into RTMR3 via
+/// `emit_event` so a quote covers them and not just the base image.
+#[cfg(feature = "tee")]
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Measurements {
+    pub version: String,
+    /// SHA-256 of the effective configuration as canonical JSON, with proxy credentials removed
+    pub config_sha256: String,
+    /// SHA-256 of the skills manifest: canonical JSON of `{relative path: SHA-256 of contents}`
+    pub skills_sha256: String,
+    pub measured_at: String,
+}
+
+/// Measure version, configuration, and skills at startup
+#[cfg(feature = "tee")]
+pub async fn measure_startup(state: &AppState) {
+    let mut current = state.measurements.lock().await;
+    let measurements = match compute(state).await {
+        Ok(measurements) => measurements,
+        Err(e) => {
+            tracing::error!("Failed to measure sandbox configuration: {}", e);
+            return;
+        }
+    };
+
+    for (event, digest) in [
+        ("sandbox-version", &measurements.version),
+        ("sandbox-config", &measurements.config_sha256),
+        ("sandbox-skills", &measurements.skills_sha256),
+    ] {
+        if let Err(e) = state.tee_service.emit_event(event, digest).await {
+            tracing::error!("Failed to extend RTMR with {}: {}", event, e);
+        }
+    }
+    *current = Some(measurements);
+}
+
+/// Re-measure the skills directory after it changes
+#[cfg(feature = "tee")]
+pub async fn measure_skills(state: &AppState) {
+    // Held across emit so concurrent changes extend the RTMR in the order they are recorded
+    let mut current = state.measurements.lock().await;
+    let skills_sha256 = match all_skills_digest(state).await {
+        Ok(digest) => digest,
+        Err(e) => {
+            tracing::error!("Failed to measure skills: {}", e);
+            return;
+        }
+    };
+    if current.as_ref().is_some_and(|m| m.skills_sha256 == skills_sha256) {
+        return;
+    }
+
+    if let Err(e) = state.tee_service.emit_event("sandbox-skills", &skills_sha256).await {
+        tracing::error!("Failed to extend RTMR with sandbox-skills: {}", e);
+    }
+    if let Some(measurements) = current.as_mut() {
+        measurements.skills_sha256 = skills_sha256;
+        measurements.measured_at = chrono::Utc::now().to_rfc3339();
+    }
+}
+
+#[cfg(not(feature = "tee"))]
+pub async fn measure_skills(_state: &AppState) {}
+
+#[cfg(feature = "tee")]
+async fn compute(state: &AppState) -> anyhow::Result<Measurements> {
+    Ok(Measurements {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        config_sha256: crate::manifest::config_digest(&state.config).map_err(|e| anyhow::anyhow!("{}", e))?,
+        skills_sha256: all_skills_digest(state).await?,
+        measured_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Digest of every skill, copied out of the store first when it does not keep files
+#[cfg(feature = "tee")]
+async fn all_skills_digest(state: &AppState) -> anyhow::Result<String> {
+    let root = state.skills.checkout_all().await?;
+    skills_digest(&root.to_string_lossy()).await
+}
+
+/// SHA-256 of the manifest of every file under a directory; also used for a single skill
+#[cfg(feature = "tee")]
+pub async fn skills_digest(skills_dir: &str) -> anyhow::Result<String> {
+    let root = std::path::PathBuf::from(skills_dir);
+    let manifest = tokio::task::spawn_blocking(move || skills_manifest(&root)).await??;
+    digest_json(&manifest)
+}
+
+/// Relative path to content hash for every file under the skills directory
+#[cfg(feature = "tee")]
+fn skills_manifest(root: &std::path::Path) -> std::io::Result<std::collections::BTreeMap<String, String>> {
+    let mut manifest = std::collections::BTreeMap::new();
+    if !root.exists() {
+        return Ok(manifest);
+    }
+
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().into_owned();
+                manifest.insert(relative, hex::encode(crate::tee::receipt::sha256(&std::fs::read(&path)?)));
+            }
+        }
+    }
+    Ok(manifest)
+}
+
+#[cfg(feature = "tee")]
+fn digest_json(value: &impl Serialize) -> anyhow::Result<String> {
+    let json = crate::tee::receipt::canonical_json(value).map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(hex::encode(crate::tee::receipt::sha256(json.as_bytes())))
+}
+
+#[cfg(all(test, feature = "tee"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skills_manifest_is_relative_and_content_addressed() {
+        let root = std::env::temp_dir().join(format!("measure-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("helper/scripts")).unwrap();
+        std::fs::write(root.join("helper/SKILL.md"), "body").unwrap();
+        std::fs::write(root.join("helper/scripts/run.sh"), "echo hi").unwrap();
+
+        let manifest = skills_manifest(&root).unwrap();
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(
+            manifest["helper/SKILL.md"],
+            hex::encode(crate::tee::receipt::sha256(b"body"))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+        assert!(skills_manifest(&root).unwrap().is_empty());
+    }
+}
@@ -3,3 +3,35 @@ pub mod client;
 
 #[cfg(feature = "tee")]
 pub use client::TeeService;
+
+#[cfg(feature = "tee")]
+pub mod auth;
+
+#[cfg(feature = "tee")]
+pub mod ecies;
+
+#[cfg(feature = "tee")]
+pub mod eventlog;
+
+#[cfg(feature = "tee")]
+pub mod jwt;
+
+#[cfg(feature = "tee")]
+pub mod keys;
+
+#[cfg(feature = "tee")]
+pub use keys::KeyStore;
+
+pub mod measure;
+
+#[cfg(feature = "tee")]
+pub mod ratls;
+
+pub mod sealed;
+
+pub mod receipt;
+
+pub mod secrets;
+
+#[cfg(feature = "tee")]
+pub mod token;
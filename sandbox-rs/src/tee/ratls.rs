@@ -0,0 +1,89 @@
+use anyhow::Context;
+use rcgen::{CertificateParams, CustomExtension, DnType, KeyPair, PKCS_ECDSA_P256_SHA256};
+use ring::digest::{digest, SHA256};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use std::sync::Arc;
+
+use crate::tee::TeeService;
+
+/// Certificate extension holding the TDX quote. OIDs sit under Phala's
+/// enterprise arc, matching the certificates dstack's own RA-TLS issues.
+const OID_QUOTE: &[u64] = &[1, 3, 6, 1, 4, 1, 62397, 1, 1];
+/// Certificate extension holding the event log needed to replay RTMRs
+const OID_EVENT_LOG: &[u64] = &[1, 3, 6, 1, 4, 1, 62397, 1, 2];
+
+/// Report data binding a quote to a certificate key: SHA-256 of the
+/// SubjectPublicKeyInfo DER, zero-padded to the 64 bytes TDX reports carry.
+fn report_data_for_key(spki_der: &[u8]) -> [u8; 64] {
+    let mut report_data = [0u8; 64];
+    report_data[..32].copy_from_slice(digest(&SHA256, spki_der).as_ref());
+    report_data
+}
+
+/// Generate a fresh key inside the TEE and a self-signed certificate whose
+/// extensions carry a quote over that key, then build a TLS server config from it.
+pub async fn server_config(tee: &TeeService, hostnames: Vec<String>) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+    let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256).context("failed to generate key")?;
+
+    let report_data = report_data_for_key(&key_pair.public_key_der());
+    let quote = tee.get_quote(&report_data).await.context("failed to get quote")?;
+    let quote_bytes = hex::decode(&quote.quote).context("quote is not valid hex")?;
+
+    let mut params = CertificateParams::new(hostnames).context("invalid RA-TLS hostname")?;
+    params.distinguished_name.push(DnType::CommonName, "sandbox RA-TLS");
+    params.custom_extensions.push(CustomExtension::from_oid_content(
+        OID_QUOTE,
+        der_octet_string(&quote_bytes),
+    ));
+    params.custom_extensions.push(CustomExtension::from_oid_content(
+        OID_EVENT_LOG,
+        der_octet_string(quote.event_log.as_bytes()),
+    ));
+    let cert = params.self_signed(&key_pair).context("failed to sign certificate")?;
+
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key_pair.serialize_der()));
+    let mut config = rustls::ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .context("no usable TLS versions")?
+        .with_no_client_auth()
+        .with_single_cert(vec![cert.der().clone()], key)
+        .context("invalid certificate")?;
+    config.alpn_protocols = crate::tls::alpn_protocols();
+
+    Ok(Arc::new(config))
+}
+
+/// Wrap bytes as a DER OCTET STRING, the usual encoding for opaque extension values
+fn der_octet_string(bytes: &[u8]) -> Vec<u8> {
+    let mut der = vec![0x04];
+    let len = bytes.len();
+    if len < 0x80 {
+        der.push(len as u8);
+    } else {
+        let len_bytes: Vec<u8> = len.to_be_bytes().into_iter().skip_while(|b| *b == 0).collect();
+        der.push(0x80 | len_bytes.len() as u8);
+        der.extend(len_bytes);
+    }
+    der.extend_from_slice(bytes);
+    der
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_der_octet_string_lengths() {
+        assert_eq!(der_octet_string(b"ab"), vec![0x04, 0x02, b'a', b'b']);
+        let long = der_octet_string(&[0u8; 300]);
+        assert_eq!(&long[..4], &[0x04, 0x82, 0x01, 0x2c]);
+        assert_eq!(long.len(), 304);
+    }
+
+    #[test]
+    fn test_report_data_is_padded_key_hash() {
+        let report_data = report_data_for_key(b"spki");
+        assert_eq!(&report_data[..32], digest(&SHA256, b"spki").as_ref());
+        assert!(report_data[32..].iter().all(|b| *b == 0));
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::state::AppState;
+
+/// Receipt options accepted by the execution endpoints
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, ToSchema)]
+pub struct AttestOptions {
+    /// Return a TEE-signed receipt binding the request to its result
+    #[serde(default)]
+    pub attest: bool,
+    /// Also include a quote whose report_data is the receipt digest
+    #[serde(default)]
+    pub attest_quote: bool,
+}
+
+impl AttestOptions {
+    /// Reject impossible options before anything runs
+    pub fn validate(&self) -> Result<()> {
+        if self.attest_quote && !self.attest {
+            return Err(AppError::BadRequest("attest_quote requires attest".into()));
+        }
+        if self.attest && !cfg!(feature = "tee") {
+            return Err(AppError::BadRequest(
+                "attest requires a build with the tee feature".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Proof of what the sandbox ran and what it produced.
+///
+/// `digest` is the SHA-256 of the canonical JSON (sorted keys, no whitespace)
+/// of `{kind, request_sha256, response_sha256, timestamp}`; the request and
+/// response hashes are over their canonical JSON in the same way.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Receipt {
+    pub kind: String,
+    pub request_sha256: String,
+    pub response_sha256: String,
+    pub timestamp: String,
+    pub digest: String,
+    /// dstack signature response over `digest`
+    pub signature: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote: Option<serde_json::Value>,
+}
+
+#[cfg(feature = "tee")]
+pub async fn attest(
+    state: &AppState,
+    kind: &str,
+    request: &impl Serialize,
+    response: &impl Serialize,
+    with_quote: bool,
+) -> Result<Receipt> {
+    let request_sha256 = hex::encode(sha256(canonical_json(request)?.as_bytes()));
+    let response_sha256 = hex::encode(sha256(canonical_json(response)?.as_bytes()));
+    let timestamp = chrono::Utc::now().to_rfc3339();
+
+    let body = serde_json::json!({
+        "kind": kind,
+        "request_sha256": request_sha256,
+        "response_sha256": response_sha256,
+        "timestamp": timestamp,
+    });
+    let digest = sha256(canonical_json(&body)?.as_bytes());
+
+    let signature = state
+        .tee_service
+        .sign("secp256k1", &digest)
+        .await
+        .map_err(|e| AppError::Internal(format!("Failed to sign receipt: {}", e)))?;
+
+    let quote = if with_quote {
+        let mut report_data = [0u8; 64];
+        report_data[..32].copy_from_slice(&digest);
+        let quote = state
+            .tee_service
+            .get_quote(&report_data)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to generate quote: {}", e)))?;
+        Some(to_value(&quote)?)
+    } else {
+        None
+    };
+
+    Ok(Receipt {
+        kind: kind.to_string(),
+        request_sha256,
+        response_sha256,
+        timestamp,
+        digest: hex::encode(digest),
+        signature: to_value(&signature)?,
+        quote,
+    })
+}
+
+#[cfg(not(feature = "tee"))]
+pub async fn attest(
+    _state: &AppState,
+    _kind: &str,
+    _request: &impl Serialize,
+    _response: &impl Serialize,
+    _with_quote: bool,
+) -> Result<Receipt> {
+    Err(AppError::BadRequest("attest requires a build with the tee feature".into()))
+}
+
+#[cfg(feature = "tee")]
+fn to_value(value: &impl Serialize) -> Result<serde_json::Value> {
+    serde_json::to_value(value).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    let digest = ring::digest::digest(&ring::digest::SHA256, data);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(digest.as_ref());
+    out
+}
+
+/// Serialize with object keys sorted and no whitespace, so hashes are reproducible
+pub fn canonical_json(value: &impl Serialize) -> Result<String> {
+    let value = serde_json::to_value(value).map_err(|e| AppError::Internal(e.to_string()))?;
+    Ok(sorted(value).to_string())
+}
+
+/// Rebuild objects with keys in order, whatever map type serde_json was built with
+fn sorted(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(entries.into_iter().map(|(k, v)| (k, sorted(v))).collect())
+        }
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(sorted).collect()),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_requires_attest() {
+        let options = AttestOptions { attest: false, attest_quote: true };
+        assert!(options.validate().is_err());
+        assert!(AttestOptions::default().validate().is_ok());
+    }
+
+    #[cfg(feature = "tee")]
+    #[test]
+    fn test_canonical_json_sorts_nested_keys() {
+        let value = serde_json::json!({"b": 1, "a": {"d": [{"f": 1, "e": 2}], "c": null}});
+        assert_eq!(canonical_json(&value).unwrap(), r#"{"a":{"c":null,"d":[{"e":2,"f":1}]},"b":1}"#);
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(feature = "tee")]
+use anyhow::Context;
+use anyhow::{anyhow, bail};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hkdf::{Salt, HKDF_SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+
+#[cfg(feature = "tee")]
+use crate::tee::TeeService;
+
+/// Derivation path of the storage key; changing it orphans every sealed file
+#[cfg(feature = "tee")]
+const SEALING_KEY_PATH: &str = "sandbox/sealed-storage";
+/// Leading bytes of a sealed file, also bound in as associated data
+const MAGIC: &[u8; 8] = b"SBXSEAL1";
+
+/// AES-256-GCM key for data at rest, derived from the TEE so only the same
+/// app in a CVM can recover it, or from a secret kept outside one.
+///
+/// Sealed layout: `MAGIC || nonce (12 bytes) || ciphertext || tag (16 bytes)`.
+pub struct SealingKey(LessSafeKey);
+
+impl SealingKey {
+    #[cfg(feature = "tee")]
+    pub async fn derive(tee: &TeeService) -> anyhow::Result<Self> {
+        Self::derive_at(tee, SEALING_KEY_PATH).await
+    }
+
+    /// The key derived at `path`, for data that must not share the storage key
+    #[cfg(feature = "tee")]
+    pub async fn derive_at(tee: &TeeService, path: &str) -> anyhow::Result<Self> {
+        let key = tee
+            .derive_key(Some(path), Some("encryption"))
+            .await
+            .context("failed to derive sealing key")?;
+        let secret = hex::decode(&key.key).context("derived key is not valid hex")?;
+        Self::from_secret(&secret)
+    }
+
+    pub fn from_secret(secret: &[u8]) -> anyhow::Result<Self> {
+        let prk = Salt::new(HKDF_SHA256, MAGIC).extract(secret);
+        let okm = prk
+            .expand(&[b"aes-256-gcm"], &AES_256_GCM)
+            .map_err(|_| anyhow!("failed to expand sealing key"))?;
+        Ok(Self(LessSafeKey::new(UnboundKey::from(okm))))
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| anyhow!("failed to generate nonce"))?;
+
+        let mut in_out = plaintext.to_vec();
+        self.0
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(MAGIC), &mut in_out)
+            .map_err(|_| anyhow!("encryption failed"))?;
+
+        let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend(in_out);
+        Ok(sealed)
+    }
+
+    pub fn unseal(&self, sealed: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let Some(rest) = sealed.strip_prefix(MAGIC.as_slice()) else {
+            bail!("not a sealed file");
+        };
+        if rest.len() < NONCE_LEN + AES_256_GCM.tag_len() {
+            bail!("sealed file is truncated");
+        }
+
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| anyhow!("invalid nonce"))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self
+            .0
+            .open_in_place(nonce, Aad::from(MAGIC), &mut in_out)
+            .map_err(|_| anyhow!("sealed by a different enclave or corrupted"))?;
+        Ok(plaintext.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_roundtrip() {
+        let key = SealingKey::from_secret(&[7u8; 32]).unwrap();
+        let sealed = key.seal(b"api-key=secret").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(key.unseal(&sealed).unwrap(), b"api-key=secret");
+    }
+
+    #[test]
+    fn test_unseal_rejects_other_keys_and_tampering() {
+        let key = SealingKey::from_secret(&[7u8; 32]).unwrap();
+        let other = SealingKey::from_secret(&[8u8; 32]).unwrap();
+        let mut sealed = key.seal(b"data").unwrap();
+
+        assert!(other.unseal(&sealed).is_err());
+        assert!(key.unseal(b"plain text").is_err());
+
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(key.unseal(&sealed).is_err());
+    }
+}
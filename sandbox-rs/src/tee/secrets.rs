@@ -0,0 +1,71 @@
+use tokio::process::Command;
+
+use crate::state::AppState;
+
+/// Derivation path of the key clients encrypt secrets to
+#[cfg(feature = "tee")]
+pub const SECRETS_KEY_PATH: &str = "sandbox/env-secrets";
+
+/// Secrets decrypted inside the enclave and injected into the environment of
+/// exec, code, and skill runs. Values live only in memory and are never
+/// serialized, logged, or written to disk.
+#[cfg(feature = "tee")]
+#[derive(Clone, Default)]
+pub struct SecretEnv {
+    vars: std::sync::Arc<dashmap::DashMap<String, String>>,
+}
+
+#[cfg(feature = "tee")]
+impl SecretEnv {
+    pub fn set(&self, name: String, value: String) {
+        self.vars.insert(name, value);
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        self.vars.remove(name).is_some()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.vars.iter().map(|v| v.key().clone()).collect();
+        names.sort();
+        names
+    }
+}
+
+/// Environment variable names: a letter or underscore, then letters, digits, or underscores
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid environment variable name: {}", name))
+    }
+}
+
+/// Add the injected secrets to a command's environment. Call before applying
+/// request `env`, so an explicit per-run value takes precedence.
+#[cfg(feature = "tee")]
+pub fn inject(state: &AppState, cmd: &mut Command) {
+    for var in state.secret_env.vars.iter() {
+        cmd.env(var.key(), var.value());
+    }
+}
+
+#[cfg(not(feature = "tee"))]
+pub fn inject(_state: &AppState, _cmd: &mut Command) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("OPENAI_API_KEY").is_ok());
+        assert!(validate_name("_private").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("1KEY").is_err());
+        assert!(validate_name("KEY=VALUE").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine as _};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+use tokio::sync::OnceCell;
+
+use crate::error::{AppError, Result};
+use crate::tee::keys::derive_ed25519;
+use crate::tee::{jwt, receipt, TeeService};
+
+/// Derivation path of the token signing key, separate from the `/tee/auth` key
+const TOKEN_KEY_PATH: &str = "sandbox/oidc";
+const DEFAULT_TTL: u64 = 300;
+const MAX_TTL: u64 = 3600;
+/// Claims the issuer sets; callers cannot override them through `claims`
+const RESERVED_CLAIMS: &[&str] = &["iss", "sub", "aud", "iat", "nbf", "exp", "jti"];
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TokenRequest {
+    pub audience: String,
+    /// Defaults to the dstack app ID
+    pub subject: Option<String>,
+    /// Lifetime in seconds, at most an hour
+    pub ttl: Option<u64>,
+    /// Extra claims to include
+    #[serde(default)]
+    pub claims: Map<String, Value>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct IssuedToken {
+    pub token: String,
+    pub expires_at: String,
+}
+
+/// An Ed25519 JWK, plus a quote binding it to this CVM
+#[derive(Clone, Serialize, ToSchema)]
+pub struct Jwk {
+    pub kty: &'static str,
+    pub crv: &'static str,
+    pub x: String,
+    pub kid: String,
+    #[serde(rename = "use")]
+    pub use_: &'static str,
+    pub alg: &'static str,
+    /// Hex TDX quote whose report_data is the SHA-256 of the raw public key, zero-padded
+    pub tee_quote: String,
+    pub tee_event_log: String,
+}
+
+/// Issues short-lived JWTs signed by a TEE-derived key and publishes that key
+pub struct TokenIssuer {
+    tee: TeeService,
+    issuer: String,
+    key: OnceCell<Ed25519KeyPair>,
+    jwk: OnceCell<Jwk>,
+    app_id: OnceCell<String>,
+}
+
+impl TokenIssuer {
+    pub fn new(tee: TeeService, issuer: String) -> Self {
+        Self {
+            tee,
+            issuer,
+            key: OnceCell::new(),
+            jwk: OnceCell::new(),
+            app_id: OnceCell::new(),
+        }
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    async fn key(&self) -> Result<&Ed25519KeyPair> {
+        self.key
+            .get_or_try_init(|| derive_ed25519(&self.tee, TOKEN_KEY_PATH, "signing"))
+            .await
+    }
+
+    /// The signing key as a JWK; the quote is taken once and reused
+    pub async fn jwk(&self) -> Result<&Jwk> {
+        self.jwk
+            .get_or_try_init(|| async {
+                let public_key = self.key().await?.public_key().as_ref().to_vec();
+                let digest = receipt::sha256(&public_key);
+
+                let mut report_data = [0u8; 64];
+                report_data[..32].copy_from_slice(&digest);
+                let quote = self
+                    .tee
+                    .get_quote(&report_data)
+                    .await
+                    .map_err(|e| AppError::Internal(format!("Failed to generate quote: {}", e)))?;
+
+                Ok(Jwk {
+                    kty: "OKP",
+                    crv: "Ed25519",
+                    x: BASE64URL.encode(&public_key),
+                    kid: hex::encode(&digest[..8]),
+                    use_: "sig",
+                    alg: "EdDSA",
+                    tee_quote: quote.quote,
+                    tee_event_log: quote.event_log,
+                })
+            })
+            .await
+    }
+
+    async fn app_id(&self) -> Result<&String> {
+        self.app_id
+            .get_or_try_init(|| async {
+                self.tee
+                    .info()
+                    .await
+                    .map(|info| info.app_id)
+                    .map_err(|e| AppError::Internal(format!("Failed to get TEE info: {}", e)))
+            })
+            .await
+    }
+
+    pub async fn issue(&self, req: TokenRequest) -> Result<IssuedToken> {
+        let ttl = req.ttl.unwrap_or(DEFAULT_TTL);
+        if ttl == 0 || ttl > MAX_TTL {
+            return Err(AppError::BadRequest(format!("ttl must be between 1 and {} seconds", MAX_TTL)));
+        }
+        if let Some(claim) = req.claims.keys().find(|k| RESERVED_CLAIMS.contains(&k.as_str())) {
+            return Err(AppError::BadRequest(format!("Claim '{}' is set by the issuer", claim)));
+        }
+
+        let subject = match req.subject {
+            Some(subject) => subject,
+            None => self.app_id().await?.clone(),
+        };
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::seconds(ttl as i64);
+
+        let mut claims = req.claims;
+        claims.insert("iss".into(), self.issuer.clone().into());
+        claims.insert("sub".into(), subject.into());
+        claims.insert("aud".into(), req.audience.into());
+        claims.insert("iat".into(), now.timestamp().into());
+        claims.insert("nbf".into(), now.timestamp().into());
+        claims.insert("exp".into(), expires_at.timestamp().into());
+        claims.insert("jti".into(), uuid::Uuid::new_v4().to_string().into());
+
+        let kid = self.jwk().await?.kid.clone();
+        let token = jwt::sign(self.key().await?, Some(&kid), &claims)
+            .map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))?;
+        Ok(IssuedToken { token, expires_at: expires_at.to_rfc3339() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(ttl: Option<u64>, claims: Value) -> TokenRequest {
+        TokenRequest {
+            audience: "https://api.example.com".into(),
+            subject: Some("agent".into()),
+            ttl,
+            claims: claims.as_object().cloned().unwrap_or_default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_issue_validates_before_signing() {
+        let issuer = TokenIssuer::new(TeeService::new(None), "sandbox".into());
+        assert!(issuer.issue(request(Some(0), Value::Null)).await.is_err());
+        assert!(issuer.issue(request(Some(MAX_TTL + 1), Value::Null)).await.is_err());
+        assert!(issuer
+            .issue(request(None, serde_json::json!({"exp": 9999999999u64})))
+            .await
+            .is_err());
+    }
+}
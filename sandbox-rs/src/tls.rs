@@ -0,0 +1,249 @@
+use anyhow::{anyhow, Context};
+use axum::extract::{ConnectInfo, Request};
+use axum::http::header;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::serve::Listener;
+use axum::Router;
+#[cfg(not(feature = "grpc"))]
+use hyper::server::conn::http1;
+use hyper_util::rt::TokioIo;
+use hyper_util::service::TowerToHyperService;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+use crate::shutdown::Shutdown;
+
+/// How often certificate files are checked for changes
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Serve the router over HTTPS. Handshakes and connections run on their own
+/// tasks, so a slow or misbehaving client cannot stall the accept loop. Once
+/// `shutdown` fires, no new connections are accepted and open ones finish
+/// their current request before this returns.
+pub async fn serve<L>(mut listener: L, app: Router, config: Arc<rustls::ServerConfig>, shutdown: Shutdown)
+where
+    L: Listener,
+    L::Addr: Clone + Send + Sync + fmt::Debug + 'static,
+{
+    let acceptor = TlsAcceptor::from(config);
+    let mut connections = JoinSet::new();
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            conn = listener.accept() => conn,
+            _ = shutdown.clone().triggered() => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    tracing::debug!("TLS handshake with {:?} failed: {}", peer, e);
+                    return;
+                }
+            };
+
+            // Expose the peer address to handlers, as `into_make_service_with_connect_info` does
+            let connect_info = ConnectInfo(peer.clone());
+            let app = app.map_request(move |mut request: hyper::Request<hyper::body::Incoming>| {
+                request.extensions_mut().insert(connect_info.clone());
+                request
+            });
+            let service = TowerToHyperService::new(app);
+            #[cfg(not(feature = "grpc"))]
+            let conn = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades();
+            #[cfg(feature = "grpc")]
+            let builder = hyper_util::server::conn::auto::Builder::new(hyper_util::rt::TokioExecutor::new());
+            #[cfg(feature = "grpc")]
+            let conn = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            tokio::pin!(conn);
+
+            let result = tokio::select! {
+                result = conn.as_mut() => result,
+                _ = shutdown.triggered() => {
+                    conn.as_mut().graceful_shutdown();
+                    conn.await
+                }
+            };
+            if let Err(e) = result {
+                tracing::debug!("Connection from {:?} ended with error: {}", peer, e);
+            }
+        });
+        // Reap finished connections so the set only holds open ones
+        while connections.try_join_next().is_some() {}
+    }
+
+    while connections.join_next().await.is_some() {}
+}
+
+/// Protocols offered in the TLS handshake; gRPC clients need HTTP/2
+pub fn alpn_protocols() -> Vec<Vec<u8>> {
+    if cfg!(feature = "grpc") {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    }
+}
+
+/// Serves whichever certificate was loaded last, so renewals apply without a restart
+#[derive(Debug)]
+struct ReloadingResolver {
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for ReloadingResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn load_certified_key(provider: &CryptoProvider, cert: &PathBuf, key: &PathBuf) -> anyhow::Result<Arc<CertifiedKey>> {
+    let certs = CertificateDer::pem_file_iter(cert)
+        .with_context(|| format!("failed to read {}", cert.display()))?
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("invalid certificate in {}", cert.display()))?;
+    if certs.is_empty() {
+        return Err(anyhow!("no certificates in {}", cert.display()));
+    }
+    let key = PrivateKeyDer::from_pem_file(key).with_context(|| format!("invalid private key in {}", key.display()))?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(key)
+        .context("unsupported private key type")?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+fn modified(paths: &[&PathBuf]) -> Option<Vec<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// Build a TLS config from PEM files and watch them, swapping in the new
+/// certificate when either file changes. A broken renewal keeps the old one.
+pub fn reloading_config(cert: PathBuf, key: PathBuf) -> anyhow::Result<Arc<rustls::ServerConfig>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let resolver = Arc::new(ReloadingResolver {
+        current: RwLock::new(load_certified_key(&provider, &cert, &key)?),
+    });
+
+    let mut config = rustls::ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .context("no usable TLS versions")?
+        .with_no_client_auth()
+        .with_cert_resolver(resolver.clone());
+    config.alpn_protocols = alpn_protocols();
+
+    tokio::spawn(async move {
+        let mut last = modified(&[&cert, &key]);
+        let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let current = modified(&[&cert, &key]);
+            if current.is_none() || current == last {
+                continue;
+            }
+            match load_certified_key(&provider, &cert, &key) {
+                Ok(certified) => {
+                    *resolver.current.write().unwrap() = certified;
+                    last = current;
+                    tracing::info!("Reloaded TLS certificate from {}", cert.display());
+                }
+                // Files may be mid-write; retry on the next tick
+                Err(e) => tracing::warn!("Failed to reload TLS certificate: {:#}", e),
+            }
+        }
+    });
+
+    Ok(Arc::new(config))
+}
+
+/// Answer plain HTTP on `port` with permanent redirects to HTTPS on `https_port`
+pub async fn serve_redirect(port: u16, https_port: u16) {
+    let redirect = move |request: Request| async move { https_redirect(&request, https_port) };
+    let app = Router::new().fallback(redirect);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = match TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to bind HTTP redirect listener on {}: {}", addr, e);
+            return;
+        }
+    };
+    tracing::info!("redirecting HTTP on {} to HTTPS", addr);
+    if let Err(e) = axum::serve(listener, app).await {
+        tracing::error!("HTTP redirect listener failed: {}", e);
+    }
+}
+
+fn https_redirect(request: &Request, https_port: u16) -> Response {
+    let Some(host) = request.headers().get(header::HOST).and_then(|h| h.to_str().ok()) else {
+        return (axum::http::StatusCode::BAD_REQUEST, "Missing Host header").into_response();
+    };
+    // Drop any port from Host, keeping IPv6 brackets intact
+    let host = match host.rsplit_once(':') {
+        Some((name, port)) if !name.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) && !port.is_empty() => name,
+        _ => host,
+    };
+    let path = request.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let location = if https_port == 443 {
+        format!("https://{}{}", host, path)
+    } else {
+        format!("https://{}:{}{}", host, https_port, path)
+    };
+    Redirect::permanent(&location).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(host: &str, uri: &str, https_port: u16) -> String {
+        let request = Request::builder().uri(uri).header(header::HOST, host).body(axum::body::Body::empty()).unwrap();
+        let response = https_redirect(&request, https_port);
+        assert_eq!(response.status(), axum::http::StatusCode::PERMANENT_REDIRECT);
+        response.headers()[header::LOCATION].to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_https_redirect_location() {
+        assert_eq!(location("example.com", "/a?b=1", 443), "https://example.com/a?b=1");
+        assert_eq!(location("example.com:80", "/", 8443), "https://example.com:8443/");
+        assert_eq!(location("[::1]:8080", "/x", 443), "https://[::1]/x");
+        assert_eq!(location("[::1]", "/x", 443), "https://[::1]/x");
+    }
+
+    #[test]
+    fn test_load_certified_key_rejects_bad_pem() {
+        let dir = std::env::temp_dir().join(format!("tls-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (cert, key) = (dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::write(&cert, "not a certificate").unwrap();
+        std::fs::write(&key, "not a key").unwrap();
+
+        let provider = rustls::crypto::ring::default_provider();
+        assert!(load_certified_key(&provider, &cert, &key).is_err());
+        assert!(load_certified_key(&provider, &dir.join("missing.pem"), &key).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
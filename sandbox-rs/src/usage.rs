@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::browser::BrowserService;
+use crate::state::AppState;
+
+/// Resource usage of the sandbox, as of `sampled_at`
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Usage {
+    pub sampled_at: DateTime<Utc>,
+    pub cpu: CpuUsage,
+    pub memory: MemoryUsage,
+    /// The workspace and /tmp
+    pub disks: Vec<DiskUsage>,
+    /// File descriptors open across all processes that can be inspected
+    pub open_fds: u64,
+    pub processes: u64,
+    /// Resident memory of Chromium and its children, when it is running
+    pub browser_memory_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CpuUsage {
+    /// Share of all cores used since the previous sample, 0-100; missing on the first sample
+    pub percent: Option<f64>,
+    pub cores: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MemoryUsage {
+    pub used_bytes: u64,
+    /// The container's memory limit, or the host's memory when there is none
+    pub limit_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DiskUsage {
+    pub path: String,
+    /// Size of the files under `path`, not counting other filesystems mounted inside it
+    pub used_bytes: u64,
+    /// Size and free space of the filesystem holding `path`
+    pub filesystem_bytes: Option<u64>,
+    pub available_bytes: Option<u64>,
+}
+
+/// Cumulative CPU time, read from the container's cgroup when there is one
+#[derive(Debug, Clone, Copy)]
+enum CpuCounter {
+    /// Microseconds of CPU used by the cgroup, measured against wall time
+    Cgroup { usage_usec: u64, at: Instant },
+    /// Busy and total jiffies from /proc/stat
+    Host { busy: u64, total: u64 },
+}
+
+/// Latest resource usage, refreshed in the background so requests never pay for a scan
+pub struct UsageCollector {
+    latest: RwLock<Option<Usage>>,
+}
+
+impl UsageCollector {
+    pub fn new() -> Self {
+        Self { latest: RwLock::new(None) }
+    }
+
+    /// The most recent sample, or `None` until the first one is taken
+    pub fn latest(&self) -> Option<Usage> {
+        self.latest.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+/// Sample usage every `usage_interval` seconds
+pub fn spawn_collector(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut previous = None;
+        let mut interval = tokio::time::interval(Duration::from_secs(state.config.usage_interval));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            let browser_memory_bytes = browser_memory(&state.browser).await;
+            let dirs = vec![state.config.workspace.clone(), std::env::temp_dir().display().to_string()];
+            let sampled = tokio::task::spawn_blocking(move || {
+                let counter = cpu_counter();
+                let usage = sample(&dirs, previous.zip(counter), browser_memory_bytes);
+                (usage, counter)
+            })
+            .await;
+            match sampled {
+                Ok((usage, counter)) => {
+                    previous = counter;
+                    *state.usage.latest.write().unwrap_or_else(PoisonError::into_inner) = Some(usage);
+                }
+                Err(e) => tracing::warn!("Usage sampling failed: {}", e),
+            }
+        }
+    });
+}
+
+async fn browser_memory(browser: &BrowserService) -> Option<u64> {
+    let process = browser.process()?;
+    tokio::task::spawn_blocking(move || process.memory_bytes()).await.ok().flatten()
+}
+
+fn sample(dirs: &[String], cpu: Option<(CpuCounter, CpuCounter)>, browser_memory_bytes: Option<u64>) -> Usage {
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let (processes, open_fds) = processes_and_fds();
+    Usage {
+        sampled_at: Utc::now(),
+        cpu: CpuUsage {
+            percent: cpu.and_then(|(before, after)| cpu_percent(before, after, cores)),
+            cores,
+        },
+        memory: memory(),
+        disks: dirs.iter().map(|dir| disk(dir)).collect(),
+        open_fds,
+        processes,
+        browser_memory_bytes,
+    }
+}
+
+fn cpu_counter() -> Option<CpuCounter> {
+    if let Some(usage_usec) = std::fs::read_to_string("/sys/fs/cgroup/cpu.stat")
+        .ok()
+        .and_then(|stat| field(&stat, "usage_usec"))
+    {
+        return Some(CpuCounter::Cgroup { usage_usec, at: Instant::now() });
+    }
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    parse_proc_stat(stat.lines().next()?)
+}
+
+/// Busy and total jiffies from the aggregate `cpu` line of /proc/stat
+fn parse_proc_stat(line: &str) -> Option<CpuCounter> {
+    let mut fields = line.split_whitespace();
+    if fields.next()? != "cpu" {
+        return None;
+    }
+    let values: Vec<u64> = fields.map(|v| v.parse().ok()).collect::<Option<_>>()?;
+    // user nice system idle iowait irq softirq steal; guest time is already in user
+    let total: u64 = values.iter().take(8).sum();
+    let idle = values.get(3)? + values.get(4).unwrap_or(&0);
+    Some(CpuCounter::Host { busy: total - idle, total })
+}
+
+fn cpu_percent(before: CpuCounter, after: CpuCounter, cores: usize) -> Option<f64> {
+    let share = match (before, after) {
+        (CpuCounter::Cgroup { usage_usec: u0, at: t0 }, CpuCounter::Cgroup { usage_usec: u1, at: t1 }) => {
+            let wall = t1.duration_since(t0).as_micros() as f64 * cores as f64;
+            (wall > 0.0).then(|| u1.saturating_sub(u0) as f64 / wall)?
+        }
+        (CpuCounter::Host { busy: b0, total: t0 }, CpuCounter::Host { busy: b1, total: t1 }) => {
+            let total = t1.saturating_sub(t0) as f64;
+            (total > 0.0).then(|| b1.saturating_sub(b0) as f64 / total)?
+        }
+        _ => return None,
+    };
+    Some((share * 100.0).clamp(0.0, 100.0))
+}
+
+/// The cgroup's memory use and limit, or the host's when not in a cgroup
+fn memory() -> MemoryUsage {
+    let cgroup_used = std::fs::read_to_string("/sys/fs/cgroup/memory.current")
+        .ok()
+        .and_then(|v| v.trim().parse().ok());
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    let host_total = field(&meminfo, "MemTotal:").map(|kb| kb * 1024);
+    if let Some(used_bytes) = cgroup_used {
+        // "max" when unlimited
+        let limit = std::fs::read_to_string("/sys/fs/cgroup/memory.max")
+            .ok()
+            .and_then(|v| v.trim().parse().ok());
+        return MemoryUsage { used_bytes, limit_bytes: limit.or(host_total) };
+    }
+    let available = field(&meminfo, "MemAvailable:").map(|kb| kb * 1024);
+    MemoryUsage {
+        used_bytes: host_total.zip(available).map_or(0, |(total, available)| total.saturating_sub(available)),
+        limit_bytes: host_total,
+    }
+}
+
+/// The number after `name` on the line that starts with it
+fn field(text: &str, name: &str) -> Option<u64> {
+    let line = text.lines().find(|line| line.starts_with(name))?;
+    line[name.len()..].split_whitespace().next()?.parse().ok()
+}
+
+fn disk(dir: &str) -> DiskUsage {
+    let (filesystem_bytes, available_bytes) = filesystem_space(Path::new(dir)).unzip();
+    DiskUsage {
+        path: dir.to_string(),
+        used_bytes: dir_size(Path::new(dir)),
+        filesystem_bytes,
+        available_bytes,
+    }
+}
+
+/// Total size of the files under `root`, staying on its filesystem and not following symlinks
+fn dir_size(root: &Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(root) else {
+        return 0;
+    };
+    let device = meta.dev();
+    let mut total = 0;
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                if meta.dev() == device {
+                    stack.push(entry.path());
+                }
+            } else {
+                total += meta.len();
+            }
+        }
+    }
+    total
+}
+
+/// Size and free space of the filesystem holding `path`
+fn filesystem_space(path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    let block = stat.f_frsize as u64;
+    Some((stat.f_blocks as u64 * block, stat.f_bavail as u64 * block))
+}
+
+/// Running processes, and file descriptors open across the ones we may inspect
+fn processes_and_fds() -> (u64, u64) {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return (0, 0);
+    };
+    let mut processes = 0;
+    let mut fds = 0;
+    for entry in entries.flatten() {
+        if !entry.file_name().to_str().is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit())) {
+            continue;
+        }
+        processes += 1;
+        if let Ok(open) = std::fs::read_dir(entry.path().join("fd")) {
+            fds += open.count() as u64;
+        }
+    }
+    (processes, fds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_percent_from_proc_stat() {
+        let before = parse_proc_stat("cpu  100 0 100 700 100 0 0 0 0 0").unwrap();
+        let after = parse_proc_stat("cpu  200 0 200 1300 100 0 0 0 0 0").unwrap();
+        assert_eq!(cpu_percent(before, after, 4), Some(25.0));
+        assert!(parse_proc_stat("cpu0 1 2 3 4").is_none());
+    }
+
+    #[test]
+    fn test_dir_size() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), [0u8; 100]).unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join("sub/b"), [0u8; 50]).unwrap();
+        assert_eq!(dir_size(dir.path()), 150);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_sample() {
+        let dir = tempfile::tempdir().unwrap();
+        let usage = sample(&[dir.path().display().to_string()], None, None);
+        assert!(usage.processes > 0);
+        assert!(usage.open_fds > 0);
+        assert!(usage.cpu.percent.is_none());
+        assert!(usage.disks[0].filesystem_bytes.unwrap() > 0);
+    }
+}
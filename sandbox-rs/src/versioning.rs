@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::sync::Arc;
+
+use crate::error::AppError;
+use crate::state::AppState;
+
+pub static X_API_VERSION: HeaderName = HeaderName::from_static("x-api-version");
+
+/// The API version served, and the prefix its routes live under
+pub const API_VERSION: &str = "1";
+pub const PREFIX: &str = "/v1";
+
+/// Serve `router` under `/v1` as well as at its unprefixed legacy paths
+pub fn prefixed<S: Clone + Send + Sync + 'static>(router: Router<S>) -> Router<S> {
+    Router::new().nest(PREFIX, router.clone()).merge(router)
+}
+
+/// `path` with any `/v1` prefix removed
+pub fn unprefixed(path: &str) -> &str {
+    path.strip_prefix(PREFIX)
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .unwrap_or(path)
+}
+
+/// Paths that are not part of the versioned API: probes, the API docs, discovery
+/// documents, and gRPC, whose package name carries its own version
+fn is_unversioned(path: &str) -> bool {
+    matches!(path, "/health" | "/ready" | "/version" | "/openapi.json")
+        || path.starts_with("/swagger-ui")
+        || path.starts_with("/.well-known/")
+        || path.starts_with("/sandbox.v1.")
+}
+
+/// Whether `path` is an unprefixed alias of a `/v1` route
+fn is_legacy(path: &str) -> bool {
+    !(path == PREFIX || path.starts_with(&format!("{}/", PREFIX)) || is_unversioned(path))
+}
+
+/// Negotiate the API version and mark legacy routes as deprecated.
+///
+/// A client may ask for a version with `X-API-Version` (`1` or `v1`); anything
+/// else is answered with 400. Every response says which version served it.
+/// Unprefixed routes carry `Deprecation` and a `Link` to their `/v1` successor,
+/// or answer 404 when `legacy_routes` is off.
+pub async fn negotiate(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    if let Some(requested) = request.headers().get(&X_API_VERSION) {
+        let requested = requested.to_str().unwrap_or_default().trim();
+        if requested.strip_prefix('v').unwrap_or(requested) != API_VERSION {
+            return with_version(
+                AppError::BadRequest(format!(
+                    "Unsupported API version \"{}\", this server supports {}",
+                    requested, API_VERSION
+                ))
+                .into_response(),
+            );
+        }
+    }
+
+    let path = request.uri().path();
+    if !is_legacy(path) {
+        return with_version(next.run(request).await);
+    }
+    if !state.config.legacy_routes {
+        return with_version(
+            AppError::NotFound(format!("Unversioned routes are disabled, use {}{}", PREFIX, path)).into_response(),
+        );
+    }
+    let successor = HeaderValue::from_str(&format!("<{}{}>; rel=\"successor-version\"", PREFIX, path));
+
+    let mut response = with_version(next.run(request).await);
+    response.headers_mut().insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = successor {
+        response.headers_mut().append(header::LINK, link);
+    }
+    response
+}
+
+fn with_version(mut response: Response) -> Response {
+    response.headers_mut().insert(X_API_VERSION.clone(), HeaderValue::from_static(API_VERSION));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_paths() {
+        assert!(is_legacy("/shell/exec"));
+        assert!(is_legacy("/ws"));
+        assert!(is_legacy("/v1beta/shell/exec"));
+        assert!(!is_legacy("/v1/shell/exec"));
+        assert!(!is_legacy("/health"));
+        assert!(!is_legacy("/swagger-ui/index.html"));
+        assert!(!is_legacy("/.well-known/jwks.json"));
+        assert!(!is_legacy("/sandbox.v1.Shell/Exec"));
+    }
+}
@@ -0,0 +1,448 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
+
+use crate::error::{AppError, Result};
+use crate::request_id::RequestId;
+use crate::state::AppState;
+use crate::storage::Storage;
+
+/// Events a webhook can subscribe to
+pub const EVENTS: &[&str] = &["job.completed", "skill.created", "factory.completed", "file.changed", "error"];
+
+/// Attempts per delivery, and the wait before the first retry; it doubles after each
+const MAX_ATTEMPTS: u32 = 5;
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+
+/// Time allowed for a receiver to answer
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// File changes closer together than this are sent as one `file.changed` event
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// A webhook as registered and saved; `secret` never leaves the server
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Webhook {
+    id: String,
+    url: String,
+    secret: Option<String>,
+    events: Vec<String>,
+    watch: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// Public description of a webhook and how its deliveries are going
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WebhookInfo {
+    pub id: String,
+    pub url: String,
+    /// Subscribed events; empty means all of them
+    pub events: Vec<String>,
+    /// Directory or file whose changes are sent as `file.changed`
+    pub watch: Option<String>,
+    /// Whether deliveries carry an `X-Webhook-Signature`
+    pub signed: bool,
+    pub created_at: DateTime<Utc>,
+    /// Deliveries that were accepted with a 2xx, and those that gave up after retrying
+    pub delivered: u64,
+    pub failed: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterWebhookRequest {
+    /// http or https URL that receives a POST per event
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature of each delivery
+    pub secret: Option<String>,
+    /// Events to send; all of them when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Watch this path, relative to the workspace unless absolute, for `file.changed`
+    pub watch: Option<String>,
+}
+
+#[derive(Default)]
+struct Stats {
+    delivered: u64,
+    failed: u64,
+    last_error: Option<String>,
+}
+
+/// A registered webhook with its delivery counters and file watcher
+struct Registered {
+    hook: Webhook,
+    stats: Mutex<Stats>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl Registered {
+    fn info(&self) -> WebhookInfo {
+        let stats = self.stats.lock().unwrap_or_else(PoisonError::into_inner);
+        WebhookInfo {
+            id: self.hook.id.clone(),
+            url: self.hook.url.clone(),
+            events: self.hook.events.clone(),
+            watch: self.hook.watch.clone(),
+            signed: self.hook.secret.is_some(),
+            created_at: self.hook.created_at,
+            delivered: stats.delivered,
+            failed: stats.failed,
+            last_error: stats.last_error.clone(),
+        }
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.hook.events.is_empty() || self.hook.events.iter().any(|e| e == event)
+    }
+}
+
+/// Key under which webhooks are saved across restarts
+const SAVED_KEY: &str = "webhooks.json";
+
+/// Registered webhooks, saved to `store` whenever they change
+pub struct Webhooks {
+    store: Storage,
+    client: reqwest::Client,
+    hooks: DashMap<String, Arc<Registered>>,
+}
+
+impl Webhooks {
+    pub fn new(store: Storage) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .user_agent(concat!("sandbox-api/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .expect("TLS backend is available");
+        Self { store, client, hooks: DashMap::new() }
+    }
+
+    /// Register a webhook, starting its file watch if it has one
+    pub fn register(self: &Arc<Self>, state: &AppState, req: RegisterWebhookRequest) -> Result<WebhookInfo> {
+        let url = url::Url::parse(&req.url).map_err(|e| AppError::BadRequest(format!("Invalid URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(AppError::BadRequest("Webhook URL must be http or https".into()));
+        }
+        if let Some(event) = req.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown event \"{}\", expected some of {}",
+                event,
+                EVENTS.join(", ")
+            )));
+        }
+        let watch = req
+            .watch
+            .map(|path| state.resolve(&path).map(|path| path.display().to_string()))
+            .transpose()?;
+
+        let hook = Webhook {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: req.url,
+            secret: req.secret.filter(|secret| !secret.is_empty()),
+            events: req.events,
+            watch,
+            created_at: Utc::now(),
+        };
+        let info = self.insert(hook)?.info();
+        self.save();
+        Ok(info)
+    }
+
+    fn insert(self: &Arc<Self>, hook: Webhook) -> Result<Arc<Registered>> {
+        let watcher = match &hook.watch {
+            Some(path) if hook.events.is_empty() || hook.events.iter().any(|e| e == "file.changed") => {
+                let watcher = self
+                    .watch(&hook.id, Path::new(path))
+                    .map_err(|e| AppError::BadRequest(format!("Failed to watch {}: {}", path, e)))?;
+                Some(watcher)
+            }
+            _ => None,
+        };
+        let registered = Arc::new(Registered { hook, stats: Mutex::default(), _watcher: watcher });
+        self.hooks.insert(registered.hook.id.clone(), registered.clone());
+        Ok(registered)
+    }
+
+    pub fn list(&self) -> Vec<WebhookInfo> {
+        let mut hooks: Vec<_> = self.hooks.iter().map(|entry| entry.value().info()).collect();
+        hooks.sort_by_key(|hook| hook.created_at);
+        hooks
+    }
+
+    /// Forget a webhook, stopping its file watch; deliveries under way still finish
+    pub fn remove(&self, id: &str) -> Result<()> {
+        if self.hooks.remove(id).is_none() {
+            return Err(AppError::NotFound(format!("Webhook '{}' not found", id)).with_code("WEBHOOK_NOT_FOUND"));
+        }
+        self.save();
+        Ok(())
+    }
+
+    /// Send a `ping` event to one webhook and wait for the outcome
+    pub async fn ping(&self, id: &str) -> Result<()> {
+        let hook = self
+            .hooks
+            .get(id)
+            .map(|entry| entry.value().clone())
+            .ok_or_else(|| AppError::NotFound(format!("Webhook '{}' not found", id)).with_code("WEBHOOK_NOT_FOUND"))?;
+        let payload = payload("ping", None, json!({ "webhook": id }));
+        self.delivery().attempt(&hook.hook, &payload).await.map_err(AppError::BadRequest)
+    }
+
+    /// Send `event` to every webhook subscribed to it, in the background
+    pub fn emit(&self, event: &'static str, session: Option<&str>, data: Value) {
+        let targets: Vec<_> = self
+            .hooks
+            .iter()
+            .filter(|entry| entry.value().wants(event))
+            .map(|entry| entry.value().clone())
+            .collect();
+        if targets.is_empty() {
+            return;
+        }
+        let payload = payload(event, session, data);
+        for hook in targets {
+            tokio::spawn(self.delivery().deliver(hook, payload.clone()));
+        }
+    }
+
+    fn delivery(&self) -> Delivery {
+        Delivery { client: self.client.clone() }
+    }
+
+    /// Send coalesced changes under `path` to the webhook `id`
+    fn watch(self: &Arc<Self>, id: &str, path: &Path) -> notify::Result<RecommendedWatcher> {
+        let (changes, mut received) = mpsc::unbounded_channel::<(&'static str, Vec<PathBuf>)>();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let kind = match event.kind {
+                EventKind::Create(_) => "create",
+                EventKind::Modify(_) => "modify",
+                EventKind::Remove(_) => "remove",
+                EventKind::Access(_) => return,
+                EventKind::Any | EventKind::Other => "other",
+            };
+            let _ = changes.send((kind, event.paths));
+        })?;
+        watcher.watch(path, RecursiveMode::Recursive)?;
+
+        // Holds only a weak reference, so removing the webhook drops the watcher and ends this task
+        let webhooks = Arc::downgrade(self);
+        let id = id.to_string();
+        let watched = path.display().to_string();
+        tokio::spawn(async move {
+            while let Some(first) = received.recv().await {
+                let mut kinds = BTreeSet::from([first.0]);
+                let mut paths: BTreeSet<PathBuf> = first.1.into_iter().collect();
+                let deadline = tokio::time::Instant::now() + WATCH_DEBOUNCE;
+                while let Ok(Some((kind, more))) = tokio::time::timeout_at(deadline, received.recv()).await {
+                    kinds.insert(kind);
+                    paths.extend(more);
+                }
+                let Some(webhooks) = webhooks.upgrade() else {
+                    return;
+                };
+                let Some(hook) = webhooks.hooks.get(&id).map(|entry| entry.value().clone()) else {
+                    return;
+                };
+                let data = json!({
+                    "watch": watched,
+                    "kinds": kinds,
+                    "paths": paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>(),
+                });
+                tokio::spawn(webhooks.delivery().deliver(hook, payload("file.changed", None, data)));
+            }
+        });
+        Ok(watcher)
+    }
+
+    /// Re-register the webhooks saved by a previous run
+    pub fn restore(self: &Arc<Self>) -> usize {
+        let saved: Vec<Webhook> = match self.store.read(SAVED_KEY) {
+            Ok(Some(data)) => match serde_json::from_slice(&data) {
+                Ok(hooks) => hooks,
+                Err(e) => {
+                    tracing::warn!("Ignoring saved webhooks: {}", e);
+                    return 0;
+                }
+            },
+            Ok(None) => return 0,
+            Err(e) => {
+                tracing::warn!("Failed to read saved webhooks: {}", e);
+                return 0;
+            }
+        };
+        for hook in saved {
+            let id = hook.id.clone();
+            if let Err(e) = self.insert(hook) {
+                tracing::warn!("Failed to restore webhook {}: {}", id, e);
+            }
+        }
+        self.hooks.len()
+    }
+
+    fn save(&self) {
+        let hooks: Vec<Webhook> = self.hooks.iter().map(|entry| entry.value().hook.clone()).collect();
+        let written = serde_json::to_vec_pretty(&hooks)
+            .map_err(std::io::Error::other)
+            .and_then(|data| self.store.write(SAVED_KEY, &data));
+        if let Err(e) = written {
+            tracing::warn!("Failed to save webhooks: {}", e);
+        }
+    }
+}
+
+/// What a delivery task needs, so it does not keep the registry alive
+struct Delivery {
+    client: reqwest::Client,
+}
+
+impl Delivery {
+    /// POST `payload`, retrying with exponential backoff on network errors, 408, 429, and 5xx
+    async fn deliver(self, hook: Arc<Registered>, payload: Value) {
+        let mut wait = FIRST_RETRY;
+        for attempt in 1..=MAX_ATTEMPTS {
+            match self.attempt(&hook.hook, &payload).await {
+                Ok(()) => {
+                    let mut stats = hook.stats.lock().unwrap_or_else(PoisonError::into_inner);
+                    stats.delivered += 1;
+                    return;
+                }
+                Err(e) if attempt == MAX_ATTEMPTS || !retryable(&e) => {
+                    tracing::warn!("Webhook {} gave up on {}: {}", hook.hook.id, payload["event"], e);
+                    let mut stats = hook.stats.lock().unwrap_or_else(PoisonError::into_inner);
+                    stats.failed += 1;
+                    stats.last_error = Some(e);
+                    return;
+                }
+                Err(e) => {
+                    tracing::debug!("Webhook {} attempt {} failed: {}", hook.hook.id, attempt, e);
+                    tokio::time::sleep(wait).await;
+                    wait *= 2;
+                }
+            }
+        }
+    }
+
+    async fn attempt(&self, hook: &Webhook, payload: &Value) -> std::result::Result<(), String> {
+        let body = serde_json::to_vec(payload).map_err(|e| e.to_string())?;
+        let timestamp = Utc::now().timestamp().to_string();
+        let mut request = self
+            .client
+            .post(&hook.url)
+            .header("content-type", "application/json")
+            .header("x-webhook-id", payload["id"].as_str().unwrap_or_default())
+            .header("x-webhook-event", payload["event"].as_str().unwrap_or_default())
+            .header("x-webhook-timestamp", &timestamp);
+        if let Some(secret) = &hook.secret {
+            request = request.header("x-webhook-signature", sign(secret, &timestamp, &body));
+        }
+        let response = request.body(body).send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", status.as_u16()))
+        }
+    }
+}
+
+/// Whether a failed attempt is worth repeating: anything but a definite rejection
+fn retryable(error: &str) -> bool {
+    match error.strip_prefix("HTTP ").and_then(|status| status.parse::<u16>().ok()) {
+        Some(status) => status == 408 || status == 429 || status >= 500,
+        None => true,
+    }
+}
+
+fn payload(event: &str, session: Option<&str>, data: Value) -> Value {
+    json!({
+        "id": uuid::Uuid::new_v4().to_string(),
+        "event": event,
+        "created_at": Utc::now(),
+        "session": session,
+        "data": data,
+    })
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `timestamp.body`, so a captured delivery cannot be
+/// replayed later with a fresh timestamp
+fn sign(secret: &str, timestamp: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let mut context = hmac::Context::with_key(&key);
+    context.update(timestamp.as_bytes());
+    context.update(b".");
+    context.update(body);
+    let tag = context.sign();
+    let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Send `event` on behalf of the session `state` belongs to, if any
+pub fn emit(state: &AppState, event: &'static str, data: Value) {
+    state.webhooks.emit(event, state.session.as_deref(), data);
+}
+
+/// Send an `error` event for every 5xx response
+pub async fn notify_errors(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let request_id = request.extensions().get::<RequestId>().map(|RequestId(id)| id.clone());
+    let response = next.run(request).await;
+    if response.status().is_server_error() {
+        emit(
+            &state,
+            "error",
+            json!({
+                "method": method,
+                "path": path,
+                "status": response.status().as_u16(),
+                "request_id": request_id,
+            }),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        // HMAC-SHA256("key", "1700000000.{}")
+        let expected = {
+            let key = hmac::Key::new(hmac::HMAC_SHA256, b"key");
+            let tag = hmac::sign(&key, b"1700000000.{}");
+            format!("sha256={}", tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect::<String>())
+        };
+        assert_eq!(sign("key", "1700000000", b"{}"), expected);
+        assert_ne!(sign("other", "1700000000", b"{}"), expected);
+        assert_ne!(sign("key", "1700000001", b"{}"), expected);
+    }
+
+    #[test]
+    fn test_retryable() {
+        assert!(retryable("HTTP 503"));
+        assert!(retryable("HTTP 429"));
+        assert!(retryable("error sending request"));
+        assert!(!retryable("HTTP 404"));
+        assert!(!retryable("HTTP 401"));
+    }
+}
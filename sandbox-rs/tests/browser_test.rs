@@ -99,6 +99,60 @@ async fn test_browser_screenshot() {
     assert!(!data.is_empty(), "Screenshot data should not be empty");
 }
 
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_screenshot_full_page_jpeg() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/screenshot", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "full_page": true,
+            "format": "jpeg",
+            "quality": 70
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["format"], "jpeg");
+    assert!(body["height"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_screenshot_clip() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/screenshot", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "clip": { "x": 0, "y": 0, "width": 200, "height": 100 }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["width"], 200);
+    assert_eq!(body["height"], 100);
+}
+
 #[tokio::test]
 #[ignore] // Requires running server with Chromium
 async fn test_browser_evaluate() {
@@ -277,3 +331,865 @@ async fn test_browser_goto_invalid_url() {
     // Should fail with navigation error (500)
     assert!(resp.status().is_client_error() || resp.status().is_server_error());
 }
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_pdf() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/pdf", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "format": "a4",
+            "landscape": true,
+            "margin": { "top": 0.5, "bottom": 0.5 }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["data"].is_string());
+    assert!(body["size"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_pdf_invalid_format() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/pdf", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "format": "postcard"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_content() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/content", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "include_html": false
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["title"].as_str().unwrap().contains("Example"));
+    assert!(body["text"].as_str().unwrap().contains("Example Domain"));
+    assert!(body.get("html").is_none());
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_content_missing_selector() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/content", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "selector": "#nonexistent-element-12345"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_fill_invalid_field() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    // A field must set exactly one of value/check/select
+    let resp = client
+        .post(format!("{}/browser/fill", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "fields": [{ "selector": "input", "value": "x", "check": true }]
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_fill_nonexistent() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/fill", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "fields": [{ "selector": "#nonexistent-input-12345", "value": "hello" }]
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_har_capture() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/har/start", base_url))
+        .json(&json!({ "url": "https://example.com" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let har_id = body["har_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/browser/har/stop", base_url))
+        .json(&json!({ "har_id": har_id }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["har"]["log"]["version"], "1.2");
+    assert!(body["entries"].as_u64().unwrap() >= 1);
+
+    // The session is gone once stopped
+    let resp = client
+        .post(format!("{}/browser/har/stop", base_url))
+        .json(&json!({ "har_id": har_id }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_tabs() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "https://example.com", "new_tab": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    // The tab is listed and usable by later actions without a URL
+    let resp = client
+        .get(format!("{}/browser/pages", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let pages = body["pages"].as_array().unwrap();
+    assert!(pages.iter().any(|p| p["page_id"] == page_id.as_str()));
+
+    let resp = client
+        .post(format!("{}/browser/evaluate", base_url))
+        .json(&json!({ "page_id": page_id, "script": "document.title" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["result"].as_str().unwrap().contains("Example"));
+
+    let resp = client
+        .delete(format!("{}/browser/pages/{}", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{}/browser/pages/{}/activate", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+async fn test_browser_proxy_requires_new_tab() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "proxy": { "server": "http://127.0.0.1:3128" }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_goto_emulation() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "new_tab": true,
+            "user_agent": "sandbox-test-agent",
+            "viewport": { "width": 400, "height": 300, "device_scale_factor": 2 }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/browser/evaluate", base_url))
+        .json(&json!({
+            "page_id": page_id,
+            "script": "[navigator.userAgent, window.innerWidth, window.devicePixelRatio].join(',')"
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["result"], "sandbox-test-agent,400,2");
+
+    client
+        .delete(format!("{}/browser/pages/{}", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+}
+
+#[tokio::test]
+async fn test_browser_download_requires_trigger() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/download", base_url))
+        .json(&json!({}))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .get(format!("{}/browser/downloads", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["downloads"].is_array());
+}
+
+#[tokio::test]
+async fn test_browser_press_unknown_key() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/press", base_url))
+        .json(&json!({ "key": "Hyper+NotAKey" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_scroll_and_press() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let page = "data:text/html,<body style='height:5000px'><input id=q></body>";
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": page, "new_tab": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/browser/scroll", base_url))
+        .json(&json!({ "page_id": page_id, "y": 1000 }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["scroll_y"], 1000.0);
+
+    let resp = client
+        .post(format!("{}/browser/press", base_url))
+        .json(&json!({ "page_id": page_id, "selector": "#q", "key": "Shift+a" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{}/browser/evaluate", base_url))
+        .json(&json!({ "page_id": page_id, "script": "document.getElementById('q').value" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["result"], "A");
+
+    client
+        .delete(format!("{}/browser/pages/{}", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+}
+
+#[tokio::test]
+async fn test_browser_select_requires_one_choice() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/select", base_url))
+        .json(&json!({ "selector": "#country", "value": "ca", "index": 2 }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_select() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let page = "data:text/html,<select id=s><option value=a>Alpha</option><option value=b>Beta</option></select><input id=c type=checkbox>";
+
+    let resp = client
+        .post(format!("{}/browser/select", base_url))
+        .json(&json!({ "url": page, "selector": "#s", "label": "Beta" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["selected"], json!(["b"]));
+
+    let resp = client
+        .post(format!("{}/browser/select", base_url))
+        .json(&json!({ "url": page, "selector": "#c", "checked": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["checked"], true);
+
+    let resp = client
+        .post(format!("{}/browser/select", base_url))
+        .json(&json!({ "url": page, "selector": "#s", "value": "zzz" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+async fn test_browser_capture_rejects_unknown_format() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/capture", base_url))
+        .json(&json!({ "url": "https://example.com", "format": "warc" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_capture() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    for format in ["mhtml", "html"] {
+        let resp = client
+            .post(format!("{}/browser/capture", base_url))
+            .json(&json!({ "url": "https://example.com", "format": format }))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        assert!(body["path"].as_str().unwrap().ends_with(format));
+        assert!(body["size"].as_u64().unwrap() > 0);
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_status_after_launch() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "https://example.com", "new_tab": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .get(format!("{}/browser/status", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["running"], true);
+    assert!(body["version"].as_str().unwrap().contains("Chrome"));
+    assert!(body["uptime_secs"].as_f64().unwrap() > 0.0);
+    assert!(body["open_targets"].as_u64().unwrap() >= 1);
+    assert!(body["pages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|p| p["page_id"] == page_id.as_str()));
+
+    let resp = client
+        .get(format!("{}/health", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["services"]["browser"], true);
+
+    client
+        .delete(format!("{}/browser/pages/{}", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+}
+
+#[tokio::test]
+async fn test_browser_record_validation() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/record/start", base_url))
+        .json(&json!({ "url": "https://example.com", "format": "gif" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .post(format!("{}/browser/record/stop", base_url))
+        .json(&json!({ "recording_id": "does-not-exist" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium and ffmpeg
+async fn test_browser_record() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/record/start", base_url))
+        .json(&json!({ "url": "https://example.com" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let recording_id = body["recording_id"].as_str().unwrap().to_string();
+
+    sleep(Duration::from_secs(1)).await;
+
+    let resp = client
+        .post(format!("{}/browser/record/stop", base_url))
+        .json(&json!({ "recording_id": recording_id }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["path"].as_str().unwrap().ends_with(".webm"));
+    assert!(body["frames"].as_u64().unwrap() > 0);
+    assert!(body["size"].as_u64().unwrap() > 0);
+}
+
+#[tokio::test]
+async fn test_browser_dialog_policy_requires_tab() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "https://example.com", "dialog": { "action": "dismiss" } }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+
+    let resp = client
+        .get(format!("{}/browser/pages/does-not-exist/console", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 404);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_dialogs_are_handled() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({
+            "url": "https://example.com",
+            "new_tab": true,
+            "dialog": { "action": "accept", "prompt_text": "sandbox" }
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    // Would hang without a handler
+    let resp = client
+        .post(format!("{}/browser/evaluate", base_url))
+        .json(&json!({ "page_id": page_id, "script": "console.log('hi'); prompt('name?')" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["result"], "sandbox");
+
+    let resp = client
+        .get(format!("{}/browser/pages/{}/console", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let entries = body["entries"].as_array().unwrap();
+    assert!(entries.iter().any(|e| e["kind"] == "console" && e["text"] == "hi"));
+    assert!(entries.iter().any(|e| e["kind"] == "dialog" && e["action"] == "accepted"));
+
+    client
+        .delete(format!("{}/browser/pages/{}", base_url, page_id))
+        .send()
+        .await
+        .expect("Failed to send request");
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_query() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/query", base_url))
+        .json(&json!({ "url": "https://example.com", "selector": "a", "attributes": ["href", "missing"] }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["exists"], true);
+    assert!(body["count"].as_u64().unwrap() >= 1);
+    let first = &body["matches"][0];
+    assert_eq!(first["tag"], "a");
+    assert!(first["attributes"]["href"].is_string());
+    assert!(first["attributes"]["missing"].is_null());
+    assert!(first["bounding_box"]["width"].as_f64().unwrap() > 0.0);
+
+    let resp = client
+        .post(format!("{}/browser/query", base_url))
+        .json(&json!({ "url": "https://example.com", "selector": "#nope" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["exists"], false);
+    assert_eq!(body["count"], 0);
+}
+
+#[tokio::test]
+async fn test_browser_upload_requires_existing_files() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    for files in [json!([]), json!(["does/not/exist.txt"])] {
+        let resp = client
+            .post(format!("{}/browser/upload", base_url))
+            .json(&json!({ "url": "https://example.com", "selector": "input", "files": files }))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 400);
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_upload() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    client
+        .post(format!("{}/file/write", base_url))
+        .json(&json!({ "path": "upload-test.txt", "content": "hello" }))
+        .send()
+        .await
+        .expect("Failed to write file");
+
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "data:text/html,<input type=file id=f><input id=t>", "new_tab": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/browser/upload", base_url))
+        .json(&json!({ "page_id": page_id, "selector": "#f", "files": ["upload-test.txt"] }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+
+    let resp = client
+        .post(format!("{}/browser/evaluate", base_url))
+        .json(&json!({ "page_id": page_id, "script": "document.getElementById('f').files[0].name" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["result"], "upload-test.txt");
+
+    // Not a file input
+    let resp = client
+        .post(format!("{}/browser/upload", base_url))
+        .json(&json!({ "page_id": page_id, "selector": "#t", "files": ["upload-test.txt"] }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with BROWSER_URL_DENY=169.254.0.0/16
+async fn test_browser_url_policy_blocks_goto() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "http://169.254.169.254/latest/meta-data/" }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 403);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["error"].as_str().unwrap().contains("deny rule"));
+}
+
+#[tokio::test]
+async fn test_browser_fill_wait_requires_submit() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/fill", base_url))
+        .json(&json!({ "url": "https://example.com", "fields": [], "wait_for_navigation": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 400);
+}
+
+#[tokio::test]
+#[ignore] // Requires running server with Chromium
+async fn test_browser_click_waits_for_navigation() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/browser/goto", base_url))
+        .json(&json!({ "url": "https://example.com", "new_tab": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    let page_id = body["page_id"].as_str().unwrap().to_string();
+
+    let resp = client
+        .post(format!("{}/browser/click", base_url))
+        .json(&json!({ "page_id": page_id, "selector": "a", "wait_for_navigation": true }))
+        .send()
+        .await
+        .expect("Failed to send request");
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["success"], true);
+    assert!(body["url"].as_str().unwrap().contains("iana.org"));
+    assert!(!body["title"].as_str().unwrap().is_empty());
+}
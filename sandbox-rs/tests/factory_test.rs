@@ -201,7 +201,7 @@ async fn test_check_trigger_phrases() {
     assert_eq!(body["triggers_factory"], true);
     assert!(body["matched_phrases"].is_array());
     let phrases = body["matched_phrases"].as_array().unwrap();
-    assert!(phrases.len() > 0);
+    assert!(!phrases.is_empty());
     assert!(phrases.iter().any(|p| p.as_str().unwrap() == "teach me"));
 
     // Test "create a skill" trigger
@@ -129,3 +129,29 @@ async fn test_file_download() {
     let content = resp.text().await.expect("Failed to get body");
     assert_eq!(content, "download content");
 }
+
+#[tokio::test]
+async fn test_file_write_body_too_large() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+
+    // Default MAX_BODY_SIZE is 2 MiB
+    let resp = client
+        .post(format!("{}/file/write", base_url))
+        .json(&json!({
+            "path": "/tmp/too_large.txt",
+            "content": "x".repeat(3 * 1024 * 1024)
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 413);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert_eq!(body["limit"], 2 * 1024 * 1024);
+}
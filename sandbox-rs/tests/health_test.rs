@@ -38,6 +38,7 @@ async fn test_health_check() {
     let body: Value = resp.json().await.expect("Failed to parse JSON");
     assert_eq!(body["status"], "healthy");
     assert!(body["uptime"].as_f64().unwrap() >= 0.0);
+    assert!(body["services"]["browser"].is_boolean());
 }
 
 #[tokio::test]
@@ -60,3 +61,26 @@ async fn test_sandbox_info() {
     assert!(body["workspace"].as_str().is_some());
     assert!(body["display"].as_str().is_some());
 }
+
+#[tokio::test]
+async fn test_openapi_spec() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .get(format!("{}/openapi.json", base_url))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 200);
+
+    let body: Value = resp.json().await.expect("Failed to parse JSON");
+    assert!(body["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(body["paths"]["/shell/exec"]["post"].is_object());
+    assert!(body["paths"]["/browser/pages/{page_id}"]["delete"].is_object());
+    assert!(body["components"]["schemas"]["ShellExecRequest"].is_object());
+}
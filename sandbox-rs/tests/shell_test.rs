@@ -112,3 +112,24 @@ async fn test_shell_exec_nonzero_exit() {
     let body: Value = resp.json().await.expect("Failed to parse JSON");
     assert_eq!(body["exit_code"], 42);
 }
+
+#[tokio::test]
+async fn test_shell_exec_attest_quote_requires_attest() {
+    let base_url =
+        std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+    wait_for_server(&base_url).await;
+
+    let client = Client::new();
+    let resp = client
+        .post(format!("{}/shell/exec", base_url))
+        .json(&json!({
+            "command": "echo hello",
+            "attest_quote": true
+        }))
+        .send()
+        .await
+        .expect("Failed to send request");
+
+    assert_eq!(resp.status(), 400);
+}
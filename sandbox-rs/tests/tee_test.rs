@@ -59,6 +59,11 @@ mod tee_tests {
 
         // The exact fields depend on dstack SDK's InfoResponse structure
         // Common fields might include: instance_id, attestation_type, etc.
+
+        // Startup measurements are extended into RTMR3 and reported alongside
+        let measurements = &body["sandbox_measurements"];
+        assert_eq!(measurements["config_sha256"].as_str().unwrap().len(), 64);
+        assert_eq!(measurements["skills_sha256"].as_str().unwrap().len(), 64);
     }
 
     #[tokio::test]
@@ -342,6 +347,82 @@ mod tee_tests {
         assert!(message.contains("emitted successfully"));
     }
 
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_event_log_replays_emitted_event() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .post(format!("{}/tee/emit-event", base_url))
+            .json(&json!({"event": "event-log-test", "payload": "replay me"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 200);
+
+        let resp = client
+            .get(format!("{}/tee/event-log", base_url))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        let events = body["events"].as_array().expect("events should be an array");
+        assert!(events.iter().any(|e| e["event"] == "event-log-test"));
+        assert_eq!(body["replayed_rtmrs"].as_array().unwrap().len(), 4);
+        assert_eq!(body["matches"], true);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_named_key_sign() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .post(format!("{}/tee/keys", base_url))
+            .json(&json!({"name": "test-signing", "path": "test/signing"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let key: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(key["algorithm"], "ed25519");
+        assert_eq!(key["public_key"].as_str().unwrap().len(), 64);
+
+        let resp = client
+            .post(format!("{}/tee/sign", base_url))
+            .json(&json!({"key": "test-signing", "data": "68656c6c6f"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["public_key"], key["public_key"]);
+        assert_eq!(body["signature"].as_str().unwrap().len(), 128);
+
+        let resp = client
+            .post(format!("{}/tee/sign", base_url))
+            .json(&json!({"key": "missing", "data": "00"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 404);
+    }
+
     #[tokio::test]
     #[ignore] // Requires dstack socket
     async fn test_sign_and_verify_roundtrip() {
@@ -392,4 +473,316 @@ mod tee_tests {
         assert!(sign_body.is_object());
         assert!(key_body.is_object());
     }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_shell_exec_receipt() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .post(format!("{}/shell/exec", base_url))
+            .json(&json!({
+                "command": "echo attested",
+                "attest": true,
+                "attest_quote": true
+            }))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        let receipt = &body["receipt"];
+        assert_eq!(receipt["kind"], "shell.exec");
+        assert_eq!(receipt["digest"].as_str().unwrap().len(), 64);
+        assert!(receipt["signature"].is_object());
+        assert!(receipt["quote"].is_object());
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_seal_unseal_roundtrip() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .post(format!("{}/file/seal", base_url))
+            .json(&json!({"path": "/tmp/sealed-test.bin", "content": "top secret"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        // The file on disk must not contain the plaintext
+        let on_disk = std::fs::read("/tmp/sealed-test.bin").expect("Sealed file missing");
+        assert!(!on_disk.windows(10).any(|w| w == b"top secret"));
+
+        let resp = client
+            .post(format!("{}/file/unseal", base_url))
+            .json(&json!({"path": "/tmp/sealed-test.bin"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["content"], "top secret");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_env_rejects_undecryptable_secret() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .get(format!("{}/tee/env", base_url))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(body["public_key"].as_str().unwrap().len(), 64);
+
+        // 60 zero bytes: long enough to parse, but not encrypted to this enclave
+        let resp = client
+            .post(format!("{}/tee/env", base_url))
+            .json(&json!({"secrets": {"API_KEY": "A".repeat(80)}}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_auth_challenge_response() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let challenge: Value = client
+            .post(format!("{}/tee/auth/challenge", base_url))
+            .send()
+            .await
+            .expect("Failed to send request")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+        let nonce = challenge["nonce"].as_str().unwrap();
+
+        // Same app, so this sandbox's own proof is trusted
+        let proof: Value = client
+            .post(format!("{}/tee/auth/prove", base_url))
+            .json(&json!({"nonce": nonce}))
+            .send()
+            .await
+            .expect("Failed to send request")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+
+        let body = json!({
+            "nonce": nonce,
+            "public_key": proof["public_key"],
+            "signature": proof["signature"]
+        });
+        let resp = client
+            .post(format!("{}/tee/auth", base_url))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+        let token: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(token["token"].as_str().unwrap().split('.').count(), 3);
+
+        // Nonces are single use
+        let resp = client
+            .post(format!("{}/tee/auth", base_url))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 401);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_skill_sign_output_is_stable() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        client
+            .post(format!("{}/skills", base_url))
+            .json(&json!({
+                "name": "tee-signer",
+                "description": "Signs outputs in tests",
+                "body": "Test skill"
+            }))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        let mut public_keys = Vec::new();
+        for content in ["first artifact", "second artifact"] {
+            let resp = client
+                .post(format!("{}/skills/tee-signer/sign-output", base_url))
+                .json(&json!({"content": content}))
+                .send()
+                .await
+                .expect("Failed to send request");
+
+            assert_eq!(resp.status(), 200);
+
+            let body: Value = resp.json().await.expect("Failed to parse JSON");
+            assert_eq!(body["skill"], "tee-signer");
+            assert_eq!(body["signature"].as_str().unwrap().len(), 128);
+            public_keys.push(body["public_key"].clone());
+        }
+        assert_eq!(public_keys[0], public_keys[1]);
+
+        client
+            .delete(format!("{}/skills/tee-signer", base_url))
+            .send()
+            .await
+            .expect("Failed to send request");
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_attest_payload() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let digest_of = |payload: Value| {
+            let client = client.clone();
+            let base_url = base_url.clone();
+            async move {
+                let resp = client
+                    .post(format!("{}/tee/attest-payload", base_url))
+                    .json(&json!({ "payload": payload }))
+                    .send()
+                    .await
+                    .expect("Failed to send request");
+                assert_eq!(resp.status(), 200);
+                let body: Value = resp.json().await.expect("Failed to parse JSON");
+                assert!(body["quote"].is_object());
+                body["payload_sha256"].as_str().unwrap().to_string()
+            }
+        };
+
+        // Key order does not change the canonical hash
+        let a = digest_of(json!({"b": 1, "a": [1, 2]})).await;
+        let b = digest_of(json!({"a": [1, 2], "b": 1})).await;
+        assert_eq!(a, b);
+        assert_eq!(a.len(), 64);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_token_matches_jwks() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let resp = client
+            .post(format!("{}/tee/token", base_url))
+            .json(&json!({"audience": "https://api.example.com", "claims": {"scope": "read"}}))
+            .send()
+            .await
+            .expect("Failed to send request");
+
+        assert_eq!(resp.status(), 200);
+
+        let body: Value = resp.json().await.expect("Failed to parse JSON");
+        let token = body["token"].as_str().unwrap();
+        assert_eq!(token.split('.').count(), 3);
+
+        let jwks: Value = client
+            .get(format!("{}/.well-known/jwks.json", base_url))
+            .send()
+            .await
+            .expect("Failed to send request")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+        let key = &jwks["keys"][0];
+        assert_eq!(key["crv"], "Ed25519");
+        assert!(!key["tee_quote"].as_str().unwrap().is_empty());
+
+        // Reserved claims cannot be overridden
+        let resp = client
+            .post(format!("{}/tee/token", base_url))
+            .json(&json!({"audience": "x", "claims": {"iss": "someone-else"}}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[tokio::test]
+    #[ignore] // Requires dstack socket
+    async fn test_encrypt_decrypt_roundtrip() {
+        let base_url =
+            std::env::var("TEST_BASE_URL").unwrap_or_else(|_| "http://localhost:8080".into());
+
+        wait_for_server(&base_url).await;
+
+        let client = Client::new();
+        let key: Value = client
+            .get(format!("{}/tee/encryption-key", base_url))
+            .send()
+            .await
+            .expect("Failed to send request")
+            .json()
+            .await
+            .expect("Failed to parse JSON");
+
+        // Encrypt to the enclave's own key, then have it decrypt
+        let resp = client
+            .post(format!("{}/tee/encrypt", base_url))
+            .json(&json!({"public_key": key["public_key"], "plaintext": "one-shot secret"}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 200);
+        let encrypted: Value = resp.json().await.expect("Failed to parse JSON");
+
+        let resp = client
+            .post(format!("{}/tee/decrypt", base_url))
+            .json(&json!({"ciphertext": encrypted["ciphertext"]}))
+            .send()
+            .await
+            .expect("Failed to send request");
+        assert_eq!(resp.status(), 200);
+        let decrypted: Value = resp.json().await.expect("Failed to parse JSON");
+        assert_eq!(decrypted["plaintext"], "one-shot secret");
+    }
 }